@@ -642,10 +642,9 @@ internal fun Event.Companion.fromNative(s: MemorySegment, app: Application): Eve
 
         desktop_gtk_h.NativeEvent_DataTransferAvailable() -> {
             val nativeEvent = NativeEvent.data_transfer_available(s)
-            val mimeTypesString = readStringFromNativeU8Array(NativeDataTransferAvailableEvent.mime_types(nativeEvent))!!
             Event.DataTransferAvailable(
                 dataSource = DataSource.fromNative(NativeDataTransferAvailableEvent.data_source(nativeEvent)),
-                mimeTypes = splitCsv(mimeTypesString),
+                mimeTypes = readNativeBorrowedUtf8Array(NativeDataTransferAvailableEvent.mime_types_list(nativeEvent)),
             )
         }
 
@@ -138,8 +138,8 @@ public class Application : AutoCloseable {
 
     // called from native
     private fun onEvent(nativeEvent: MemorySegment): Boolean {
-        val event = Event.fromNative(nativeEvent, this)
         return ffiUpCall(defaultResult = false) {
+            val event = Event.fromNative(nativeEvent, this)
             val result = applicationConfig?.eventHandler(event)
             when (result) {
                 EventHandlerResult.Continue -> false
@@ -407,4 +407,21 @@ public class Application : AutoCloseable {
             desktop_linux_h.application_close_notification(appPtr, notificationId.toInt())
         }
     }
+
+    /**
+     * Start reporting [Event.NetworkAvailabilityChanged], [Event.PowerSaverChanged], [Event.OnBatteryChanged]
+     * and [Event.LowMemoryWarning] events. The current state is reported right away. Calling it again has no effect.
+     */
+    public fun monitorsEnable() {
+        ffiDownCall {
+            desktop_linux_h.application_monitors_enable(appPtr)
+        }
+    }
+
+    /** Answer the [Event.SessionQueryEnd] event, for which the event handler returned `true`. */
+    public fun sessionQueryEndResponse() {
+        ffiDownCall {
+            desktop_linux_h.application_session_query_end_response(appPtr)
+        }
+    }
 }
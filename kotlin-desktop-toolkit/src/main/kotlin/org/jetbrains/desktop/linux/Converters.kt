@@ -565,6 +565,25 @@ internal fun DragAndDropAction.Companion.fromNative(nativeVal: Int): DragAndDrop
     else -> null
 }
 
+private fun SessionState.Companion.fromNative(raw: Int): SessionState {
+    return when (raw) {
+        desktop_linux_h.NativeSessionState_Running() -> SessionState.Running
+        desktop_linux_h.NativeSessionState_QueryEnd() -> SessionState.QueryEnd
+        desktop_linux_h.NativeSessionState_Ending() -> SessionState.Ending
+        else -> error("Unexpected session state: $raw")
+    }
+}
+
+private fun NetworkConnectivity.Companion.fromNative(raw: Int): NetworkConnectivity {
+    return when (raw) {
+        desktop_linux_h.NativeNetworkConnectivity_Local() -> NetworkConnectivity.Local
+        desktop_linux_h.NativeNetworkConnectivity_Limited() -> NetworkConnectivity.Limited
+        desktop_linux_h.NativeNetworkConnectivity_CaptivePortal() -> NetworkConnectivity.CaptivePortal
+        desktop_linux_h.NativeNetworkConnectivity_Full() -> NetworkConnectivity.Full
+        else -> error("Unexpected network connectivity: $raw")
+    }
+}
+
 internal fun Set<DragAndDropAction>.toNativeDragAndDropActions(): Int {
     var result = desktop_linux_h.NativeDragAndDropAction_None()
     for (e in this) {
@@ -677,6 +696,46 @@ internal fun Event.Companion.fromNative(s: MemorySegment, app: Application): Eve
             Event.DisplayConfigurationChange(screens = app.allScreens())
         }
 
+        desktop_linux_h.NativeEvent_SessionStateChanged() -> {
+            val nativeEvent = NativeEvent.session_state_changed(s)
+            Event.SessionStateChanged(
+                sessionState = SessionState.fromNative(NativeSessionStateChangedEvent.session_state(nativeEvent)),
+                screensaverActive = NativeSessionStateChangedEvent.screensaver_active(nativeEvent),
+            )
+        }
+
+        desktop_linux_h.NativeEvent_SessionQueryEnd() -> {
+            Event.SessionQueryEnd
+        }
+
+        desktop_linux_h.NativeEvent_SessionEnding() -> {
+            Event.SessionEnding
+        }
+
+        desktop_linux_h.NativeEvent_NetworkAvailabilityChanged() -> {
+            val nativeEvent = NativeEvent.network_availability_changed(s)
+            Event.NetworkAvailabilityChanged(
+                available = NativeNetworkAvailabilityChangedEvent.available(nativeEvent),
+                metered = NativeNetworkAvailabilityChangedEvent.metered(nativeEvent),
+                connectivity = NetworkConnectivity.fromNative(NativeNetworkAvailabilityChangedEvent.connectivity(nativeEvent)),
+            )
+        }
+
+        desktop_linux_h.NativeEvent_PowerSaverChanged() -> {
+            val nativeEvent = NativeEvent.power_saver_changed(s)
+            Event.PowerSaverChanged(enabled = NativePowerSaverChangedEvent.enabled(nativeEvent))
+        }
+
+        desktop_linux_h.NativeEvent_OnBatteryChanged() -> {
+            val nativeEvent = NativeEvent.on_battery_changed(s)
+            Event.OnBatteryChanged(onBattery = NativeOnBatteryChangedEvent.on_battery(nativeEvent))
+        }
+
+        desktop_linux_h.NativeEvent_LowMemoryWarning() -> {
+            val nativeEvent = NativeEvent.low_memory_warning(s)
+            Event.LowMemoryWarning(level = NativeLowMemoryWarningEvent.level(nativeEvent).toUByte())
+        }
+
         desktop_linux_h.NativeEvent_DesktopSettingChange() -> {
             val nativeEvent = NativeEvent.desktop_setting_change(s)
             Event.DesktopSettingChange(
@@ -686,10 +745,9 @@ internal fun Event.Companion.fromNative(s: MemorySegment, app: Application): Eve
 
         desktop_linux_h.NativeEvent_DataTransferAvailable() -> {
             val nativeEvent = NativeEvent.data_transfer_available(s)
-            val mimeTypesString = readStringFromNativeU8Array(NativeDataTransferAvailableEvent.mime_types(nativeEvent))!!
             Event.DataTransferAvailable(
                 dataSource = DataSource.fromNative(NativeDataTransferAvailableEvent.data_source(nativeEvent)),
-                mimeTypes = mimeTypesString.split(","),
+                mimeTypes = readNativeBorrowedUtf8Array(NativeDataTransferAvailableEvent.mime_types_list(nativeEvent)),
             )
         }
 
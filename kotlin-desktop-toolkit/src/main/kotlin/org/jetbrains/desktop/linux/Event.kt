@@ -97,6 +97,38 @@ public enum class WindowDecorationMode {
     internal companion object;
 }
 
+public enum class SessionState {
+    Running,
+
+    /** The session is about to end, see [Event.SessionQueryEnd]. */
+    QueryEnd,
+
+    /** The session is ending, see [Event.SessionEnding]. */
+    Ending,
+
+    ;
+
+    internal companion object;
+}
+
+public enum class NetworkConnectivity {
+    /** The host is not configured with a route to the Internet. */
+    Local,
+
+    /** The host is connected to a network, but can't reach the full Internet. */
+    Limited,
+
+    /** The host is behind a captive portal, and can't reach the full Internet. */
+    CaptivePortal,
+
+    /** The host is connected to a network, and can reach the full Internet. */
+    Full,
+
+    ;
+
+    internal companion object;
+}
+
 @JvmInline
 public value class RequestId private constructor(private val id: Int) {
     internal companion object {
@@ -132,6 +164,53 @@ public sealed class Event {
 
     public data object ApplicationWillTerminate : Event()
 
+    /** Reported by the session monitor of the inhibit portal. */
+    @ConsistentCopyVisibility
+    public data class SessionStateChanged internal constructor(
+        val sessionState: SessionState,
+        val screensaverActive: Boolean,
+    ) : Event()
+
+    /**
+     * The session is about to end, e.g. because the user is logging out.
+     * Return `true` from the event handler to answer later with [Application.sessionQueryEndResponse],
+     * otherwise the answer is sent immediately.
+     */
+    public data object SessionQueryEnd : Event()
+
+    /** The session is ending, and the application will be terminated soon. */
+    public data object SessionEnding : Event()
+
+    /** Reported after [Application.monitorsEnable], and then whenever the network status changes. */
+    @ConsistentCopyVisibility
+    public data class NetworkAvailabilityChanged internal constructor(
+        /** Whether the network is considered available, i.e. the host has a default route. */
+        val available: Boolean,
+
+        /** Whether the network is considered metered, i.e. the data usage should be limited. */
+        val metered: Boolean,
+
+        val connectivity: NetworkConnectivity,
+    ) : Event()
+
+    /** Reported after [Application.monitorsEnable], and then whenever the power saver mode is toggled. */
+    @ConsistentCopyVisibility
+    public data class PowerSaverChanged internal constructor(val enabled: Boolean) : Event()
+
+    /** Reported after [Application.monitorsEnable], and then whenever the system switches between battery and external power. */
+    @ConsistentCopyVisibility
+    public data class OnBatteryChanged internal constructor(val onBattery: Boolean) : Event()
+
+    /**
+     * The system is running low on memory, and the application should free the memory it doesn't need.
+     * Reported only after [Application.monitorsEnable].
+     */
+    @ConsistentCopyVisibility
+    public data class LowMemoryWarning internal constructor(
+        /** Severity of the memory shortage: `50` (low), `100` (medium) or `255` (critical). */
+        val level: UByte,
+    ) : Event()
+
     @ConsistentCopyVisibility
     public data class DesktopSettingChange internal constructor(val setting: DesktopSetting) : Event()
 
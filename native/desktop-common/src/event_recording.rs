@@ -0,0 +1,709 @@
+//! Recording of the events sent to the application, and their replay, e.g. to reproduce the input handling bugs.
+//!
+//! A recording is a text file with a JSON value per line. The first line is the header, e.g.
+//! `{"format":"kdt-event-recording","version":1,"platform":"linux"}`, and each of the following lines is an event, e.g.
+//! `{"time_us":1520,"event":{"KeyUp":{"code":38,"key":97}}}`, where `time_us` is the time since the recording was started.
+
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{BufRead, BufReader, LineWriter, Write},
+    path::Path,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, bail};
+use log::{info, warn};
+use serde_json::json;
+
+use crate::{
+    ffi_utils::{BorrowedArray, BorrowedUtf8},
+    mime_types::DataTransferContentKind,
+};
+
+pub use serde_json::{Map, Value};
+
+/// Holds the arrays of the replayed events. The values allocated in it are never dropped.
+pub type ReplayArena = bumpalo::Bump;
+
+pub const EVENT_RECORDING_FORMAT: &str = "kdt-event-recording";
+
+/// Incremented when the recorded events change in a way that the older versions can't replay.
+pub const EVENT_RECORDING_VERSION: u64 = 1;
+
+/// Conversion between an FFI value and its JSON representation in the recording.
+/// Implemented with [`recorded_struct`], [`recorded_enum`], [`recorded_newtype`] and [`recorded_variants`].
+pub trait RecordedValue<'a>: Sized + 'a {
+    fn record(&self) -> Value;
+
+    fn replay(value: &'a Value, arena: &'a ReplayArena) -> anyhow::Result<Self>;
+
+    fn record_slice(items: &[Self]) -> Value {
+        Value::Array(items.iter().map(Self::record).collect())
+    }
+
+    fn replay_slice(value: &'a Value, arena: &'a ReplayArena) -> anyhow::Result<&'a [Self]> {
+        let items = value
+            .as_array()
+            .with_context(|| format!("Expected an array, got {value}"))?
+            .iter()
+            .map(|item| Self::replay(item, arena))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(arena.alloc_slice_fill_iter(items))
+    }
+}
+
+/// The name of the variant, e.g. for the trace of the event dispatch.
+/// Implemented with [`recorded_variants`].
+pub trait RecordedVariant {
+    fn variant_name(&self) -> &'static str;
+}
+
+pub fn replay_field<'a, T: RecordedValue<'a>>(value: &'a Value, name: &str, arena: &'a ReplayArena) -> anyhow::Result<T> {
+    let field = value.get(name).with_context(|| format!("Missing field {name:?} in {value}"))?;
+    T::replay(field, arena).with_context(|| format!("Invalid field {name:?}"))
+}
+
+pub fn replay_variant_name(value: &Value) -> anyhow::Result<&str> {
+    value.as_str().with_context(|| format!("Expected a variant name, got {value}"))
+}
+
+#[must_use]
+pub fn record_variant(name: &str, data: Value) -> Value {
+    let mut variant = Map::new();
+    variant.insert(name.to_owned(), data);
+    Value::Object(variant)
+}
+
+/// Returns the name of the variant, and its data, if any.
+pub fn replay_variant(value: &Value) -> anyhow::Result<(&str, Option<&Value>)> {
+    match value {
+        Value::String(name) => Ok((name, None)),
+        Value::Object(variant) if variant.len() == 1 => Ok(variant.iter().next().map(|(name, data)| (name.as_str(), Some(data))).unwrap()),
+        _ => bail!("Expected a variant, got {value}"),
+    }
+}
+
+pub fn replay_variant_data<'a, T: RecordedValue<'a>>(name: &str, data: Option<&'a Value>, arena: &'a ReplayArena) -> anyhow::Result<T> {
+    let data = data.with_context(|| format!("Missing data of the {name:?} variant"))?;
+    T::replay(data, arena).with_context(|| format!("Invalid data of the {name:?} variant"))
+}
+
+/// `recorded_struct!(Name { field, … })` or `recorded_struct!(Name<'a> { field, … })`, with every field of the struct.
+#[macro_export]
+macro_rules! recorded_struct {
+    (@impl $lt:lifetime, $ty:ty, { $($field:ident),* }) => {
+        impl<$lt> $crate::event_recording::RecordedValue<$lt> for $ty {
+            fn record(&self) -> $crate::event_recording::Value {
+                let mut fields = $crate::event_recording::Map::new();
+                $(fields.insert(stringify!($field).to_owned(), $crate::event_recording::RecordedValue::record(&self.$field));)*
+                $crate::event_recording::Value::Object(fields)
+            }
+
+            fn replay(
+                value: &$lt $crate::event_recording::Value,
+                arena: &$lt $crate::event_recording::ReplayArena,
+            ) -> ::anyhow::Result<Self> {
+                Ok(Self {
+                    $($field: $crate::event_recording::replay_field(value, stringify!($field), arena)?,)*
+                })
+            }
+        }
+    };
+    ($name:ident<$lt:lifetime> { $($field:ident),* $(,)? }) => {
+        $crate::recorded_struct!(@impl $lt, $name<$lt>, { $($field),* });
+    };
+    ($name:ident { $($field:ident),* $(,)? }) => {
+        $crate::recorded_struct!(@impl 'a, $name, { $($field),* });
+    };
+}
+
+/// `recorded_enum!(Name { Variant, … })`, with every variant of the fieldless enum.
+#[macro_export]
+macro_rules! recorded_enum {
+    ($name:ident { $($variant:ident),* $(,)? }) => {
+        impl<'a> $crate::event_recording::RecordedValue<'a> for $name {
+            fn record(&self) -> $crate::event_recording::Value {
+                let name = match self {
+                    $(Self::$variant => stringify!($variant),)*
+                };
+                $crate::event_recording::Value::String(name.to_owned())
+            }
+
+            fn replay(
+                value: &'a $crate::event_recording::Value,
+                _arena: &'a $crate::event_recording::ReplayArena,
+            ) -> ::anyhow::Result<Self> {
+                match $crate::event_recording::replay_variant_name(value)? {
+                    $(stringify!($variant) => Ok(Self::$variant),)*
+                    name => ::anyhow::bail!("Unknown {} variant {name:?}", stringify!($name)),
+                }
+            }
+        }
+    };
+}
+
+/// `recorded_newtype!(Name)`, for a tuple struct with a single field.
+#[macro_export]
+macro_rules! recorded_newtype {
+    ($name:ident) => {
+        impl<'a> $crate::event_recording::RecordedValue<'a> for $name {
+            fn record(&self) -> $crate::event_recording::Value {
+                $crate::event_recording::RecordedValue::record(&self.0)
+            }
+
+            fn replay(
+                value: &'a $crate::event_recording::Value,
+                arena: &'a $crate::event_recording::ReplayArena,
+            ) -> ::anyhow::Result<Self> {
+                Ok(Self($crate::event_recording::RecordedValue::replay(value, arena)?))
+            }
+        }
+    };
+}
+
+/// `recorded_variants!(Name { unit: [Variant, …], data: [Variant, …] })`, with every variant of the enum.
+///
+/// The `data` variants have a single field. Recorded as `"Variant"` or `{"Variant": data}`.
+#[macro_export]
+macro_rules! recorded_variants {
+    (@impl $lt:lifetime, $ty:ty, $name:ident, [$($unit:ident),*], [$($data:ident),*]) => {
+        impl<$lt> $crate::event_recording::RecordedValue<$lt> for $ty {
+            fn record(&self) -> $crate::event_recording::Value {
+                match self {
+                    $(Self::$unit => $crate::event_recording::Value::String(stringify!($unit).to_owned()),)*
+                    $(Self::$data(data) => $crate::event_recording::record_variant(
+                        stringify!($data),
+                        $crate::event_recording::RecordedValue::record(data),
+                    ),)*
+                }
+            }
+
+            fn replay(
+                value: &$lt $crate::event_recording::Value,
+                arena: &$lt $crate::event_recording::ReplayArena,
+            ) -> ::anyhow::Result<Self> {
+                let (name, data) = $crate::event_recording::replay_variant(value)?;
+                match name {
+                    $(stringify!($unit) => Ok(Self::$unit),)*
+                    $(stringify!($data) => Ok(Self::$data($crate::event_recording::replay_variant_data(name, data, arena)?)),)*
+                    _ => ::anyhow::bail!("Unknown {} variant {name:?}", stringify!($name)),
+                }
+            }
+        }
+
+        impl<$lt> $crate::event_recording::RecordedVariant for $ty {
+            fn variant_name(&self) -> &'static str {
+                match self {
+                    $(Self::$unit => stringify!($unit),)*
+                    $(Self::$data(_) => stringify!($data),)*
+                }
+            }
+        }
+    };
+    ($name:ident<$lt:lifetime> { unit: [$($unit:ident),* $(,)?], data: [$($data:ident),* $(,)?] $(,)? }) => {
+        $crate::recorded_variants!(@impl $lt, $name<$lt>, $name, [$($unit),*], [$($data),*]);
+    };
+    ($name:ident { unit: [$($unit:ident),* $(,)?], data: [$($data:ident),* $(,)?] $(,)? }) => {
+        $crate::recorded_variants!(@impl 'a, $name, $name, [$($unit),*], [$($data),*]);
+    };
+}
+
+fn replay_int<T: TryFrom<u64> + TryFrom<i64>>(value: &Value) -> anyhow::Result<T> {
+    let n = if let Some(n) = value.as_u64() {
+        T::try_from(n).ok()
+    } else {
+        value.as_i64().and_then(|n| T::try_from(n).ok())
+    };
+    n.with_context(|| format!("Expected {}, got {value}", std::any::type_name::<T>()))
+}
+
+macro_rules! recorded_int {
+    ($($ty:ty),*) => {
+        $(impl<'a> RecordedValue<'a> for $ty {
+            fn record(&self) -> Value {
+                Value::from(*self)
+            }
+
+            fn replay(value: &'a Value, _arena: &'a ReplayArena) -> anyhow::Result<Self> {
+                replay_int(value)
+            }
+        })*
+    };
+}
+
+recorded_int!(u32, u64, i32, i64);
+
+impl<'a> RecordedValue<'a> for u8 {
+    fn record(&self) -> Value {
+        Value::from(*self)
+    }
+
+    fn replay(value: &'a Value, _arena: &'a ReplayArena) -> anyhow::Result<Self> {
+        replay_int(value)
+    }
+
+    /// Recorded as a hex string, which is much shorter for e.g. the clipboard content.
+    fn record_slice(items: &[Self]) -> Value {
+        Value::String(items.iter().fold(String::with_capacity(items.len() * 2), |mut s, b| {
+            _ = write!(s, "{b:02x}");
+            s
+        }))
+    }
+
+    fn replay_slice(value: &'a Value, arena: &'a ReplayArena) -> anyhow::Result<&'a [Self]> {
+        let hex = value.as_str().with_context(|| format!("Expected a hex string, got {value}"))?;
+        if hex.len() % 2 != 0 {
+            bail!("Odd length of the hex string {hex:?}");
+        }
+        // Also rules out the non-ASCII characters, the string is sliced at every second byte
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            bail!("Invalid hex string {hex:?}");
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| Self::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Invalid hex string {hex:?}"))?;
+        Ok(arena.alloc_slice_copy(&bytes))
+    }
+}
+
+impl<'a> RecordedValue<'a> for bool {
+    fn record(&self) -> Value {
+        Value::Bool(*self)
+    }
+
+    fn replay(value: &'a Value, _arena: &'a ReplayArena) -> anyhow::Result<Self> {
+        value.as_bool().with_context(|| format!("Expected a boolean, got {value}"))
+    }
+}
+
+impl<'a> RecordedValue<'a> for f64 {
+    /// Non-finite values are recorded as `null`, because JSON can't represent them.
+    fn record(&self) -> Value {
+        serde_json::Number::from_f64(*self).map_or(Value::Null, Value::Number)
+    }
+
+    fn replay(value: &'a Value, _arena: &'a ReplayArena) -> anyhow::Result<Self> {
+        if value.is_null() {
+            return Ok(Self::NAN);
+        }
+        value.as_f64().with_context(|| format!("Expected a number, got {value}"))
+    }
+}
+
+impl<'a> RecordedValue<'a> for char {
+    fn record(&self) -> Value {
+        Value::String(self.to_string())
+    }
+
+    fn replay(value: &'a Value, _arena: &'a ReplayArena) -> anyhow::Result<Self> {
+        let mut chars = value.as_str().map(str::chars).into_iter().flatten();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => bail!("Expected a single character, got {value}"),
+        }
+    }
+}
+
+impl<'a> RecordedValue<'a> for BorrowedUtf8<'a> {
+    fn record(&self) -> Value {
+        self.as_optional_bytes()
+            .map_or(Value::Null, |bytes| Value::String(String::from_utf8_lossy(bytes).into_owned()))
+    }
+
+    fn replay(value: &'a Value, _arena: &'a ReplayArena) -> anyhow::Result<Self> {
+        match value {
+            Value::Null => Ok(Self::null()),
+            Value::String(s) => Ok(Self::new(s)),
+            _ => bail!("Expected a string, got {value}"),
+        }
+    }
+}
+
+impl<'a, T: RecordedValue<'a>> RecordedValue<'a> for BorrowedArray<'a, T> {
+    fn record(&self) -> Value {
+        self.as_optional_slice().map_or(Value::Null, T::record_slice)
+    }
+
+    fn replay(value: &'a Value, arena: &'a ReplayArena) -> anyhow::Result<Self> {
+        if value.is_null() {
+            return Ok(Self::null());
+        }
+        Ok(Self::from_slice(T::replay_slice(value, arena)?))
+    }
+}
+
+recorded_enum!(DataTransferContentKind { Other, Text, Files, Image });
+
+struct EventRecorder {
+    writer: LineWriter<Box<dyn Write + Send>>,
+    started: Instant,
+}
+
+static EVENT_RECORDER: Mutex<Option<EventRecorder>> = Mutex::new(None);
+
+// Avoids locking `EVENT_RECORDER` for every event when nothing is recorded.
+static EVENT_RECORDING_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Starts writing the events passed to [`record_event`] to `path`, replacing the recording in progress, if any.
+pub fn start_event_recording(path: &Path, platform: &str) -> anyhow::Result<()> {
+    let file = File::create(path).with_context(|| format!("Can't create the event recording {}", path.display()))?;
+    start_event_recording_to(Box::new(file), platform)?;
+    info!("Started the event recording to {}", path.display());
+    Ok(())
+}
+
+/// Like [`start_event_recording`], but writes the events to `writer`.
+pub fn start_event_recording_to(writer: Box<dyn Write + Send>, platform: &str) -> anyhow::Result<()> {
+    let mut writer = LineWriter::new(writer);
+    let header = json!({
+        "format": EVENT_RECORDING_FORMAT,
+        "version": EVENT_RECORDING_VERSION,
+        "platform": platform,
+    });
+    writeln!(writer, "{header}")?;
+    let mut recorder = EVENT_RECORDER.lock().unwrap();
+    *recorder = Some(EventRecorder {
+        writer,
+        started: Instant::now(),
+    });
+    EVENT_RECORDING_ACTIVE.store(true, Ordering::Release);
+    drop(recorder);
+    Ok(())
+}
+
+pub fn stop_event_recording() -> anyhow::Result<()> {
+    let recorder = EVENT_RECORDER.lock().unwrap().take();
+    EVENT_RECORDING_ACTIVE.store(false, Ordering::Release);
+    if let Some(mut recorder) = recorder {
+        recorder.writer.flush()?;
+        info!("Stopped the event recording");
+    }
+    Ok(())
+}
+
+#[must_use]
+pub fn is_event_recording_active() -> bool {
+    EVENT_RECORDING_ACTIVE.load(Ordering::Acquire)
+}
+
+/// Appends the event to the recording in progress, if any. The recording is stopped if the event can't be written.
+pub fn record_event<'a>(event: &impl RecordedValue<'a>) {
+    if !is_event_recording_active() {
+        return;
+    }
+    let event = event.record();
+    let mut recorder = EVENT_RECORDER.lock().unwrap();
+    let Some(r) = recorder.as_mut() else {
+        return;
+    };
+    let time_us = u64::try_from(r.started.elapsed().as_micros()).unwrap_or(u64::MAX);
+    let entry = json!({ "time_us": time_us, "event": event });
+    if let Err(e) = writeln!(r.writer, "{entry}") {
+        *recorder = None;
+        EVENT_RECORDING_ACTIVE.store(false, Ordering::Release);
+        drop(recorder);
+        warn!("Stopped the event recording, because the event couldn't be written: {e}");
+    }
+}
+
+/// Calls `f` with each event of the recording at `path`, and returns the number of the replayed events.
+///
+/// The original timing is kept when `speed` is `1.0`, and scaled otherwise, e.g. `2.0` replays twice as fast.
+/// With `speed` of `0.0`, the events are replayed without any delays.
+pub fn replay_events(
+    path: &Path,
+    platform: &str,
+    speed: f64,
+    f: impl for<'v> FnMut(&'v Value, &'v ReplayArena) -> anyhow::Result<()>,
+) -> anyhow::Result<usize> {
+    let file = File::open(path).with_context(|| format!("Can't open the event recording {}", path.display()))?;
+    replay_events_from(BufReader::new(file), platform, speed, f)
+}
+
+/// Like [`replay_events`], but reads the recording from `reader`.
+pub fn replay_events_from(
+    reader: impl BufRead,
+    platform: &str,
+    speed: f64,
+    mut f: impl for<'v> FnMut(&'v Value, &'v ReplayArena) -> anyhow::Result<()>,
+) -> anyhow::Result<usize> {
+    let mut lines = reader.lines();
+    let header: Value = serde_json::from_str(&lines.next().context("The event recording is empty")??)?;
+    if header.get("format").and_then(Value::as_str) != Some(EVENT_RECORDING_FORMAT) {
+        bail!("Not an event recording: {header}");
+    }
+    let version = header.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version == 0 || version > EVENT_RECORDING_VERSION {
+        bail!("Unsupported event recording version {version}, expected at most {EVENT_RECORDING_VERSION}");
+    }
+    let recorded_platform = header.get("platform").and_then(Value::as_str).unwrap_or_default();
+    if recorded_platform != platform {
+        bail!("The events were recorded on {recorded_platform:?}, and can't be replayed on {platform:?}");
+    }
+
+    let started = Instant::now();
+    let mut arena = ReplayArena::new();
+    let mut count = 0;
+    for (i, line) in lines.enumerate() {
+        let line_number = i + 2;
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let entry: Value = serde_json::from_str(&line).with_context(|| format!("Invalid JSON on line {line_number}"))?;
+        let time_us = entry
+            .get("time_us")
+            .and_then(Value::as_u64)
+            .with_context(|| format!("Missing time on line {line_number}"))?;
+        let event = entry.get("event").with_context(|| format!("Missing event on line {line_number}"))?;
+        if speed > 0.0 && speed.is_finite() {
+            let due = Duration::from_micros(time_us).div_f64(speed);
+            if let Some(delay) = due.checked_sub(started.elapsed()) {
+                std::thread::sleep(delay);
+            }
+        }
+        f(event, &arena).with_context(|| format!("Can't replay the event on line {line_number}"))?;
+        arena.reset();
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum TestAction {
+        Copy,
+        Move,
+    }
+
+    recorded_enum!(TestAction { Copy, Move });
+
+    struct TestPreedit<'a> {
+        text: BorrowedUtf8<'a>,
+        cursor_begin: i32,
+        cursor_end: i32,
+    }
+
+    recorded_struct!(TestPreedit<'a> { text, cursor_begin, cursor_end });
+
+    struct TestDrop<'a> {
+        mime_types: BorrowedArray<'a, BorrowedUtf8<'a>>,
+        data: BorrowedArray<'a, u8>,
+        action: TestAction,
+    }
+
+    recorded_struct!(TestDrop<'a> { mime_types, data, action });
+
+    enum TestEvent<'a> {
+        Started,
+        Preedit(TestPreedit<'a>),
+        Drop(TestDrop<'a>),
+        Scale(f64),
+    }
+
+    recorded_variants!(TestEvent<'a> { unit: [Started], data: [Preedit, Drop, Scale] });
+
+    /// In-memory writer for the recording, shared with the recorder.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Replays the `recording`, returning each event recorded again, and the time of its replay since the start.
+    fn replay_recording(recording: &str, platform: &str, speed: f64) -> anyhow::Result<Vec<(Value, Duration)>> {
+        let started = Instant::now();
+        let mut replayed = Vec::new();
+        let count = replay_events_from(recording.as_bytes(), platform, speed, |value, arena| {
+            replayed.push((TestEvent::replay(value, arena)?.record(), started.elapsed()));
+            Ok(())
+        })?;
+        assert_eq!(count, replayed.len());
+        Ok(replayed)
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let mime_types = [BorrowedUtf8::new("text/uri-list"), BorrowedUtf8::new("text/plain;charset=utf-8")];
+        let events = [
+            TestEvent::Started,
+            TestEvent::Preedit(TestPreedit {
+                text: BorrowedUtf8::new("日本\"語\""),
+                cursor_begin: 3,
+                cursor_end: -1,
+            }),
+            TestEvent::Preedit(TestPreedit {
+                text: BorrowedUtf8::null(),
+                cursor_begin: 0,
+                cursor_end: 0,
+            }),
+            TestEvent::Drop(TestDrop {
+                mime_types: BorrowedArray::from_slice(&mime_types),
+                data: BorrowedArray::from_slice(&[0x00, 0x7F, 0xFF]),
+                action: TestAction::Move,
+            }),
+            TestEvent::Drop(TestDrop {
+                mime_types: BorrowedArray::null(),
+                data: BorrowedArray::from_slice(&[]),
+                action: TestAction::Copy,
+            }),
+            TestEvent::Scale(1.25),
+        ];
+
+        let buffer = SharedBuffer::default();
+        start_event_recording_to(Box::new(buffer.clone()), "test").unwrap();
+        assert!(is_event_recording_active());
+        for event in &events {
+            record_event(event);
+        }
+        stop_event_recording().unwrap();
+        assert!(!is_event_recording_active());
+        // Not recorded after the recording was stopped
+        record_event(&TestEvent::Started);
+
+        let recording = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let mut lines = recording.lines();
+        let header: Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+        assert_eq!(
+            header,
+            json!({"format": EVENT_RECORDING_FORMAT, "version": EVENT_RECORDING_VERSION, "platform": "test"})
+        );
+        assert_eq!(lines.count(), events.len());
+        assert!(recording.contains(r#""data":"007fff""#), "{recording}");
+
+        let replayed = replay_recording(&recording, "test", 0.0).unwrap();
+        let replayed_events: Vec<_> = replayed.into_iter().map(|(event, _)| event).collect();
+        let recorded_events: Vec<_> = events.iter().map(RecordedValue::record).collect();
+        assert_eq!(replayed_events, recorded_events);
+
+        replay_events_from(recording.as_bytes(), "test", 0.0, |value, arena| {
+            if let TestEvent::Drop(drop) = TestEvent::replay(value, arena)?
+                && let Some(mime_types) = drop.mime_types.as_optional_slice()
+            {
+                let mime_types: Vec<_> = mime_types.iter().map(|m| m.get("mime_type").unwrap()).collect();
+                assert_eq!(mime_types, ["text/uri-list", "text/plain;charset=utf-8"]);
+                assert_eq!(drop.data.as_slice()?, [0x00, 0x7F, 0xFF]);
+                assert_eq!(drop.action, TestAction::Move);
+            }
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_replay_header_checks() {
+        let event = r#"{"time_us":0,"event":"Started"}"#;
+        let cases = [
+            (
+                r#"{"format":"other","version":1,"platform":"test"}"#.to_owned(),
+                "Not an event recording",
+            ),
+            (
+                format!(r#"{{"format":"{EVENT_RECORDING_FORMAT}","version":0,"platform":"test"}}"#),
+                "Unsupported event recording version 0",
+            ),
+            (
+                format!(r#"{{"format":"{EVENT_RECORDING_FORMAT}","platform":"test"}}"#),
+                "Unsupported event recording version 0",
+            ),
+            (
+                format!(
+                    r#"{{"format":"{EVENT_RECORDING_FORMAT}","version":{},"platform":"test"}}"#,
+                    EVENT_RECORDING_VERSION + 1
+                ),
+                "Unsupported event recording version",
+            ),
+            (
+                format!(r#"{{"format":"{EVENT_RECORDING_FORMAT}","version":{EVENT_RECORDING_VERSION},"platform":"other"}}"#),
+                "The events were recorded on \"other\"",
+            ),
+        ];
+        for (header, expected) in cases {
+            let error = replay_recording(&format!("{header}\n{event}\n"), "test", 0.0).unwrap_err();
+            assert!(error.to_string().starts_with(expected), "{header:?}: {error}");
+        }
+        let error = replay_recording("", "test", 0.0).unwrap_err();
+        assert_eq!(error.to_string(), "The event recording is empty");
+    }
+
+    #[test]
+    fn test_replay_invalid_events() {
+        let header = format!(r#"{{"format":"{EVENT_RECORDING_FORMAT}","version":{EVENT_RECORDING_VERSION},"platform":"test"}}"#);
+        let cases = [
+            ("{", "Invalid JSON on line 2"),
+            (r#"{"event":"Started"}"#, "Missing time on line 2"),
+            (r#"{"time_us":0}"#, "Missing event on line 2"),
+            (r#"{"time_us":0,"event":"Unknown"}"#, "Can't replay the event on line 2"),
+            (
+                r#"{"time_us":0,"event":{"Drop":{"mime_types":null}}}"#,
+                "Can't replay the event on line 2",
+            ),
+        ];
+        for (event, expected) in cases {
+            let error = replay_recording(&format!("{header}\n{event}\n"), "test", 0.0).unwrap_err();
+            assert_eq!(error.to_string(), expected, "{event}");
+        }
+        // Empty lines are skipped
+        let replayed = replay_recording(&format!("{header}\n\n{{\"time_us\":0,\"event\":\"Started\"}}\n"), "test", 0.0).unwrap();
+        assert_eq!(replayed.len(), 1);
+    }
+
+    #[test]
+    fn test_replay_hex_bytes() {
+        let arena = ReplayArena::new();
+        let value = Value::String("007fFF".to_owned());
+        assert_eq!(u8::replay_slice(&value, &arena).unwrap(), [0x00, 0x7f, 0xff]);
+        assert!(u8::replay_slice(&Value::String(String::new()), &arena).unwrap().is_empty());
+
+        let cases = [
+            (Value::String("abc".to_owned()), "Odd length of the hex string \"abc\""),
+            (Value::String("aéa".to_owned()), "Invalid hex string \"aéa\""),
+            (Value::String("+f".to_owned()), "Invalid hex string \"+f\""),
+            (Value::String("zz".to_owned()), "Invalid hex string \"zz\""),
+            (Value::Number(1.into()), "Expected a hex string, got 1"),
+        ];
+        for (value, expected) in cases {
+            let error = u8::replay_slice(&value, &arena).unwrap_err();
+            assert_eq!(error.to_string(), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn test_replay_timing() {
+        let recording = format!(
+            "{}\n{}\n{}\n",
+            json!({"format": EVENT_RECORDING_FORMAT, "version": EVENT_RECORDING_VERSION, "platform": "test"}),
+            json!({"time_us": 0, "event": "Started"}),
+            json!({"time_us": 400_000, "event": {"Scale": 2.0}}),
+        );
+        // 10 times faster, so the second event is replayed after 40ms instead of 400ms
+        let replayed = replay_recording(&recording, "test", 10.0).unwrap();
+        assert_eq!(replayed.len(), 2);
+        assert!(replayed[0].1 < Duration::from_millis(40), "{replayed:?}");
+        assert!(replayed[1].1 >= Duration::from_millis(40), "{replayed:?}");
+        assert!(replayed[1].1 < Duration::from_millis(400), "{replayed:?}");
+
+        // Without any delays
+        let replayed = replay_recording(&recording, "test", 0.0).unwrap();
+        assert!(replayed[1].1 < Duration::from_millis(400), "{replayed:?}");
+    }
+}
@@ -1,6 +1,6 @@
 #![allow(clippy::missing_safety_doc)]
 
-use anyhow::{Context, anyhow, bail};
+use anyhow::{Context, anyhow};
 use core::slice;
 use log::trace;
 use std::{
@@ -11,6 +11,8 @@ use std::{
     rc::Rc,
 };
 
+use crate::logger_api::NativeErrorKind;
+
 #[derive(Debug, Copy)]
 #[repr(transparent)]
 struct GenericRawPtr<'a, T> {
@@ -275,6 +277,12 @@ impl AutoDropStrPtr {
     pub const fn borrow(&self) -> BorrowedStrPtr<'_> {
         BorrowedStrPtr(self.0.0)
     }
+
+    /// The returned pointer is valid as long as `self` is alive.
+    #[must_use]
+    pub(crate) const fn as_raw(&self) -> RustAllocatedStrPtr {
+        RustAllocatedStrPtr(self.0.0)
+    }
 }
 
 impl Drop for AutoDropStrPtr {
@@ -327,6 +335,16 @@ impl AutoDropArray<AutoDropStrPtr> {
     }
 }
 
+impl AutoDropArray<AutoDropStrPtr> {
+    pub fn from_strs<S: Into<Vec<u8>>>(strs: impl IntoIterator<Item = S>) -> Result<Self, NulError> {
+        let strs = strs
+            .into_iter()
+            .map(|s| RustAllocatedStrPtr::allocate(s).map(RustAllocatedStrPtr::to_auto_drop))
+            .collect::<Result<Box<_>, _>>()?;
+        Ok(Self::new(strs))
+    }
+}
+
 impl std::fmt::Display for AutoDropArray<AutoDropStrPtr> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.ptr.is_null() {
@@ -388,7 +406,7 @@ impl<'a, T> BorrowedArray<'a, T> {
 
     pub fn as_slice(&'a self) -> anyhow::Result<&'a [T]> {
         if self.ptr.is_null() {
-            bail!("Null pointer!")
+            return Err(anyhow!(NativeErrorKind::InvalidArgument).context("Null pointer!"));
         }
         let slice = unsafe { slice::from_raw_parts(self.ptr, self.len) };
         Ok(slice)
@@ -433,6 +451,11 @@ impl<'a> BorrowedUtf8<'a> {
         }
     }
 
+    #[must_use]
+    pub const fn as_optional_bytes(&'a self) -> Option<&'a [u8]> {
+        self.0.as_optional_slice()
+    }
+
     const fn get_optional_impl(&'a self) -> Result<Option<&'a str>, (&'a [u8], std::str::Utf8Error)> {
         match self.0.as_optional_slice() {
             None => Ok(None),
@@ -446,21 +469,32 @@ impl<'a> BorrowedUtf8<'a> {
     pub fn get_optional(&'a self, context: &str) -> anyhow::Result<Option<&'a str>> {
         match self.get_optional_impl() {
             Ok(s) => Ok(s),
-            Err((slice, e)) => Err(anyhow!(
+            Err((slice, e)) => Err(anyhow!(NativeErrorKind::InvalidArgument).context(format!(
                 "{context}: error decoding {inner:?} ({slice:?}) as UTF-8: {e}",
                 inner = self.0
-            )),
+            ))),
         }
     }
 
     pub fn get(&'a self, context: &str) -> anyhow::Result<&'a str> {
         match self.get_optional(context)? {
-            None => bail!("{context} must not be null"),
+            None => Err(anyhow!(NativeErrorKind::InvalidArgument).context(format!("{context} must not be null"))),
             Some(s) => Ok(s),
         }
     }
 }
 
+impl<'a> BorrowedArray<'a, BorrowedUtf8<'a>> {
+    /// Decodes every element, failing if the array or any of its elements is null.
+    pub fn get_strs(&'a self, context: &str) -> anyhow::Result<Box<[&'a str]>> {
+        self.as_slice()
+            .with_context(|| format!("{context} must not be null"))?
+            .iter()
+            .map(|s| s.get(context))
+            .collect()
+    }
+}
+
 impl std::fmt::Debug for BorrowedUtf8<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.get_optional_impl() {
@@ -1,3 +1,6 @@
+pub mod event_recording;
 pub mod ffi_utils;
 pub mod logger;
 pub mod logger_api;
+pub mod mime_types;
+pub mod perf_trace;
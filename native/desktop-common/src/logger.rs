@@ -1,206 +1,480 @@
-use std::{any::Any, cell::RefCell, panic::AssertUnwindSafe};
+use std::{
+    any::Any,
+    backtrace::BacktraceStatus,
+    cell::{Cell, RefCell},
+    panic::AssertUnwindSafe,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
-use anyhow::Context;
-use log::error;
+use anyhow::{Context, anyhow};
+use log::{LevelFilter, Record, debug, error};
 use log4rs::{
-    Config,
+    Config, Handle,
     append::{
+        Append,
         console::{ConsoleAppender, Target},
         rolling_file::{
             RollingFileAppender,
             policy::compound::{CompoundPolicy, roll::fixed_window::FixedWindowRoller, trigger::size::SizeTrigger},
         },
     },
-    config::{Appender, Root},
+    config::{Appender, Logger, Root},
     encode::pattern::PatternEncoder,
-    filter::threshold::ThresholdFilter,
+    filter::{Filter, Response},
 };
 
 use crate::{
-    ffi_utils::{ArraySize, AutoDropArray, RustAllocatedStrPtr},
-    logger_api::{ExceptionsArray, LogLevel, LoggerConfiguration},
+    ffi_utils::{ArraySize, AutoDropArray, AutoDropStrPtr, BorrowedUtf8, RustAllocatedStrPtr},
+    logger_api::{ExceptionsArray, LogCallback, LogLevel, LogRecord, LoggerConfiguration, LoggerLevels, NativeError, NativeErrorKind},
+    perf_trace::TraceSpan,
 };
 
-const MAX_EXCEPTIONS_COUNT: usize = 10;
+const MAX_EXCEPTIONS_COUNT: usize = 100;
+
+static FAIL_FAST: AtomicBool = AtomicBool::new(false);
+
+pub fn set_fail_fast(enabled: bool) {
+    FAIL_FAST.store(enabled, Ordering::Relaxed);
+}
+
+fn allocate_lossy(s: &str) -> AutoDropStrPtr {
+    RustAllocatedStrPtr::allocate(s.replace('\0', "\\0"))
+        .expect("NUL bytes are escaped")
+        .to_auto_drop()
+}
+
+fn allocate_optional_lossy(s: Option<&str>) -> AutoDropStrPtr {
+    s.map_or_else(|| RustAllocatedStrPtr::null().to_auto_drop(), allocate_lossy)
+}
+
+/// The explicitly attached kind wins, otherwise it's derived from the first recognized source error.
+fn error_kind(err: &anyhow::Error) -> NativeErrorKind {
+    if let Some(kind) = err.downcast_ref::<NativeErrorKind>() {
+        return *kind;
+    }
+    for cause in err.chain() {
+        if let Some(io_err) = cause.downcast_ref::<std::io::Error>() {
+            return match io_err.kind() {
+                std::io::ErrorKind::NotFound => NativeErrorKind::NotFound,
+                std::io::ErrorKind::InvalidInput | std::io::ErrorKind::InvalidData => NativeErrorKind::InvalidArgument,
+                std::io::ErrorKind::Unsupported => NativeErrorKind::Unsupported,
+                _ => NativeErrorKind::PlatformError,
+            };
+        }
+        if cause.is::<std::str::Utf8Error>() || cause.is::<std::ffi::NulError>() {
+            return NativeErrorKind::InvalidArgument;
+        }
+    }
+    NativeErrorKind::Other
+}
 
-struct LastExceptionMessages {
-    messages: [RustAllocatedStrPtr; MAX_EXCEPTIONS_COUNT],
-    count: usize,
+struct LastExceptions {
+    // Both have the capacity of `MAX_EXCEPTIONS_COUNT`, so the pointers given out are stable until `clear`.
+    messages: Vec<RustAllocatedStrPtr>,
+    errors: Vec<NativeError>,
+    dropped_count: usize,
 }
 
-impl LastExceptionMessages {
+impl LastExceptions {
     const fn new() -> Self {
         Self {
-            messages: [RustAllocatedStrPtr::null(); MAX_EXCEPTIONS_COUNT],
-            count: 0,
+            messages: Vec::new(),
+            errors: Vec::new(),
+            dropped_count: 0,
         }
     }
 
-    #[allow(clippy::needless_pass_by_value)]
-    fn append(&mut self, msg: String) {
-        if self.count < MAX_EXCEPTIONS_COUNT {
-            match RustAllocatedStrPtr::allocate(msg.as_bytes()) {
-                Ok(msg) => {
-                    self.messages[self.count] = msg;
-                    self.count += 1;
-                }
-                Err(err) => {
-                    error!("Can't append exception: {err}");
-                }
+    /// Returns `true` if the error is the first one dropped since `clear`.
+    ///
+    /// The caller reports it, after releasing `LAST_EXCEPTIONS`: the log callback may call back into the native code.
+    fn append(&mut self, error: NativeError) -> bool {
+        if self.errors.len() < MAX_EXCEPTIONS_COUNT {
+            // Only allocated on the first use, `clear` keeps the capacity
+            if self.errors.capacity() == 0 {
+                self.messages.reserve_exact(MAX_EXCEPTIONS_COUNT);
+                self.errors.reserve_exact(MAX_EXCEPTIONS_COUNT);
             }
+            self.messages.push(error.message.as_raw());
+            self.errors.push(error);
+            false
         } else {
-            error!("Can't append more exceptions we already have {MAX_EXCEPTIONS_COUNT}");
+            self.dropped_count += 1;
+            self.dropped_count == 1
         }
     }
 
     fn clear(&mut self) {
-        for i in 0..self.count {
-            self.messages[i].deallocate();
-            self.messages[i] = RustAllocatedStrPtr::null();
-        }
-        self.count = 0;
+        self.messages.clear();
+        self.errors.clear();
+        self.dropped_count = 0;
     }
 
     pub const fn exceptions_array(&self) -> ExceptionsArray {
         ExceptionsArray {
             items: self.messages.as_ptr(),
-            count: self.count as ArraySize,
+            count: self.errors.len() as ArraySize,
+            errors: self.errors.as_ptr(),
+            dropped_count: self.dropped_count as ArraySize,
         }
     }
 }
 
 thread_local! {
-    pub static LAST_EXCEPTION_MSGS: RefCell<LastExceptionMessages> = const { RefCell::new(LastExceptionMessages::new()) };
+    static LAST_EXCEPTIONS: RefCell<LastExceptions> = const { RefCell::new(LastExceptions::new()) };
+    static LAST_PANIC_BACKTRACE: RefCell<Option<String>> = const { RefCell::new(None) };
 }
 
+fn append_exception(kind: NativeErrorKind, function_name: Option<&str>, message: &str, context_chain: &[String], backtrace: Option<&str>) {
+    if FAIL_FAST.load(Ordering::Relaxed) {
+        error!("Aborting on the first error ({kind}), because the fail fast mode is enabled: {message}");
+        log::logger().flush();
+        std::process::abort();
+    }
+    let error = NativeError {
+        kind,
+        function_name: allocate_optional_lossy(function_name),
+        message: allocate_lossy(message),
+        context_chain: AutoDropArray::new(context_chain.iter().map(|s| allocate_lossy(s)).collect()),
+        backtrace: allocate_optional_lossy(backtrace),
+    };
+    if LAST_EXCEPTIONS.with_borrow_mut(|last_exceptions| last_exceptions.append(error)) {
+        error!("Can't append more exceptions we already have {MAX_EXCEPTIONS_COUNT}");
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
 pub fn append_exception_msg(msg: String) {
-    LAST_EXCEPTION_MSGS.with_borrow_mut(|last_exception_msgs| {
-        last_exception_msgs.append(msg);
-    });
+    append_exception(NativeErrorKind::Other, None, &msg, std::slice::from_ref(&msg), None);
+}
+
+fn append_error(function_name: &str, err: &anyhow::Error) {
+    let context_chain = err.chain().map(ToString::to_string).collect::<Vec<_>>();
+    let backtrace = err.backtrace();
+    let backtrace = (backtrace.status() == BacktraceStatus::Captured).then(|| backtrace.to_string());
+    append_exception(
+        error_kind(err),
+        Some(function_name),
+        &format!("{err:#}"),
+        &context_chain,
+        backtrace.as_deref(),
+    );
 }
 
 pub fn clear_exception_msgs() {
-    LAST_EXCEPTION_MSGS.with_borrow_mut(|last_exception_msgs| {
-        last_exception_msgs.clear();
-    });
+    LAST_EXCEPTIONS.with_borrow_mut(LastExceptions::clear);
 }
 
 pub fn exceptions_array() -> ExceptionsArray {
-    LAST_EXCEPTION_MSGS.with_borrow(LastExceptionMessages::exceptions_array)
+    LAST_EXCEPTIONS.with_borrow(LastExceptions::exceptions_array)
+}
+
+impl From<log::Level> for LogLevel {
+    fn from(value: log::Level) -> Self {
+        match value {
+            log::Level::Error => Self::Error,
+            log::Level::Warn => Self::Warn,
+            log::Level::Info => Self::Info,
+            log::Level::Debug => Self::Debug,
+            log::Level::Trace => Self::Trace,
+        }
+    }
 }
 
 impl LogLevel {
-    const fn level_filter(&self) -> log::LevelFilter {
+    const fn level_filter(&self) -> LevelFilter {
         match self {
-            Self::Off => log::LevelFilter::Off,
-            Self::Error => log::LevelFilter::Error,
-            Self::Warn => log::LevelFilter::Warn,
-            Self::Info => log::LevelFilter::Info,
-            Self::Debug => log::LevelFilter::Debug,
-            Self::Trace => log::LevelFilter::Trace,
+            Self::Off => LevelFilter::Off,
+            Self::Error => LevelFilter::Error,
+            Self::Warn => LevelFilter::Warn,
+            Self::Info => LevelFilter::Info,
+            Self::Debug => LevelFilter::Debug,
+            Self::Trace => LevelFilter::Trace,
         }
     }
 }
 
-impl LoggerConfiguration<'_> {
-    fn file_path(&self) -> anyhow::Result<&str> {
-        self.file_path.as_str()
-    }
+/// The levels set with `logger_set_levels`, or the initial ones from `LoggerConfiguration`.
+#[derive(Clone, Debug)]
+struct LogLevels {
+    console: LevelFilter,
+    file: LevelFilter,
+    /// Per-target levels, e.g. `("desktop_linux::linux::data_transfer", Trace)`, used for both the console and the file.
+    overrides: Vec<(String, LevelFilter)>,
+}
 
-    const fn console_log_level(&self) -> log::LevelFilter {
-        self.console_level.level_filter()
+impl LogLevels {
+    fn max_level(&self) -> LevelFilter {
+        self.overrides
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(std::cmp::max(self.console, self.file), std::cmp::max)
     }
+}
 
-    const fn file_log_level(&self) -> log::LevelFilter {
-        self.file_level.level_filter()
+/// Applies the appender threshold only to the targets without an overridden level,
+/// the overridden targets get the level of the most specific override, like their loggers.
+#[derive(Debug)]
+struct TargetThresholdFilter {
+    level: LevelFilter,
+    overrides: Vec<(String, LevelFilter)>,
+}
+
+impl TargetThresholdFilter {
+    fn new(level: LevelFilter, levels: &LogLevels) -> Self {
+        Self {
+            level,
+            overrides: levels.overrides.clone(),
+        }
     }
 
-    fn file_appender(&self) -> anyhow::Result<RollingFileAppender> {
-        const TRIGGER_FILE_SIZE: u64 = 2 * 1024 * 1024; // 2Mb
-        const LOG_FILE_COUNT: u32 = 3;
+    fn target_level(&self, target: &str) -> LevelFilter {
+        self.overrides
+            .iter()
+            .filter(|(overridden, _)| {
+                target
+                    .strip_prefix(overridden.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(overridden, _)| overridden.len())
+            .map_or(self.level, |(_, level)| *level)
+    }
+}
 
-        let file_path = std::path::Path::new(self.file_path()?);
+impl Filter for TargetThresholdFilter {
+    fn filter(&self, record: &Record) -> Response {
+        if record.level() <= self.target_level(record.target()) {
+            Response::Neutral
+        } else {
+            Response::Reject
+        }
+    }
+}
 
-        let file_name = file_path
-            .file_stem()
-            .context("File expected")?
-            .to_str()
-            .context("Can't convert OS string")?;
-        let archive_pattern = file_path.with_file_name(format!("{file_name}{{}}.log"));
+thread_local! {
+    static IN_LOG_CALLBACK: Cell<bool> = const { Cell::new(false) };
+}
 
-        let trigger = SizeTrigger::new(TRIGGER_FILE_SIZE);
-        let roller = FixedWindowRoller::builder().build(
-            archive_pattern.to_str().context("file_path contains invalid unicode")?,
-            LOG_FILE_COUNT,
-        )?;
-        let policy = CompoundPolicy::new(Box::new(trigger), Box::new(roller));
+/// Passes the records to the host application, e.g. to have them in the same log as the application's own records.
+struct CallbackAppender {
+    callback: LogCallback,
+}
 
-        RollingFileAppender::builder()
-            .encoder(Box::new(PatternEncoder::new(
-                "[{d(%Y%m%d %H:%M:%S%.6f)} {h({l:5})} {M}:{L}] {m}{n}",
-            )))
-            .build(file_path, Box::new(policy))
-            .context("Failed to create file appender")
+impl std::fmt::Debug for CallbackAppender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallbackAppender").finish_non_exhaustive()
     }
+}
 
-    fn console_appender() -> ConsoleAppender {
-        ConsoleAppender::builder()
-            .encoder(Box::new(PatternEncoder::new(
-                "[{d(%Y%m%d %H:%M:%S%.3f)} {h({l:5})} {M}:{L}] {m}{n}",
-            )))
-            .target(Target::Stderr)
-            .build()
+impl Append for CallbackAppender {
+    fn append(&self, record: &Record) -> anyhow::Result<()> {
+        // The callback may log itself (e.g. by calling into the native code), don't let it recurse
+        if IN_LOG_CALLBACK.replace(true) {
+            return Ok(());
+        }
+        let message = record.args().to_string();
+        let log_record = LogRecord {
+            level: record.level().into(),
+            target: BorrowedUtf8::new(record.target()),
+            module_path: record.module_path().map_or_else(BorrowedUtf8::null, BorrowedUtf8::new),
+            line: record.line().unwrap_or(0),
+            message: BorrowedUtf8::new(&message),
+        };
+        (self.callback)(&log_record);
+        IN_LOG_CALLBACK.set(false);
+        Ok(())
     }
 
-    pub fn init_logger(&self) {
-        unsafe {
-            // enable backtraces for anyhow errors
-            std::env::set_var("RUST_LIB_BACKTRACE", "1");
-        }
-        let console_level = self.console_log_level();
-        let file_level = self.file_log_level();
+    fn flush(&self) {}
+}
 
-        let mut appenders = vec![];
+struct LoggerState {
+    handle: Option<Handle>,
+    file_path: Option<String>,
+    log_callback: Option<LogCallback>,
+    levels: Option<LogLevels>,
+}
 
-        let console_appender = Self::console_appender();
-        appenders.push(
-            Appender::builder()
-                .filter(Box::new(ThresholdFilter::new(console_level)))
-                .build("stderr", Box::new(console_appender)),
-        );
+/// Kept to allow changing the levels at runtime, see `set_log_levels`.
+static LOGGER_STATE: Mutex<LoggerState> = Mutex::new(LoggerState {
+    handle: None,
+    file_path: None,
+    log_callback: None,
+    levels: None,
+});
+
+fn file_appender(file_path: &str) -> anyhow::Result<RollingFileAppender> {
+    const TRIGGER_FILE_SIZE: u64 = 2 * 1024 * 1024; // 2Mb
+    const LOG_FILE_COUNT: u32 = 3;
+
+    let file_path = std::path::Path::new(file_path);
+
+    let file_name = file_path
+        .file_stem()
+        .context("File expected")?
+        .to_str()
+        .context("Can't convert OS string")?;
+    let archive_pattern = file_path.with_file_name(format!("{file_name}{{}}.log"));
+
+    let trigger = SizeTrigger::new(TRIGGER_FILE_SIZE);
+    let roller = FixedWindowRoller::builder().build(
+        archive_pattern.to_str().context("file_path contains invalid unicode")?,
+        LOG_FILE_COUNT,
+    )?;
+    let policy = CompoundPolicy::new(Box::new(trigger), Box::new(roller));
+
+    RollingFileAppender::builder()
+        .encoder(Box::new(PatternEncoder::new(
+            "[{d(%Y%m%d %H:%M:%S%.6f)} {h({l:5})} {M}:{L}] {m}{n}",
+        )))
+        .build(file_path, Box::new(policy))
+        .context("Failed to create file appender")
+}
+
+fn console_appender() -> ConsoleAppender {
+    ConsoleAppender::builder()
+        .encoder(Box::new(PatternEncoder::new(
+            "[{d(%Y%m%d %H:%M:%S%.3f)} {h({l:5})} {M}:{L}] {m}{n}",
+        )))
+        .target(Target::Stderr)
+        .build()
+}
+
+fn logger_config(file_path: Option<&str>, log_callback: Option<LogCallback>, levels: &LogLevels) -> anyhow::Result<Config> {
+    let mut appenders = vec![];
+
+    appenders.push(
+        Appender::builder()
+            .filter(Box::new(TargetThresholdFilter::new(levels.console, levels)))
+            .build("stderr", Box::new(console_appender())),
+    );
 
-        match self.file_appender() {
+    if let Some(file_path) = file_path {
+        match file_appender(file_path) {
             Ok(file_appender) => {
                 appenders.push(
                     Appender::builder()
-                        .filter(Box::new(ThresholdFilter::new(file_level)))
+                        .filter(Box::new(TargetThresholdFilter::new(levels.file, levels)))
                         .build("logfile", Box::new(file_appender)),
                 );
             }
             Err(err) => {
-                append_exception_msg(format!("File appender creatrion failed: {err}"));
+                append_exception_msg(format!("File appender creation failed: {err}"));
             }
         }
+    }
 
-        let appender_names: Vec<_> = appenders.iter().map(|a| a.name().to_string()).collect();
-        let config = Config::builder().appenders(appenders).build(
+    if let Some(callback) = log_callback {
+        appenders.push(
+            Appender::builder()
+                .filter(Box::new(TargetThresholdFilter::new(levels.file, levels)))
+                .build("callback", Box::new(CallbackAppender { callback })),
+        );
+    }
+
+    let appender_names: Vec<_> = appenders.iter().map(|a| a.name().to_string()).collect();
+    let loggers = levels
+        .overrides
+        .iter()
+        .map(|(target, level)| Logger::builder().build(target, *level));
+    Config::builder()
+        .appenders(appenders)
+        .loggers(loggers)
+        .build(
             Root::builder()
                 .appenders(appender_names)
-                .build(std::cmp::max(console_level, file_level)),
-        );
-        match config {
-            Ok(config) => {
-                match log4rs::init_config(config) {
-                    Ok(_handle) => {
-                        // todo store handler and allow to change logger severity
-                    }
-                    Err(err) => {
-                        append_exception_msg(format!("Failed to init logger: {err}"));
-                    }
-                }
+                .build(std::cmp::max(levels.console, levels.file)),
+        )
+        .context("Failed to create logger config")
+}
+
+/// Changes the levels of the initialized logger, or stores them to be used by `LoggerConfiguration::init_logger`.
+fn set_log_levels(levels: LogLevels) -> anyhow::Result<()> {
+    let mut state = LOGGER_STATE.lock().map_err(|e| anyhow!("{e}"))?;
+    if let Some(handle) = &state.handle {
+        let config = logger_config(state.file_path.as_deref(), state.log_callback, &levels)?;
+        handle.set_config(config);
+    }
+    let max_level = levels.max_level();
+    state.levels = Some(levels);
+    drop(state);
+    debug!("Log levels changed, max level: {max_level}");
+    Ok(())
+}
+
+impl LoggerLevels<'_> {
+    fn log_levels(&self) -> anyhow::Result<LogLevels> {
+        let overrides = self
+            .overrides
+            .as_optional_slice()
+            .unwrap_or_default()
+            .iter()
+            .map(|o| Ok((o.target.as_str()?.to_owned(), o.level.level_filter())))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(LogLevels {
+            console: self.console_level.level_filter(),
+            file: self.file_level.level_filter(),
+            overrides,
+        })
+    }
+
+    pub fn set_levels(&self) -> anyhow::Result<()> {
+        set_log_levels(self.log_levels()?)
+    }
+}
+
+impl LoggerConfiguration<'_> {
+    fn file_path(&self) -> anyhow::Result<&str> {
+        self.file_path.as_str()
+    }
+
+    const fn console_log_level(&self) -> LevelFilter {
+        self.console_level.level_filter()
+    }
+
+    const fn file_log_level(&self) -> LevelFilter {
+        self.file_level.level_filter()
+    }
+
+    pub fn init_logger(&self) {
+        unsafe {
+            // enable backtraces for anyhow errors
+            std::env::set_var("RUST_LIB_BACKTRACE", "1");
+        }
+        let Ok(mut state_guard) = LOGGER_STATE.lock() else {
+            append_exception_msg("Failed to init logger: logger state is poisoned".to_owned());
+            return;
+        };
+        let state = &mut *state_guard;
+
+        state.file_path = match self.file_path() {
+            Ok(file_path) => Some(file_path.to_owned()),
+            Err(err) => {
+                append_exception_msg(format!("File appender creation failed: {err}"));
+                None
             }
+        };
+        state.log_callback = self.log_callback;
+        // The levels set with `logger_set_levels` before the initialization take precedence
+        let levels = state.levels.get_or_insert_with(|| LogLevels {
+            console: self.console_log_level(),
+            file: self.file_log_level(),
+            overrides: Vec::new(),
+        });
+
+        match logger_config(state.file_path.as_deref(), state.log_callback, levels) {
+            Ok(config) => match log4rs::init_config(config) {
+                Ok(handle) => {
+                    state.handle = Some(handle);
+                }
+                Err(err) => {
+                    append_exception_msg(format!("Failed to init logger: {err}"));
+                }
+            },
             Err(err) => {
-                append_exception_msg(format!("Failed to create logger config: {err}"));
+                append_exception_msg(format!("{err:#}"));
             }
         }
     }
@@ -310,18 +584,27 @@ impl PanicDefault for RustAllocatedStrPtr {
 // some mutable data types invariants might be violated.
 // E.g. thread withdraw an amount form one account and panicked before entering it to another account.
 pub fn ffi_boundary<R: PanicDefault, F: FnOnce() -> anyhow::Result<R>>(name: &str, f: F) -> R {
+    let _span = TraceSpan::new("ffi", name);
     match std::panic::catch_unwind(AssertUnwindSafe(f)) {
         Ok(Ok(result)) => result,
         Ok(Err(err)) => {
             let err = err.context(format!("{name:?} returned error"));
             error!("{err:?}");
-            append_exception_msg(format!("{err:#}"));
+            append_error(name, &err);
             PanicDefault::default()
         }
         Err(payload) => {
             let payload_msg = panic_payload_msg(&payload);
             let message = format!("{name:?} panic with payload: {payload_msg}");
-            append_exception_msg(message); // message will be also logged by panic handler
+            let backtrace = LAST_PANIC_BACKTRACE.take();
+            // message will be also logged by panic handler
+            append_exception(
+                NativeErrorKind::Panic,
+                Some(name),
+                &message,
+                std::slice::from_ref(&message),
+                backtrace.as_deref(),
+            );
             PanicDefault::default()
         }
     }
@@ -331,8 +614,202 @@ pub fn init_panic_handler() {
     std::panic::set_hook(Box::new(|panic_info| {
         let thread = std::thread::current();
         let thread_name = thread.name().unwrap_or("<unnamed>");
-        let backtrace = std::backtrace::Backtrace::force_capture();
+        let backtrace = std::backtrace::Backtrace::force_capture().to_string();
         error!("thread = {thread_name}, {panic_info}, Unhandled panic\n{backtrace}");
+        // Picked up by `ffi_boundary`, if the panic is caught there.
+        LAST_PANIC_BACKTRACE.set(Some(backtrace));
         log::logger().flush();
     }));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn native_error(message: &str) -> NativeError {
+        NativeError {
+            kind: NativeErrorKind::Other,
+            function_name: allocate_optional_lossy(None),
+            message: allocate_lossy(message),
+            context_chain: AutoDropArray::new(Box::new([])),
+            backtrace: allocate_optional_lossy(None),
+        }
+    }
+
+    fn log_levels(console: LevelFilter, file: LevelFilter, overrides: &[(&str, LevelFilter)]) -> LogLevels {
+        LogLevels {
+            console,
+            file,
+            overrides: overrides.iter().map(|(target, level)| ((*target).to_owned(), *level)).collect(),
+        }
+    }
+
+    fn filter_record(filter: &TargetThresholdFilter, level: log::Level, target: &str) -> Response {
+        filter.filter(&Record::builder().level(level).target(target).args(format_args!("message")).build())
+    }
+
+    #[test]
+    fn test_target_threshold_filter() {
+        let levels = log_levels(
+            LevelFilter::Info,
+            LevelFilter::Info,
+            &[
+                ("desktop_linux", LevelFilter::Warn),
+                ("desktop_linux::linux::data_transfer", LevelFilter::Trace),
+                ("desktop_linux::linux::data", LevelFilter::Off),
+            ],
+        );
+        let filter = TargetThresholdFilter::new(LevelFilter::Info, &levels);
+
+        // The longest matching prefix wins, regardless of the order of the overrides
+        assert_eq!(filter.target_level("desktop_linux::linux::data_transfer"), LevelFilter::Trace);
+        assert_eq!(
+            filter.target_level("desktop_linux::linux::data_transfer::stream"),
+            LevelFilter::Trace
+        );
+        assert_eq!(filter.target_level("desktop_linux::linux::data"), LevelFilter::Off);
+        assert_eq!(filter.target_level("desktop_linux::linux::window"), LevelFilter::Warn);
+        // Only whole path segments match
+        assert_eq!(filter.target_level("desktop_linux::linux::data_control"), LevelFilter::Warn);
+        assert_eq!(filter.target_level("desktop_linux_extra"), LevelFilter::Info);
+        // Targets without an override fall back to the appender level
+        assert_eq!(filter.target_level("desktop_common::logger"), LevelFilter::Info);
+
+        assert_eq!(
+            filter_record(&filter, log::Level::Trace, "desktop_linux::linux::data_transfer"),
+            Response::Neutral
+        );
+        assert_eq!(
+            filter_record(&filter, log::Level::Error, "desktop_linux::linux::data"),
+            Response::Reject
+        );
+        assert_eq!(
+            filter_record(&filter, log::Level::Info, "desktop_linux::linux::window"),
+            Response::Reject
+        );
+        assert_eq!(
+            filter_record(&filter, log::Level::Info, "desktop_common::logger"),
+            Response::Neutral
+        );
+        assert_eq!(
+            filter_record(&filter, log::Level::Debug, "desktop_common::logger"),
+            Response::Reject
+        );
+
+        let filter = TargetThresholdFilter::new(LevelFilter::Off, &log_levels(LevelFilter::Off, LevelFilter::Off, &[]));
+        assert_eq!(
+            filter_record(&filter, log::Level::Error, "desktop_common::logger"),
+            Response::Reject
+        );
+    }
+
+    #[test]
+    fn test_set_log_levels_before_and_after_init() {
+        fn state_levels() -> Option<LogLevels> {
+            LOGGER_STATE.lock().unwrap().levels.clone()
+        }
+
+        // The only test initializing the global logger, it can be done once per process
+        set_log_levels(log_levels(
+            LevelFilter::Warn,
+            LevelFilter::Debug,
+            &[("desktop_common::logger", LevelFilter::Trace)],
+        ))
+        .unwrap();
+        assert!(LOGGER_STATE.lock().unwrap().handle.is_none());
+
+        let file_path = std::env::temp_dir().join(format!("desktop-common-logger-test-{}.log", std::process::id()));
+        let file_path_c = std::ffi::CString::new(file_path.to_str().unwrap()).unwrap();
+        LoggerConfiguration {
+            file_path: crate::ffi_utils::BorrowedStrPtr::new(&file_path_c),
+            console_level: LogLevel::Error,
+            file_level: LogLevel::Error,
+            log_callback: None,
+        }
+        .init_logger();
+        assert!(LOGGER_STATE.lock().unwrap().handle.is_some());
+        // The levels set before the initialization take precedence over the configuration
+        let levels = state_levels().unwrap();
+        assert_eq!((levels.console, levels.file), (LevelFilter::Warn, LevelFilter::Debug));
+        assert_eq!(log::max_level(), LevelFilter::Trace);
+
+        set_log_levels(log_levels(LevelFilter::Error, LevelFilter::Info, &[])).unwrap();
+        let levels = state_levels().unwrap();
+        assert_eq!((levels.console, levels.file), (LevelFilter::Error, LevelFilter::Info));
+        assert!(levels.overrides.is_empty());
+        assert_eq!(log::max_level(), LevelFilter::Info);
+
+        let _ = std::fs::remove_file(file_path);
+    }
+
+    thread_local! {
+        static CALLBACK_RECORDS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    extern "C" fn recursive_log_callback(record: &LogRecord) {
+        CALLBACK_RECORDS.with_borrow_mut(|records| records.push(record.message.get("message").unwrap().to_owned()));
+        // Like a host application logging through the native code from its callback
+        let appender = CallbackAppender {
+            callback: recursive_log_callback,
+        };
+        appender
+            .append(&Record::builder().level(log::Level::Info).args(format_args!("nested")).build())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_callback_appender_is_not_reentrant() {
+        let appender = CallbackAppender {
+            callback: recursive_log_callback,
+        };
+        for message in ["first", "second"] {
+            appender
+                .append(&Record::builder().level(log::Level::Info).args(format_args!("{message}")).build())
+                .unwrap();
+            assert!(!IN_LOG_CALLBACK.get());
+        }
+        assert_eq!(CALLBACK_RECORDS.take(), ["first", "second"]);
+    }
+
+    #[test]
+    fn test_exceptions_array_pointers_are_stable() {
+        let mut last_exceptions = LastExceptions::new();
+        last_exceptions.append(native_error("first"));
+        let array = last_exceptions.exceptions_array();
+        assert_eq!((array.count, array.dropped_count), (1, 0));
+
+        last_exceptions.append(native_error("second"));
+        let next_array = last_exceptions.exceptions_array();
+        assert_eq!(next_array.count, 2);
+        assert_eq!(next_array.items, array.items);
+        assert_eq!(next_array.errors, array.errors);
+
+        last_exceptions.clear();
+        last_exceptions.append(native_error("after clear"));
+        let array_after_clear = last_exceptions.exceptions_array();
+        assert_eq!(array_after_clear.count, 1);
+        assert_eq!(array_after_clear.items, array.items);
+        assert_eq!(array_after_clear.errors, array.errors);
+    }
+
+    #[test]
+    fn test_exceptions_array_limit() {
+        let mut last_exceptions = LastExceptions::new();
+        last_exceptions.append(native_error("first"));
+        let array = last_exceptions.exceptions_array();
+        let first_dropped = (1..MAX_EXCEPTIONS_COUNT + 3)
+            .map(|i| last_exceptions.append(native_error(&i.to_string())))
+            .collect::<Vec<_>>();
+        assert_eq!(first_dropped.iter().position(|dropped| *dropped), Some(MAX_EXCEPTIONS_COUNT - 1));
+        assert_eq!(first_dropped.iter().filter(|dropped| **dropped).count(), 1);
+        let full_array = last_exceptions.exceptions_array();
+        assert_eq!(full_array.count, MAX_EXCEPTIONS_COUNT);
+        assert_eq!(full_array.dropped_count, 3);
+        assert_eq!(full_array.items, array.items);
+        assert_eq!(full_array.errors, array.errors);
+
+        last_exceptions.clear();
+        let array = last_exceptions.exceptions_array();
+        assert_eq!((array.count, array.dropped_count), (0, 0));
+    }
+}
@@ -1,17 +1,64 @@
 use log::{error, info};
 
 use crate::{
-    ffi_utils::{ArraySize, BorrowedStrPtr, RustAllocatedStrPtr},
-    logger::{append_exception_msg, clear_exception_msgs, exceptions_array, init_panic_handler, panic_payload_msg},
+    ffi_utils::{ArraySize, AutoDropArray, AutoDropStrPtr, BorrowedArray, BorrowedStrPtr, BorrowedUtf8, RustAllocatedStrPtr},
+    logger::{append_exception_msg, clear_exception_msgs, exceptions_array, init_panic_handler, panic_payload_msg, set_fail_fast},
 };
 
 #[repr(C)]
 pub struct ExceptionsArray {
+    /// The `message` of each of the `errors`.
     pub items: *const RustAllocatedStrPtr,
     pub count: ArraySize,
+    /// Has the same `count` as `items`.
+    pub errors: *const NativeError,
+    /// The errors that were not stored, because there were already too many of them.
+    pub dropped_count: ArraySize,
 }
 
+/// Can be attached to an [`anyhow::Error`], e.g. `.context(NativeErrorKind::NotFound)`,
+/// to override the kind that would be derived from its source errors.
 #[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeErrorKind {
+    Other,
+    InvalidArgument,
+    NotFound,
+    Unsupported,
+    PlatformError,
+    Panic,
+}
+
+impl std::fmt::Display for NativeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Other => "Other error",
+            Self::InvalidArgument => "Invalid argument",
+            Self::NotFound => "Not found",
+            Self::Unsupported => "Unsupported",
+            Self::PlatformError => "Platform error",
+            Self::Panic => "Panic",
+        })
+    }
+}
+
+impl std::error::Error for NativeErrorKind {}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct NativeError {
+    pub kind: NativeErrorKind,
+    /// The FFI function that returned the error, null if it was reported outside of one.
+    pub function_name: AutoDropStrPtr,
+    pub message: AutoDropStrPtr,
+    /// The messages of the error and of its causes, outermost first.
+    pub context_chain: AutoDropArray<AutoDropStrPtr>,
+    /// Null if no backtrace was captured, see `RUST_BACKTRACE`.
+    pub backtrace: AutoDropStrPtr,
+}
+
+#[repr(C)]
+#[derive(Debug)]
 pub enum LogLevel {
     Off,
     Error,
@@ -21,11 +68,45 @@ pub enum LogLevel {
     Trace,
 }
 
+#[repr(C)]
+#[derive(Debug)]
+pub struct LogRecord<'a> {
+    pub level: LogLevel,
+    pub target: BorrowedUtf8<'a>,
+    /// Can be null.
+    pub module_path: BorrowedUtf8<'a>,
+    /// `0` if unknown.
+    pub line: u32,
+    pub message: BorrowedUtf8<'a>,
+}
+
+/// Called from the thread that logged the record.
+/// The records logged from within the callback itself are not passed to it again.
+pub type LogCallback = extern "C" fn(&LogRecord);
+
 #[repr(C)]
 pub struct LoggerConfiguration<'a> {
     pub file_path: BorrowedStrPtr<'a>,
     pub console_level: LogLevel,
     pub file_level: LogLevel,
+
+    /// Optional. Receives the records that pass the `file_level`, in addition to the file.
+    pub log_callback: Option<LogCallback>,
+}
+
+/// Level for the log records of the `target` module, and its submodules, e.g. `desktop_linux::linux::data_transfer`.
+#[repr(C)]
+pub struct LogLevelOverride<'a> {
+    pub target: BorrowedStrPtr<'a>,
+    pub level: LogLevel,
+}
+
+#[repr(C)]
+pub struct LoggerLevels<'a> {
+    pub console_level: LogLevel,
+    pub file_level: LogLevel,
+    /// Applied to both the console and the file, can be null.
+    pub overrides: BorrowedArray<'a, LogLevelOverride<'a>>,
 }
 
 #[unsafe(no_mangle)]
@@ -37,6 +118,8 @@ pub extern "C" fn logger_check_exceptions_impl() -> ExceptionsArray {
         ExceptionsArray {
             items: std::ptr::null(),
             count: 0,
+            errors: std::ptr::null(),
+            dropped_count: 0,
         }
     })
 }
@@ -61,3 +144,25 @@ pub extern "C" fn logger_init_impl(logger_configuration: &LoggerConfiguration) {
     init_panic_handler();
     info!("Logger initialized");
 }
+
+/// Changes the log levels without restarting the application.
+/// When called before `logger_init_impl`, the levels replace the ones from `LoggerConfiguration`.
+#[unsafe(no_mangle)]
+pub extern "C" fn logger_set_levels_impl(logger_levels: &LoggerLevels) {
+    let result = std::panic::catch_unwind(|| logger_levels.set_levels());
+
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => append_exception_msg(format!("logger_set_levels failed: {err:#}")),
+        Err(payload) => {
+            let msg = panic_payload_msg(&payload);
+            append_exception_msg(format!("logger_set_levels panic with payload: {msg}"));
+        }
+    }
+}
+
+/// When enabled, the process is aborted on the first error that is reported, which is useful in tests.
+#[unsafe(no_mangle)]
+pub extern "C" fn logger_set_fail_fast_impl(enabled: bool) {
+    set_fail_fast(enabled);
+}
@@ -0,0 +1,530 @@
+//! MIME type negotiation and conversions for the clipboard and drag & drop data,
+//! so that applications only need to deal with one MIME type per kind of content.
+
+pub const UTF8_TEXT_MIME_TYPE: &str = "text/plain;charset=utf-8";
+pub const URI_LIST_MIME_TYPE: &str = "text/uri-list";
+pub const PNG_MIME_TYPE: &str = "image/png";
+
+/// Text MIME types that are offered for any text content, in the order of preference when receiving.
+///
+/// `UTF8_STRING`, `TEXT` and `STRING` are the legacy X11 targets, still used by some applications (e.g. through Xwayland).
+pub const TEXT_MIME_TYPE_ALIASES: [&str; 5] = [UTF8_TEXT_MIME_TYPE, "UTF8_STRING", "text/plain", "TEXT", "STRING"];
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataTransferContentKind {
+    /// The content is only available as `data`.
+    Other,
+
+    /// The content is available as `text`, converted to UTF-8.
+    Text,
+
+    /// The content is available as `files`, decoded from `text/uri-list`.
+    Files,
+
+    /// `data` is an image, in the format from `image_format` (e.g. `"png"`).
+    Image,
+}
+
+/// MIME type requested by the application, and the one that is actually received from the other client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegotiatedMimeType {
+    pub requested: String,
+    pub received: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Charset {
+    Utf8,
+    Utf16,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+/// Returns `None` if the MIME type is not a text type.
+/// Without the `charset` parameter, `text/plain` is treated as UTF-8, like most applications do.
+fn text_charset(mime_type: &str) -> Option<Charset> {
+    match mime_type {
+        "UTF8_STRING" | "TEXT" => return Some(Charset::Utf8),
+        "STRING" => return Some(Charset::Latin1),
+        _ => {}
+    }
+    let mut parts = mime_type.split(';');
+    if !parts.next()?.trim().eq_ignore_ascii_case("text/plain") {
+        return None;
+    }
+    let charset = parts
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_ascii_lowercase());
+    Some(match charset.as_deref() {
+        Some("utf-16") => Charset::Utf16,
+        Some("utf-16le") => Charset::Utf16Le,
+        Some("utf-16be") => Charset::Utf16Be,
+        Some("iso-8859-1" | "latin1" | "latin-1" | "us-ascii" | "ascii") => Charset::Latin1,
+        // Unknown charsets are decoded as UTF-8, replacing the invalid sequences
+        _ => Charset::Utf8,
+    })
+}
+
+#[must_use]
+pub fn is_text_mime_type(mime_type: &str) -> bool {
+    text_charset(mime_type).is_some()
+}
+
+#[must_use]
+pub fn is_image_mime_type(mime_type: &str) -> bool {
+    mime_type.starts_with("image/")
+}
+
+/// Returns e.g. `"png"` for `image/png`, or `None` if the MIME type is not an image type.
+#[must_use]
+pub fn image_format(mime_type: &str) -> Option<&str> {
+    let format = mime_type.strip_prefix("image/")?;
+    let format = format.split(';').next().unwrap_or(format).trim();
+    Some(format.strip_prefix("x-").unwrap_or(format))
+}
+
+fn decode_utf16(data: &[u8], big_endian: bool) -> String {
+    let units = data.chunks_exact(2).map(|c| {
+        let bytes = [c[0], c[1]];
+        if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    });
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Decodes the text data according to the charset of the MIME type.
+/// The byte order mark, and the trailing null characters that some X11 applications send, are removed.
+#[must_use]
+pub fn decode_text(mime_type: &str, data: &[u8]) -> String {
+    let text = match text_charset(mime_type).unwrap_or(Charset::Utf8) {
+        Charset::Utf8 => String::from_utf8_lossy(data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data)).into_owned(),
+        Charset::Latin1 => data.iter().copied().map(char::from).collect(),
+        Charset::Utf16 => match data {
+            [0xFE, 0xFF, rest @ ..] => decode_utf16(rest, true),
+            [0xFF, 0xFE, rest @ ..] => decode_utf16(rest, false),
+            _ => decode_utf16(data, false),
+        },
+        Charset::Utf16Le => decode_utf16(data, false),
+        Charset::Utf16Be => decode_utf16(data, true),
+    };
+    match text.trim_end_matches('\0').len() {
+        len if len == text.len() => text,
+        len => text[..len].to_owned(),
+    }
+}
+
+/// Encodes the text according to the charset of the MIME type.
+/// Characters that can't be represented in Latin-1 are replaced with `?`.
+#[must_use]
+pub fn encode_text(mime_type: &str, text: &str) -> Vec<u8> {
+    match text_charset(mime_type).unwrap_or(Charset::Utf8) {
+        Charset::Utf8 => text.as_bytes().to_vec(),
+        Charset::Latin1 => text.chars().map(|c| u8::try_from(c).unwrap_or(b'?')).collect(),
+        Charset::Utf16 => [0xFF, 0xFE]
+            .into_iter()
+            .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+            .collect(),
+        Charset::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        Charset::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+    }
+}
+
+/// Converts the text data from one text MIME type to another. Returns `data` as is if both use the same charset.
+#[must_use]
+pub fn convert_text(from_mime_type: &str, to_mime_type: &str, data: Vec<u8>) -> Vec<u8> {
+    if text_charset(from_mime_type) == text_charset(to_mime_type) {
+        data
+    } else {
+        encode_text(to_mime_type, &decode_text(from_mime_type, &data))
+    }
+}
+
+/// Adds the MIME types that the offered content can be converted to:
+/// * all the `TEXT_MIME_TYPE_ALIASES`, right after the first offered text type,
+/// * `image/png`, after the first image type for which `can_convert_to_png` returns `true`.
+#[must_use]
+pub fn add_mime_type_aliases<'a>(mime_types: &[&'a str], can_convert_to_png: impl Fn(&str) -> bool) -> Vec<&'a str> {
+    let mut result = Vec::with_capacity(mime_types.len() + TEXT_MIME_TYPE_ALIASES.len() + 1);
+    let mut has_text = false;
+    let has_png = mime_types.contains(&PNG_MIME_TYPE);
+    let mut png_added = false;
+    for &mime_type in mime_types {
+        if !result.contains(&mime_type) {
+            result.push(mime_type);
+        }
+        if !has_text && is_text_mime_type(mime_type) {
+            has_text = true;
+            for alias in TEXT_MIME_TYPE_ALIASES {
+                if !mime_types.contains(&alias) && !result.contains(&alias) {
+                    result.push(alias);
+                }
+            }
+        }
+        if !has_png && !png_added && is_image_mime_type(mime_type) && can_convert_to_png(mime_type) {
+            png_added = true;
+            result.push(PNG_MIME_TYPE);
+        }
+    }
+    result
+}
+
+fn text_mime_type_rank(mime_type: &str) -> usize {
+    TEXT_MIME_TYPE_ALIASES
+        .iter()
+        .position(|&alias| alias == mime_type)
+        .unwrap_or(TEXT_MIME_TYPE_ALIASES.len())
+}
+
+/// Returns the MIME type from `available` that the data for `mime_type` can be converted from:
+/// * `mime_type` itself, if available,
+/// * the best available text type, for a text `mime_type`,
+/// * the first available image type for which `can_convert_to_png` returns `true`, for `image/png`.
+#[must_use]
+pub fn source_mime_type<'a, S: AsRef<str>>(
+    mime_type: &str,
+    available: &'a [S],
+    can_convert_to_png: impl Fn(&str) -> bool,
+) -> Option<&'a str> {
+    let mut available = available.iter().map(AsRef::as_ref);
+    if let Some(exact) = available.clone().find(|&m| m == mime_type) {
+        Some(exact)
+    } else if is_text_mime_type(mime_type) {
+        available.filter(|m| is_text_mime_type(m)).min_by_key(|m| text_mime_type_rank(m))
+    } else if mime_type == PNG_MIME_TYPE {
+        available.find(|&m| is_image_mime_type(m) && can_convert_to_png(m))
+    } else {
+        None
+    }
+}
+
+/// Returns the first of the `supported` MIME types that can be received from the `available` ones,
+/// as `(supported_mime_type, available_mime_type)`.
+#[must_use]
+pub fn negotiate_mime_type<'a, 'b, S: AsRef<str>>(
+    supported: impl IntoIterator<Item = &'a str>,
+    available: &'b [S],
+    can_convert_to_png: impl Fn(&str) -> bool,
+) -> Option<(&'a str, &'b str)> {
+    supported
+        .into_iter()
+        .find_map(|mime_type| Some((mime_type, source_mime_type(mime_type, available, &can_convert_to_png)?)))
+}
+
+/// Returns the percent-decoded path of a local `file://` URI, or `None` for other URIs.
+/// The path is not necessarily valid UTF-8.
+#[must_use]
+pub fn decode_file_uri(uri: &str) -> Option<Vec<u8>> {
+    let rest = uri.strip_prefix("file://")?;
+    let (host, path) = rest.split_at(rest.find('/')?);
+    if !host.is_empty() && host != "localhost" {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    Some(bytes)
+}
+
+/// Decodes the local file paths from the `text/uri-list` data.
+/// Comments, non-`file://` URIs, remote hosts, and paths that are not valid UTF-8 are skipped.
+#[must_use]
+pub fn parse_uri_list(data: &str) -> Vec<String> {
+    data.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|uri| String::from_utf8(decode_file_uri(uri)?).ok())
+        .collect()
+}
+
+/// Received data in the MIME type requested by the application, with its typed form.
+#[derive(Debug)]
+pub struct ReceivedContent {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+    pub kind: DataTransferContentKind,
+    pub files: Vec<String>,
+}
+
+impl ReceivedContent {
+    /// Converts the data received as `received_mime_type` to `requested_mime_type`.
+    /// Text is converted to the charset of the requested MIME type, and `text/uri-list` is decoded to the file paths.
+    /// Image conversions are done by the caller, before creating the content.
+    #[must_use]
+    pub fn new(requested_mime_type: &str, received_mime_type: &str, data: Vec<u8>) -> Self {
+        if is_text_mime_type(requested_mime_type) && is_text_mime_type(received_mime_type) {
+            let text = decode_text(received_mime_type, &data);
+            // The typed form is only available when UTF-8 was requested, otherwise `data` is in the requested charset
+            let (kind, data) = if text_charset(requested_mime_type) == Some(Charset::Utf8) {
+                (DataTransferContentKind::Text, text.into_bytes())
+            } else {
+                (DataTransferContentKind::Other, encode_text(requested_mime_type, &text))
+            };
+            return Self {
+                mime_type: requested_mime_type.to_owned(),
+                data,
+                kind,
+                files: Vec::new(),
+            };
+        }
+        let kind = if received_mime_type == URI_LIST_MIME_TYPE {
+            DataTransferContentKind::Files
+        } else if is_image_mime_type(received_mime_type) {
+            DataTransferContentKind::Image
+        } else {
+            DataTransferContentKind::Other
+        };
+        let files = if kind == DataTransferContentKind::Files {
+            parse_uri_list(&String::from_utf8_lossy(&data))
+        } else {
+            Vec::new()
+        };
+        Self {
+            mime_type: received_mime_type.to_owned(),
+            data,
+            kind,
+            files,
+        }
+    }
+
+    /// The UTF-8 text, for `DataTransferContentKind::Text`.
+    #[must_use]
+    pub fn text(&self) -> Option<&str> {
+        match self.kind {
+            DataTransferContentKind::Text => std::str::from_utf8(&self.data).ok(),
+            _ => None,
+        }
+    }
+
+    /// The image format, for `DataTransferContentKind::Image`.
+    #[must_use]
+    pub fn image_format(&self) -> Option<&str> {
+        match self.kind {
+            DataTransferContentKind::Image => image_format(&self.mime_type),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_charset() {
+        let cases = [
+            ("text/plain", Some(Charset::Utf8)),
+            ("text/plain;charset=utf-8", Some(Charset::Utf8)),
+            ("TEXT/PLAIN; CHARSET=\"UTF-8\"", Some(Charset::Utf8)),
+            ("text/plain;charset=unknown", Some(Charset::Utf8)),
+            ("text/plain;format=flowed;charset=utf-16", Some(Charset::Utf16)),
+            ("text/plain;charset=UTF-16LE", Some(Charset::Utf16Le)),
+            ("text/plain; charset=utf-16be", Some(Charset::Utf16Be)),
+            ("text/plain;charset=iso-8859-1", Some(Charset::Latin1)),
+            ("text/plain;charset=US-ASCII", Some(Charset::Latin1)),
+            ("UTF8_STRING", Some(Charset::Utf8)),
+            ("TEXT", Some(Charset::Utf8)),
+            ("STRING", Some(Charset::Latin1)),
+            ("text/html", None),
+            ("text/uri-list", None),
+            ("image/png", None),
+            ("", None),
+        ];
+        for (mime_type, expected) in cases {
+            assert_eq!(text_charset(mime_type), expected, "{mime_type:?}");
+        }
+    }
+
+    #[test]
+    fn test_image_format() {
+        let cases = [
+            ("image/png", Some("png")),
+            ("image/x-bmp", Some("bmp")),
+            ("image/jpeg; q=1", Some("jpeg")),
+            ("text/plain", None),
+        ];
+        for (mime_type, expected) in cases {
+            assert_eq!(image_format(mime_type), expected, "{mime_type:?}");
+        }
+    }
+
+    #[test]
+    fn test_decode_text() {
+        let cases: [(&str, &[u8], &str); 9] = [
+            ("text/plain;charset=utf-8", b"\xEF\xBB\xBFh\xC3\xA9", "hé"),
+            ("text/plain", b"bad \xFF", "bad \u{FFFD}"),
+            ("UTF8_STRING", b"text\0\0", "text"),
+            ("STRING", b"caf\xE9", "café"),
+            ("text/plain;charset=utf-16", b"\xFE\xFF\0h\0i", "hi"),
+            ("text/plain;charset=utf-16", b"\xFF\xFEh\0i\0", "hi"),
+            ("text/plain;charset=utf-16", b"h\0i\0", "hi"),
+            ("text/plain;charset=utf-16le", b"h\0i\0\0\0", "hi"),
+            ("text/plain;charset=utf-16be", b"\0h\0i", "hi"),
+        ];
+        for (mime_type, data, expected) in cases {
+            assert_eq!(decode_text(mime_type, data), expected, "{mime_type:?} {data:?}");
+        }
+    }
+
+    #[test]
+    fn test_encode_text() {
+        let cases: [(&str, &[u8]); 5] = [
+            ("text/plain;charset=utf-8", b"h\xC3\xA9\xE2\x82\xAC"),
+            ("STRING", b"h\xE9?"),
+            ("text/plain;charset=utf-16", b"\xFF\xFEh\0\xE9\0\xAC\x20"),
+            ("text/plain;charset=utf-16le", b"h\0\xE9\0\xAC\x20"),
+            ("text/plain;charset=utf-16be", b"\0h\0\xE9\x20\xAC"),
+        ];
+        for (mime_type, expected) in cases {
+            assert_eq!(encode_text(mime_type, "hé€"), expected, "{mime_type:?}");
+        }
+    }
+
+    #[test]
+    fn test_convert_text() {
+        // Same charset, the data is kept as is
+        assert_eq!(convert_text("text/plain", "UTF8_STRING", b"a\0".to_vec()), b"a\0");
+        assert_eq!(convert_text("STRING", "text/plain;charset=utf-8", b"\xE9".to_vec()), "é".as_bytes());
+        assert_eq!(
+            convert_text(
+                "text/plain;charset=utf-16be",
+                "text/plain;charset=iso-8859-1",
+                b"\0h\0\xE9".to_vec()
+            ),
+            b"h\xE9"
+        );
+    }
+
+    #[test]
+    fn test_add_mime_type_aliases() {
+        let can_convert_bmp = |mime_type: &str| mime_type == "image/bmp";
+        let cases: [(&[&str], &[&str]); 6] = [
+            (&[], &[]),
+            (&["application/json"], &["application/json"]),
+            (
+                &["text/html", "text/plain", "text/uri-list"],
+                &[
+                    "text/html",
+                    "text/plain",
+                    UTF8_TEXT_MIME_TYPE,
+                    "UTF8_STRING",
+                    "TEXT",
+                    "STRING",
+                    "text/uri-list",
+                ],
+            ),
+            (
+                &["STRING", UTF8_TEXT_MIME_TYPE, "STRING"],
+                &["STRING", "UTF8_STRING", "text/plain", "TEXT", UTF8_TEXT_MIME_TYPE],
+            ),
+            (
+                &["image/jpeg", "image/bmp", "image/x-bmp"],
+                &["image/jpeg", "image/bmp", PNG_MIME_TYPE, "image/x-bmp"],
+            ),
+            (&["image/bmp", PNG_MIME_TYPE], &["image/bmp", PNG_MIME_TYPE]),
+        ];
+        for (mime_types, expected) in cases {
+            assert_eq!(add_mime_type_aliases(mime_types, can_convert_bmp), expected, "{mime_types:?}");
+        }
+    }
+
+    #[test]
+    fn test_negotiate_mime_type() {
+        type Case<'a> = (&'a [&'a str], &'a [&'a str], Option<(&'a str, &'a str)>);
+        let can_convert_bmp = |mime_type: &str| mime_type == "image/bmp";
+        let cases: [Case; 9] = [
+            (&[UTF8_TEXT_MIME_TYPE], &[], None),
+            (&[UTF8_TEXT_MIME_TYPE], &["text/html"], None),
+            (
+                &[UTF8_TEXT_MIME_TYPE],
+                &["STRING", "text/plain"],
+                Some((UTF8_TEXT_MIME_TYPE, "text/plain")),
+            ),
+            (&[UTF8_TEXT_MIME_TYPE], &["TEXT", "STRING"], Some((UTF8_TEXT_MIME_TYPE, "TEXT"))),
+            (
+                &[UTF8_TEXT_MIME_TYPE],
+                &["text/plain;charset=utf-16", "STRING"],
+                Some((UTF8_TEXT_MIME_TYPE, "STRING")),
+            ),
+            (
+                &["STRING"],
+                &["text/plain;charset=utf-16"],
+                Some(("STRING", "text/plain;charset=utf-16")),
+            ),
+            (
+                &[URI_LIST_MIME_TYPE, UTF8_TEXT_MIME_TYPE],
+                &["UTF8_STRING", URI_LIST_MIME_TYPE],
+                Some((URI_LIST_MIME_TYPE, URI_LIST_MIME_TYPE)),
+            ),
+            (&[PNG_MIME_TYPE], &["image/jpeg", "image/bmp"], Some((PNG_MIME_TYPE, "image/bmp"))),
+            (&[PNG_MIME_TYPE, "image/jpeg"], &["image/jpeg"], Some(("image/jpeg", "image/jpeg"))),
+        ];
+        for (supported, available, expected) in cases {
+            assert_eq!(
+                negotiate_mime_type(supported.iter().copied(), available, can_convert_bmp),
+                expected,
+                "{supported:?} {available:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_uri_list() {
+        let cases: [(&str, &[&str]); 8] = [
+            ("", &[]),
+            ("file:///tmp/a.txt", &["/tmp/a.txt"]),
+            ("file:///tmp/a.txt\r\nfile:///tmp/b.txt\r\n", &["/tmp/a.txt", "/tmp/b.txt"]),
+            ("# comment\nfile:///tmp/a.txt\n\n  \n", &["/tmp/a.txt"]),
+            ("file:///tmp/with%20space%2C%C3%A9.txt", &["/tmp/with space,é.txt"]),
+            ("file://localhost/tmp/a\nfile://remote/tmp/b\nhttps://example.com/c", &["/tmp/a"]),
+            ("file:///tmp/%FF\nfile:///tmp/%2\nfile:///tmp/%zz\nfile:///tmp/ok", &["/tmp/ok"]),
+            ("file://no-path", &[]),
+        ];
+        for (data, expected) in cases {
+            assert_eq!(parse_uri_list(data), expected, "{data:?}");
+        }
+    }
+
+    #[test]
+    fn test_decode_file_uri() {
+        assert_eq!(decode_file_uri("file:///tmp/%FF%20a").as_deref(), Some(b"/tmp/\xFF a".as_slice()));
+        assert_eq!(decode_file_uri("file://localhost/").as_deref(), Some(b"/".as_slice()));
+        assert_eq!(decode_file_uri("file://remote/tmp"), None);
+        assert_eq!(decode_file_uri("/tmp/a"), None);
+    }
+
+    #[test]
+    fn test_received_content() {
+        let content = ReceivedContent::new(UTF8_TEXT_MIME_TYPE, "STRING", b"caf\xE9".to_vec());
+        assert_eq!(content.mime_type, UTF8_TEXT_MIME_TYPE);
+        assert_eq!(content.kind, DataTransferContentKind::Text);
+        assert_eq!(content.text(), Some("café"));
+
+        let content = ReceivedContent::new("text/plain;charset=utf-16le", "text/plain", b"hi".to_vec());
+        assert_eq!(content.kind, DataTransferContentKind::Other);
+        assert_eq!(content.data, b"h\0i\0");
+        assert_eq!(content.text(), None);
+
+        let content = ReceivedContent::new(URI_LIST_MIME_TYPE, URI_LIST_MIME_TYPE, b"file:///a%20b\r\n".to_vec());
+        assert_eq!(content.kind, DataTransferContentKind::Files);
+        assert_eq!(content.files, ["/a b"]);
+
+        let content = ReceivedContent::new(PNG_MIME_TYPE, PNG_MIME_TYPE, vec![1, 2]);
+        assert_eq!(content.kind, DataTransferContentKind::Image);
+        assert_eq!(content.image_format(), Some("png"));
+    }
+}
@@ -0,0 +1,365 @@
+//! Performance tracing of the event dispatch, FFI calls, rendering and data transfers.
+//!
+//! The trace is exported in the Chrome trace event format, which can be opened in <https://ui.perfetto.dev> or `chrome://tracing`.
+//! The spans are only collected with the `perf-trace` feature, otherwise they compile to nothing, and the tracing can't be started.
+
+use std::path::Path;
+
+#[cfg(not(feature = "perf-trace"))]
+use anyhow::Context;
+
+#[cfg(not(feature = "perf-trace"))]
+use crate::logger_api::NativeErrorKind;
+
+#[cfg(feature = "perf-trace")]
+mod imp {
+    use std::{
+        collections::BTreeMap,
+        fs::File,
+        io::{BufWriter, Write},
+        path::Path,
+        sync::{
+            LazyLock, Mutex,
+            atomic::{AtomicBool, AtomicU64, Ordering},
+        },
+        time::Instant,
+    };
+
+    use anyhow::Context;
+    use serde_json::{Value, json};
+
+    const MAX_TRACE_EVENTS: usize = 1_000_000;
+
+    // Timestamps are relative to it, so that the spans that were started before the tracing still have a valid start.
+    static EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+    static TRACE: Mutex<Option<TraceBuffer>> = Mutex::new(None);
+
+    // Avoids locking `TRACE` in every span when the tracing is not active.
+    static TRACE_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+    static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+    static NEXT_ASYNC_SPAN_ID: AtomicU64 = AtomicU64::new(1);
+
+    thread_local! {
+        static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+    }
+
+    struct TraceEvent {
+        name: String,
+        category: &'static str,
+        phase: &'static str,
+        start: Instant,
+        duration_us: Option<f64>,
+        thread_id: u64,
+        async_id: Option<u64>,
+    }
+
+    impl TraceEvent {
+        fn to_json(&self) -> Value {
+            let mut event = json!({
+                "name": self.name,
+                "cat": self.category,
+                "ph": self.phase,
+                "ts": to_us(self.start),
+                "pid": std::process::id(),
+                "tid": self.thread_id,
+            });
+            if let Some(duration_us) = self.duration_us {
+                event["dur"] = json!(duration_us);
+            }
+            if let Some(async_id) = self.async_id {
+                event["id"] = json!(async_id);
+            }
+            event
+        }
+    }
+
+    #[derive(Default)]
+    struct TraceBuffer {
+        events: Vec<TraceEvent>,
+        thread_names: BTreeMap<u64, String>,
+        dropped_count: usize,
+    }
+
+    fn to_us(instant: Instant) -> f64 {
+        instant.saturating_duration_since(*EPOCH).as_secs_f64() * 1_000_000.0
+    }
+
+    fn current_thread_id() -> u64 {
+        THREAD_ID.with(|id| *id)
+    }
+
+    fn is_active() -> bool {
+        TRACE_ACTIVE.load(Ordering::Relaxed)
+    }
+
+    fn append(event: TraceEvent) {
+        let mut trace = TRACE.lock().unwrap();
+        let Some(buffer) = trace.as_mut() else {
+            return;
+        };
+        if buffer.events.len() >= MAX_TRACE_EVENTS {
+            buffer.dropped_count += 1;
+            return;
+        }
+        buffer.thread_names.entry(event.thread_id).or_insert_with(|| {
+            let thread = std::thread::current();
+            thread.name().map_or_else(|| format!("{:?}", thread.id()), ToOwned::to_owned)
+        });
+        buffer.events.push(event);
+        drop(trace);
+    }
+
+    pub fn start() {
+        LazyLock::force(&EPOCH);
+        *TRACE.lock().unwrap() = Some(TraceBuffer::default());
+        TRACE_ACTIVE.store(true, Ordering::Relaxed);
+    }
+
+    pub fn stop(path: &Path) -> anyhow::Result<usize> {
+        TRACE_ACTIVE.store(false, Ordering::Relaxed);
+        let buffer = TRACE.lock().unwrap().take().context("The performance trace is not started")?;
+        let pid = std::process::id();
+        let thread_names = buffer
+            .thread_names
+            .iter()
+            .map(|(thread_id, name)| json!({ "name": "thread_name", "ph": "M", "pid": pid, "tid": thread_id, "args": { "name": name } }));
+        let events = thread_names
+            .chain(buffer.events.iter().map(TraceEvent::to_json))
+            .collect::<Vec<_>>();
+        let trace = json!({
+            "traceEvents": events,
+            "displayTimeUnit": "ms",
+            "otherData": { "dropped_events": buffer.dropped_count },
+        });
+        let file = File::create(path).with_context(|| format!("Can't create the performance trace {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &trace)?;
+        writer.flush()?;
+        Ok(buffer.events.len())
+    }
+
+    pub struct ActiveSpan {
+        category: &'static str,
+        name: String,
+        start: Instant,
+    }
+
+    impl ActiveSpan {
+        pub fn new(category: &'static str, name: &str) -> Option<Self> {
+            is_active().then(|| Self {
+                category,
+                name: name.to_owned(),
+                start: Instant::now(),
+            })
+        }
+    }
+
+    impl Drop for ActiveSpan {
+        fn drop(&mut self) {
+            let end = Instant::now();
+            append(TraceEvent {
+                name: std::mem::take(&mut self.name),
+                category: self.category,
+                phase: "X",
+                start: self.start,
+                duration_us: Some(end.saturating_duration_since(self.start).as_secs_f64() * 1_000_000.0),
+                thread_id: current_thread_id(),
+                async_id: None,
+            });
+        }
+    }
+
+    pub struct ActiveAsyncSpan {
+        category: &'static str,
+        name: String,
+        id: u64,
+    }
+
+    impl ActiveAsyncSpan {
+        pub fn new(category: &'static str, name: &str) -> Option<Self> {
+            if !is_active() {
+                return None;
+            }
+            let span = Self {
+                category,
+                name: name.to_owned(),
+                id: NEXT_ASYNC_SPAN_ID.fetch_add(1, Ordering::Relaxed),
+            };
+            append(TraceEvent {
+                name: span.name.clone(),
+                category,
+                phase: "b",
+                start: Instant::now(),
+                duration_us: None,
+                thread_id: current_thread_id(),
+                async_id: Some(span.id),
+            });
+            Some(span)
+        }
+    }
+
+    impl Drop for ActiveAsyncSpan {
+        fn drop(&mut self) {
+            append(TraceEvent {
+                name: std::mem::take(&mut self.name),
+                category: self.category,
+                phase: "e",
+                start: Instant::now(),
+                duration_us: None,
+                thread_id: current_thread_id(),
+                async_id: Some(self.id),
+            });
+        }
+    }
+}
+
+/// Measures the time until it's dropped, on the current thread.
+#[must_use]
+pub struct TraceSpan {
+    #[cfg(feature = "perf-trace")]
+    _span: Option<imp::ActiveSpan>,
+}
+
+impl TraceSpan {
+    #[cfg(feature = "perf-trace")]
+    pub fn new(category: &'static str, name: &str) -> Self {
+        Self {
+            _span: imp::ActiveSpan::new(category, name),
+        }
+    }
+
+    #[cfg(not(feature = "perf-trace"))]
+    #[inline]
+    pub const fn new(_category: &'static str, _name: &str) -> Self {
+        Self {}
+    }
+}
+
+/// Measures the time until it's dropped, e.g. of an asynchronous read, which can end on a different thread.
+#[must_use]
+pub struct AsyncTraceSpan {
+    #[cfg(feature = "perf-trace")]
+    _span: Option<imp::ActiveAsyncSpan>,
+}
+
+impl AsyncTraceSpan {
+    #[cfg(feature = "perf-trace")]
+    pub fn new(category: &'static str, name: &str) -> Self {
+        Self {
+            _span: imp::ActiveAsyncSpan::new(category, name),
+        }
+    }
+
+    #[cfg(not(feature = "perf-trace"))]
+    #[inline]
+    pub const fn new(_category: &'static str, _name: &str) -> Self {
+        Self {}
+    }
+}
+
+/// Starts collecting the spans, discarding the ones collected so far, if any.
+pub fn start_performance_trace() -> anyhow::Result<()> {
+    #[cfg(feature = "perf-trace")]
+    {
+        imp::start();
+        log::info!("Started the performance trace");
+        Ok(())
+    }
+    #[cfg(not(feature = "perf-trace"))]
+    Err(anyhow::Error::new(NativeErrorKind::Unsupported)).context("The performance tracing requires the `perf-trace` feature")
+}
+
+/// Stops collecting the spans, and writes them to `path`.
+pub fn stop_performance_trace(path: &Path) -> anyhow::Result<()> {
+    #[cfg(feature = "perf-trace")]
+    {
+        let count = imp::stop(path)?;
+        log::info!("Wrote {count} performance trace events to {}", path.display());
+        Ok(())
+    }
+    #[cfg(not(feature = "perf-trace"))]
+    Err(anyhow::Error::new(NativeErrorKind::Unsupported)).with_context(|| {
+        format!(
+            "The performance trace can't be written to {}, it requires the `perf-trace` feature",
+            path.display()
+        )
+    })
+}
+
+#[cfg(all(test, feature = "perf-trace"))]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::time::Duration;
+
+    fn trace_events<'a>(trace: &'a Value, name: &str) -> Vec<&'a Value> {
+        trace["traceEvents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|event| event["name"] == name)
+            .collect()
+    }
+
+    #[test]
+    fn test_performance_trace() {
+        let path = std::env::temp_dir().join(format!("kdt-perf-trace-test-{}.json", std::process::id()));
+        assert!(stop_performance_trace(&path).is_err());
+
+        // Not collected before the tracing is started
+        drop(TraceSpan::new("test", "span"));
+        start_performance_trace().unwrap();
+        {
+            let _span = TraceSpan::new("test", "span");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let async_span = AsyncTraceSpan::new("test", "async span");
+        std::thread::Builder::new()
+            .name("trace test thread".to_owned())
+            .spawn(move || drop(async_span))
+            .unwrap()
+            .join()
+            .unwrap();
+        stop_performance_trace(&path).unwrap();
+        // Not collected after the tracing is stopped
+        drop(TraceSpan::new("test", "span"));
+
+        let trace: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(trace["otherData"]["dropped_events"], 0);
+
+        let spans = trace_events(&trace, "span");
+        assert_eq!(spans.len(), 1, "{trace}");
+        let span = spans[0];
+        assert_eq!(span["ph"], "X");
+        assert_eq!(span["cat"], "test");
+        assert_eq!(span["pid"], std::process::id());
+        assert!(span["ts"].as_f64().unwrap() >= 0.0, "{span}");
+        assert!(span["dur"].as_f64().unwrap() >= 5000.0, "{span}");
+        let tid = span["tid"].as_u64().unwrap();
+
+        let async_spans = trace_events(&trace, "async span");
+        let phases: Vec<_> = async_spans.iter().map(|event| event["ph"].as_str().unwrap()).collect();
+        assert_eq!(phases, ["b", "e"], "{trace}");
+        assert_eq!(async_spans[0]["id"], async_spans[1]["id"]);
+        assert!(async_spans[0].get("dur").is_none());
+        assert_eq!(async_spans[0]["tid"], tid);
+        let other_tid = async_spans[1]["tid"].as_u64().unwrap();
+        assert_ne!(other_tid, tid);
+        assert!(async_spans[0]["ts"].as_f64().unwrap() >= span["ts"].as_f64().unwrap() + 5000.0);
+        assert!(async_spans[1]["ts"].as_f64().unwrap() >= async_spans[0]["ts"].as_f64().unwrap());
+
+        let thread_names = trace_events(&trace, "thread_name");
+        let thread_name = |tid: u64| {
+            thread_names
+                .iter()
+                .find(|event| event["tid"] == tid)
+                .map(|event| event["args"]["name"].as_str().unwrap())
+        };
+        assert!(thread_names.iter().all(|event| event["ph"] == "M"));
+        assert_eq!(thread_name(tid), std::thread::current().name());
+        assert_eq!(thread_name(other_tid), Some("trace test thread"));
+    }
+}
@@ -1,17 +1,20 @@
-use desktop_common::ffi_utils::BorrowedUtf8;
+use desktop_common::ffi_utils::{BorrowedArray, BorrowedUtf8};
 use desktop_gtk::gtk::text_input_api::TextInputContext;
 use desktop_gtk::gtk::{
     application_api::{
         RenderingMode,
-        application_clipboard_paste,
-        application_clipboard_put,
-        application_primary_selection_paste,
-        application_primary_selection_put,
+        application_clipboard_paste_v2,
+        application_clipboard_put_v2,
+        application_primary_selection_paste_v2,
+        application_primary_selection_put_v2,
         application_set_prefer_dark_theme,
         application_stop_drag_and_drop,
         application_stop_event_loop,
         //
     },
+    application_menu_api::{
+        AppMenuItem, AppMenuItemId, AppMenuItemToggleState, AppMenuItemToggleType, AppMenuStructure, application_menu_update,
+    },
     events::{WindowDecorationMode, WindowId},
     geometry::LogicalSize,
     window_api::{
@@ -34,14 +37,17 @@ pub enum Action {
     Dummy,
     ApplicationClipboardPaste {
         serial: i32,
-        supported_mime_types: &'static str,
+        supported_mime_types: &'static [&'static str],
     },
-    ApplicationClipboardPut(&'static str),
+    ApplicationClipboardPut(&'static [&'static str]),
     ApplicationPrimarySelectionPaste {
         serial: i32,
-        supported_mime_types: &'static str,
+        supported_mime_types: &'static [&'static str],
+    },
+    ApplicationPrimarySelectionPut(&'static [&'static str]),
+    ApplicationMenuUpdate {
+        animation_paused: bool,
     },
-    ApplicationPrimarySelectionPut(&'static str),
     ApplicationSetPreferDarkTheme(bool),
     ApplicationStopEventLoop,
     ApplicationStopDragAndDrop,
@@ -66,10 +72,50 @@ pub enum Action {
     WindowTextInputEnable(WindowId, TextInputContext),
 }
 
+pub const MENU_ITEM_QUIT: AppMenuItemId = 1;
+pub const MENU_ITEM_ANIMATION: AppMenuItemId = 2;
+
+fn application_menu_update_impl(animation_paused: bool) {
+    let file_items = [AppMenuItem::ActionItem {
+        item_id: MENU_ITEM_QUIT,
+        title: BorrowedUtf8::new("_Quit"),
+        enabled: true,
+        toggle_type: AppMenuItemToggleType::None,
+        toggle_state: AppMenuItemToggleState::Off,
+        shortcut: BorrowedUtf8::new("<Control>q"),
+    }];
+    let view_items = [AppMenuItem::ActionItem {
+        item_id: MENU_ITEM_ANIMATION,
+        title: BorrowedUtf8::new("_Animation"),
+        enabled: true,
+        toggle_type: AppMenuItemToggleType::CheckMark,
+        toggle_state: if animation_paused {
+            AppMenuItemToggleState::Off
+        } else {
+            AppMenuItemToggleState::On
+        },
+        shortcut: BorrowedUtf8::null(),
+    }];
+    let items = [
+        AppMenuItem::SubMenuItem {
+            title: BorrowedUtf8::new("_File"),
+            items: BorrowedArray::from_slice(&file_items),
+        },
+        AppMenuItem::SubMenuItem {
+            title: BorrowedUtf8::new("_View"),
+            items: BorrowedArray::from_slice(&view_items),
+        },
+    ];
+    application_menu_update(AppMenuStructure {
+        items: BorrowedArray::from_slice(&items),
+    });
+}
+
 impl Action {
     pub fn perform(self) {
         match self {
             Self::Dummy => {}
+            Self::ApplicationMenuUpdate { animation_paused } => application_menu_update_impl(animation_paused),
             Self::ApplicationSetPreferDarkTheme(v) => application_set_prefer_dark_theme(v),
             Self::ApplicationStopDragAndDrop => application_stop_drag_and_drop(),
             Self::ApplicationStopEventLoop => application_stop_event_loop(),
@@ -101,13 +147,25 @@ impl Action {
             Self::ApplicationClipboardPaste {
                 serial,
                 supported_mime_types,
-            } => application_clipboard_paste(serial, BorrowedUtf8::new(supported_mime_types)),
-            Self::ApplicationClipboardPut(mime_types) => application_clipboard_put(BorrowedUtf8::new(mime_types)),
+            } => {
+                let supported_mime_types = supported_mime_types.iter().map(|m| BorrowedUtf8::new(m)).collect::<Vec<_>>();
+                application_clipboard_paste_v2(serial, BorrowedArray::from_slice(&supported_mime_types));
+            }
+            Self::ApplicationClipboardPut(mime_types) => {
+                let mime_types = mime_types.iter().map(|m| BorrowedUtf8::new(m)).collect::<Vec<_>>();
+                application_clipboard_put_v2(BorrowedArray::from_slice(&mime_types));
+            }
             Self::ApplicationPrimarySelectionPaste {
                 serial,
                 supported_mime_types,
-            } => application_primary_selection_paste(serial, BorrowedUtf8::new(supported_mime_types)),
-            Self::ApplicationPrimarySelectionPut(mime_types) => application_primary_selection_put(BorrowedUtf8::new(mime_types)),
+            } => {
+                let supported_mime_types = supported_mime_types.iter().map(|m| BorrowedUtf8::new(m)).collect::<Vec<_>>();
+                application_primary_selection_paste_v2(serial, BorrowedArray::from_slice(&supported_mime_types));
+            }
+            Self::ApplicationPrimarySelectionPut(mime_types) => {
+                let mime_types = mime_types.iter().map(|m| BorrowedUtf8::new(m)).collect::<Vec<_>>();
+                application_primary_selection_put_v2(BorrowedArray::from_slice(&mime_types));
+            }
         }
     }
 }
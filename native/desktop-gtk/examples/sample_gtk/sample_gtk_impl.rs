@@ -5,7 +5,7 @@ use crate::sample_gtk_draw::{OpenglState, draw};
 #[cfg(feature = "skia")]
 use crate::sample_gtk_draw_skia::{OpenglState, draw};
 
-use crate::sample_gtk_actions::Action;
+use crate::sample_gtk_actions::{Action, MENU_ITEM_ANIMATION, MENU_ITEM_QUIT};
 use core::str;
 use desktop_common::{
     ffi_utils::{BorrowedArray, BorrowedStrPtr, BorrowedUtf8},
@@ -15,6 +15,7 @@ use desktop_gtk::gtk::application_api::{
     FfiTextInputSurroundingText, FfiTransferDataResponse, application_close_notification, application_open_file_manager,
     application_open_url, application_request_redraw_drag_icon, application_request_show_notification,
 };
+use desktop_gtk::gtk::application_menu_api::{AppMenuItemId, AppMenuItemState, AppMenuItemToggleState};
 use desktop_gtk::gtk::desktop_settings_api::{FfiDesktopSetting, XdgDesktopColorScheme};
 use desktop_gtk::gtk::events::{OpenGlDrawData, WindowDecorationMode};
 use desktop_gtk::gtk::file_dialog_api::{CommonFileDialogParams, OpenFileDialogParams, SaveFileDialogParams};
@@ -43,7 +44,7 @@ use desktop_gtk::gtk::{
         window_request_redraw,
         window_show_open_file_dialog,
         window_show_save_file_dialog,
-        window_start_drag_and_drop,
+        window_start_drag_and_drop_v2,
         window_text_input_update,
         //
     },
@@ -58,7 +59,7 @@ const HTML_TEXT_MIME_TYPE: &str = "text/html";
 const TEXT_MIME_TYPE: &str = "text/plain;charset=utf-8";
 const URI_LIST_MIME_TYPE: &str = "text/uri-list";
 
-const ALL_MIMES: &str = "text/html,text/plain;charset=utf-8";
+const ALL_MIMES: &[&str] = &["text/html", TEXT_MIME_TYPE];
 
 const DRAG_WIDTH_NEXT_TO_INSETS: i32 = 50;
 const DRAG_AND_DROP_LEFT_OF: f64 = 100.;
@@ -100,6 +101,7 @@ struct State {
     request_sources: HashMap<RequestId, WindowId>,
     notification_sources: HashMap<u32, WindowId>,
     data_request_sources: HashMap<i32, WindowId>,
+    animation_paused: bool,
 }
 
 impl State {
@@ -228,10 +230,10 @@ fn on_keydown(event: &KeyDownEvent, state: &mut State, window_id: WindowId) -> O
         }
         (KeyModifiers::Ctrl, keycode::KeyMappingCode::KeyV) => Some(Action::ApplicationClipboardPaste {
             serial: state.add_data_request_source(window_id),
-            supported_mime_types: TEXT_MIME_TYPE,
+            supported_mime_types: &[TEXT_MIME_TYPE],
         }),
         (KeyModifiers::Ctrl, keycode::KeyMappingCode::KeyC) => Some(Action::ApplicationClipboardPut(ALL_MIMES)),
-        (KeyModifiers::Ctrl, keycode::KeyMappingCode::KeyF) => Some(Action::ApplicationClipboardPut("")),
+        (KeyModifiers::Ctrl, keycode::KeyMappingCode::KeyF) => Some(Action::ApplicationClipboardPut(&[])),
         (KeyModifiers::Ctrl, keycode::KeyMappingCode::KeyP) => {
             let title = format!("Notification from window {}", window_id.0);
             let body = format!("Clicking this notification will activate window {}", window_id.0);
@@ -388,6 +390,9 @@ fn on_application_started(state: &mut State) -> Vec<Action> {
     state.windows.insert(WindowId(2), WindowState::default());
 
     vec![
+        Action::ApplicationMenuUpdate {
+            animation_paused: state.animation_paused,
+        },
         Action::WindowCreate {
             window_id: WindowId(1),
             size: LogicalSize { width: 200, height: 300 },
@@ -461,7 +466,9 @@ fn event_handler_impl(event: &Event) -> Vec<Action> {
         }
         Event::WindowDraw(data) => {
             if let Some(window_state) = state.windows.get_mut(&data.window_id) {
-                window_state.animation_tick();
+                if !state.animation_paused {
+                    window_state.animation_tick();
+                }
 
                 draw_with_init(&data.opengl_draw_data, data.physical_size, window_state.scale, window_state);
             }
@@ -483,13 +490,14 @@ fn event_handler_impl(event: &Event) -> Vec<Action> {
                             let mime_types = if state.key_modifiers == KeyModifiers::Shift {
                                 ALL_MIMES
                             } else {
-                                TEXT_MIME_TYPE
+                                &[TEXT_MIME_TYPE]
                             };
+                            let mime_types = mime_types.iter().map(|m| BorrowedUtf8::new(m)).collect::<Vec<_>>();
                             let dnd_actions = DragAndDropActions(DragAndDropAction::Copy as u32 | DragAndDropAction::Move as u32);
                             let drag_icon_size = LogicalSize { width: 300, height: 300 };
-                            window_start_drag_and_drop(
+                            window_start_drag_and_drop_v2(
                                 data.window_id,
-                                BorrowedUtf8::new(mime_types),
+                                BorrowedArray::from_slice(&mime_types),
                                 dnd_actions,
                                 RenderingMode::Auto,
                                 drag_icon_size,
@@ -508,7 +516,7 @@ fn event_handler_impl(event: &Event) -> Vec<Action> {
                     MOUSE_BUTTON_MIDDLE => {
                         actions.push(Action::ApplicationPrimarySelectionPaste {
                             serial: state.add_data_request_source(data.window_id),
-                            supported_mime_types: TEXT_MIME_TYPE,
+                            supported_mime_types: &[TEXT_MIME_TYPE],
                         });
                     }
                     _ => {}
@@ -610,6 +618,16 @@ fn event_handler_impl(event: &Event) -> Vec<Action> {
         Event::DragIconFrameTick => {
             application_request_redraw_drag_icon();
         }
+        Event::AppMenuItemActivated(data) => match data.item_id {
+            MENU_ITEM_QUIT => actions.push(Action::ApplicationStopEventLoop),
+            MENU_ITEM_ANIMATION => {
+                state.animation_paused = data.toggle_state == AppMenuItemToggleState::Off;
+                actions.push(Action::ApplicationMenuUpdate {
+                    animation_paused: state.animation_paused,
+                });
+            }
+            _ => {}
+        },
         _ => {}
     });
     actions
@@ -761,11 +779,26 @@ extern "C" fn retrieve_surrounding_text(window_id: WindowId) -> FfiTextInputSurr
     }
 }
 
+extern "C" fn query_app_menu_item_state(item_id: AppMenuItemId) -> AppMenuItemState {
+    with_borrow_mut_state(|state| {
+        let toggle_state = if item_id == MENU_ITEM_ANIMATION && !state.animation_paused {
+            AppMenuItemToggleState::On
+        } else {
+            AppMenuItemToggleState::Off
+        };
+        AppMenuItemState {
+            enabled: true,
+            toggle_state,
+        }
+    })
+}
+
 pub fn main_impl() {
     logger_init_impl(&LoggerConfiguration {
         file_path: BorrowedStrPtr::new(c"/tmp/a"),
         console_level: LogLevel::Debug,
         file_level: LogLevel::Error,
+        log_callback: None,
     });
     application_init(BorrowedUtf8::new(APP_ID));
     application_run_event_loop(ApplicationCallbacks {
@@ -776,5 +809,6 @@ pub fn main_impl() {
         retrieve_surrounding_text,
         window_close_request,
         application_wants_to_terminate,
+        query_app_menu_item_state: Some(query_app_menu_item_state),
     });
 }
@@ -2,9 +2,11 @@ use crate::gtk::application_api::ApplicationCallbacks;
 use crate::gtk::application_state::ApplicationState;
 use crate::gtk::events::{Event, EventHandler};
 use anyhow::{Context, bail};
+use desktop_common::event_recording::{RecordedVariant, record_event};
 use desktop_common::logger::catch_panic;
+use desktop_common::perf_trace::TraceSpan;
 use gtk4::glib;
-use gtk4::prelude::ApplicationExtManual;
+use gtk4::prelude::{ApplicationExt, ApplicationExtManual};
 use log::debug;
 use std::cell::{Cell, OnceCell, RefCell};
 use std::sync::OnceLock;
@@ -60,6 +62,11 @@ pub fn send_event<'a, T: Into<Event<'a>>>(event_handler: EventHandler, event_dat
         Event::MouseMoved(_) | Event::WindowFrameTick(_) | Event::WindowDraw(_) | Event::DragIconDraw(_) | Event::DragIconFrameTick => {}
         _ => debug!("Sending event: {event:?}"),
     }
+    // The draw events only provide the rendering target, so there is nothing to replay.
+    if !matches!(event, Event::WindowDraw(_) | Event::DragIconDraw(_)) {
+        record_event(&event);
+    }
+    let _span = TraceSpan::new("event", event.variant_name());
     catch_panic(|| Ok(event_handler(&event))).unwrap_or(false)
 }
 
@@ -104,6 +111,14 @@ impl Application {
         app.run_with_args::<&str>(&[]);
 
         debug!("Application event loop stopped");
+        // Taken out of the cell first, so that the callbacks running while the clipboard is stored don't find it borrowed
+        let state = self.state.take();
+        if let Some(state) = &state {
+            state.store_clipboard_on_exit();
+        }
+        debug!("Deinitializing application state");
+        drop(state);
+        debug!("Deinitialized application state");
         Ok(())
     }
 
@@ -111,9 +126,9 @@ impl Application {
         Self::run_on_event_loop_async(|| {
             APP_INSTANCE.with(|app_cell| {
                 let app = app_cell.get().expect("Application not initialized");
-                debug!("Deinitializing application state");
-                *app.state.borrow_mut() = None;
-                debug!("Deinitialized application state");
+                if let Some(state) = app.state.borrow().as_ref() {
+                    state.gtk_app.quit();
+                }
             });
         });
     }
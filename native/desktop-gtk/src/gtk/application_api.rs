@@ -1,14 +1,17 @@
 use crate::gtk::application::{Application, with_app_state, with_app_state_mut};
+use crate::gtk::application_menu_api::FfiQueryAppMenuItemState;
 use crate::gtk::application_state::{EglInstance, get_egl, get_gl};
 use crate::gtk::data_transfer_api::{DataSource, DragAndDropAction, DragAndDropActions, DragAndDropQueryData};
 use crate::gtk::events::{EventHandler, RequestId, WindowId};
 use crate::gtk::mime_types::MimeTypes;
-use desktop_common::ffi_utils::RustAllocatedStrPtr;
+use crate::gtk::notifications::NotificationContent;
+use desktop_common::ffi_utils::{AutoDropArray, AutoDropStrPtr, RustAllocatedStrPtr};
 use desktop_common::{
     ffi_utils::{BorrowedArray, BorrowedOpaquePtr, BorrowedStrPtr, BorrowedUtf8},
     logger::ffi_boundary,
 };
 use log::{debug, warn};
+use std::time::Duration;
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -72,6 +75,9 @@ pub struct ApplicationCallbacks {
     pub window_close_request: FfiWindowCloseRequest,
     pub application_wants_to_terminate: FfiApplicationWantsToTerminate,
     pub retrieve_surrounding_text: FfiRetrieveSurroundingText,
+
+    /// Optional. Used to refresh the state of the application menu items.
+    pub query_app_menu_item_state: Option<FfiQueryAppMenuItemState>,
 }
 
 #[unsafe(no_mangle)]
@@ -182,6 +188,7 @@ pub extern "C" fn application_run_on_event_loop_async(f: extern "C" fn()) {
     });
 }
 
+#[deprecated(note = "use application_clipboard_put_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn application_clipboard_put(mime_types: BorrowedUtf8) {
     debug!("application_clipboard_put");
@@ -192,6 +199,32 @@ pub extern "C" fn application_clipboard_put(mime_types: BorrowedUtf8) {
     });
 }
 
+/// Keep the clipboard content available after the event loop stops, by handing it to the clipboard manager.
+///
+/// When the event loop stops, waits at most `timeout_ms` milliseconds for the clipboard manager to store the content.
+/// `0` disables it, which is the default.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_set_clipboard_store_on_exit(timeout_ms: u32) {
+    debug!("application_set_clipboard_store_on_exit: {timeout_ms}");
+    ffi_boundary("application_set_clipboard_store_on_exit", || {
+        with_app_state_mut(|app| {
+            app.set_clipboard_store_on_exit((timeout_ms > 0).then(|| Duration::from_millis(timeout_ms.into())));
+            Ok(())
+        })
+    });
+}
+
+/// Takes ownership of the clipboard, offering the data in `mime_types`. Empty `mime_types` clear the clipboard.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_clipboard_put_v2(mime_types: BorrowedArray<BorrowedUtf8>) {
+    debug!("application_clipboard_put_v2");
+    ffi_boundary("application_clipboard_put_v2", || {
+        let mime_types = MimeTypes::from_array(&mime_types, "application_clipboard_put_v2: mime_types")?;
+        with_app_state_mut(|app| app.clipboard_put(&mime_types))
+    });
+}
+
+#[deprecated(note = "use application_clipboard_paste_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn application_clipboard_paste(serial: i32, supported_mime_types: BorrowedUtf8) {
     debug!("application_clipboard_paste: {supported_mime_types:?}");
@@ -205,6 +238,20 @@ pub extern "C" fn application_clipboard_paste(serial: i32, supported_mime_types:
     });
 }
 
+/// Reads the clipboard content in the first of the `supported_mime_types` that is available, reported with `Event::DataTransfer`.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_clipboard_paste_v2(serial: i32, supported_mime_types: BorrowedArray<BorrowedUtf8>) {
+    debug!("application_clipboard_paste_v2");
+    ffi_boundary("application_clipboard_paste_v2", || {
+        let supported_mime_types = MimeTypes::from_array(&supported_mime_types, "application_clipboard_paste_v2: supported_mime_types")?;
+        with_app_state(|app| {
+            app.clipboard_paste(serial, &supported_mime_types);
+            Ok(())
+        })
+    });
+}
+
+#[deprecated(note = "use application_primary_selection_put_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn application_primary_selection_put(mime_types: BorrowedUtf8) {
     debug!("application_primary_selection_put");
@@ -215,10 +262,21 @@ pub extern "C" fn application_primary_selection_put(mime_types: BorrowedUtf8) {
     });
 }
 
+/// Takes ownership of the primary selection, offering the data in `mime_types`. Empty `mime_types` clear the primary selection.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_primary_selection_put_v2(mime_types: BorrowedArray<BorrowedUtf8>) {
+    debug!("application_primary_selection_put_v2");
+    ffi_boundary("application_primary_selection_put_v2", || {
+        let mime_types = MimeTypes::from_array(&mime_types, "application_primary_selection_put_v2: mime_types")?;
+        with_app_state_mut(|app| app.primary_selection_put(&mime_types))
+    });
+}
+
+#[deprecated(note = "use application_primary_selection_paste_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn application_primary_selection_paste(serial: i32, supported_mime_types: BorrowedUtf8) {
     debug!("application_primary_selection_paste: {supported_mime_types:?}");
-    ffi_boundary("application_clipboard_paste", || {
+    ffi_boundary("application_primary_selection_paste", || {
         let supported_mime_types_str = supported_mime_types.get("application_primary_selection_paste: supported_mime_types")?;
         let supported_mime_types = MimeTypes::new(supported_mime_types_str);
         with_app_state(|app| {
@@ -228,28 +286,64 @@ pub extern "C" fn application_primary_selection_paste(serial: i32, supported_mim
     });
 }
 
+/// Like `application_clipboard_paste_v2`, but for the primary selection.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_primary_selection_paste_v2(serial: i32, supported_mime_types: BorrowedArray<BorrowedUtf8>) {
+    debug!("application_primary_selection_paste_v2");
+    ffi_boundary("application_primary_selection_paste_v2", || {
+        let supported_mime_types = MimeTypes::from_array(
+            &supported_mime_types,
+            "application_primary_selection_paste_v2: supported_mime_types",
+        )?;
+        with_app_state(|app| {
+            app.primary_selection_paste(serial, &supported_mime_types);
+            Ok(())
+        })
+    });
+}
+
+#[deprecated(note = "use application_clipboard_get_available_mimetypes_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn application_clipboard_get_available_mimetypes() -> RustAllocatedStrPtr {
     debug!("application_clipboard_get_available_mimetypes");
     ffi_boundary("application_clipboard_get_available_mimetypes", || {
         with_app_state(|app| {
-            let csv_mimetypes = app.clipboard_get_available_mimetypes();
+            let csv_mimetypes = app.clipboard_get_available_mimetypes().join(",");
             Ok(RustAllocatedStrPtr::allocate(csv_mimetypes)?)
         })
     })
 }
 
+/// Returns the MIME types offered for the clipboard content. The array must be freed with `string_array_drop`.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_clipboard_get_available_mimetypes_v2() -> AutoDropArray<AutoDropStrPtr> {
+    debug!("application_clipboard_get_available_mimetypes_v2");
+    ffi_boundary("application_clipboard_get_available_mimetypes_v2", || {
+        with_app_state(|app| Ok(AutoDropArray::from_strs(app.clipboard_get_available_mimetypes())?))
+    })
+}
+
+#[deprecated(note = "use application_primary_selection_get_available_mimetypes_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn application_primary_selection_get_available_mimetypes() -> RustAllocatedStrPtr {
     debug!("application_primary_selection_get_available_mimetypes");
     ffi_boundary("application_primary_selection_get_available_mimetypes", || {
         with_app_state(|app| {
-            let csv_mimetypes = app.primary_selection_get_available_mimetypes();
+            let csv_mimetypes = app.primary_selection_get_available_mimetypes().join(",");
             Ok(RustAllocatedStrPtr::allocate(csv_mimetypes)?)
         })
     })
 }
 
+/// Returns the MIME types offered for the primary selection content. The array must be freed with `string_array_drop`.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_primary_selection_get_available_mimetypes_v2() -> AutoDropArray<AutoDropStrPtr> {
+    debug!("application_primary_selection_get_available_mimetypes_v2");
+    ffi_boundary("application_primary_selection_get_available_mimetypes_v2", || {
+        with_app_state(|app| Ok(AutoDropArray::from_strs(app.primary_selection_get_available_mimetypes())?))
+    })
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn application_request_redraw_drag_icon() {
     ffi_boundary("window_request_redraw", || {
@@ -297,7 +391,84 @@ pub extern "C" fn application_request_show_notification(
         let sound_file_path_opt = sound_file_path
             .get_optional("application_show_notification: sound_file_path")?
             .map(ToOwned::to_owned);
-        with_app_state(|app| app.request_show_notification(summary, body, sound_file_path_opt))
+        with_app_state(|app| app.request_show_notification(NotificationContent::new(summary, body, sound_file_path_opt)))
+    })
+}
+
+/// Values match the `urgency` hint of the Desktop Notifications Specification.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NotificationUrgency {
+    Low = 0,
+    Normal = 1,
+    Critical = 2,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct NotificationActionButton<'a> {
+    /// Reported back in `NotificationClosedEvent::action` when the button is clicked.
+    /// Must not be `"default"`, which is reserved for clicking the notification itself.
+    pub key: BorrowedUtf8<'a>,
+
+    /// User-visible button text.
+    pub label: BorrowedUtf8<'a>,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct NotificationImage<'a> {
+    pub width: i32,
+    pub height: i32,
+
+    /// Optional. Non-premultiplied RGBA, 8 bits per channel, without row padding (`width * height * 4` bytes).
+    pub rgba_data: BorrowedArray<'a, u8>,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct NotificationParams<'a> {
+    /// User-visible string to display as the title.
+    pub title: BorrowedUtf8<'a>,
+
+    /// User-visible string to display as the body.
+    pub body: BorrowedUtf8<'a>,
+
+    /// Optional. See `application_request_show_notification`.
+    pub sound_file_path: BorrowedUtf8<'a>,
+
+    /// Optional. Buttons to display on the notification, the server may ignore them.
+    pub actions: BorrowedArray<'a, NotificationActionButton<'a>>,
+
+    /// Optional. Icon name from the icon theme, or a `file://` URI.
+    /// If not set, the server picks the icon based on the application `.desktop` file.
+    pub app_icon: BorrowedUtf8<'a>,
+
+    /// Optional (with null `rgba_data`). Takes precedence over `app_icon`.
+    pub image: NotificationImage<'a>,
+
+    pub urgency: NotificationUrgency,
+
+    /// Optional. E.g. `"transfer.complete"`, see the Desktop Notifications Specification for the list of categories.
+    pub category: BorrowedUtf8<'a>,
+
+    /// `-1` means that the timeout depends on the notification server settings, `0` means that the notification never expires.
+    pub expire_timeout_ms: i32,
+
+    /// ID of the notification to update in-place (e.g. for progress reporting), or `0` to show a new notification.
+    pub replaces_id: u32,
+}
+
+/// Same as `application_request_show_notification`, but with support for action buttons, images, and other hints.
+///
+/// Clicked action button is reported with its key in `Event::NotificationClosed`.
+/// When `replaces_id` is set, `Event::NotificationShown` is reported with the same notification ID.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_request_show_notification_with_params(params: NotificationParams) -> RequestId {
+    debug!("application_request_show_notification_with_params");
+    ffi_boundary("application_request_show_notification_with_params", || {
+        let content = NotificationContent::from_unsafe(&params)?;
+        with_app_state(|app| app.request_show_notification(content))
     })
 }
 
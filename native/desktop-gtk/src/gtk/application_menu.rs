@@ -0,0 +1,229 @@
+use crate::gtk::{
+    application::send_event,
+    application_menu_api::{
+        AppMenuItem, AppMenuItemId, AppMenuItemState, AppMenuItemToggleState, AppMenuItemToggleType, AppMenuStructure,
+        FfiQueryAppMenuItemState,
+    },
+    events::{AppMenuItemActivatedEvent, EventHandler},
+};
+use anyhow::Context as _;
+use gtk4::prelude::{ActionExt, ActionMapExt, GtkApplicationExt, ToVariant};
+use gtk4::{gio, glib};
+use std::{cell::RefCell, rc::Rc};
+
+/// cbindgen:ignore
+const RADIO_ITEM_TARGET: &str = "on";
+
+#[derive(Debug, Clone)]
+pub enum AppMenuItemSafe {
+    Action {
+        item_id: AppMenuItemId,
+        title: String,
+        state: AppMenuItemState,
+        toggle_type: AppMenuItemToggleType,
+        shortcut: Option<String>,
+    },
+    Separator,
+    SubMenu {
+        title: String,
+        items: Vec<Self>,
+    },
+}
+
+impl AppMenuItemSafe {
+    fn from_unsafe(item: &AppMenuItem) -> anyhow::Result<Self> {
+        Ok(match item {
+            AppMenuItem::ActionItem {
+                item_id,
+                title,
+                enabled,
+                toggle_type,
+                toggle_state,
+                shortcut,
+            } => Self::Action {
+                item_id: *item_id,
+                title: title.get("AppMenuItem::ActionItem: title")?.to_owned(),
+                state: AppMenuItemState {
+                    enabled: *enabled,
+                    toggle_state: *toggle_state,
+                },
+                toggle_type: *toggle_type,
+                shortcut: shortcut.get_optional("AppMenuItem::ActionItem: shortcut")?.map(str::to_owned),
+            },
+            AppMenuItem::SeparatorItem => Self::Separator,
+            AppMenuItem::SubMenuItem { title, items } => Self::SubMenu {
+                title: title.get("AppMenuItem::SubMenuItem: title")?.to_owned(),
+                items: items
+                    .as_slice()
+                    .context("AppMenuItem::SubMenuItem: items")?
+                    .iter()
+                    .map(Self::from_unsafe)
+                    .collect::<anyhow::Result<_>>()?,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AppMenuStructureSafe {
+    pub items: Vec<AppMenuItemSafe>,
+}
+
+impl AppMenuStructureSafe {
+    pub fn from_unsafe(menu: &AppMenuStructure) -> anyhow::Result<Self> {
+        let items = menu
+            .items
+            .as_slice()
+            .context("AppMenuStructure: items")?
+            .iter()
+            .map(AppMenuItemSafe::from_unsafe)
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { items })
+    }
+}
+
+struct AppMenuAction {
+    item_id: AppMenuItemId,
+    toggle_type: AppMenuItemToggleType,
+    action: gio::SimpleAction,
+}
+
+impl AppMenuAction {
+    fn new(item_id: AppMenuItemId, toggle_type: AppMenuItemToggleType, state: AppMenuItemState, event_handler: EventHandler) -> Self {
+        let name = format!("app-menu-item-{item_id}");
+        let action = match toggle_type {
+            AppMenuItemToggleType::None => gio::SimpleAction::new(&name, None),
+            AppMenuItemToggleType::CheckMark => gio::SimpleAction::new_stateful(&name, None, &false.to_variant()),
+            AppMenuItemToggleType::Radio => gio::SimpleAction::new_stateful(&name, Some(glib::VariantTy::STRING), &"".to_variant()),
+        };
+        action.connect_activate(move |action, _parameter| {
+            let toggle_state = match toggle_type {
+                AppMenuItemToggleType::None => AppMenuItemToggleState::Off,
+                AppMenuItemToggleType::CheckMark => {
+                    if action.state().and_then(|s| s.get::<bool>()) == Some(true) {
+                        AppMenuItemToggleState::Off
+                    } else {
+                        AppMenuItemToggleState::On
+                    }
+                }
+                AppMenuItemToggleType::Radio => AppMenuItemToggleState::On,
+            };
+            set_action_toggle_state(action, toggle_type, toggle_state);
+            send_event(event_handler, AppMenuItemActivatedEvent { item_id, toggle_state });
+        });
+        let app_menu_action = Self {
+            item_id,
+            toggle_type,
+            action,
+        };
+        app_menu_action.set_state(state);
+        app_menu_action
+    }
+
+    fn detailed_name(&self) -> String {
+        format!("app.{}", self.action.name())
+    }
+
+    fn set_state(&self, state: AppMenuItemState) {
+        self.action.set_enabled(state.enabled);
+        set_action_toggle_state(&self.action, self.toggle_type, state.toggle_state);
+    }
+}
+
+fn set_action_toggle_state(action: &gio::SimpleAction, toggle_type: AppMenuItemToggleType, toggle_state: AppMenuItemToggleState) {
+    let is_on = toggle_state == AppMenuItemToggleState::On;
+    match toggle_type {
+        AppMenuItemToggleType::None => {}
+        AppMenuItemToggleType::CheckMark => action.set_state(&is_on.to_variant()),
+        AppMenuItemToggleType::Radio => action.set_state(&(if is_on { RADIO_ITEM_TARGET } else { "" }).to_variant()),
+    }
+}
+
+/// Separators are represented by splitting the menu into sections.
+fn create_menu(items: &[AppMenuItemSafe], actions: &mut Vec<AppMenuAction>, event_handler: EventHandler) -> gio::Menu {
+    let mut sections = vec![gio::Menu::new()];
+    for item in items {
+        let menu_item = match item {
+            AppMenuItemSafe::Action {
+                item_id,
+                title,
+                state,
+                toggle_type,
+                shortcut,
+            } => {
+                let action = AppMenuAction::new(*item_id, *toggle_type, *state, event_handler);
+                let menu_item = gio::MenuItem::new(Some(title), None);
+                let target = (*toggle_type == AppMenuItemToggleType::Radio).then(|| RADIO_ITEM_TARGET.to_variant());
+                menu_item.set_action_and_target_value(Some(&action.detailed_name()), target.as_ref());
+                if let Some(shortcut) = shortcut {
+                    menu_item.set_attribute_value("accel", Some(&shortcut.to_variant()));
+                }
+                actions.push(action);
+                menu_item
+            }
+            AppMenuItemSafe::Separator => {
+                sections.push(gio::Menu::new());
+                continue;
+            }
+            AppMenuItemSafe::SubMenu { title, items } => {
+                let submenu = create_menu(items, actions, event_handler);
+                gio::MenuItem::new_submenu(Some(title), &submenu)
+            }
+        };
+        sections.last().unwrap().append_item(&menu_item);
+    }
+    if sections.len() == 1 {
+        return sections.pop().unwrap();
+    }
+    let menu = gio::Menu::new();
+    for section in sections {
+        menu.append_section(None, &section);
+    }
+    menu
+}
+
+pub struct AppMenu {
+    gtk_app: gtk4::Application,
+    event_handler: EventHandler,
+    actions: Rc<RefCell<Vec<AppMenuAction>>>,
+}
+
+impl AppMenu {
+    pub fn new(gtk_app: &gtk4::Application, event_handler: EventHandler, query_item_state: Option<FfiQueryAppMenuItemState>) -> Self {
+        let actions: Rc<RefCell<Vec<AppMenuAction>>> = Rc::default();
+        if let Some(query_item_state) = query_item_state {
+            // There's no notification when the exported menu is about to be shown,
+            // so refresh the state when the user is likely to interact with it.
+            let actions = Rc::downgrade(&actions);
+            gtk_app.connect_active_window_notify(move |_gtk_app| {
+                if let Some(actions) = actions.upgrade() {
+                    for action in actions.borrow().iter() {
+                        action.set_state(query_item_state(action.item_id));
+                    }
+                }
+            });
+        }
+        Self {
+            gtk_app: gtk_app.clone(),
+            event_handler,
+            actions,
+        }
+    }
+
+    pub fn update(&self, menu: &AppMenuStructureSafe) {
+        for old_action in self.actions.borrow_mut().drain(..) {
+            self.gtk_app.remove_action(&old_action.action.name());
+        }
+        let mut actions = Vec::new();
+        let menubar = create_menu(&menu.items, &mut actions, self.event_handler);
+        for action in &actions {
+            self.gtk_app.add_action(&action.action);
+        }
+        *self.actions.borrow_mut() = actions;
+        if menu.items.is_empty() {
+            self.gtk_app.set_menubar(None::<&gio::MenuModel>);
+        } else {
+            self.gtk_app.set_menubar(Some(&menubar));
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::gtk::{application::with_app_state, application_menu::AppMenuStructureSafe};
+use desktop_common::{
+    ffi_utils::{BorrowedArray, BorrowedUtf8},
+    logger::ffi_boundary,
+};
+use log::debug;
+
+pub type AppMenuItemId = i64;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AppMenuItemToggleType {
+    None,
+    CheckMark,
+    Radio,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AppMenuItemToggleState {
+    Off,
+    On,
+    Indeterminate,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub enum AppMenuItem<'a> {
+    ActionItem {
+        item_id: AppMenuItemId,
+
+        /// Underscore marks the mnemonic, e.g. `"_Open"`.
+        title: BorrowedUtf8<'a>,
+
+        enabled: bool,
+        toggle_type: AppMenuItemToggleType,
+
+        /// `Indeterminate` is shown as `Off`.
+        toggle_state: AppMenuItemToggleState,
+
+        /// Optional. Accelerator in the GTK format, e.g. `"<Control><Shift>s"`.
+        /// Only displayed in the menu, the key events are still delivered to the window as usual.
+        shortcut: BorrowedUtf8<'a>,
+    },
+    SeparatorItem,
+    SubMenuItem {
+        /// Underscore marks the mnemonic, e.g. `"_File"`.
+        title: BorrowedUtf8<'a>,
+        items: BorrowedArray<'a, Self>,
+    },
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct AppMenuStructure<'a> {
+    pub items: BorrowedArray<'a, AppMenuItem<'a>>,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AppMenuItemState {
+    pub enabled: bool,
+    pub toggle_state: AppMenuItemToggleState,
+}
+
+/// Called when the active window changes, for every action item in the menu.
+pub type FfiQueryAppMenuItemState = extern "C" fn(item_id: AppMenuItemId) -> AppMenuItemState;
+
+/// Sets the application menubar, which GTK exports over D-Bus (`org.gtk.Menus` and `org.gtk.Actions`) for the global menu.
+///
+/// The menubar is not shown inside the windows.
+/// Activation of the menu items is reported with `Event::AppMenuItemActivated`.
+/// An empty structure sets an empty menubar, the menubar stays exported until the application exits.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_menu_update(menu: AppMenuStructure) {
+    debug!("application_menu_update");
+    ffi_boundary("application_menu_update", || {
+        let menu = AppMenuStructureSafe::from_unsafe(&menu)?;
+        with_app_state(|state| {
+            state.app_menu_update(&menu);
+            Ok(())
+        })
+    });
+}
@@ -1,5 +1,6 @@
 use crate::gtk::application::{Application, send_event, with_app_state_mut};
 use crate::gtk::application_api::{ApplicationCallbacks, FfiWindowCloseRequest, RenderingMode};
+use crate::gtk::application_menu::{AppMenu, AppMenuStructureSafe};
 use crate::gtk::async_event_result::AsyncEventResult;
 use crate::gtk::clipboard::{ClipboardContentProvider, KdtClipboard};
 use crate::gtk::data_transfer_api::{DataSource, DragAndDropAction, DragAndDropActions};
@@ -13,11 +14,13 @@ use crate::gtk::events::{
 use crate::gtk::ffi_return_conversions::{QueryDragAndDropTarget, RetrieveSurroundingText, TransferDataGetter};
 use crate::gtk::file_dialog::show_file_dialog_impl;
 use crate::gtk::file_dialog_api::{CommonFileDialogParams, OpenFileDialogParams, SaveFileDialogParams};
+use crate::gtk::frame_capture::send_window_capture_frame_response;
 use crate::gtk::geometry::{LogicalSize, PhysicalSize};
 use crate::gtk::gl_widget::GlWidget;
 use crate::gtk::keyboard::key_modifiers_from_gdk;
+use crate::gtk::launcher_entry::LauncherEntry;
 use crate::gtk::mime_types::MimeTypes;
-use crate::gtk::notifications::{NewNotificationData, NotificationAction, init_notifications_task};
+use crate::gtk::notifications::{NewNotificationData, NotificationAction, NotificationContent, init_notifications_task};
 use crate::gtk::window::SimpleWindow;
 use crate::gtk::window_api::WindowParams;
 use anyhow::{Context, anyhow, bail};
@@ -35,6 +38,7 @@ use std::ptr::NonNull;
 use std::rc::Rc;
 use std::sync::atomic::AtomicU32;
 use std::sync::{LazyLock, OnceLock, atomic};
+use std::time::Duration;
 
 /// cbindgen:ignore
 pub type EglInstance = khronos_egl::DynamicInstance<khronos_egl::EGL1_0>;
@@ -88,6 +92,8 @@ pub struct ApplicationState {
     desktop_settings: DesktopSettings,
     notification_action_sender: Option<async_channel::Sender<NotificationAction>>,
     notification_task_join_handle: Option<glib::JoinHandle<()>>,
+    app_menu: AppMenu,
+    clipboard_store_timeout: Option<Duration>,
 }
 
 impl Drop for ApplicationState {
@@ -105,7 +111,6 @@ impl Drop for ApplicationState {
         if wait_for_notification_task && let Some(join_handle) = self.notification_task_join_handle.take() {
             _ = main_context.block_on(join_handle);
         }
-        self.gtk_app.quit();
     }
 }
 
@@ -221,6 +226,7 @@ impl ApplicationState {
             transfer_data_getter,
         );
         let desktop_settings = DesktopSettings::new(display);
+        let app_menu = AppMenu::new(&gtk_app, event_handler, callbacks.query_app_menu_item_state);
 
         Ok(Self {
             event_handler,
@@ -246,6 +252,8 @@ impl ApplicationState {
             desktop_settings,
             notification_action_sender: None,
             notification_task_join_handle: None,
+            app_menu,
+            clipboard_store_timeout: None,
         })
     }
 
@@ -352,17 +360,37 @@ impl ApplicationState {
         f(window)
     }
 
+    pub fn window_capture_frame(&self, window_id: WindowId) -> anyhow::Result<RequestId> {
+        let raw_request_id = self.async_request_counter.fetch_add(1, atomic::Ordering::Relaxed);
+        let request_id = RequestId(raw_request_id);
+        let event_handler = self.event_handler;
+        self.with_window(window_id, |w| {
+            w.capture_frame(move |result| send_window_capture_frame_response(event_handler, request_id, window_id, result));
+            Ok(())
+        })?;
+        Ok(request_id)
+    }
+
+    #[allow(clippy::unused_self)]
+    pub fn launcher_entry_update(&self, entry: LauncherEntry) {
+        entry.emit_update();
+    }
+
+    pub fn app_menu_update(&self, menu: &AppMenuStructureSafe) {
+        self.app_menu.update(menu);
+    }
+
     pub fn clipboard_put(&mut self, mime_types: &MimeTypes) -> anyhow::Result<()> {
         debug!("clipboard_put: mime_types={:?}", mime_types.val);
         self.clipboard.put(mime_types)
     }
 
-    pub fn clipboard_get_available_mimetypes(&self) -> String {
-        self.clipboard.mime_types_csv()
+    pub fn clipboard_get_available_mimetypes(&self) -> Vec<glib::GString> {
+        self.clipboard.mime_types()
     }
 
-    pub fn primary_selection_get_available_mimetypes(&self) -> String {
-        self.primary_clipboard.mime_types_csv()
+    pub fn primary_selection_get_available_mimetypes(&self) -> Vec<glib::GString> {
+        self.primary_clipboard.mime_types()
     }
 
     pub fn primary_selection_put(&mut self, mime_types: &MimeTypes) -> anyhow::Result<()> {
@@ -547,7 +575,7 @@ impl ApplicationState {
         }))
     }
 
-    pub fn request_show_notification(&self, summary: String, body: String, sound_file_path: Option<String>) -> anyhow::Result<RequestId> {
+    pub fn request_show_notification(&self, content: NotificationContent) -> anyhow::Result<RequestId> {
         let Some(action_sender) = self.notification_action_sender.clone() else {
             bail!("Didn't try initializing notifications");
         };
@@ -558,12 +586,7 @@ impl ApplicationState {
                     AsyncEventResult::NotificationShown(result).send_as_event(event_handler, request_id);
                 });
             });
-            let new_notification_data = NewNotificationData {
-                summary,
-                body,
-                sound_file_path,
-                result_reporter,
-            };
+            let new_notification_data = Box::new(NewNotificationData { content, result_reporter });
             if let Err(e) = action_sender.send(NotificationAction::Show(new_notification_data)).await
                 && let NotificationAction::Show(d) = e.0
             {
@@ -587,4 +610,15 @@ impl ApplicationState {
     pub fn set_prefer_dark_theme(&self, value: bool) {
         self.desktop_settings.set_prefer_dark_theme(value);
     }
+
+    pub const fn set_clipboard_store_on_exit(&mut self, timeout: Option<Duration>) {
+        self.clipboard_store_timeout = timeout;
+    }
+
+    /// Called after the event loop has stopped, before the application state is torn down.
+    pub fn store_clipboard_on_exit(&self) {
+        if let Some(timeout) = self.clipboard_store_timeout {
+            self.clipboard.store(timeout);
+        }
+    }
 }
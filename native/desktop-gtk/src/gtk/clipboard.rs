@@ -3,7 +3,8 @@ use crate::gtk::data_transfer::read_all;
 use crate::gtk::data_transfer_api::DataSource;
 use crate::gtk::events::{DataTransferAvailableEvent, DataTransferCancelledEvent, DataTransferContent, DataTransferEvent, EventHandler};
 use crate::gtk::ffi_return_conversions::TransferDataGetter;
-use crate::gtk::mime_types::MimeTypes;
+use crate::gtk::mime_types::{MimeTypes, can_convert_to_png, convert_data_async};
+use desktop_common::mime_types::{NegotiatedMimeType, add_mime_type_aliases, negotiate_mime_type, source_mime_type};
 use gdk4::prelude::{ObjectExt, OutputStreamExtManual};
 use gdk4::subclass::content_provider::ContentProviderImplExt;
 use gdk4::subclass::prelude::ObjectSubclassIsExt;
@@ -11,10 +12,14 @@ use gtk4::{gdk as gdk4, gio, glib};
 use log::{debug, warn};
 use std::cell::OnceCell;
 use std::pin::Pin;
+use std::time::Duration;
 
 #[derive(Default)]
 pub struct ClipboardContentProviderImpl {
     pub formats: OnceCell<gdk4::ContentFormats>,
+
+    /// MIME types provided by the application, `formats` also include the ones that the data can be converted to.
+    pub provided_mime_types: OnceCell<Vec<String>>,
     pub clipboard_type: OnceCell<DataSource>,
     pub transfer_data_getter: OnceCell<TransferDataGetter>,
     pub event_handler: OnceCell<EventHandler>,
@@ -70,31 +75,39 @@ impl gdk4::subclass::content_provider::ContentProviderImpl for ClipboardContentP
     ) -> Pin<Box<dyn Future<Output = Result<(), glib::Error>> + 'static>> {
         debug!("ContentProviderImpl::write_mime_type_future: {mime_type}");
         let clipboard_type = *self.clipboard_type.get().unwrap();
-        if let Some(data) = self.transfer_data_getter.get().unwrap().get(clipboard_type, mime_type) {
-            let stream = stream.clone();
-            Box::pin(async move {
-                match stream.write_all_future(data, io_priority).await {
-                    Ok((_buf, size, e)) => {
-                        if let Some(e) = e {
-                            warn!("Error writing clipboard data ({size} written): {e}");
-                            Err(e)
-                        } else {
-                            debug!("Finished writing clipboard data, size={size}");
-                            Ok(())
-                        }
-                    }
-                    Err((_buf, e)) => {
-                        warn!("Error writing clipboard data: {e}");
-                        Err(e)
-                    }
-                }
-            })
-        } else {
+        let provided_mime_types = self.provided_mime_types.get().unwrap();
+        let provided_mime_type = source_mime_type(mime_type, provided_mime_types, can_convert_to_png).unwrap_or(mime_type);
+        let Some(data) = self.transfer_data_getter.get().unwrap().get(clipboard_type, provided_mime_type) else {
             let msg = format!("Clipboard type {mime_type} not supported");
             warn!("Error writing clipboard data: {msg}");
             let e = glib::Error::new(gio::IOErrorEnum::NotSupported, &msg);
-            Box::pin(async { Err(e) })
-        }
+            return Box::pin(async { Err(e) });
+        };
+        let stream = stream.clone();
+        let provided_mime_type = provided_mime_type.to_owned();
+        let mime_type = mime_type.to_owned();
+        Box::pin(async move {
+            let data = convert_data_async(provided_mime_type.clone(), mime_type, data).await.map_err(|e| {
+                let msg = format!("Converting clipboard data from {provided_mime_type} failed: {e}");
+                warn!("Error writing clipboard data: {msg}");
+                glib::Error::new(gio::IOErrorEnum::Failed, &msg)
+            })?;
+            match stream.write_all_future(data, io_priority).await {
+                Ok((_buf, size, e)) => {
+                    if let Some(e) = e {
+                        warn!("Error writing clipboard data ({size} written): {e}");
+                        Err(e)
+                    } else {
+                        debug!("Finished writing clipboard data, size={size}");
+                        Ok(())
+                    }
+                }
+                Err((_buf, e)) => {
+                    warn!("Error writing clipboard data: {e}");
+                    Err(e)
+                }
+            }
+        })
     }
 
     fn value(&self, type_: glib::Type) -> Result<glib::Value, glib::Error> {
@@ -119,7 +132,11 @@ impl ClipboardContentProvider {
         let obj = glib::Object::new::<Self>();
         let imp = obj.imp();
         imp.clipboard_type.set(clipboard_type).unwrap();
-        imp.formats.set(gdk4::ContentFormats::new(&mime_types.val)).unwrap();
+        let offered_mime_types = add_mime_type_aliases(&mime_types.val, can_convert_to_png);
+        imp.formats.set(gdk4::ContentFormats::new(&offered_mime_types)).unwrap();
+        imp.provided_mime_types
+            .set(mime_types.val.iter().map(|&m| m.to_owned()).collect())
+            .unwrap();
         imp.transfer_data_getter.get_or_init(|| transfer_data_getter);
         imp.event_handler.get_or_init(|| event_handler);
         obj
@@ -154,8 +171,8 @@ impl KdtClipboard {
         transfer_data_getter: TransferDataGetter,
     ) -> Self {
         let connect_changed_handler_id = gdk_clipboard.connect_changed(move |clipboard| {
-            let mime_types = clipboard.formats().mime_types().join(",");
-            send_event(event_handler, DataTransferAvailableEvent::new(clipboard_type, &mime_types));
+            let mime_types = clipboard.formats().mime_types();
+            DataTransferAvailableEvent::with(clipboard_type, &mime_types, |event| send_event(event_handler, event));
         });
         Self {
             gdk_clipboard,
@@ -169,13 +186,27 @@ impl KdtClipboard {
 
     pub fn paste(&self, serial: i32, mime_types: &MimeTypes) {
         debug!("KdtClipboard::paste start");
-        let mime_types = &mime_types.val;
         let event_handler = self.event_handler;
+        let available_mime_types = self.gdk_clipboard.formats().mime_types();
+        let Some((requested, received)) = negotiate_mime_type(mime_types.val.iter().copied(), &available_mime_types, can_convert_to_png)
+        else {
+            debug!("KdtClipboard::paste: clipboard content not supported, available: {available_mime_types:?}");
+            let event = DataTransferEvent {
+                serial,
+                content: DataTransferContent::null(),
+            };
+            send_event(event_handler, event);
+            return;
+        };
+        let mime_type = NegotiatedMimeType {
+            requested: requested.to_owned(),
+            received: received.to_owned(),
+        };
 
         self.gdk_clipboard
-            .read_async(mime_types, glib::Priority::DEFAULT, gio::Cancellable::NONE, move |res| match res {
-                Ok((input_stream, mime_type)) => {
-                    debug!("KdtClipboard::paste: reading {mime_type}");
+            .read_async(&[received], glib::Priority::DEFAULT, gio::Cancellable::NONE, move |res| match res {
+                Ok((input_stream, _)) => {
+                    debug!("KdtClipboard::paste: reading {mime_type:?}");
                     read_all(input_stream, mime_type, move |content| {
                         let content = content.unwrap_or(DataTransferContent::null());
                         let event = DataTransferEvent { serial, content };
@@ -212,7 +243,22 @@ impl KdtClipboard {
         Ok(())
     }
 
-    pub fn mime_types_csv(&self) -> String {
-        self.gdk_clipboard.formats().mime_types().join(",")
+    /// Hands the clipboard content to the clipboard manager, if we own the clipboard.
+    /// Blocks until the clipboard manager has read the content, or the `timeout` expires.
+    pub fn store(&self, timeout: Duration) {
+        if self.content_provider.is_none() {
+            return;
+        }
+        debug!("KdtClipboard::store: timeout={timeout:?}");
+        let store = self.gdk_clipboard.store_future(glib::Priority::DEFAULT);
+        match glib::MainContext::default().block_on(glib::future_with_timeout(timeout, store)) {
+            Ok(Ok(())) => debug!("KdtClipboard::store: done"),
+            Ok(Err(e)) => warn!("KdtClipboard::store: {e}"),
+            Err(_) => warn!("KdtClipboard::store: timed out"),
+        }
+    }
+
+    pub fn mime_types(&self) -> Vec<glib::GString> {
+        self.gdk_clipboard.formats().mime_types()
     }
 }
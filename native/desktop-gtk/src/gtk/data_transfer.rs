@@ -3,7 +3,10 @@ use crate::gtk::data_transfer_api::{DragAndDropAction, DragAndDropActions, DragA
 use crate::gtk::events::{DataTransferContent, DragAndDropLeaveEvent, DropPerformedEvent, EventHandler, WindowId};
 use crate::gtk::ffi_return_conversions::QueryDragAndDropTarget;
 use crate::gtk::geometry::{LogicalPixels, LogicalPoint};
+use crate::gtk::mime_types::{can_convert_to_png, convert_data_async};
 use desktop_common::ffi_utils::{BorrowedArray, BorrowedUtf8};
+use desktop_common::mime_types::{NegotiatedMimeType, ReceivedContent, is_text_mime_type, source_mime_type};
+use desktop_common::perf_trace::AsyncTraceSpan;
 use gtk4::gio::prelude::InputStreamExtManual;
 use gtk4::prelude::{IsA, WidgetExt};
 use gtk4::{gdk as gdk4, gio, glib};
@@ -11,7 +14,7 @@ use log::{debug, trace, warn};
 
 #[derive(Debug)]
 pub struct DragOfferMimetypeAndActions {
-    pub mime_type: Option<String>,
+    pub mime_type: Option<NegotiatedMimeType>,
     pub supported_actions: gdk4::DragAction,
     pub preferred_action: gdk4::DragAction,
 }
@@ -50,17 +53,36 @@ impl From<gdk4::DragAction> for DragAndDropActions {
     }
 }
 
+/// Converts the received data to the requested MIME type, see `ReceivedContent::new` for the text conversions.
+async fn received_content(mime_type: NegotiatedMimeType, data: Vec<u8>) -> anyhow::Result<ReceivedContent> {
+    let NegotiatedMimeType { requested, received } = mime_type;
+    if received == requested || is_text_mime_type(&received) {
+        Ok(ReceivedContent::new(&requested, &received, data))
+    } else {
+        let data = convert_data_async(received, requested.clone(), data).await?;
+        Ok(ReceivedContent::new(&requested, &requested, data))
+    }
+}
+
 fn read_all_recursive(
     res: Result<(Vec<u8>, usize), (Vec<u8>, glib::Error)>,
     input_stream: gio::InputStream,
-    mime_type: glib::GString,
+    mime_type: NegotiatedMimeType,
     mut buf_all: Vec<u8>,
     callback: impl FnOnce(Option<DataTransferContent>) + 'static,
 ) {
     match res {
         Ok((_chunk_buf, 0)) => {
             debug!("read_all end: size={}", buf_all.len());
-            callback(Some(DataTransferContent::new(mime_type.as_str(), &buf_all)));
+            glib::spawn_future_local(async move {
+                match received_content(mime_type, buf_all).await {
+                    Ok(content) => DataTransferContent::with_received(&content, |content| callback(Some(content))),
+                    Err(e) => {
+                        warn!("error converting the received data: {e}");
+                        callback(None);
+                    }
+                }
+            });
         }
         Ok((chunk_buf, size)) => {
             debug!("read_all loop: size={size}");
@@ -78,8 +100,17 @@ fn read_all_recursive(
     }
 }
 
-pub fn read_all(input_stream: gio::InputStream, mime_type: glib::GString, callback: impl FnOnce(Option<DataTransferContent>) + 'static) {
+pub fn read_all(
+    input_stream: gio::InputStream,
+    mime_type: NegotiatedMimeType,
+    callback: impl FnOnce(Option<DataTransferContent>) + 'static,
+) {
     debug!("read_all start");
+    let span = AsyncTraceSpan::new("data_transfer", "read_all");
+    let callback = move |content: Option<DataTransferContent>| {
+        drop(span);
+        callback(content);
+    };
     let buf_all: Vec<u8> = Vec::new();
     input_stream
         .clone()
@@ -107,14 +138,20 @@ pub fn get_drag_offer_actions(
     };
 
     query_drag_and_drop_target.with(&drag_and_drop_query_data, |target_info| {
-        let supported_mime_with_actions = target_info
-            .iter()
-            .find(|&e| mime_types.iter().any(|s| s == e.get_supported_mime_type().unwrap()));
+        let supported_mime_with_actions = target_info.iter().find_map(|e| {
+            let requested = e.get_supported_mime_type().unwrap();
+            let received = source_mime_type(requested, &mime_types, can_convert_to_png)?;
+            let mime_type = NegotiatedMimeType {
+                requested: requested.to_owned(),
+                received: received.to_owned(),
+            };
+            Some((e, mime_type))
+        });
         trace!("query_drag_and_drop_target -> {target_info:?}, supported_mime_with_actions={supported_mime_with_actions:?}");
 
-        if let Some(v) = supported_mime_with_actions {
+        if let Some((v, mime_type)) = supported_mime_with_actions {
             DragOfferMimetypeAndActions {
-                mime_type: Some(v.get_supported_mime_type().unwrap().to_owned()),
+                mime_type: Some(mime_type),
                 supported_actions: gdk4::DragAction::from(v.supported_actions),
                 preferred_action: gdk4::DragAction::from(v.preferred_action),
             }
@@ -152,7 +189,7 @@ pub fn handle_drop_target_drop(
         y: LogicalPixels(y),
     };
     let mime_type_and_actions = get_drag_offer_actions(query_drag_and_drop_target, drop, location_in_window, window_id);
-    let Some(mime_type) = mime_type_and_actions.mime_type.as_ref() else {
+    let Some(mime_type) = mime_type_and_actions.mime_type.clone() else {
         debug!("DropTarget::drop: no matching MIME type");
         send_event(
             event_handler,
@@ -169,18 +206,19 @@ pub fn handle_drop_target_drop(
 
     drop.status(mime_type_and_actions.supported_actions, mime_type_and_actions.preferred_action);
     debug!(
-        "DropTarget::drop: selected mime_type={mime_type}, supported_actions={:?}, preferred_action={:?}",
+        "DropTarget::drop: selected mime_type={mime_type:?}, supported_actions={:?}, preferred_action={:?}",
         mime_type_and_actions.supported_actions, mime_type_and_actions.preferred_action
     );
 
     let gtk_action = get_best_dnd_action(&mime_type_and_actions, drop.actions());
     let drop_clone = drop.clone();
+    let received_mime_type = mime_type.received.clone();
     drop.read_async(
-        &[mime_type],
+        &[&received_mime_type],
         glib::Priority::DEFAULT,
         gio::Cancellable::NONE,
         move |res| match res {
-            Ok((input_stream, mime_type)) => {
+            Ok((input_stream, _)) => {
                 read_all(input_stream, mime_type, move |data| {
                     let gtk_action = if data.is_some() { gtk_action } else { gdk4::DragAction::empty() };
 
@@ -0,0 +1,314 @@
+use crate::gtk::{
+    application_menu_api::AppMenuItemToggleState,
+    data_transfer_api::{DataSource, DragAndDropAction},
+    desktop_settings_api::{AccentColor, FfiDesktopSetting, FontHinting, FontRgbaOrder, XdgDesktopColorScheme},
+    events::{
+        AppMenuItemActivatedEvent, DataTransferAvailableEvent, DataTransferCancelledEvent, DataTransferContent, DataTransferEvent,
+        DragAndDropFeedbackFinishedEvent, DragAndDropFinishedEvent, DragAndDropLeaveEvent, DragIconDrawEvent, DropPerformedEvent, Event,
+        FileChooserResponse, KeyCode, KeyDownEvent, KeyModifiers, KeyUpEvent, ModifiersChangedEvent, MouseButton, MouseDownEvent,
+        MouseEnteredEvent, MouseExitedEvent, MouseMovedEvent, MouseUpEvent, NotificationClosedEvent, NotificationShownEvent,
+        OpenGlDrawData, RequestId, ScreenId, ScrollWheelEvent, TextInputDeleteSurroundingTextData, TextInputEvent,
+        TextInputPreeditAttribute, TextInputPreeditStringData, TextInputPreeditUnderlineType, Timestamp, WindowCaptureFrameResponse,
+        WindowClosedEvent, WindowConfigureEvent, WindowDecorationMode, WindowDrawEvent, WindowFrameTick, WindowId,
+        WindowKeyboardEnterEvent, WindowKeyboardLeaveEvent, WindowScaleChangedEvent, WindowScreenChangeEvent,
+    },
+    geometry::{LogicalPixels, LogicalPoint, LogicalSize, PhysicalPixels, PhysicalSize},
+};
+use desktop_common::{
+    event_recording::{RecordedValue, ReplayArena, Value, replay_field},
+    ffi_utils::BorrowedArray,
+    recorded_enum, recorded_newtype, recorded_struct, recorded_variants,
+};
+
+pub const EVENT_RECORDING_PLATFORM: &str = "gtk";
+
+recorded_newtype!(Timestamp);
+recorded_newtype!(ScreenId);
+recorded_newtype!(WindowId);
+recorded_newtype!(RequestId);
+recorded_newtype!(MouseButton);
+recorded_newtype!(KeyCode);
+recorded_newtype!(PhysicalPixels);
+recorded_newtype!(LogicalPixels);
+
+recorded_struct!(LogicalPoint { x, y });
+recorded_struct!(LogicalSize { width, height });
+recorded_struct!(PhysicalSize { width, height });
+
+impl<'a> RecordedValue<'a> for KeyModifiers {
+    fn record(&self) -> Value {
+        self.bits().record()
+    }
+
+    fn replay(value: &'a Value, arena: &'a ReplayArena) -> anyhow::Result<Self> {
+        Ok(Self::from_bits_retain(RecordedValue::replay(value, arena)?))
+    }
+}
+
+recorded_enum!(DataSource {
+    Clipboard,
+    DragAndDrop,
+    PrimarySelection
+});
+recorded_enum!(DragAndDropAction { None, Copy, Move });
+recorded_enum!(AppMenuItemToggleState { Off, On, Indeterminate });
+recorded_enum!(TextInputPreeditUnderlineType {
+    None,
+    Single,
+    Double,
+    Low,
+    Error
+});
+recorded_enum!(XdgDesktopColorScheme {
+    NoPreference,
+    PreferDark,
+    PreferLight
+});
+recorded_enum!(AccentColor {
+    Unknown,
+    Blue,
+    Teal,
+    Green,
+    Yellow,
+    Orange,
+    Red,
+    Pink,
+    Purple,
+    Slate
+});
+recorded_enum!(FontHinting {
+    None,
+    Slight,
+    Medium,
+    Full
+});
+recorded_enum!(FontRgbaOrder {
+    None,
+    Rgb,
+    Bgr,
+    Vrgb,
+    Vbgr
+});
+
+recorded_variants!(WindowDecorationMode {
+    unit: [Server],
+    data: [CustomTitlebar],
+});
+
+recorded_variants!(FfiDesktopSetting {
+    unit: [],
+    data: [
+        AccentColor,
+        AudibleBell,
+        ColorScheme,
+        CursorBlink,
+        CursorBlinkTimeMs,
+        CursorBlinkTimeoutSeconds,
+        DoubleClickIntervalMs,
+        DoubleClickDistancePixels,
+        DragAndDropDragThresholdPixels,
+        EnableAnimations,
+        FontHinting,
+        FontRgbaOrder,
+        IsComposited,
+        MiddleClickPaste,
+        OverlayScrolling,
+        PrimaryButtonWarpsSlider,
+        RecentFilesEnabled,
+        RecentFilesMaxAgeDays,
+    ],
+});
+
+recorded_struct!(DataTransferContent<'a> {
+    mime_type,
+    data,
+    kind,
+    text,
+    files,
+    image_format
+});
+recorded_struct!(DataTransferEvent<'a> { serial, content });
+recorded_struct!(DragAndDropLeaveEvent { window_id });
+recorded_struct!(DragAndDropFinishedEvent { window_id, action });
+recorded_struct!(DragAndDropFeedbackFinishedEvent { window_id });
+recorded_struct!(DropPerformedEvent<'a> {
+    window_id,
+    content,
+    action,
+    location_in_window
+});
+recorded_struct!(DataTransferAvailableEvent<'a> { data_source, mime_types, mime_types_list });
+recorded_struct!(DataTransferCancelledEvent { data_source });
+recorded_struct!(KeyDownEvent {
+    window_id,
+    code,
+    has_character,
+    character,
+    key
+});
+recorded_struct!(KeyUpEvent { window_id, code, key });
+recorded_struct!(ModifiersChangedEvent { modifiers });
+recorded_struct!(MouseEnteredEvent {
+    window_id,
+    location_in_window
+});
+recorded_struct!(MouseExitedEvent { window_id });
+recorded_struct!(MouseMovedEvent {
+    window_id,
+    location_in_window,
+    timestamp
+});
+recorded_struct!(MouseDownEvent {
+    window_id,
+    button,
+    location_in_window,
+    timestamp
+});
+recorded_struct!(MouseUpEvent {
+    window_id,
+    button,
+    location_in_window,
+    timestamp
+});
+recorded_struct!(ScrollWheelEvent {
+    window_id,
+    timestamp,
+    scroll_delta_x,
+    scroll_delta_y,
+    is_smooth_scroll,
+    is_stop
+});
+recorded_struct!(TextInputPreeditAttribute {
+    begin_byte_pos,
+    end_byte_pos,
+    underline,
+    foreground_highlight,
+    background_highlight,
+    strikethrough,
+    bold,
+    italic
+});
+recorded_struct!(TextInputPreeditStringData<'a> {
+    text,
+    cursor_byte_pos,
+    attributes
+});
+recorded_struct!(TextInputDeleteSurroundingTextData {
+    before_length_in_bytes,
+    after_length_in_bytes
+});
+recorded_struct!(TextInputEvent<'a> {
+    window_id,
+    has_preedit_string,
+    preedit_string,
+    has_commit_string,
+    commit_string,
+    has_delete_surrounding_text,
+    delete_surrounding_text
+});
+recorded_struct!(WindowClosedEvent { window_id });
+recorded_struct!(WindowConfigureEvent {
+    window_id,
+    size,
+    active,
+    maximized,
+    fullscreen,
+    decoration_mode,
+    inset_start,
+    inset_end
+});
+recorded_struct!(WindowFrameTick {
+    window_id,
+    frame_time_microseconds
+});
+recorded_struct!(OpenGlDrawData { framebuffer, is_es });
+recorded_struct!(WindowDrawEvent {
+    window_id,
+    opengl_draw_data,
+    physical_size
+});
+recorded_struct!(DragIconDrawEvent {
+    opengl_draw_data,
+    physical_size,
+    scale
+});
+recorded_struct!(WindowKeyboardEnterEvent { window_id });
+recorded_struct!(WindowKeyboardLeaveEvent { window_id });
+recorded_struct!(WindowScaleChangedEvent { window_id, new_scale });
+recorded_struct!(WindowScreenChangeEvent { window_id, new_screen_id });
+/// The `pixels` are not recorded, and are replayed as empty.
+impl<'a> RecordedValue<'a> for WindowCaptureFrameResponse<'a> {
+    fn record(&self) -> Value {
+        let mut fields = desktop_common::event_recording::Map::new();
+        fields.insert("request_id".to_owned(), self.request_id.record());
+        fields.insert("window_id".to_owned(), self.window_id.record());
+        fields.insert("physical_size".to_owned(), self.physical_size.record());
+        fields.insert("scale".to_owned(), self.scale.record());
+        fields.insert("error".to_owned(), self.error.record());
+        Value::Object(fields)
+    }
+
+    fn replay(value: &'a Value, arena: &'a ReplayArena) -> anyhow::Result<Self> {
+        Ok(Self {
+            request_id: replay_field(value, "request_id", arena)?,
+            window_id: replay_field(value, "window_id", arena)?,
+            physical_size: replay_field(value, "physical_size", arena)?,
+            scale: replay_field(value, "scale", arena)?,
+            pixels: BorrowedArray::from_slice(&[]),
+            error: replay_field(value, "error", arena)?,
+        })
+    }
+}
+
+recorded_struct!(FileChooserResponse<'a> {
+    request_id,
+    newline_separated_files
+});
+recorded_struct!(NotificationShownEvent {
+    request_id,
+    notification_id
+});
+recorded_struct!(NotificationClosedEvent<'a> {
+    notification_id,
+    action,
+    activation_token
+});
+recorded_struct!(AppMenuItemActivatedEvent { item_id, toggle_state });
+
+recorded_variants!(Event<'a> {
+    unit: [ApplicationStarted, DisplayConfigurationChange, DragIconFrameTick],
+    data: [
+        DesktopSettingChange,
+        DataTransfer,
+        DragAndDropLeave,
+        DragAndDropFinished,
+        DragAndDropFeedbackFinished,
+        DragIconDraw,
+        DropPerformed,
+        DataTransferAvailable,
+        DataTransferCancelled,
+        FileChooserResponse,
+        WindowCaptureFrameResponse,
+        NotificationShown,
+        NotificationClosed,
+        AppMenuItemActivated,
+        KeyDown,
+        KeyUp,
+        ModifiersChanged,
+        MouseEntered,
+        MouseExited,
+        MouseMoved,
+        MouseDown,
+        MouseUp,
+        WindowFrameTick,
+        ScrollWheel,
+        TextInput,
+        WindowClosed,
+        WindowConfigure,
+        WindowDraw,
+        WindowKeyboardEnter,
+        WindowKeyboardLeave,
+        WindowScaleChanged,
+        WindowScreenChange,
+    ],
+});
@@ -0,0 +1,50 @@
+use crate::gtk::{
+    event_recording::EVENT_RECORDING_PLATFORM,
+    events::{Event, EventHandler},
+};
+use desktop_common::{
+    event_recording::{RecordedValue, replay_events, start_event_recording, stop_event_recording},
+    ffi_utils::BorrowedUtf8,
+    logger::ffi_boundary,
+};
+use log::debug;
+use std::path::Path;
+
+/// Starts writing the events sent to the event handler to the file at `path`, e.g. to attach it to a bug report.
+/// Replaces the recording in progress, if any.
+///
+/// The draw events are not recorded.
+#[unsafe(no_mangle)]
+pub extern "C" fn event_recording_start(path: BorrowedUtf8) -> bool {
+    debug!("event_recording_start");
+    ffi_boundary("event_recording_start", || {
+        let path = path.get("event_recording_start: path")?;
+        start_event_recording(Path::new(path), EVENT_RECORDING_PLATFORM)?;
+        Ok(true)
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn event_recording_stop() {
+    debug!("event_recording_stop");
+    ffi_boundary("event_recording_stop", stop_event_recording);
+}
+
+/// Passes the events from the recording at `path` to `event_handler`, on the calling thread.
+/// Returns `false` if the recording couldn't be read, or one of its events couldn't be replayed.
+///
+/// * `speed`: `1.0` keeps the original timing, `2.0` replays twice as fast, and `0.0` replays without any delays.
+#[unsafe(no_mangle)]
+pub extern "C" fn event_recording_replay(path: BorrowedUtf8, speed: f64, event_handler: EventHandler) -> bool {
+    debug!("event_recording_replay: speed = {speed}");
+    ffi_boundary("event_recording_replay", || {
+        let path = path.get("event_recording_replay: path")?;
+        let count = replay_events(Path::new(path), EVENT_RECORDING_PLATFORM, speed, |value, arena| {
+            let event = Event::replay(value, arena)?;
+            event_handler(&event);
+            Ok(())
+        })?;
+        debug!("event_recording_replay: replayed {count} events");
+        Ok(true)
+    })
+}
@@ -1,4 +1,5 @@
 use crate::gtk::{
+    application_menu_api::{AppMenuItemId, AppMenuItemToggleState},
     data_transfer_api::{DataSource, DragAndDropAction},
     desktop_settings_api::FfiDesktopSetting,
     geometry::{LogicalPixels, LogicalPoint, LogicalSize, PhysicalSize},
@@ -7,6 +8,7 @@ use bitflag_attr::bitflag;
 use core::f64;
 use desktop_common::ffi_utils::{BorrowedArray, BorrowedUtf8};
 use desktop_common::logger::PanicDefault;
+use desktop_common::mime_types::{DataTransferContentKind, ReceivedContent};
 use std::ffi::c_int;
 
 pub type EventHandler = extern "C" fn(&Event) -> bool;
@@ -82,9 +84,22 @@ pub enum WindowDecorationMode {
 ///
 /// * `{ mime_type: "text/uri-list", data: "file:///data/some-file\r\nfile:///data/Some%20File%20With%20Spaces.txt\r\n" }`
 /// * `{ mime_type: "text/plain;charset=utf-8", data: "some text\r\nhere" }`
+///
+/// Text received in a different MIME type or charset (e.g. `UTF8_STRING` or `text/plain;charset=utf-16`)
+/// is converted to the requested one, and `text/uri-list` is decoded into `files`.
 pub struct DataTransferContent<'a> {
     pub mime_type: BorrowedUtf8<'a>,
     pub data: BorrowedArray<'a, u8>,
+    pub kind: DataTransferContentKind,
+
+    /// Set for `DataTransferContentKind::Text`.
+    pub text: BorrowedUtf8<'a>,
+
+    /// Local file paths, set for `DataTransferContentKind::Files`.
+    pub files: BorrowedArray<'a, BorrowedUtf8<'a>>,
+
+    /// Set for `DataTransferContentKind::Image`, e.g. `"png"` or `"jpeg"`.
+    pub image_format: BorrowedUtf8<'a>,
 }
 
 impl<'a> DataTransferContent<'a> {
@@ -93,6 +108,10 @@ impl<'a> DataTransferContent<'a> {
         Self {
             mime_type: BorrowedUtf8::new(mime_type),
             data: BorrowedArray::from_slice(data),
+            kind: DataTransferContentKind::Other,
+            text: BorrowedUtf8::null(),
+            files: BorrowedArray::null(),
+            image_format: BorrowedUtf8::null(),
         }
     }
 
@@ -101,8 +120,25 @@ impl<'a> DataTransferContent<'a> {
         Self {
             mime_type: BorrowedUtf8::null(),
             data: BorrowedArray::null(),
+            kind: DataTransferContentKind::Other,
+            text: BorrowedUtf8::null(),
+            files: BorrowedArray::null(),
+            image_format: BorrowedUtf8::null(),
         }
     }
+
+    /// Calls `f` with the content that borrows from the received `content`.
+    pub fn with_received<R>(content: &ReceivedContent, f: impl FnOnce(DataTransferContent) -> R) -> R {
+        let files = content.files.iter().map(|path| BorrowedUtf8::new(path)).collect::<Vec<_>>();
+        f(DataTransferContent {
+            mime_type: BorrowedUtf8::new(&content.mime_type),
+            data: BorrowedArray::from_slice(&content.data),
+            kind: content.kind,
+            text: content.text().map_or_else(BorrowedUtf8::null, BorrowedUtf8::new),
+            files: BorrowedArray::from_slice(&files),
+            image_format: content.image_format().map_or_else(BorrowedUtf8::null, BorrowedUtf8::new),
+        })
+    }
 }
 
 // TODO: figure out the cut events
@@ -175,7 +211,11 @@ impl<'a> From<DropPerformedEvent<'a>> for Event<'a> {
 #[derive(Debug)]
 pub struct DataTransferAvailableEvent<'a> {
     pub data_source: DataSource,
+    /// Comma-separated list of the MIME types.
+    ///
+    /// Deprecated: MIME types may contain commas, use `mime_types_list` instead.
     pub mime_types: BorrowedUtf8<'a>,
+    pub mime_types_list: BorrowedArray<'a, BorrowedUtf8<'a>>,
 }
 
 impl<'a> From<DataTransferAvailableEvent<'a>> for Event<'a> {
@@ -184,13 +224,15 @@ impl<'a> From<DataTransferAvailableEvent<'a>> for Event<'a> {
     }
 }
 
-impl<'a> DataTransferAvailableEvent<'a> {
-    #[must_use]
-    pub const fn new(data_source: DataSource, mime_types: &'a str) -> Self {
-        Self {
+impl DataTransferAvailableEvent<'_> {
+    pub fn with<T>(data_source: DataSource, mime_types: &[impl AsRef<str>], f: impl FnOnce(DataTransferAvailableEvent) -> T) -> T {
+        let mime_types_str = mime_types.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(",");
+        let mime_types_list = mime_types.iter().map(|m| BorrowedUtf8::new(m.as_ref())).collect::<Vec<_>>();
+        f(DataTransferAvailableEvent {
             data_source,
-            mime_types: BorrowedUtf8::new(mime_types),
-        }
+            mime_types: BorrowedUtf8::new(&mime_types_str),
+            mime_types_list: BorrowedArray::from_slice(&mime_types_list),
+        })
     }
 }
 
@@ -564,6 +606,31 @@ impl<'a> From<FileChooserResponse<'a>> for Event<'a> {
     }
 }
 
+#[repr(C)]
+#[derive(Debug)]
+pub struct WindowCaptureFrameResponse<'a> {
+    pub request_id: RequestId,
+    pub window_id: WindowId,
+
+    /// The size of the captured frame. Zero if the frame couldn't be captured.
+    pub physical_size: PhysicalSize,
+
+    /// The window scale when the frame was rendered.
+    pub scale: f64,
+
+    /// RGBA, 4 bytes per pixel, top row first, without any padding between the rows. Null if the frame couldn't be captured.
+    pub pixels: BorrowedArray<'a, u8>,
+
+    /// Optional. Present only if the frame couldn't be captured, e.g. when the window was closed before rendering it.
+    pub error: BorrowedUtf8<'a>,
+}
+
+impl<'a> From<WindowCaptureFrameResponse<'a>> for Event<'a> {
+    fn from(value: WindowCaptureFrameResponse<'a>) -> Self {
+        Self::WindowCaptureFrameResponse(value)
+    }
+}
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct NotificationShownEvent {
@@ -608,6 +675,21 @@ impl<'a> From<NotificationClosedEvent<'a>> for Event<'a> {
     }
 }
 
+#[repr(C)]
+#[derive(Debug)]
+pub struct AppMenuItemActivatedEvent {
+    pub item_id: AppMenuItemId,
+
+    /// The new state, for the check mark and radio items. The application should update the menu accordingly.
+    pub toggle_state: AppMenuItemToggleState,
+}
+
+impl From<AppMenuItemActivatedEvent> for Event<'_> {
+    fn from(value: AppMenuItemActivatedEvent) -> Self {
+        Self::AppMenuItemActivated(value)
+    }
+}
+
 #[repr(C)]
 #[derive(Debug)]
 pub enum Event<'a> {
@@ -643,9 +725,14 @@ pub enum Event<'a> {
 
     FileChooserResponse(FileChooserResponse<'a>),
 
+    /// The frame requested with `window_capture_frame`.
+    WindowCaptureFrameResponse(WindowCaptureFrameResponse<'a>),
+
     NotificationShown(NotificationShownEvent),
     NotificationClosed(NotificationClosedEvent<'a>),
 
+    AppMenuItemActivated(AppMenuItemActivatedEvent),
+
     /// Modifier keys (e.g., Ctrl, Shift, etc.) are never reported. Use `ModifiersChanged` for them.
     KeyDown(KeyDownEvent),
 
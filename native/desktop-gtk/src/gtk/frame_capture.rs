@@ -0,0 +1,64 @@
+use anyhow::ensure;
+use desktop_common::ffi_utils::{BorrowedArray, BorrowedUtf8};
+use log::error;
+
+use crate::gtk::application::send_event;
+use crate::gtk::events::{EventHandler, RequestId, WindowCaptureFrameResponse, WindowId};
+use crate::gtk::geometry::{PhysicalPixels, PhysicalSize};
+
+/// The rendered window content, requested with `window_capture_frame`.
+pub struct CapturedFrame {
+    pub physical_size: PhysicalSize,
+    pub scale: f64,
+
+    /// RGBA, top row first, without any padding between the rows.
+    pub pixels: Vec<u8>,
+}
+
+impl CapturedFrame {
+    /// The application renders with the top left origin, so unlike with the usual OpenGL convention,
+    /// the `glReadPixels` output already has the top row first.
+    pub fn from_gl_rgba(physical_size: PhysicalSize, scale: f64, pixels: Vec<u8>) -> anyhow::Result<Self> {
+        let width = usize::try_from(physical_size.width.0)?;
+        let height = usize::try_from(physical_size.height.0)?;
+        ensure!(width > 0 && height > 0, "The frame is empty: {physical_size:?}");
+        ensure!(
+            pixels.len() == width * height * 4,
+            "Unexpected pixels size for {physical_size:?}: {}",
+            pixels.len()
+        );
+        Ok(Self {
+            physical_size,
+            scale,
+            pixels,
+        })
+    }
+}
+
+pub fn send_window_capture_frame_response(
+    event_handler: EventHandler,
+    request_id: RequestId,
+    window_id: WindowId,
+    result: &anyhow::Result<CapturedFrame>,
+) {
+    let error = result.as_ref().err().map(|e| {
+        error!("Error capturing the frame of {window_id:?} for {request_id:?}: {e}");
+        format!("{e:#}")
+    });
+    let frame = result.as_ref().ok();
+    let response = WindowCaptureFrameResponse {
+        request_id,
+        window_id,
+        physical_size: frame.map_or(
+            PhysicalSize {
+                width: PhysicalPixels(0),
+                height: PhysicalPixels(0),
+            },
+            |frame| frame.physical_size,
+        ),
+        scale: frame.map_or(0.0, |frame| frame.scale),
+        pixels: frame.map_or_else(BorrowedArray::null, |frame| BorrowedArray::from_slice(&frame.pixels)),
+        error: BorrowedUtf8::optional(error.as_ref()),
+    };
+    send_event(event_handler, response);
+}
@@ -3,8 +3,9 @@ use gtk4::prelude::{NativeExt, SurfaceExt};
 
 use crate::gtk::application_api::{RenderingMode, gl_get_proc_address_impl};
 use crate::gtk::events::OpenGlDrawData;
+use crate::gtk::frame_capture::CapturedFrame;
 use crate::gtk::geometry::{LogicalSize, PhysicalSize};
-use anyhow::bail;
+use anyhow::{Context, anyhow, bail};
 use gtk4::gdk as gdk4;
 use gtk4::glib;
 use gtk4::prelude::{GLContextExt, SnapshotExt, WidgetExt};
@@ -75,6 +76,9 @@ pub struct GlFuncs {
     pub CheckFramebufferStatus: extern "C" fn(target: c_uint) -> c_uint,
     pub DeleteTextures: extern "C" fn(n: c_int, textures: *const c_uint),
     pub DeleteFramebuffers: extern "C" fn(n: c_int, framebuffers: *const c_uint),
+    pub ReadPixels:
+        extern "C" fn(x: c_int, y: c_int, width: c_int, height: c_int, format: c_uint, type_: c_uint, data: *mut std::ffi::c_void),
+    pub GetError: extern "C" fn() -> c_uint,
 }
 
 impl GlFuncs {
@@ -89,6 +93,8 @@ impl GlFuncs {
             CheckFramebufferStatus: get_egl_f("glCheckFramebufferStatus").unwrap(),
             DeleteTextures: get_egl_f("glDeleteTextures").unwrap(),
             DeleteFramebuffers: get_egl_f("glDeleteFramebuffers").unwrap(),
+            ReadPixels: get_egl_f("glReadPixels").unwrap(),
+            GetError: get_egl_f("glGetError").unwrap(),
         }
     }
 
@@ -141,10 +147,49 @@ pub struct GlRenderData {
     pub framebuffer_id: c_uint,
     pub texture_id: c_uint,
     pub texture_size: PhysicalSize,
+    /// The scale of the frame in the texture, if any was rendered since the texture was resized.
+    pub rendered_scale: Option<f64>,
     // pub frame_clock: Option<gdk4::FrameClock>,
     pub gl: GlFuncs,
 }
 
+impl GlRenderData {
+    fn read_pixels(&self) -> anyhow::Result<CapturedFrame> {
+        const GL_UNSIGNED_BYTE: c_uint = 0x1401;
+        const GL_RGBA: c_uint = 0x1908;
+        const GL_NO_ERROR: c_uint = 0;
+        const GL_READ_FRAMEBUFFER: c_uint = 0x8CA8;
+        const GL_READ_FRAMEBUFFER_BINDING: c_uint = 0x8CAA;
+
+        let gl = &self.gl;
+        let scale = self.rendered_scale.context("No frame was rendered")?;
+        let size = self.texture_size;
+        let len = usize::try_from(size.width.0)? * usize::try_from(size.height.0)? * 4;
+        let mut pixels = vec![0_u8; len];
+
+        self.context.make_current();
+        let mut read_framebuffer = 0;
+        (gl.GetIntegerv)(GL_READ_FRAMEBUFFER_BINDING, &raw mut read_framebuffer);
+        (gl.BindFramebuffer)(GL_READ_FRAMEBUFFER, self.framebuffer_id);
+        (gl.ReadPixels)(
+            0,
+            0,
+            size.width.0,
+            size.height.0,
+            GL_RGBA,
+            GL_UNSIGNED_BYTE,
+            pixels.as_mut_ptr().cast(),
+        );
+        let error = (gl.GetError)();
+        (gl.BindFramebuffer)(GL_READ_FRAMEBUFFER, c_uint::try_from(read_framebuffer).unwrap_or(0));
+        if error != GL_NO_ERROR {
+            bail!("glReadPixels failed with {error:#x}");
+        }
+
+        CapturedFrame::from_gl_rgba(size, scale, pixels)
+    }
+}
+
 impl Drop for GlRenderData {
     fn drop(&mut self) {
         let gl = &self.gl;
@@ -162,6 +207,7 @@ impl Drop for GlRenderData {
 
 type OnAllocate = Box<dyn Fn(LogicalSize)>;
 type OnDraw = Box<dyn Fn(OpenGlDrawData, PhysicalSize, f64)>;
+type OnFrameCaptured = Box<dyn FnOnce(&anyhow::Result<CapturedFrame>)>;
 
 #[derive(Default)]
 pub struct GlWidgetImpl {
@@ -169,6 +215,16 @@ pub struct GlWidgetImpl {
     pub rendering_mode: OnceCell<RenderingMode>,
     pub on_allocate: OnceCell<OnAllocate>,
     pub on_draw: OnceCell<OnDraw>,
+    pending_frame_captures: RefCell<Vec<OnFrameCaptured>>,
+}
+
+impl GlWidgetImpl {
+    fn finish_frame_captures(&self, result: &anyhow::Result<CapturedFrame>) {
+        let pending_frame_captures = self.pending_frame_captures.take();
+        for on_captured in pending_frame_captures {
+            on_captured(result);
+        }
+    }
 }
 
 #[glib::object_subclass]
@@ -222,6 +278,7 @@ impl gtk4::subclass::widget::WidgetImpl for GlWidgetImpl {
             framebuffer_id,
             texture_id,
             texture_size: physical_size,
+            rendered_scale: None,
             gl,
         };
         *self.data.borrow_mut() = Some(data);
@@ -259,6 +316,7 @@ impl gtk4::subclass::widget::WidgetImpl for GlWidgetImpl {
 
             if data.texture_size != physical_size {
                 data.texture_size = physical_size;
+                data.rendered_scale = None;
                 gl.resize_texture(data.texture_id, physical_size, context.uses_es());
             }
 
@@ -279,15 +337,23 @@ impl gtk4::subclass::widget::WidgetImpl for GlWidgetImpl {
         let on_draw = self.on_draw.get().unwrap();
         on_draw(opengl_draw_data, physical_size, scale);
 
-        let data_borrow = self.data.borrow();
-        let Some(data) = data_borrow.as_ref() else { return };
-        let gl_texture = unsafe { gdk4::GLTexture::new(&data.context, data.texture_id, physical_size.width.0, physical_size.height.0) };
-        snapshot.append_texture(&gl_texture, &gtk4::graphene::Rect::from(logical_size));
+        let captured = {
+            let mut data_borrow = self.data.borrow_mut();
+            let Some(data) = data_borrow.as_mut() else { return };
+            data.rendered_scale = Some(scale);
+            let gl_texture = unsafe { gdk4::GLTexture::new(&data.context, data.texture_id, physical_size.width.0, physical_size.height.0) };
+            snapshot.append_texture(&gl_texture, &gtk4::graphene::Rect::from(logical_size));
+            (!self.pending_frame_captures.borrow().is_empty()).then(|| data.read_pixels())
+        };
+        if let Some(result) = captured {
+            self.finish_frame_captures(&result);
+        }
     }
 
     fn unrealize(&self) {
         debug!("GlWidget::unrealize");
         *self.data.borrow_mut() = None;
+        self.finish_frame_captures(&Err(anyhow!("The window was closed before rendering a frame")));
         self.parent_unrealize();
     }
 }
@@ -320,4 +386,22 @@ impl GlWidget {
 
         obj
     }
+
+    /// Reads back the last rendered frame, or the next one if nothing has been rendered yet.
+    pub fn capture_frame(&self, on_captured: impl FnOnce(&anyhow::Result<CapturedFrame>) + 'static) {
+        let imp = self.imp();
+        let result = imp
+            .data
+            .borrow()
+            .as_ref()
+            .filter(|data| data.rendered_scale.is_some())
+            .map(GlRenderData::read_pixels);
+        if let Some(result) = result {
+            on_captured(&result);
+        } else {
+            debug!("Capturing the next frame");
+            imp.pending_frame_captures.borrow_mut().push(Box::new(on_captured));
+            self.queue_draw();
+        }
+    }
 }
@@ -0,0 +1,77 @@
+use crate::gtk::launcher_entry_api::LauncherEntryParams;
+use gtk4::prelude::ToVariant;
+use gtk4::{gio, glib};
+use log::warn;
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+const LAUNCHER_ENTRY_INTERFACE: &str = "com.canonical.Unity.LauncherEntry";
+
+#[derive(Debug, Clone)]
+pub struct LauncherEntry {
+    /// `application://<desktop file id>.desktop`
+    pub app_uri: String,
+    pub count: i64,
+    pub count_visible: bool,
+    pub progress: f64,
+    pub progress_visible: bool,
+    pub urgent: bool,
+}
+
+impl LauncherEntry {
+    pub fn from_unsafe(params: &LauncherEntryParams) -> anyhow::Result<Self> {
+        let desktop_file_id = params.desktop_file_id.get("LauncherEntryParams: desktop_file_id")?;
+        let app_uri = if desktop_file_id.ends_with(".desktop") {
+            format!("application://{desktop_file_id}")
+        } else {
+            format!("application://{desktop_file_id}.desktop")
+        };
+        Ok(Self {
+            app_uri,
+            count: params.count,
+            count_visible: params.count_visible,
+            progress: params.progress.clamp(0.0, 1.0),
+            progress_visible: params.progress_visible,
+            urgent: params.urgent,
+        })
+    }
+
+    /// The object path doesn't matter to the listeners, but it should be unique per application.
+    fn object_path(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.app_uri.hash(&mut hasher);
+        format!("/com/canonical/unity/launcherentry/{}", hasher.finish())
+    }
+
+    fn parameters(&self) -> glib::Variant {
+        let properties = HashMap::from([
+            ("count", self.count.to_variant()),
+            ("count-visible", self.count_visible.to_variant()),
+            ("progress", self.progress.to_variant()),
+            ("progress-visible", self.progress_visible.to_variant()),
+            ("urgent", self.urgent.to_variant()),
+        ]);
+        (self.app_uri.as_str(), properties).to_variant()
+    }
+
+    pub fn emit_update(self) {
+        gio::bus_get(gio::BusType::Session, gio::Cancellable::NONE, move |result| match result {
+            Ok(connection) => {
+                if let Err(e) = connection.emit_signal(
+                    None,
+                    &self.object_path(),
+                    LAUNCHER_ENTRY_INTERFACE,
+                    "Update",
+                    Some(&self.parameters()),
+                ) {
+                    warn!("Error updating the launcher entry: {e}");
+                }
+            }
+            Err(e) => {
+                warn!("Error establishing DBus session connection: {e}");
+            }
+        });
+    }
+}
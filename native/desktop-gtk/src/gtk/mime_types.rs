@@ -1,3 +1,9 @@
+use anyhow::{Context, anyhow, bail};
+use desktop_common::ffi_utils::{BorrowedArray, BorrowedUtf8};
+use desktop_common::mime_types::{PNG_MIME_TYPE, convert_text, is_image_mime_type, is_text_mime_type};
+use gtk4::gdk::gdk_pixbuf::{self, prelude::PixbufLoaderExt};
+use gtk4::gio;
+
 #[derive(Debug)]
 pub struct MimeTypes<'a> {
     pub val: Box<[&'a str]>,
@@ -13,4 +19,46 @@ impl<'a> MimeTypes<'a> {
             }
         }
     }
+
+    pub fn from_array(mime_types: &'a BorrowedArray<'a, BorrowedUtf8<'a>>, context: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            val: mime_types.get_strs(context)?,
+        })
+    }
+}
+
+/// Any image format supported by gdk-pixbuf can be converted to PNG.
+pub fn can_convert_to_png(mime_type: &str) -> bool {
+    is_image_mime_type(mime_type)
+}
+
+fn image_to_png(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let loader = gdk_pixbuf::PixbufLoader::new();
+    loader.write(data)?;
+    loader.close()?;
+    let pixbuf = loader.pixbuf().context("Couldn't decode the image")?;
+    Ok(pixbuf.save_to_bufferv("png", &[])?)
+}
+
+/// Converts the data between the MIME types matched by `desktop_common::mime_types::source_mime_type`.
+fn convert_data(from_mime_type: &str, to_mime_type: &str, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+    if from_mime_type == to_mime_type {
+        Ok(data)
+    } else if is_text_mime_type(from_mime_type) && is_text_mime_type(to_mime_type) {
+        Ok(convert_text(from_mime_type, to_mime_type, data))
+    } else if to_mime_type == PNG_MIME_TYPE && can_convert_to_png(from_mime_type) {
+        image_to_png(&data)
+    } else {
+        bail!("Conversion from {from_mime_type} to {to_mime_type} is not supported")
+    }
+}
+
+/// Converting images can take a while, so it's done on a blocking thread, instead of the main thread.
+pub async fn convert_data_async(from_mime_type: String, to_mime_type: String, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+    if from_mime_type == to_mime_type {
+        return Ok(data);
+    }
+    gio::spawn_blocking(move || convert_data(&from_mime_type, &to_mime_type, data))
+        .await
+        .map_err(|_| anyhow!("Converting the data panicked"))?
 }
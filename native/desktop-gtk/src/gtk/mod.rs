@@ -1,5 +1,7 @@
 mod application;
 pub mod application_api;
+mod application_menu;
+pub mod application_menu_api;
 mod application_state;
 mod async_event_result;
 mod clipboard;
@@ -7,17 +9,23 @@ mod data_transfer;
 pub mod data_transfer_api;
 mod desktop_settings;
 pub mod desktop_settings_api;
+mod event_recording;
+pub mod event_recording_api;
 pub mod events;
 mod ffi_return_conversions;
 mod file_dialog;
 pub mod file_dialog_api;
+mod frame_capture;
 pub mod geometry;
 mod gl_widget;
 mod keyboard;
+mod launcher_entry;
+pub mod launcher_entry_api;
 mod layout_manager_wrapper;
 mod mime_types;
 mod mouse;
 mod notifications;
+pub mod perf_trace_api;
 mod pointer_shapes;
 pub mod pointer_shapes_api;
 pub mod screen;
@@ -1,4 +1,7 @@
-use desktop_common::{ffi_utils::RustAllocatedStrPtr, logger::ffi_boundary};
+use desktop_common::{
+    ffi_utils::{AutoDropArray, AutoDropStrPtr, RustAllocatedStrPtr},
+    logger::ffi_boundary,
+};
 
 #[unsafe(no_mangle)]
 pub extern "C" fn string_drop(mut str_ptr: RustAllocatedStrPtr) {
@@ -7,3 +10,11 @@ pub extern "C" fn string_drop(mut str_ptr: RustAllocatedStrPtr) {
         Ok(())
     });
 }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn string_array_drop(str_array: AutoDropArray<AutoDropStrPtr>) {
+    ffi_boundary("string_array_drop", || {
+        drop(str_array);
+        Ok(())
+    });
+}
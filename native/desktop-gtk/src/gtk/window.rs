@@ -7,6 +7,7 @@ use crate::gtk::events::{
     WindowKeyboardEnterEvent, WindowKeyboardLeaveEvent, WindowScaleChangedEvent, WindowScreenChangeEvent,
 };
 use crate::gtk::ffi_return_conversions::{QueryDragAndDropTarget, RetrieveSurroundingText};
+use crate::gtk::frame_capture::CapturedFrame;
 use crate::gtk::geometry::{LogicalRect, LogicalSize, PhysicalSize};
 use crate::gtk::gl_widget::GlWidget;
 use crate::gtk::keyboard::set_keyboard_event_handlers;
@@ -15,7 +16,7 @@ use crate::gtk::mouse::set_mouse_event_handlers;
 use crate::gtk::pointer_shapes_api::PointerShape;
 use crate::gtk::text_input::create_im_context;
 use crate::gtk::text_input_api::TextInputContext;
-use anyhow::Context;
+use anyhow::{Context, anyhow};
 use gtk4::gdk as gdk4;
 use gtk4::glib;
 use gtk4::prelude::{
@@ -317,6 +318,8 @@ impl SimpleWindow {
     ) -> Self {
         let mut window_builder = gtk4::ApplicationWindow::builder()
             .application(gtk_application)
+            // The application menu is only exported for the global menu, see `application_menu_update`
+            .show_menubar(false)
             .resizable(true)
             .default_width(size.width)
             .default_height(size.height);
@@ -453,6 +456,18 @@ impl SimpleWindow {
         });
     }
 
+    pub fn capture_frame(&self, on_captured: impl FnOnce(&anyhow::Result<CapturedFrame>) + 'static) {
+        // Do this async, so that the response is never reported before the request ID is returned.
+        let gl_widget = self.gl_widget.clone();
+        glib::idle_add_local_once(move || {
+            if let Some(gl_widget) = gl_widget.upgrade() {
+                gl_widget.capture_frame(on_captured);
+            } else {
+                on_captured(&Err(anyhow!("The window was closed before rendering a frame")));
+            }
+        });
+    }
+
     pub fn maximize(&self) {
         if let Some(window) = self.window.upgrade() {
             window.maximize();
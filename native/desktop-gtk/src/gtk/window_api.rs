@@ -10,7 +10,7 @@ use crate::gtk::pointer_shapes_api::PointerShape;
 use crate::gtk::text_input_api::TextInputContext;
 
 use desktop_common::{
-    ffi_utils::BorrowedUtf8,
+    ffi_utils::{BorrowedArray, BorrowedUtf8},
     logger::{PanicDefault, ffi_boundary},
 };
 use log::debug;
@@ -137,6 +137,7 @@ pub extern "C" fn window_unset_fullscreen(window_id: WindowId) {
     });
 }
 
+#[deprecated(note = "use window_start_drag_and_drop_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn window_start_drag_and_drop(
     window_id: WindowId,
@@ -160,6 +161,21 @@ pub extern "C" fn window_start_drag_and_drop(
     });
 }
 
+#[unsafe(no_mangle)]
+pub extern "C" fn window_start_drag_and_drop_v2(
+    window_id: WindowId,
+    mime_types: BorrowedArray<BorrowedUtf8>,
+    actions: DragAndDropActions,
+    drag_icon_rendering_mode: RenderingMode,
+    drag_icon_size: LogicalSize,
+) {
+    debug!("window_start_drag_and_drop_v2: {window_id:?}, {mime_types:?}, {actions:?}");
+    ffi_boundary("window_start_drag_and_drop_v2", || {
+        let mime_types = MimeTypes::from_array(&mime_types, "window_start_drag_and_drop_v2: mime_types")?;
+        with_app_state(|app| app.start_drag(window_id, &mime_types, actions, drag_icon_rendering_mode, drag_icon_size))
+    });
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn window_show_open_file_dialog(
     window_id: WindowId,
@@ -184,6 +200,15 @@ pub extern "C" fn window_show_save_file_dialog(
     })
 }
 
+/// Captures the rendered window content, e.g. for the screenshot tests. The result is reported with `Event::WindowCaptureFrameResponse`.
+///
+/// It's the last rendered frame, read with `glReadPixels`, or the next frame if nothing has been rendered yet.
+#[unsafe(no_mangle)]
+pub extern "C" fn window_capture_frame(window_id: WindowId) -> RequestId {
+    debug!("window_capture_frame for {window_id:?}");
+    ffi_boundary("window_capture_frame", || with_app_state(|app| app.window_capture_frame(window_id)))
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn window_activate(window_id: WindowId, token: BorrowedUtf8) {
     debug!("window_activate: {window_id:?}");
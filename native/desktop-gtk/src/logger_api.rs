@@ -1,4 +1,4 @@
-use desktop_common::logger_api::{ExceptionsArray, LoggerConfiguration};
+use desktop_common::logger_api::{ExceptionsArray, LoggerConfiguration, LoggerLevels};
 
 #[unsafe(no_mangle)]
 pub extern "C" fn logger_check_exceptions() -> ExceptionsArray {
@@ -14,3 +14,13 @@ pub extern "C" fn logger_clear_exceptions() {
 pub extern "C" fn logger_init(logger_configuration: &LoggerConfiguration) {
     desktop_common::logger_api::logger_init_impl(logger_configuration);
 }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn logger_set_levels(logger_levels: &LoggerLevels) {
+    desktop_common::logger_api::logger_set_levels_impl(logger_levels);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn logger_set_fail_fast(enabled: bool) {
+    desktop_common::logger_api::logger_set_fail_fast_impl(enabled);
+}
@@ -19,16 +19,20 @@ use desktop_linux::linux::{
         DragAndDropAction,
         DragAndDropActions,
         DragAndDropQueryData,
+        NotificationActionButton,
+        NotificationImage,
+        NotificationParams,
+        NotificationUrgency,
         RenderingMode,
-        application_clipboard_paste,
-        application_clipboard_put,
+        application_clipboard_paste_v2,
+        application_clipboard_put_v2,
         application_close_notification,
         application_init,
         application_is_event_loop_thread,
         application_open_file_manager,
         application_open_url,
-        application_primary_selection_paste,
-        application_request_show_notification,
+        application_primary_selection_paste_v2,
+        application_request_show_notification_with_params,
         application_run_event_loop,
         application_set_cursor_theme,
         application_shutdown,
@@ -38,9 +42,13 @@ use desktop_linux::linux::{
         application_text_input_update,
         //
     },
+    application_menu_api::{
+        AppMenuItem, AppMenuItemId, AppMenuItemState, AppMenuItemToggleState, AppMenuItemToggleType, AppMenuStructure,
+        application_menu_update,
+    },
     desktop_settings_api::FfiDesktopSetting,
     events::{DataTransferContent, Event, KeyDownEvent, KeyModifiers, RequestId, TextInputEvent, WindowId},
-    file_dialog_api::{CommonFileDialogParams, OpenFileDialogParams, SaveFileDialogParams},
+    file_dialog_api::{CommonFileDialogParams, FileDialogChoice, FileDialogFilter, OpenFileDialogParams, SaveFileDialogParams},
     geometry::{LogicalRect, LogicalSize},
     text_input_api::{TextInputContentHints, TextInputContentPurpose, TextInputContext},
     window_api::{
@@ -51,7 +59,7 @@ use desktop_linux::linux::{
         window_request_internal_activation_token,
         window_show_open_file_dialog,
         window_show_save_file_dialog,
-        window_start_drag_and_drop,
+        window_start_drag_and_drop_v2,
         //
     },
 };
@@ -63,9 +71,14 @@ const APP_ID: &str = "org.jetbrains.desktop.linux.native.sample1";
 const TEXT_MIME_TYPE: &str = "text/plain;charset=utf-8";
 const URI_LIST_MIME_TYPE: &str = "text/uri-list";
 
-const ALL_MIMES: &str = "text/uri-list,text/plain;charset=utf-8";
+const ALL_MIMES: &[&str] = &[URI_LIST_MIME_TYPE, TEXT_MIME_TYPE];
 const DRAG_ICON_WINDOW_ID: WindowId = WindowId(-1);
 
+const MENU_ITEM_QUIT: AppMenuItemId = 1;
+const MENU_ITEM_ANIMATION: AppMenuItemId = 2;
+
+const NOTIFICATION_ACTION_DISMISS: &str = "dismiss";
+
 #[derive(Debug, Default)]
 struct OptionalAppPtr(Option<AppPtr<'static>>);
 
@@ -123,6 +136,7 @@ struct State {
     notification_sources: HashMap<u32, WindowId>,
     activation_token_action: HashMap<RequestId, ActivationTokenAction>,
     data_request_sources: HashMap<i32, WindowId>,
+    animation_paused: bool,
 }
 
 thread_local! {
@@ -246,29 +260,76 @@ fn on_keydown(event: &KeyDownEvent, app_ptr: AppPtr<'_>, state: &mut State) -> b
             true
         }
         (KEY_MODIFIER_CTRL, keycode::KeyMappingCode::KeyV) => {
-            application_clipboard_paste(app_ptr, state.add_data_request_source(window_id), BorrowedUtf8::new(TEXT_MIME_TYPE));
+            application_clipboard_paste_v2(
+                app_ptr,
+                state.add_data_request_source(window_id),
+                BorrowedArray::from_slice(&[BorrowedUtf8::new(TEXT_MIME_TYPE)]),
+            );
             true
         }
         (KEY_MODIFIER_CTRL, keycode::KeyMappingCode::KeyC) => {
-            application_clipboard_put(app_ptr, BorrowedUtf8::new(ALL_MIMES));
+            let mime_types = ALL_MIMES.iter().map(|m| BorrowedUtf8::new(m)).collect::<Vec<_>>();
+            application_clipboard_put_v2(app_ptr, BorrowedArray::from_slice(&mime_types));
             true
         }
         (KEY_MODIFIER_CTRL, keycode::KeyMappingCode::KeyP) => {
             let title = format!("Notification from window {}", window_id.0);
             let body = format!("Clicking this notification will activate window {}", window_id.0);
-            let request_id =
-                application_request_show_notification(app_ptr, BorrowedUtf8::new(&title), BorrowedUtf8::new(&body), BorrowedUtf8::null());
+            let actions = [NotificationActionButton {
+                key: BorrowedUtf8::new(NOTIFICATION_ACTION_DISMISS),
+                label: BorrowedUtf8::new("Dismiss"),
+            }];
+            let params = NotificationParams {
+                title: BorrowedUtf8::new(&title),
+                body: BorrowedUtf8::new(&body),
+                sound_file_path: BorrowedUtf8::null(),
+                actions: BorrowedArray::from_slice(&actions),
+                app_icon: BorrowedUtf8::null(),
+                image: NotificationImage {
+                    width: 0,
+                    height: 0,
+                    rgba_data: BorrowedArray::null(),
+                },
+                urgency: NotificationUrgency::Normal,
+                category: BorrowedUtf8::null(),
+                expire_timeout_ms: -1,
+                replaces_id: 0,
+            };
+            let request_id = application_request_show_notification_with_params(app_ptr, params);
             if request_id.0 != 0 {
                 state.request_sources.insert(request_id, window_id);
             }
             true
         }
         (KEY_MODIFIER_CTRL, keycode::KeyMappingCode::KeyO) => {
+            let conf_globs = [BorrowedUtf8::new("*.conf"), BorrowedUtf8::new("*.cfg")];
+            let text_mime_types = [BorrowedUtf8::new("text/plain")];
+            let filters = [
+                FileDialogFilter {
+                    name: BorrowedUtf8::new("Configuration files"),
+                    globs: BorrowedArray::from_slice(&conf_globs),
+                    mime_types: BorrowedArray::null(),
+                },
+                FileDialogFilter {
+                    name: BorrowedUtf8::new("Text files"),
+                    globs: BorrowedArray::null(),
+                    mime_types: BorrowedArray::from_slice(&text_mime_types),
+                },
+            ];
+            let choices = [FileDialogChoice {
+                id: BorrowedUtf8::new("new-window"),
+                label: BorrowedUtf8::new("Open in new window"),
+                options: BorrowedArray::null(),
+                initial_selection: BorrowedUtf8::new("false"),
+            }];
             let common_params = CommonFileDialogParams {
                 modal: false,
                 title: BorrowedUtf8::new("Open File for Linux Native Sample App test"),
                 accept_label: BorrowedUtf8::new("Let's go!"),
                 current_folder: BorrowedUtf8::new("/etc"),
+                filters: BorrowedArray::from_slice(&filters),
+                current_filter_index: 0,
+                choices: BorrowedArray::from_slice(&choices),
             };
             let open_params = OpenFileDialogParams {
                 select_directories: false,
@@ -285,6 +346,9 @@ fn on_keydown(event: &KeyDownEvent, app_ptr: AppPtr<'_>, state: &mut State) -> b
                 title: BorrowedUtf8::new("Save File for Linux Native Sample App test"),
                 accept_label: BorrowedUtf8::new("Let's go!"),
                 current_folder: BorrowedUtf8::new("/tmp"),
+                filters: BorrowedArray::null(),
+                current_filter_index: -1,
+                choices: BorrowedArray::null(),
             };
             let save_params = SaveFileDialogParams {
                 name_field_string_value: BorrowedUtf8::new("file from Linux Native Sample App.txt"),
@@ -417,7 +481,63 @@ fn on_data_transfer_received(content: &DataTransferContent, window_state: &mut W
     window_state.drag_and_drop_target = false;
 }
 
+fn update_app_menu(state: &State) {
+    let animation_toggle_state = if state.animation_paused {
+        AppMenuItemToggleState::Off
+    } else {
+        AppMenuItemToggleState::On
+    };
+    let file_items = [AppMenuItem::ActionItem {
+        item_id: MENU_ITEM_QUIT,
+        title: BorrowedUtf8::new("_Quit"),
+        enabled: true,
+        toggle_type: AppMenuItemToggleType::None,
+        toggle_state: AppMenuItemToggleState::Off,
+        shortcut: BorrowedUtf8::new("<Control>q"),
+    }];
+    let view_items = [AppMenuItem::ActionItem {
+        item_id: MENU_ITEM_ANIMATION,
+        title: BorrowedUtf8::new("_Animation"),
+        enabled: true,
+        toggle_type: AppMenuItemToggleType::CheckMark,
+        toggle_state: animation_toggle_state,
+        shortcut: BorrowedUtf8::null(),
+    }];
+    let items = [
+        AppMenuItem::SubMenuItem {
+            title: BorrowedUtf8::new("_File"),
+            items: BorrowedArray::from_slice(&file_items),
+        },
+        AppMenuItem::SubMenuItem {
+            title: BorrowedUtf8::new("_View"),
+            items: BorrowedArray::from_slice(&view_items),
+        },
+    ];
+    application_menu_update(
+        state.app_ptr.get(),
+        AppMenuStructure {
+            items: BorrowedArray::from_slice(&items),
+        },
+    );
+}
+
+extern "C" fn query_app_menu_item_state(item_id: AppMenuItemId) -> AppMenuItemState {
+    STATE.with_borrow(|state| {
+        let toggle_state = if item_id == MENU_ITEM_ANIMATION && !state.animation_paused {
+            AppMenuItemToggleState::On
+        } else {
+            AppMenuItemToggleState::Off
+        };
+        AppMenuItemState {
+            enabled: true,
+            toggle_state,
+        }
+    })
+}
+
 fn on_application_started(state: &mut State) {
+    update_app_menu(state);
+
     let window_1_id = WindowId(1);
     window_create(
         state.app_ptr.get(),
@@ -503,7 +623,9 @@ extern "C" fn event_handler(event: &Event) -> bool {
                     {
                         debug!("different draw data: {event:?}");
                     }
-                    window_state.animation_tick();
+                    if !state.animation_paused {
+                        window_state.animation_tick();
+                    }
 
                     if data.software_draw_data.canvas.is_null() {
                         draw_opengl_triangle_with_init(data.physical_size, data.window_id, window_state);
@@ -548,14 +670,15 @@ extern "C" fn event_handler(event: &Event) -> bool {
                         let mime_types = if state.key_modifiers == KeyModifiers::Shift {
                             ALL_MIMES
                         } else {
-                            TEXT_MIME_TYPE
+                            &[TEXT_MIME_TYPE]
                         };
+                        let mime_types = mime_types.iter().map(|m| BorrowedUtf8::new(m)).collect::<Vec<_>>();
                         let actions = DragAndDropActions(DragAndDropAction::Copy as u32 | DragAndDropAction::Move as u32);
                         let drag_icon_size = LogicalSize { width: 300, height: 300 };
-                        window_start_drag_and_drop(
+                        window_start_drag_and_drop_v2(
                             app_ptr,
                             data.window_id,
-                            BorrowedUtf8::new(mime_types),
+                            BorrowedArray::from_slice(&mime_types),
                             actions,
                             RenderingMode::Auto,
                             drag_icon_size,
@@ -567,10 +690,10 @@ extern "C" fn event_handler(event: &Event) -> bool {
                     true
                 }
                 MOUSE_BUTTON_MIDDLE => {
-                    application_primary_selection_paste(
+                    application_primary_selection_paste_v2(
                         app_ptr,
                         state.add_data_request_source(data.window_id),
-                        BorrowedUtf8::new(TEXT_MIME_TYPE),
+                        BorrowedArray::from_slice(&[BorrowedUtf8::new(TEXT_MIME_TYPE)]),
                     );
                     true
                 }
@@ -597,7 +720,17 @@ extern "C" fn event_handler(event: &Event) -> bool {
                     .unwrap()
                 {
                     let files = s.trim_ascii_end().split("\r\n").collect::<Vec<_>>();
-                    info!("Selected files: {files:?}");
+                    let choices = file_chooser_response
+                        .choices
+                        .as_slice()
+                        .unwrap()
+                        .iter()
+                        .map(|choice| (choice.id.get("id").unwrap(), choice.value.get("value").unwrap()))
+                        .collect::<Vec<_>>();
+                    info!(
+                        "Selected files: {files:?}, filter index: {}, choices: {choices:?}",
+                        file_chooser_response.current_filter_index
+                    );
                 }
                 true
             }
@@ -693,6 +826,7 @@ extern "C" fn event_handler(event: &Event) -> bool {
             }
             Event::NotificationClosed(data) => {
                 if let Some(window_id_to_activate) = state.notification_sources.remove(&data.notification_id)
+                    && data.action.get_optional("Event::NotificationClosed.action").unwrap() != Some(NOTIFICATION_ACTION_DISMISS)
                     && let Some(activation_token) = data
                         .activation_token
                         .get_optional("Event::NotificationClosed.activation_token")
@@ -702,6 +836,17 @@ extern "C" fn event_handler(event: &Event) -> bool {
                 }
                 true
             }
+            Event::AppMenuItemActivated(data) => {
+                match data.item_id {
+                    MENU_ITEM_QUIT => application_stop_event_loop(app_ptr),
+                    MENU_ITEM_ANIMATION => {
+                        state.animation_paused = data.toggle_state == AppMenuItemToggleState::Off;
+                        update_app_menu(state);
+                    }
+                    _ => {}
+                }
+                true
+            }
             _ => false,
         }
     })
@@ -806,7 +951,11 @@ extern "C" fn get_data_transfer_data(source: DataSource, mime_type: BorrowedUtf8
 
     let (static_str, obj_id) = leak_string_data(v.to_owned());
     let data = BorrowedArray::from_slice(static_str);
-    FfiTransferDataResponse { obj_id, data }
+    FfiTransferDataResponse {
+        obj_id,
+        data,
+        streamed: false,
+    }
 }
 
 pub fn main() {
@@ -814,12 +963,14 @@ pub fn main() {
         file_path: BorrowedStrPtr::new(c"/tmp/a"),
         console_level: LogLevel::Debug,
         file_level: LogLevel::Error,
+        log_callback: None,
     });
     let app_ptr = application_init(ApplicationCallbacks {
         obj_dealloc,
         event_handler,
         query_drag_and_drop_target,
         get_data_transfer_data,
+        query_app_menu_item_state: Some(query_app_menu_item_state),
     });
     STATE.with_borrow_mut(|state| {
         state.app_ptr = OptionalAppPtr(Some(app_ptr.clone()));
@@ -1,30 +1,49 @@
 use crate::linux::{
-    application_api::{ApplicationCallbacks, DragAndDropActions, RenderingMode},
+    application_api::{ApplicationCallbacks, DataSource, DragAndDropAction, DragAndDropActions, RenderingMode},
+    application_menu::{APP_MENU_OBJECT_PATH, AppMenuRequest, AppMenuStructureSafe, DbusMenuLayout, publish_app_menu},
     application_state::{ApplicationState, KdtRequestData, get_egl},
     async_event_result::AsyncEventResult,
+    background::request_background_impl,
+    background_api::BackgroundRequestParams,
     data_transfer::MimeTypes,
     desktop_settings::init_desktop_settings_notifier_task,
     desktop_settings_api::FfiDesktopSetting,
     drag_icon::DragIcon,
     events::{
-        DataTransferContent, DataTransferEvent, Event, EventHandler, NotificationClosedEvent, RequestId, WindowClosedEvent, WindowId,
+        AppMenuItemActivatedEvent, DataTransferContent, DataTransferEvent, DataTransferId, Event, EventHandler, NotificationClosedEvent,
+        RequestId, SessionState, SessionStateChangedEvent, WindowClosedEvent, WindowId,
     },
-    file_dialog::{show_open_file_dialog_impl, show_save_file_dialog_impl},
+    file_dialog::show_file_dialog_impl,
     file_dialog_api::{CommonFileDialogParams, OpenFileDialogParams, SaveFileDialogParams},
+    file_transfer::{check_file_transfer_availability, mime_type_to_receive},
     geometry::{LogicalPoint, LogicalSize},
-    notifications::{NewNotificationData, NotificationAction, NotificationData, init_notifications_task},
-    window::SimpleWindow,
+    launcher_entry::{LauncherEntry, emit_launcher_entry_update},
+    mime_conversion::{can_convert_to_png, offered_mime_types},
+    monitors::{MonitorEvent, init_monitors_task},
+    notifications::{NewNotificationData, NotificationAction, NotificationContent, NotificationData, init_notifications_task},
+    print::{prepare_print_impl, print_impl},
+    print_api::{PreparePrintParams, PrintParams},
+    screenshot::{pick_color_impl, take_screenshot_impl},
+    secrets::{SecretError, SecretRequest, secret_request_impl},
+    session::{InhibitRequest, inhibit_impl, init_session_monitor_task},
+    session_api::InhibitFlags,
+    window::{FRAME_CAPTURE_TIMEOUT, SimpleWindow},
     window_api::WindowParams,
     window_resize_edge_api::WindowResizeEdge,
 };
 use anyhow::{Context, anyhow, bail};
+use desktop_common::event_recording::{RecordedVariant, record_event};
 use desktop_common::logger::catch_panic;
+use desktop_common::mime_types::{NegotiatedMimeType, negotiate_mime_type};
+use desktop_common::perf_trace::TraceSpan;
 use log::{debug, warn};
 use smithay_client_toolkit::{
+    data_device_manager::ReadPipe,
     reexports::{
         calloop::{
             EventLoop, LoopHandle, RegistrationToken,
             channel::{self, Sender},
+            timer::{TimeoutAction, Timer},
         },
         calloop_wayland_source::WaylandSource,
         client::{
@@ -35,8 +54,12 @@ use smithay_client_toolkit::{
     },
     shell::WaylandSurface,
 };
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::{thread::ThreadId, time::Duration};
+use std::{
+    thread::ThreadId,
+    time::{Duration, Instant},
+};
 use tokio::sync::RwLock;
 
 /// cbindgen:ignore
@@ -53,6 +76,11 @@ pub struct Application {
     async_request_counter: u32,
     run_async_sender: Sender<AsyncEventResult>,
     notifications_task_info: Option<AsyncTaskInfo>,
+    monitors_task_info: Option<AsyncTaskInfo>,
+    app_menu_request_sender: Option<Sender<AppMenuRequest>>,
+    session_query_end_response_sender: Option<tokio::sync::mpsc::Sender<()>>,
+    inhibitions: HashMap<RequestId, tokio::sync::oneshot::Sender<()>>,
+    clipboard_store_timeout: Option<Duration>,
 }
 
 struct AsyncTaskInfo {
@@ -103,6 +131,11 @@ pub fn send_event<'a, T: Into<Event<'a>>>(event_handler: EventHandler, event_dat
         Event::MouseMoved(_) | Event::WindowDraw(_) | Event::DragIconDraw(_) => {}
         _ => debug!("Sending event: {event:?}"),
     }
+    // The draw events only provide the rendering target, so there is nothing to replay.
+    if !matches!(event, Event::WindowDraw(_) | Event::DragIconDraw(_)) {
+        record_event(&event);
+    }
+    let _span = TraceSpan::new("event", event.variant_name());
     catch_panic(|| Ok(event_handler(&event))).unwrap_or(false)
 }
 
@@ -117,12 +150,16 @@ impl Application {
         let loop_handle = event_loop.handle();
         let wl_display = conn.display();
 
-        WaylandSource::new(conn, event_queue)
-            .insert(loop_handle)
+        // Like `WaylandSource::insert`, with the span only covering the dispatch, not waiting for the events
+        loop_handle
+            .insert_source(WaylandSource::new(conn, event_queue), |(), queue, state| {
+                let _span = TraceSpan::new("event_loop", "wayland_dispatch");
+                queue.dispatch_pending(state)
+            })
             .map_err(|e| anyhow!(e.to_string()))?;
 
-        let state = ApplicationState::new(&globals, &qh, callbacks, event_loop.handle(), wl_display)?;
         let rt = tokio::runtime::Builder::new_multi_thread().enable_io().worker_threads(1).build()?;
+        let state = ApplicationState::new(&globals, &qh, callbacks, event_loop.handle(), wl_display, rt.handle().clone())?;
         let run_async_sender = create_run_async_sender(&event_loop);
 
         Ok(Self {
@@ -136,6 +173,11 @@ impl Application {
             async_request_counter: 0,
             run_async_sender,
             notifications_task_info: None,
+            monitors_task_info: None,
+            app_menu_request_sender: None,
+            session_query_end_response_sender: None,
+            inhibitions: HashMap::new(),
+            clipboard_store_timeout: None,
         })
     }
 
@@ -206,6 +248,82 @@ impl Application {
         }
     }
 
+    fn init_session_monitor(&mut self) -> AsyncTaskInfo {
+        let (query_end_response_sender, query_end_response_receiver) = tokio::sync::mpsc::channel(1);
+        self.session_query_end_response_sender = Some(query_end_response_sender.clone());
+
+        let (session_state_sender, session_state_channel) = channel::channel();
+        let mut last_session_state = SessionState::Running;
+        let registration_token = self
+            .event_loop
+            .handle()
+            .insert_source(session_state_channel, move |event, (), state| {
+                if let channel::Event::Msg(e) = event {
+                    let SessionStateChangedEvent { session_state, .. } = e;
+                    state.send_event(e);
+                    if session_state != last_session_state {
+                        last_session_state = session_state;
+                        match session_state {
+                            SessionState::QueryEnd => {
+                                if !state.send_event(Event::SessionQueryEnd) {
+                                    let _ = query_end_response_sender.try_send(());
+                                }
+                            }
+                            SessionState::Ending => {
+                                state.send_event(Event::SessionEnding);
+                            }
+                            SessionState::Running => {}
+                        }
+                    }
+                }
+            })
+            .unwrap();
+
+        let join_handle = self.rt.spawn(init_session_monitor_task(
+            move |e| session_state_sender.send(e).map_err(Into::into),
+            query_end_response_receiver,
+        ));
+        AsyncTaskInfo {
+            name: "Session monitor",
+            registration_token,
+            join_handle,
+        }
+    }
+
+    /// The monitors are started only on request, since each of them reports the current state right away.
+    pub fn enable_monitors(&mut self) {
+        if self.monitors_task_info.is_none() {
+            self.monitors_task_info = Some(self.init_monitors());
+        }
+    }
+
+    fn init_monitors(&self) -> AsyncTaskInfo {
+        let (monitor_event_sender, monitor_event_channel) = channel::channel();
+        let registration_token = self
+            .event_loop
+            .handle()
+            .insert_source(monitor_event_channel, move |event, (), state| {
+                if let channel::Event::Msg(e) = event {
+                    match e {
+                        MonitorEvent::NetworkAvailabilityChanged(e) => state.send_event(e),
+                        MonitorEvent::PowerSaverChanged(e) => state.send_event(e),
+                        MonitorEvent::OnBatteryChanged(e) => state.send_event(e),
+                        MonitorEvent::LowMemoryWarning(e) => state.send_event(e),
+                    };
+                }
+            })
+            .unwrap();
+
+        let join_handle = self
+            .rt
+            .spawn(init_monitors_task(move |e| monitor_event_sender.send(e).map_err(Into::into)));
+        AsyncTaskInfo {
+            name: "Monitors",
+            registration_token,
+            join_handle,
+        }
+    }
+
     fn init_run_on_event_loop(&mut self) {
         let (s, c) = channel::channel();
         self.run_on_event_loop = Some(s);
@@ -221,12 +339,16 @@ impl Application {
     }
 
     pub fn event_loop_iteration(&mut self) -> Result<bool, anyhow::Error> {
+        // Mostly waiting for the events, the dispatch of the Wayland events and the callbacks have their own spans
         self.event_loop.dispatch(Duration::from_millis(16), &mut self.state)?;
 
         let event_handler = self.state.callbacks.event_handler;
-        for (k, v) in self.state.windows.extract_if(|_, v| v.close) {
+        for (k, mut v) in self.state.windows.extract_if(|_, v| v.close) {
             let window_id = v.window_id;
             debug!("Closing window {window_id:?} ({k})");
+            if let Some(response) = v.cancel_frame_captures() {
+                response.send_as_event(event_handler);
+            }
             drop(v);
             self.state.window_id_to_surface_id.remove(&window_id);
             send_event(event_handler, WindowClosedEvent { window_id });
@@ -254,12 +376,25 @@ impl Application {
         self.state.send_event(Event::ApplicationStarted);
 
         let desktop_settings_task_info = self.init_desktop_settings_notifier();
+        let session_monitor_task_info = self.init_session_monitor();
+        self.rt.spawn(check_file_transfer_availability());
 
         while self.event_loop_iteration()? {
             // debug!("Application event loop: continuing");
         }
+        if let Some(timeout) = self.clipboard_store_timeout
+            && let Err(e) = self.store_clipboard(timeout)
+        {
+            warn!("Storing the clipboard content failed: {e}");
+        }
         let loop_handle = self.event_loop.handle();
         desktop_settings_task_info.stop(&self.rt, &loop_handle);
+        session_monitor_task_info.stop(&self.rt, &loop_handle);
+        if let Some(monitors_task_info) = self.monitors_task_info.take() {
+            monitors_task_info.stop(&self.rt, &loop_handle);
+        }
+        self.session_query_end_response_sender = None;
+        self.inhibitions.clear();
         if let Some(notifications_task_info) = self.notifications_task_info.take() {
             notifications_task_info.stop(&self.rt, &loop_handle);
         }
@@ -270,12 +405,43 @@ impl Application {
         Ok(())
     }
 
+    pub const fn set_clipboard_store_on_exit(&mut self, timeout: Option<Duration>) {
+        self.clipboard_store_timeout = timeout;
+    }
+
+    /// Keeps serving the snapshot of the clipboard content after the event loop has stopped,
+    /// until a clipboard manager (or any other client) reads it or takes over the clipboard, or the `timeout` expires.
+    fn store_clipboard(&mut self, timeout: Duration) -> anyhow::Result<()> {
+        // After the first read, give the clipboard manager some time to read the other MIME types too
+        const READ_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+        if !self.state.snapshot_clipboard() {
+            return Ok(());
+        }
+        debug!("Storing the clipboard content, timeout={timeout:?}");
+        let mut deadline = Instant::now() + timeout;
+        let mut served = false;
+        while self.state.owns_clipboard() && Instant::now() < deadline {
+            self.event_loop.dispatch(Duration::from_millis(16), &mut self.state)?;
+            if !served && self.state.clipboard_snapshot_served {
+                served = true;
+                deadline = deadline.min(Instant::now() + READ_GRACE_PERIOD);
+            }
+        }
+        debug!("Stopped storing the clipboard content, served={served}");
+        self.state.clipboard_snapshot = None;
+        Ok(())
+    }
+
     pub fn new_window(&mut self, params: &WindowParams) -> anyhow::Result<()> {
         let window_id = params.window_id;
         if self.state.window_id_to_surface_id.contains_key(&window_id) {
             return Err(anyhow!("Window with ID {window_id:?} already exists"));
         }
-        let w = SimpleWindow::new(window_id, &self.state, &self.qh, params)?;
+        let mut w = SimpleWindow::new(window_id, &self.state, &self.qh, params)?;
+        if let (Some(app_menu_manager), Some(service_name)) = (&self.state.app_menu_manager, &self.state.app_menu_service_name) {
+            w.set_app_menu_address(app_menu_manager, &self.qh, service_name, APP_MENU_OBJECT_PATH);
+        }
         let surface_id = w.window.wl_surface().id();
         self.state.windows.insert(surface_id.clone(), w);
         self.state.window_id_to_surface_id.insert(window_id, surface_id);
@@ -294,7 +460,7 @@ impl Application {
         self.state.set_cursor_theme(&self.qh, name, size)
     }
 
-    pub fn clipboard_put(&mut self, mime_types: MimeTypes) {
+    pub fn clipboard_put(&mut self, mime_types: &MimeTypes) {
         debug!("application_clipboard_put: mime_types = {:?}", mime_types.val);
         if mime_types.val.is_empty() {
             self.state.copy_paste_source = None;
@@ -308,15 +474,16 @@ impl Application {
             let copy_paste_source = self
                 .state
                 .data_device_manager_state
-                .create_copy_paste_source(&self.qh, mime_types.val);
+                .create_copy_paste_source(&self.qh, offered_mime_types(&mime_types.val, true));
             copy_paste_source.set_selection(device, serial);
             self.state.copy_paste_source = Some(copy_paste_source);
+            self.state.set_provided_mime_types(DataSource::Clipboard, mime_types);
         } else {
             warn!("application_clipboard_put: No last event serial");
         }
     }
 
-    pub fn clipboard_get_available_mimetypes(&self) -> Option<String> {
+    pub fn clipboard_get_available_mimetypes(&self) -> Option<Vec<String>> {
         let Some(data_device) = self.state.data_device.as_ref() else {
             warn!("application_clipboard_get_available_mimetypes: No data device available");
             return None;
@@ -325,10 +492,10 @@ impl Application {
             debug!("application_clipboard_get_available_mimetypes: No selection offer found");
             return None;
         };
-        selection_offer.with_mime_types(|mime_types| Some(mime_types.join(",")))
+        Some(selection_offer.with_mime_types(<[String]>::to_vec))
     }
 
-    pub fn primary_selection_get_available_mimetypes(&self) -> Option<String> {
+    pub fn primary_selection_get_available_mimetypes(&self) -> Option<Vec<String>> {
         let Some(device) = self.state.primary_selection_device.as_ref() else {
             warn!("application_primary_selection_get_available_mimetypes: No primary selection device");
             return None;
@@ -337,10 +504,10 @@ impl Application {
             debug!("application_primary_selection_get_available_mimetypes: No selection offer found");
             return None;
         };
-        selection_offer.with_mime_types(|mime_types| Some(mime_types.join(",")))
+        Some(selection_offer.with_mime_types(<[String]>::to_vec))
     }
 
-    pub fn primary_selection_put(&mut self, mime_types: MimeTypes) {
+    pub fn primary_selection_put(&mut self, mime_types: &MimeTypes) {
         if mime_types.val.is_empty() {
             self.state.primary_selection_source = None;
             return;
@@ -354,40 +521,51 @@ impl Application {
             return;
         };
         if let Some((_seat, serial)) = self.state.get_latest_event_seat_and_serial() {
-            let source = manager.create_selection_source(&self.qh, mime_types.val);
+            let source = manager.create_selection_source(&self.qh, offered_mime_types(&mime_types.val, false));
             source.set_selection(device, serial);
             self.state.primary_selection_source = Some(source);
+            self.state.set_provided_mime_types(DataSource::PrimarySelection, mime_types);
         } else {
             warn!("application_primary_selection_put: No last event serial");
         }
     }
 
-    fn primary_selection_paste_impl(&self, serial: i32, supported_mime_types: &str) -> bool {
-        let Some(device) = self.state.primary_selection_device.as_ref() else {
-            return false;
-        };
+    fn primary_selection_read_pipe(&self, f_name: &str, supported_mime_types: &MimeTypes) -> Option<(ReadPipe, NegotiatedMimeType)> {
+        let device = self.state.primary_selection_device.as_ref()?;
 
         let Some(offer) = device.data().selection_offer() else {
-            debug!("application_primary_selection_paste: No selection offer found");
-            return false;
+            debug!("{f_name}: No selection offer found");
+            return None;
         };
         let Some(mime_type) = offer.with_mime_types(|mime_types| {
-            debug!("application_primary_selection_paste: offer MIME types: {mime_types:?}, supported MIME types: {supported_mime_types}");
-            supported_mime_types
-                .split(',')
-                .find(|&supported_mime_type| mime_types.iter().any(|m| m == supported_mime_type))
-                .map(str::to_owned)
+            debug!(
+                "{f_name}: offer MIME types: {mime_types:?}, supported MIME types: {:?}",
+                supported_mime_types.val
+            );
+            negotiate_mime_type(supported_mime_types.val.iter().copied(), mime_types, can_convert_to_png).map(|(requested, received)| {
+                NegotiatedMimeType {
+                    requested: requested.to_owned(),
+                    received: received.to_owned(),
+                }
+            })
         }) else {
-            debug!("application_primary_selection_paste: clipboard content not supported");
-            return false;
+            debug!("{f_name}: clipboard content not supported");
+            return None;
         };
-        debug!("application_primary_selection_paste: reading {mime_type}");
-        let read_pipe = match offer.receive(mime_type.clone()) {
-            Ok(v) => v,
+        debug!("{f_name}: reading {mime_type:?}");
+        match offer.receive(mime_type.received.clone()) {
+            Ok(read_pipe) => Some((read_pipe, mime_type)),
             Err(e) => {
-                warn!("application_primary_selection_paste: failed receive the data offer: {e}");
-                return false;
+                warn!("{f_name}: failed receive the data offer: {e}");
+                None
             }
+        }
+    }
+
+    fn primary_selection_paste_impl(&self, serial: i32, supported_mime_types: &MimeTypes) -> bool {
+        let Some((read_pipe, mime_type)) = self.primary_selection_read_pipe("application_primary_selection_paste", supported_mime_types)
+        else {
+            return false;
         };
         let event_handler = self.state.callbacks.event_handler;
         self.state
@@ -401,7 +579,7 @@ impl Application {
         true
     }
 
-    pub fn primary_selection_paste(&self, serial: i32, supported_mime_types: &str) {
+    pub fn primary_selection_paste(&self, serial: i32, supported_mime_types: &MimeTypes) {
         if !self.primary_selection_paste_impl(serial, supported_mime_types) {
             self.state.send_event(DataTransferEvent {
                 serial,
@@ -410,33 +588,61 @@ impl Application {
         }
     }
 
-    fn clipboard_paste_impl(&self, serial: i32, supported_mime_types: &str) -> bool {
+    pub fn primary_selection_paste_chunked(&mut self, serial: i32, supported_mime_types: &MimeTypes) -> Option<DataTransferId> {
+        const F_NAME: &str = "application_primary_selection_paste_chunked";
+        let (read_pipe, mime_type) = self.primary_selection_read_pipe(F_NAME, supported_mime_types)?;
+        self.state
+            .read_from_pipe_chunked(F_NAME, read_pipe, mime_type.received, serial, |_| {})
+    }
+
+    /// With `use_file_transfer`, the file transfer portal is used for `text/uri-list`, if offered.
+    fn clipboard_read_pipe(
+        &self,
+        f_name: &str,
+        supported_mime_types: &MimeTypes,
+        use_file_transfer: bool,
+    ) -> Option<(ReadPipe, NegotiatedMimeType)> {
         let Some(device) = self.state.data_device.as_ref() else {
-            warn!("application_clipboard_paste: No data device available");
-            return false;
+            warn!("{f_name}: No data device available");
+            return None;
         };
         let Some(offer) = device.data().selection_offer() else {
-            debug!("application_clipboard_paste: No selection offer found");
-            return false;
+            debug!("{f_name}: No selection offer found");
+            return None;
         };
         let Some(mime_type) = offer.with_mime_types(|mime_types| {
-            debug!("application_clipboard_paste: offer MIME types: {mime_types:?}, supported MIME types: {supported_mime_types}");
-            supported_mime_types
-                .split(',')
-                .find(|&supported_mime_type| mime_types.iter().any(|m| m == supported_mime_type))
-                .map(str::to_owned)
+            debug!(
+                "{f_name}: offer MIME types: {mime_types:?}, supported MIME types: {:?}",
+                supported_mime_types.val
+            );
+            negotiate_mime_type(supported_mime_types.val.iter().copied(), mime_types, can_convert_to_png).map(|(requested, received)| {
+                NegotiatedMimeType {
+                    requested: requested.to_owned(),
+                    received: if use_file_transfer {
+                        mime_type_to_receive(received, mime_types).to_owned()
+                    } else {
+                        received.to_owned()
+                    },
+                }
+            })
         }) else {
-            debug!("application_clipboard_paste: clipboard content not supported");
-            return false;
+            debug!("{f_name}: clipboard content not supported");
+            return None;
         };
 
-        debug!("application_clipboard_paste: reading {mime_type}");
-        let read_pipe = match offer.receive(mime_type.clone()) {
-            Ok(v) => v,
+        debug!("{f_name}: reading {mime_type:?}");
+        match offer.receive(mime_type.received.clone()) {
+            Ok(read_pipe) => Some((read_pipe, mime_type)),
             Err(e) => {
-                warn!("application_clipboard_paste: failed receive the data offer: {e}");
-                return false;
+                warn!("{f_name}: failed receive the data offer: {e}");
+                None
             }
+        }
+    }
+
+    fn clipboard_paste_impl(&self, serial: i32, supported_mime_types: &MimeTypes) -> bool {
+        let Some((read_pipe, mime_type)) = self.clipboard_read_pipe("application_clipboard_paste", supported_mime_types, true) else {
+            return false;
         };
         let event_handler = self.state.callbacks.event_handler;
         self.state
@@ -450,7 +656,7 @@ impl Application {
         true
     }
 
-    pub fn clipboard_paste(&self, serial: i32, supported_mime_types: &str) {
+    pub fn clipboard_paste(&self, serial: i32, supported_mime_types: &MimeTypes) {
         if !self.clipboard_paste_impl(serial, supported_mime_types) {
             self.state.send_event(DataTransferEvent {
                 serial,
@@ -459,10 +665,56 @@ impl Application {
         }
     }
 
+    /// The file transfer portal is not used here, because its data can't be converted to `text/uri-list` chunk by chunk.
+    pub fn clipboard_paste_chunked(&mut self, serial: i32, supported_mime_types: &MimeTypes) -> Option<DataTransferId> {
+        const F_NAME: &str = "application_clipboard_paste_chunked";
+        let (read_pipe, mime_type) = self.clipboard_read_pipe(F_NAME, supported_mime_types, false)?;
+        self.state
+            .read_from_pipe_chunked(F_NAME, read_pipe, mime_type.received, serial, |_| {})
+    }
+
+    pub const fn is_data_control_available(&self) -> bool {
+        self.state.is_data_control_available()
+    }
+
+    fn data_control_paste_impl(&self, data_source: DataSource, serial: i32, supported_mime_types: &MimeTypes) -> bool {
+        const F_NAME: &str = "application_data_control_paste";
+        let Some((read_pipe, mime_type)) = self.state.data_control_read_pipe(F_NAME, data_source, supported_mime_types) else {
+            return false;
+        };
+        let event_handler = self.state.callbacks.event_handler;
+        self.state.read_from_pipe(F_NAME, read_pipe, mime_type, move |content| {
+            let event = DataTransferEvent {
+                serial,
+                content: content.unwrap_or(DataTransferContent::null()),
+            };
+            send_event(event_handler, event);
+        });
+        true
+    }
+
+    pub fn data_control_paste(&self, data_source: DataSource, serial: i32, supported_mime_types: &MimeTypes) {
+        if !self.data_control_paste_impl(data_source, serial, supported_mime_types) {
+            self.state.send_event(DataTransferEvent {
+                serial,
+                content: DataTransferContent::null(),
+            });
+        }
+    }
+
+    pub fn data_control_put(&mut self, data_source: DataSource, mime_types: &MimeTypes) -> anyhow::Result<()> {
+        debug!("application_data_control_put: {data_source:?}, mime_types = {:?}", mime_types.val);
+        self.state.data_control_put(&self.qh, data_source, mime_types)
+    }
+
+    pub fn drop_finish(&self, action: DragAndDropAction) -> anyhow::Result<()> {
+        self.state.drop_finish(action)
+    }
+
     pub fn start_drag(
         &mut self,
         window_id: WindowId,
-        mime_types: MimeTypes,
+        mime_types: &MimeTypes,
         actions: DragAndDropActions,
         drag_icon_rendering_mode: RenderingMode,
         drag_icon_size: LogicalSize,
@@ -496,10 +748,11 @@ impl Application {
             .window
             .wl_surface();
 
-        let drag_source =
-            self.state
-                .data_device_manager_state
-                .create_drag_and_drop_source(&self.qh, mime_types.val, DndAction::from(actions));
+        let drag_source = self.state.data_device_manager_state.create_drag_and_drop_source(
+            &self.qh,
+            offered_mime_types(&mime_types.val, true),
+            DndAction::from(actions),
+        );
 
         let device = self.state.data_device.as_ref().context("No data device found")?;
 
@@ -519,6 +772,7 @@ impl Application {
         self.state.drag_icon = drag_icon;
         self.state.current_drag_source_window_id = Some(window_id);
         self.state.drag_source = Some(drag_source);
+        self.state.set_provided_mime_types(DataSource::DragAndDrop, mime_types);
 
         Ok(())
     }
@@ -661,7 +915,7 @@ impl Application {
         let request = common_params.create_open_request(open_params)?;
         Ok(self.run_async(|request_id| async move {
             let identifier = ashpd::WindowIdentifier::from_wayland(&wl_surface).await;
-            let result = show_open_file_dialog_impl(identifier, request).await;
+            let result = show_file_dialog_impl(identifier, request).await;
             AsyncEventResult::FileChooserResponse { request_id, result }
         }))
     }
@@ -676,11 +930,121 @@ impl Application {
         let request = common_params.create_save_request(save_params)?;
         Ok(self.run_async(|request_id| async move {
             let identifier: Option<ashpd::WindowIdentifier> = ashpd::WindowIdentifier::from_wayland(&wl_surface).await;
-            let result = show_save_file_dialog_impl(identifier, request).await;
+            let result = show_file_dialog_impl(identifier, request).await;
             AsyncEventResult::FileChooserResponse { request_id, result }
         }))
     }
 
+    pub fn request_screenshot(&mut self, window_id: WindowId, interactive: bool) -> anyhow::Result<RequestId> {
+        let wl_surface = self.get_wl_surface(window_id)?;
+        Ok(self.run_async(|request_id| async move {
+            let identifier = ashpd::WindowIdentifier::from_wayland(&wl_surface).await;
+            let result = take_screenshot_impl(identifier, interactive).await;
+            AsyncEventResult::ScreenshotResponse { request_id, result }
+        }))
+    }
+
+    pub fn window_capture_frame(&mut self, window_id: WindowId) -> anyhow::Result<RequestId> {
+        let window = self.state.get_window_mut(window_id)?;
+        self.async_request_counter = self.async_request_counter.wrapping_add(1);
+        let request_id = RequestId(self.async_request_counter);
+        if let Some(response) = window.capture_frame(&self.qh, request_id) {
+            // Reported from the event loop, like the other responses
+            self.run_async_sender.send(response)?;
+        } else {
+            self.event_loop
+                .handle()
+                .insert_source(Timer::from_duration(FRAME_CAPTURE_TIMEOUT), move |_, (), state| {
+                    if let Ok(window) = state.get_window_mut(window_id)
+                        && let Some(response) = window.time_out_frame_capture(request_id)
+                    {
+                        response.send_as_event(state.callbacks.event_handler);
+                    }
+                    TimeoutAction::Drop
+                })
+                .map_err(|e| anyhow!(e.to_string()))?;
+        }
+        Ok(request_id)
+    }
+
+    pub fn request_pick_color(&mut self, window_id: WindowId) -> anyhow::Result<RequestId> {
+        let wl_surface = self.get_wl_surface(window_id)?;
+        Ok(self.run_async(|request_id| async move {
+            let identifier = ashpd::WindowIdentifier::from_wayland(&wl_surface).await;
+            let result = pick_color_impl(identifier).await;
+            AsyncEventResult::ColorPickerResponse { request_id, result }
+        }))
+    }
+
+    pub fn request_prepare_print(&mut self, window_id: WindowId, params: &PreparePrintParams) -> anyhow::Result<RequestId> {
+        let wl_surface = self.get_wl_surface(window_id)?;
+        let request = params.to_request()?;
+        Ok(self.run_async(|request_id| async move {
+            let identifier = ashpd::WindowIdentifier::from_wayland(&wl_surface).await;
+            let result = prepare_print_impl(identifier, request).await;
+            AsyncEventResult::PreparePrintResponse { request_id, result }
+        }))
+    }
+
+    pub fn request_print(&mut self, window_id: WindowId, params: &PrintParams) -> anyhow::Result<RequestId> {
+        let wl_surface = self.get_wl_surface(window_id)?;
+        let request = params.to_request()?;
+        Ok(self.run_async(|request_id| async move {
+            let identifier = ashpd::WindowIdentifier::from_wayland(&wl_surface).await;
+            let result = print_impl(identifier, request).await;
+            AsyncEventResult::PrintResponse { request_id, result }
+        }))
+    }
+
+    pub fn inhibit(&mut self, window_id: WindowId, flags: InhibitFlags, reason: &str) -> anyhow::Result<RequestId> {
+        let wl_surface = self.get_wl_surface(window_id)?;
+        let request = InhibitRequest::new(flags, reason);
+        let (uninhibit_sender, uninhibit_receiver) = tokio::sync::oneshot::channel();
+        let request_id = self.run_async(|request_id| async move {
+            let identifier = ashpd::WindowIdentifier::from_wayland(&wl_surface).await;
+            let result = inhibit_impl(identifier, request, uninhibit_receiver).await;
+            AsyncEventResult::InhibitResponse { request_id, result }
+        });
+        self.inhibitions.insert(request_id, uninhibit_sender);
+        Ok(request_id)
+    }
+
+    pub fn uninhibit(&mut self, request_id: RequestId) {
+        if let Some(uninhibit_sender) = self.inhibitions.remove(&request_id) {
+            let _ = uninhibit_sender.send(());
+        } else {
+            warn!("application_uninhibit: no inhibition for {request_id:?}");
+        }
+    }
+
+    pub fn session_query_end_response(&self) {
+        if let Some(sender) = &self.session_query_end_response_sender {
+            if let Err(e) = sender.try_send(()) {
+                warn!("application_session_query_end_response: {e}");
+            }
+        } else {
+            warn!("application_session_query_end_response: session monitor is not running");
+        }
+    }
+
+    pub fn request_background(&mut self, params: &BackgroundRequestParams) -> anyhow::Result<RequestId> {
+        let request = params.to_request()?;
+        Ok(self.run_async(|request_id| async move {
+            let result = request_background_impl(request).await;
+            AsyncEventResult::BackgroundResponse { request_id, result }
+        }))
+    }
+
+    pub fn secret_request(&mut self, request: SecretRequest) -> RequestId {
+        self.run_async(|request_id| async move {
+            let result = match Self::dbus_connection().await {
+                Ok(connection) => secret_request_impl(connection, request).await,
+                Err(e) => Err(SecretError::from(e)),
+            };
+            AsyncEventResult::SecretResponse { request_id, result }
+        })
+    }
+
     fn init_notifications(&mut self) {
         let (notification_action_sender, notification_action_receiver) = tokio::sync::mpsc::channel(100);
 
@@ -711,12 +1075,7 @@ impl Application {
         });
     }
 
-    pub fn request_show_notification(
-        &mut self,
-        summary: String,
-        body: String,
-        sound_file_path: Option<String>,
-    ) -> anyhow::Result<RequestId> {
+    pub fn request_show_notification(&mut self, content: NotificationContent) -> anyhow::Result<RequestId> {
         if self.notifications_task_info.is_none() {
             self.init_notifications();
         }
@@ -732,12 +1091,7 @@ impl Application {
                 .send(AsyncEventResult::NotificationShown { request_id, result })
                 .unwrap();
         });
-        let new_notification_data = NewNotificationData {
-            summary,
-            body,
-            sound_file_path,
-            result_reporter,
-        };
+        let new_notification_data = Box::new(NewNotificationData { content, result_reporter });
         self.rt.spawn(async move {
             if let Err(e) = action_sender.send(NotificationAction::Show(new_notification_data)).await
                 && let NotificationAction::Show(d) = e.0
@@ -760,4 +1114,63 @@ impl Application {
         });
         Ok(())
     }
+
+    fn init_app_menu(&self) -> Sender<AppMenuRequest> {
+        let (request_sender, request_channel) = channel::channel();
+        let qh = self.qh.clone();
+        self.event_loop
+            .handle()
+            .insert_source(request_channel, move |event: channel::Event<AppMenuRequest>, (), state| {
+                if let channel::Event::Msg(request) = event {
+                    match request {
+                        AppMenuRequest::Exported { service_name } => state.set_app_menu_service_name(&qh, service_name),
+                        AppMenuRequest::ItemActivated { item_id, toggle_state } => {
+                            state.send_event(AppMenuItemActivatedEvent { item_id, toggle_state });
+                        }
+                        AppMenuRequest::QueryItemStates { item_ids, reply } => {
+                            let Some(query) = state.callbacks.query_app_menu_item_state else {
+                                return;
+                            };
+                            let states = item_ids.into_iter().map(|item_id| query(item_id)).collect();
+                            if reply.send(states).is_err() {
+                                debug!("Application menu item states are no longer needed");
+                            }
+                        }
+                    }
+                }
+            })
+            .unwrap();
+        request_sender
+    }
+
+    pub fn launcher_entry_update(&self, entry: LauncherEntry) {
+        self.rt.spawn(async move {
+            if let Err(e) = emit_launcher_entry_update(entry).await {
+                warn!("Error updating the launcher entry: {e}");
+            }
+        });
+    }
+
+    pub fn app_menu_update(&mut self, menu: &AppMenuStructureSafe) -> anyhow::Result<()> {
+        let layout = DbusMenuLayout::new(menu)?;
+        let request_sender = if let Some(sender) = &self.app_menu_request_sender {
+            sender.clone()
+        } else {
+            let sender = self.init_app_menu();
+            self.app_menu_request_sender = Some(sender.clone());
+            sender
+        };
+        self.rt.spawn(async move {
+            match publish_app_menu(layout, request_sender.clone()).await {
+                Ok(Some(service_name)) => {
+                    if let Err(e) = request_sender.send(AppMenuRequest::Exported { service_name }) {
+                        warn!("Error announcing the application menu: {e}");
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Error publishing the application menu: {e}"),
+            }
+        });
+        Ok(())
+    }
 }
@@ -1,13 +1,15 @@
 use crate::linux::{
     application::Application,
+    application_menu_api::FfiQueryAppMenuItemState,
     application_state::get_egl,
     data_transfer::MimeTypes,
-    events::{EventHandler, RequestId, WindowId},
+    events::{DataTransferId, DataTransferWriteResult, EventHandler, RequestId, WindowId},
     geometry::LogicalPoint,
+    notifications::NotificationContent,
     text_input_api::TextInputContext,
 };
 use anyhow::{Context, bail};
-use desktop_common::ffi_utils::{AutoDropArray, BorrowedUtf8};
+use desktop_common::ffi_utils::{AutoDropArray, AutoDropStrPtr, BorrowedUtf8};
 use desktop_common::{
     ffi_utils::{BorrowedArray, RustAllocatedRawPtr},
     logger::ffi_boundary,
@@ -15,6 +17,7 @@ use desktop_common::{
 use log::debug;
 use std::mem::ManuallyDrop;
 use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -24,7 +27,10 @@ pub enum DragAndDropAction {
     Copy = 0b0000_0001,
 
     Move = 0b0000_0010,
-    // TODO?: Ask
+
+    /// Let the user choose the action after the drop, e.g. with a "Copy / Move / Link here" menu.
+    /// When reported in `DropPerformed`, the final action must be set with `application_drop_finish`.
+    Ask = 0b0000_0100,
 }
 
 #[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
@@ -74,10 +80,13 @@ pub struct FfiDragAndDropQueryResponse {
 pub struct FfiTransferDataResponse {
     pub obj_id: i64,
     pub data: BorrowedArray<'static, u8>,
+
+    /// If `true`, `data` is ignored, and the data should be provided in chunks after the `DataTransferWriteRequested` event.
+    pub streamed: bool,
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum DataSource {
     Clipboard,
     DragAndDrop,
@@ -95,6 +104,9 @@ pub struct ApplicationCallbacks {
     pub event_handler: EventHandler,
     pub query_drag_and_drop_target: FfiQueryDragAndDropTarget,
     pub get_data_transfer_data: FfiTransferDataGetter,
+
+    /// Optional. Used to refresh the state of the application menu items before they are shown.
+    pub query_app_menu_item_state: Option<FfiQueryAppMenuItemState>,
 }
 
 pub type AppPtr<'a> = RustAllocatedRawPtr<'a>;
@@ -233,76 +245,360 @@ pub extern "C" fn application_text_input_disable(mut app_ptr: AppPtr) {
     });
 }
 
+/// Start reporting `NetworkAvailabilityChanged`, `PowerSaverChanged`, `OnBatteryChanged` and `LowMemoryWarning` events.
+///
+/// The current state is reported right away, and then whenever it changes. Calling it again has no effect.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_monitors_enable(mut app_ptr: AppPtr) {
+    debug!("application_monitors_enable");
+    ffi_boundary("application_monitors_enable", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        app.enable_monitors();
+        Ok(())
+    });
+}
+
+/// Keep the clipboard content available after the event loop stops, until a clipboard manager takes it.
+///
+/// When the event loop stops, the clipboard data is fetched for every MIME type passed to `application_clipboard_put_v2`,
+/// and served for at most `timeout_ms` milliseconds. Streamed data is not stored.
+/// `0` disables it, which is the default.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_set_clipboard_store_on_exit(mut app_ptr: AppPtr, timeout_ms: u32) {
+    debug!("application_set_clipboard_store_on_exit: {timeout_ms}");
+    ffi_boundary("application_set_clipboard_store_on_exit", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        let timeout = (timeout_ms > 0).then(|| Duration::from_millis(timeout_ms.into()));
+        app.set_clipboard_store_on_exit(timeout);
+        Ok(())
+    });
+}
+
+#[deprecated(note = "use application_clipboard_put_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn application_clipboard_put(mut app_ptr: AppPtr, mime_types: BorrowedUtf8) {
     debug!("application_clipboard_put");
     ffi_boundary("application_clipboard_put", || {
         let app = unsafe { app_ptr.borrow_mut::<Application>() };
         let mime_types_str = mime_types.get("application_clipboard_put: mime_types")?;
-        app.clipboard_put(MimeTypes::new(mime_types_str));
+        app.clipboard_put(&MimeTypes::new(mime_types_str));
+        Ok(())
+    });
+}
+
+/// Takes ownership of the clipboard, offering the data in `mime_types`. Empty `mime_types` clear the clipboard.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_clipboard_put_v2(mut app_ptr: AppPtr, mime_types: BorrowedArray<BorrowedUtf8>) {
+    debug!("application_clipboard_put_v2");
+    ffi_boundary("application_clipboard_put_v2", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        app.clipboard_put(&MimeTypes::from_array(&mime_types, "application_clipboard_put_v2: mime_types")?);
+        Ok(())
+    });
+}
+
+/// Whether the compositor supports accessing the clipboard without the keyboard focus,
+/// with `application_data_control_paste` and `application_data_control_put`.
+///
+/// When available, `Event::ClipboardChanged` is reported for every change of the clipboard and primary selection.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_data_control_available(app_ptr: AppPtr) -> bool {
+    debug!("application_data_control_available");
+    ffi_boundary("application_data_control_available", || {
+        let app = unsafe { app_ptr.borrow::<Application>() };
+        Ok(app.is_data_control_available())
+    })
+}
+
+/// Like `application_clipboard_paste_v2`, but works without the keyboard focus.
+///
+/// * `data_source`: `Clipboard` or `PrimarySelection`.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_data_control_paste(
+    app_ptr: AppPtr<'_>,
+    data_source: DataSource,
+    serial: i32,
+    supported_mime_types: BorrowedArray<BorrowedUtf8>,
+) {
+    debug!("application_data_control_paste: {data_source:?}");
+    ffi_boundary("application_data_control_paste", || {
+        let app = unsafe { app_ptr.borrow::<Application>() };
+        let supported_mime_types = MimeTypes::from_array(&supported_mime_types, "application_data_control_paste: supported_mime_types")?;
+        app.data_control_paste(data_source, serial, &supported_mime_types);
         Ok(())
     });
 }
 
+/// Like `application_clipboard_put_v2`, but works without the keyboard focus.
+///
+/// * `data_source`: `Clipboard` or `PrimarySelection`.
+///
+/// Returns `false` if data control is not available, or doesn't support `data_source`.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_data_control_put(
+    mut app_ptr: AppPtr,
+    data_source: DataSource,
+    mime_types: BorrowedArray<BorrowedUtf8>,
+) -> bool {
+    debug!("application_data_control_put: {data_source:?}");
+    ffi_boundary("application_data_control_put", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        let mime_types = MimeTypes::from_array(&mime_types, "application_data_control_put: mime_types")?;
+        app.data_control_put(data_source, &mime_types)?;
+        Ok(true)
+    })
+}
+
+/// Report the dropped data with `Event::DataTransferChunk` events, after `Event::DropPerformed` with the transfer ID.
+///
+/// The data is not converted, so the MIME type of the chunks can differ from the one returned by `query_drag_and_drop_target`.
+///
+/// With `DragAndDropAction::Ask`, `application_drop_finish` should be called after the last chunk, with the `Finished` status.
+/// Disabled by default.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_set_drop_chunked(mut app_ptr: AppPtr, chunked: bool) {
+    debug!("application_set_drop_chunked: {chunked}");
+    ffi_boundary("application_set_drop_chunked", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        app.state.drop_chunked = chunked;
+        Ok(())
+    });
+}
+
+/// Finishes the drop that was reported in `Event::DropPerformed` with `DragAndDropAction::Ask`, after the user chose the action.
+///
+/// * `action`: The action chosen by the user (`Copy` or `Move`), or `None` to cancel the drop.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_drop_finish(app_ptr: AppPtr, action: DragAndDropAction) {
+    debug!("application_drop_finish: {action:?}");
+    ffi_boundary("application_drop_finish", || {
+        let app = unsafe { app_ptr.borrow::<Application>() };
+        app.drop_finish(action)
+    });
+}
+
+#[deprecated(note = "use application_clipboard_paste_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn application_clipboard_paste(app_ptr: AppPtr<'_>, serial: i32, supported_mime_types: BorrowedUtf8) {
     debug!("application_clipboard_paste: {supported_mime_types:?}");
     ffi_boundary("application_clipboard_paste", || {
         let app = unsafe { app_ptr.borrow::<Application>() };
         let supported_mime_types_str = supported_mime_types.get("application_clipboard_paste: supported_mime_types")?;
-        app.clipboard_paste(serial, supported_mime_types_str);
+        app.clipboard_paste(serial, &MimeTypes::new(supported_mime_types_str));
         Ok(())
     });
 }
 
+/// Reads the clipboard content in the first of the `supported_mime_types` that is available, reported with `Event::DataTransfer`.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_clipboard_paste_v2(app_ptr: AppPtr<'_>, serial: i32, supported_mime_types: BorrowedArray<BorrowedUtf8>) {
+    debug!("application_clipboard_paste_v2");
+    ffi_boundary("application_clipboard_paste_v2", || {
+        let app = unsafe { app_ptr.borrow::<Application>() };
+        let supported_mime_types = MimeTypes::from_array(&supported_mime_types, "application_clipboard_paste_v2: supported_mime_types")?;
+        app.clipboard_paste(serial, &supported_mime_types);
+        Ok(())
+    });
+}
+
+#[deprecated(note = "use application_primary_selection_put_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn application_primary_selection_put(mut app_ptr: AppPtr, mime_types: BorrowedUtf8) {
     debug!("application_primary_selection_put");
     ffi_boundary("application_primary_selection_put", || {
         let app = unsafe { app_ptr.borrow_mut::<Application>() };
         let mime_types_str = mime_types.get("application_primary_selection_put: mime_types")?;
-        app.primary_selection_put(MimeTypes::new(mime_types_str));
+        app.primary_selection_put(&MimeTypes::new(mime_types_str));
         Ok(())
     });
 }
 
+/// Takes ownership of the primary selection, offering the data in `mime_types`. Empty `mime_types` clear the primary selection.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_primary_selection_put_v2(mut app_ptr: AppPtr, mime_types: BorrowedArray<BorrowedUtf8>) {
+    debug!("application_primary_selection_put_v2");
+    ffi_boundary("application_primary_selection_put_v2", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        app.primary_selection_put(&MimeTypes::from_array(
+            &mime_types,
+            "application_primary_selection_put_v2: mime_types",
+        )?);
+        Ok(())
+    });
+}
+
+#[deprecated(note = "use application_primary_selection_paste_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn application_primary_selection_paste(app_ptr: AppPtr<'_>, serial: i32, supported_mime_types: BorrowedUtf8) {
-    debug!("application_clipboard_paste: {supported_mime_types:?}");
-    ffi_boundary("application_clipboard_paste", || {
+    debug!("application_primary_selection_paste: {supported_mime_types:?}");
+    ffi_boundary("application_primary_selection_paste", || {
         let app = unsafe { app_ptr.borrow::<Application>() };
         let supported_mime_types_str = supported_mime_types.get("application_primary_selection_paste: supported_mime_types")?;
-        app.primary_selection_paste(serial, supported_mime_types_str);
+        app.primary_selection_paste(serial, &MimeTypes::new(supported_mime_types_str));
+        Ok(())
+    });
+}
+
+/// Like `application_clipboard_paste_v2`, but for the primary selection.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_primary_selection_paste_v2(
+    app_ptr: AppPtr<'_>,
+    serial: i32,
+    supported_mime_types: BorrowedArray<BorrowedUtf8>,
+) {
+    debug!("application_primary_selection_paste_v2");
+    ffi_boundary("application_primary_selection_paste_v2", || {
+        let app = unsafe { app_ptr.borrow::<Application>() };
+        let supported_mime_types = MimeTypes::from_array(
+            &supported_mime_types,
+            "application_primary_selection_paste_v2: supported_mime_types",
+        )?;
+        app.primary_selection_paste(serial, &supported_mime_types);
+        Ok(())
+    });
+}
+
+/// Like `application_clipboard_paste_v2`, but the data is reported with `DataTransferChunk` events, as it's received.
+///
+/// The data is not converted, and `mime_type` of the events is the one offered by the other application.
+///
+/// Returns `DataTransferId(0)` if the clipboard content is not available, or not supported.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_clipboard_paste_chunked(
+    mut app_ptr: AppPtr,
+    serial: i32,
+    supported_mime_types: BorrowedArray<BorrowedUtf8>,
+) -> DataTransferId {
+    debug!("application_clipboard_paste_chunked");
+    ffi_boundary("application_clipboard_paste_chunked", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        let supported_mime_types =
+            MimeTypes::from_array(&supported_mime_types, "application_clipboard_paste_chunked: supported_mime_types")?;
+        Ok(app
+            .clipboard_paste_chunked(serial, &supported_mime_types)
+            .unwrap_or(DataTransferId(0)))
+    })
+}
+
+/// Like `application_primary_selection_paste_v2`, but the data is reported with `DataTransferChunk` events, as it's received.
+///
+/// The data is not converted, and `mime_type` of the events is the one offered by the other application.
+///
+/// Returns `DataTransferId(0)` if the primary selection content is not available, or not supported.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_primary_selection_paste_chunked(
+    mut app_ptr: AppPtr,
+    serial: i32,
+    supported_mime_types: BorrowedArray<BorrowedUtf8>,
+) -> DataTransferId {
+    debug!("application_primary_selection_paste_chunked");
+    ffi_boundary("application_primary_selection_paste_chunked", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        let supported_mime_types = MimeTypes::from_array(
+            &supported_mime_types,
+            "application_primary_selection_paste_chunked: supported_mime_types",
+        )?;
+        Ok(app
+            .primary_selection_paste_chunked(serial, &supported_mime_types)
+            .unwrap_or(DataTransferId(0)))
+    })
+}
+
+/// Queues a chunk of the data for the transfer reported with `Event::DataTransferWriteRequested`.
+/// Returns immediately, the data is written asynchronously.
+///
+/// Only a limited number of chunks can be queued. When the queue is full, `QueueFull` is returned,
+/// and the same data should be written again after `Event::DataTransferWriteReady`.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_data_transfer_write(
+    app_ptr: AppPtr,
+    transfer_id: DataTransferId,
+    data: BorrowedArray<u8>,
+) -> DataTransferWriteResult {
+    ffi_boundary("application_data_transfer_write", || {
+        let app = unsafe { app_ptr.borrow::<Application>() };
+        Ok(app.state.data_transfer_write(transfer_id, data.as_slice()?))
+    })
+}
+
+/// Finishes the transfer after all the data was queued with `application_data_transfer_write`.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_data_transfer_write_end(mut app_ptr: AppPtr, transfer_id: DataTransferId) -> bool {
+    debug!("application_data_transfer_write_end: {transfer_id:?}");
+    ffi_boundary("application_data_transfer_write_end", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        Ok(app.state.data_transfer_write_end(transfer_id))
+    })
+}
+
+/// Cancels a chunked transfer, either started with one of the `*_paste_chunked` functions,
+/// or reported with `Event::DataTransferWriteRequested`.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_data_transfer_cancel(mut app_ptr: AppPtr, transfer_id: DataTransferId) {
+    debug!("application_data_transfer_cancel: {transfer_id:?}");
+    ffi_boundary("application_data_transfer_cancel", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        app.state.data_transfer_cancel(transfer_id);
         Ok(())
     });
 }
 
+#[deprecated(note = "use application_clipboard_get_available_mimetypes_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn application_clipboard_get_available_mimetypes(mut app_ptr: AppPtr) -> AutoDropArray<u8> {
     debug!("application_clipboard_get_available_mimetypes");
     ffi_boundary("application_clipboard_get_available_mimetypes", || {
         let app = unsafe { app_ptr.borrow_mut::<Application>() };
-        if let Some(csv_mimetypes) = app.clipboard_get_available_mimetypes() {
-            Ok(AutoDropArray::new(csv_mimetypes.into_bytes().into()))
+        if let Some(mime_types) = app.clipboard_get_available_mimetypes() {
+            Ok(AutoDropArray::new(mime_types.join(",").into_bytes().into()))
         } else {
             Ok(AutoDropArray::null())
         }
     })
 }
 
+/// Returns the MIME types offered for the clipboard content, or a null array if there's no content.
+/// The array must be freed with `string_array_drop`.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_clipboard_get_available_mimetypes_v2(mut app_ptr: AppPtr) -> AutoDropArray<AutoDropStrPtr> {
+    debug!("application_clipboard_get_available_mimetypes_v2");
+    ffi_boundary("application_clipboard_get_available_mimetypes_v2", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        match app.clipboard_get_available_mimetypes() {
+            Some(mime_types) => Ok(AutoDropArray::from_strs(mime_types)?),
+            None => Ok(AutoDropArray::null()),
+        }
+    })
+}
+
+#[deprecated(note = "use application_primary_selection_get_available_mimetypes_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn application_primary_selection_get_available_mimetypes(mut app_ptr: AppPtr) -> AutoDropArray<u8> {
     debug!("application_primary_selection_get_available_mimetypes");
     ffi_boundary("application_primary_selection_get_available_mimetypes", || {
         let app = unsafe { app_ptr.borrow_mut::<Application>() };
-        if let Some(csv_mimetypes) = app.primary_selection_get_available_mimetypes() {
-            Ok(AutoDropArray::new(csv_mimetypes.into_bytes().into()))
+        if let Some(mime_types) = app.primary_selection_get_available_mimetypes() {
+            Ok(AutoDropArray::new(mime_types.join(",").into_bytes().into()))
         } else {
             Ok(AutoDropArray::null())
         }
     })
 }
 
+/// Returns the MIME types offered for the primary selection content, or a null array if there's no content.
+/// The array must be freed with `string_array_drop`.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_primary_selection_get_available_mimetypes_v2(mut app_ptr: AppPtr) -> AutoDropArray<AutoDropStrPtr> {
+    debug!("application_primary_selection_get_available_mimetypes_v2");
+    ffi_boundary("application_primary_selection_get_available_mimetypes_v2", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        match app.primary_selection_get_available_mimetypes() {
+            Some(mime_types) => Ok(AutoDropArray::from_strs(mime_types)?),
+            None => Ok(AutoDropArray::null()),
+        }
+    })
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn application_open_url(mut app_ptr: AppPtr, url_string: BorrowedUtf8, activation_token: BorrowedUtf8) -> RequestId {
     ffi_boundary("application_open_url", || {
@@ -342,7 +638,89 @@ pub extern "C" fn application_request_show_notification(
         let summary = title.get("application_show_notification: title")?;
         let body = body.get("application_show_notification: body")?;
         let sound_file_path_opt = sound_file_path.get_optional("application_show_notification: sound_file_path")?;
-        app.request_show_notification(summary.to_owned(), body.to_owned(), sound_file_path_opt.map(ToOwned::to_owned))
+        let content = NotificationContent::new(summary.to_owned(), body.to_owned(), sound_file_path_opt.map(ToOwned::to_owned));
+        app.request_show_notification(content)
+    })
+}
+
+/// Values match the `urgency` hint of the Desktop Notifications Specification.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NotificationUrgency {
+    Low = 0,
+    Normal = 1,
+    Critical = 2,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct NotificationActionButton<'a> {
+    /// Reported back in `NotificationClosedEvent::action` when the button is clicked.
+    /// Must not be `"default"`, which is reserved for clicking the notification itself.
+    pub key: BorrowedUtf8<'a>,
+
+    /// User-visible button text.
+    pub label: BorrowedUtf8<'a>,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct NotificationImage<'a> {
+    pub width: i32,
+    pub height: i32,
+
+    /// Optional. Non-premultiplied RGBA, 8 bits per channel, without row padding (`width * height * 4` bytes).
+    pub rgba_data: BorrowedArray<'a, u8>,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct NotificationParams<'a> {
+    /// User-visible string to display as the title.
+    pub title: BorrowedUtf8<'a>,
+
+    /// User-visible string to display as the body.
+    pub body: BorrowedUtf8<'a>,
+
+    /// Optional. See `application_request_show_notification`.
+    pub sound_file_path: BorrowedUtf8<'a>,
+
+    /// Optional. Buttons to display on the notification, the server may ignore them.
+    pub actions: BorrowedArray<'a, NotificationActionButton<'a>>,
+
+    /// Optional. Icon name from the icon theme, or a `file://` URI.
+    /// If not set, the server picks the icon based on the application `.desktop` file.
+    pub app_icon: BorrowedUtf8<'a>,
+
+    /// Optional (with null `rgba_data`). Takes precedence over `app_icon`.
+    /// Ignored when sandboxed, because the notification portal is used then.
+    pub image: NotificationImage<'a>,
+
+    pub urgency: NotificationUrgency,
+
+    /// Optional. E.g. `"transfer.complete"`, see the Desktop Notifications Specification for the list of categories.
+    pub category: BorrowedUtf8<'a>,
+
+    /// `-1` means that the timeout depends on the notification server settings, `0` means that the notification never expires.
+    /// Ignored when sandboxed.
+    pub expire_timeout_ms: i32,
+
+    /// ID of the notification to update in-place (e.g. for progress reporting), or `0` to show a new notification.
+    pub replaces_id: u32,
+}
+
+/// Same as `application_request_show_notification`, but with support for action buttons, images, and other hints.
+///
+/// Clicked action button is reported with its key in `Event::NotificationClosed`.
+/// When sandboxed (e.g. in Flatpak), the notification portal is used, which doesn't report dismissed notifications.
+/// When `replaces_id` is set, `Event::NotificationShown` is reported with the same notification ID.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_request_show_notification_with_params(mut app_ptr: AppPtr, params: NotificationParams) -> RequestId {
+    debug!("application_request_show_notification_with_params");
+    ffi_boundary("application_request_show_notification_with_params", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        let content = NotificationContent::from_unsafe(&params)?;
+        app.request_show_notification(content)
     })
 }
 
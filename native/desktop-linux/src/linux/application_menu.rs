@@ -0,0 +1,655 @@
+use crate::linux::{
+    application::Application,
+    application_menu_api::{AppMenuItem, AppMenuItemId, AppMenuItemState, AppMenuItemToggleState, AppMenuItemToggleType, AppMenuStructure},
+};
+use anyhow::Context as _;
+use log::{debug, warn};
+use smithay_client_toolkit::reexports::calloop::channel::Sender;
+use std::collections::HashMap;
+use zbus::{
+    fdo, interface,
+    object_server::SignalEmitter,
+    zvariant::{OwnedValue, Str, Structure, Value},
+};
+
+/// cbindgen:ignore
+pub const APP_MENU_OBJECT_PATH: &str = "/MenuBar";
+
+/// cbindgen:ignore
+const ROOT_ID: i32 = 0;
+
+#[derive(Debug, Clone)]
+pub enum AppMenuItemSafe {
+    Action {
+        item_id: AppMenuItemId,
+        title: String,
+        state: AppMenuItemState,
+        toggle_type: AppMenuItemToggleType,
+        shortcut: Option<String>,
+    },
+    Separator,
+    SubMenu {
+        title: String,
+        items: Vec<Self>,
+    },
+}
+
+impl AppMenuItemSafe {
+    fn from_unsafe(item: &AppMenuItem) -> anyhow::Result<Self> {
+        Ok(match item {
+            AppMenuItem::ActionItem {
+                item_id,
+                title,
+                enabled,
+                toggle_type,
+                toggle_state,
+                shortcut,
+            } => Self::Action {
+                item_id: *item_id,
+                title: title.get("AppMenuItem::ActionItem: title")?.to_owned(),
+                state: AppMenuItemState {
+                    enabled: *enabled,
+                    toggle_state: *toggle_state,
+                },
+                toggle_type: *toggle_type,
+                shortcut: shortcut.get_optional("AppMenuItem::ActionItem: shortcut")?.map(str::to_owned),
+            },
+            AppMenuItem::SeparatorItem => Self::Separator,
+            AppMenuItem::SubMenuItem { title, items } => Self::SubMenu {
+                title: title.get("AppMenuItem::SubMenuItem: title")?.to_owned(),
+                items: items
+                    .as_slice()
+                    .context("AppMenuItem::SubMenuItem: items")?
+                    .iter()
+                    .map(Self::from_unsafe)
+                    .collect::<anyhow::Result<_>>()?,
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AppMenuStructureSafe {
+    pub items: Vec<AppMenuItemSafe>,
+}
+
+impl AppMenuStructureSafe {
+    pub fn from_unsafe(menu: &AppMenuStructure) -> anyhow::Result<Self> {
+        let items = menu
+            .items
+            .as_slice()
+            .context("AppMenuStructure: items")?
+            .iter()
+            .map(AppMenuItemSafe::from_unsafe)
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { items })
+    }
+}
+
+/// Messages from the D-Bus menu server to the event loop.
+pub enum AppMenuRequest {
+    Exported {
+        service_name: String,
+    },
+    ItemActivated {
+        item_id: AppMenuItemId,
+        toggle_state: AppMenuItemToggleState,
+    },
+    QueryItemStates {
+        item_ids: Vec<AppMenuItemId>,
+        reply: tokio::sync::oneshot::Sender<Vec<AppMenuItemState>>,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum DbusMenuNodeKind {
+    Root,
+    Action {
+        item_id: AppMenuItemId,
+        title: String,
+        state: AppMenuItemState,
+        toggle_type: AppMenuItemToggleType,
+        shortcut: Option<String>,
+    },
+    Separator,
+    SubMenu {
+        title: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct DbusMenuNode {
+    kind: DbusMenuNodeKind,
+    children: Vec<i32>,
+}
+
+/// Flattened menu, where the index of a node is its `dbusmenu` id.
+#[derive(Debug, Clone)]
+pub struct DbusMenuLayout {
+    nodes: Vec<DbusMenuNode>,
+}
+
+type DbusMenuProperties = HashMap<String, OwnedValue>;
+
+type DbusMenuLayoutItem = (i32, DbusMenuProperties, Vec<OwnedValue>);
+
+impl DbusMenuLayout {
+    pub fn new(menu: &AppMenuStructureSafe) -> anyhow::Result<Self> {
+        let mut layout = Self {
+            nodes: vec![DbusMenuNode {
+                kind: DbusMenuNodeKind::Root,
+                children: Vec::new(),
+            }],
+        };
+        layout.nodes[0].children = layout.add_items(&menu.items)?;
+        Ok(layout)
+    }
+
+    fn add_items(&mut self, items: &[AppMenuItemSafe]) -> anyhow::Result<Vec<i32>> {
+        items
+            .iter()
+            .map(|item| {
+                let (kind, children) = match item {
+                    AppMenuItemSafe::Action {
+                        item_id,
+                        title,
+                        state,
+                        toggle_type,
+                        shortcut,
+                    } => (
+                        DbusMenuNodeKind::Action {
+                            item_id: *item_id,
+                            title: title.clone(),
+                            state: *state,
+                            toggle_type: *toggle_type,
+                            shortcut: shortcut.clone(),
+                        },
+                        Vec::new(),
+                    ),
+                    AppMenuItemSafe::Separator => (DbusMenuNodeKind::Separator, Vec::new()),
+                    AppMenuItemSafe::SubMenu { title, items } => {
+                        (DbusMenuNodeKind::SubMenu { title: title.clone() }, self.add_items(items)?)
+                    }
+                };
+                let id = i32::try_from(self.nodes.len()).context("Too many menu items")?;
+                self.nodes.push(DbusMenuNode { kind, children });
+                Ok(id)
+            })
+            .collect()
+    }
+
+    fn node(&self, id: i32) -> fdo::Result<&DbusMenuNode> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|i| self.nodes.get(i))
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("Unknown menu item id {id}")))
+    }
+
+    fn node_mut(&mut self, id: i32) -> fdo::Result<&mut DbusMenuNode> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|i| self.nodes.get_mut(i))
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("Unknown menu item id {id}")))
+    }
+
+    fn properties(&self, id: i32, property_names: &[String]) -> fdo::Result<DbusMenuProperties> {
+        let node = self.node(id)?;
+        let mut props = HashMap::new();
+        let mut insert = |name: &str, value: OwnedValue| {
+            if property_names.is_empty() || property_names.iter().any(|n| n == name) {
+                props.insert(name.to_owned(), value);
+            }
+        };
+        match &node.kind {
+            DbusMenuNodeKind::Root => {
+                insert("children-display", Str::from("submenu").into());
+            }
+            DbusMenuNodeKind::Action {
+                title,
+                state,
+                toggle_type,
+                shortcut,
+                ..
+            } => {
+                insert("label", Str::from(title.clone()).into());
+                insert("enabled", state.enabled.into());
+                match toggle_type {
+                    AppMenuItemToggleType::None => {}
+                    AppMenuItemToggleType::CheckMark => insert("toggle-type", Str::from("checkmark").into()),
+                    AppMenuItemToggleType::Radio => insert("toggle-type", Str::from("radio").into()),
+                }
+                if *toggle_type != AppMenuItemToggleType::None {
+                    let toggle_state: i32 = match state.toggle_state {
+                        AppMenuItemToggleState::Off => 0,
+                        AppMenuItemToggleState::On => 1,
+                        AppMenuItemToggleState::Indeterminate => -1,
+                    };
+                    insert("toggle-state", toggle_state.into());
+                }
+                if let Some(shortcut) = shortcut.as_deref().and_then(parse_shortcut) {
+                    let value = OwnedValue::try_from(Value::from(vec![shortcut])).map_err(zbus::Error::from)?;
+                    insert("shortcut", value);
+                }
+            }
+            DbusMenuNodeKind::Separator => {
+                insert("type", Str::from("separator").into());
+            }
+            DbusMenuNodeKind::SubMenu { title } => {
+                insert("label", Str::from(title.clone()).into());
+                insert("children-display", Str::from("submenu").into());
+            }
+        }
+        Ok(props)
+    }
+
+    fn layout_item(&self, id: i32, recursion_depth: i32, property_names: &[String]) -> fdo::Result<DbusMenuLayoutItem> {
+        let node = self.node(id)?;
+        let children = if recursion_depth == 0 {
+            Vec::new()
+        } else {
+            node.children
+                .iter()
+                .map(|&child_id| {
+                    let child = self.layout_item(child_id, recursion_depth - 1, property_names)?;
+                    OwnedValue::try_from(Structure::from(child)).map_err(|e| zbus::Error::from(e).into())
+                })
+                .collect::<fdo::Result<_>>()?
+        };
+        Ok((id, self.properties(id, property_names)?, children))
+    }
+
+    fn child_action_items(&self, id: i32) -> fdo::Result<Vec<(i32, AppMenuItemId)>> {
+        let node = self.node(id)?;
+        Ok(node
+            .children
+            .iter()
+            .filter_map(|&child_id| match &self.node(child_id).ok()?.kind {
+                DbusMenuNodeKind::Action { item_id, .. } => Some((child_id, *item_id)),
+                _ => None,
+            })
+            .collect())
+    }
+}
+
+/// Converts a GTK-style accelerator (e.g. `"<Control><Shift>s"`) into the `dbusmenu` key list.
+fn parse_shortcut(accelerator: &str) -> Option<Vec<String>> {
+    let mut keys = Vec::new();
+    let mut rest = accelerator.trim();
+    while let Some(modifier_start) = rest.strip_prefix('<') {
+        let (modifier, after) = modifier_start.split_once('>')?;
+        let modifier = match modifier.to_ascii_lowercase().as_str() {
+            "control" | "ctrl" | "primary" => "Control",
+            "shift" => "Shift",
+            "alt" | "mod1" => "Alt",
+            "super" | "meta" | "hyper" => "Super",
+            _ => return None,
+        };
+        keys.push(modifier.to_owned());
+        rest = after;
+    }
+    if rest.is_empty() {
+        return None;
+    }
+    keys.push(rest.to_owned());
+    Some(keys)
+}
+
+/// Implementation of the `com.canonical.dbusmenu` interface, as used by the KDE Plasma global menu.
+/// See <https://github.com/AyatanaIndicators/libdbusmenu/blob/master/libdbusmenu-glib/dbus-menu.xml>
+pub struct DbusMenu {
+    revision: u32,
+    layout: DbusMenuLayout,
+    request_sender: Sender<AppMenuRequest>,
+}
+
+// D-Bus methods take their arguments by value
+#[allow(clippy::needless_pass_by_value)]
+#[interface(name = "com.canonical.dbusmenu")]
+impl DbusMenu {
+    fn get_layout(&self, parent_id: i32, recursion_depth: i32, property_names: Vec<String>) -> fdo::Result<(u32, DbusMenuLayoutItem)> {
+        let item = self.layout.layout_item(parent_id, recursion_depth, &property_names)?;
+        Ok((self.revision, item))
+    }
+
+    fn get_group_properties(&self, ids: Vec<i32>, property_names: Vec<String>) -> Vec<(i32, DbusMenuProperties)> {
+        ids.into_iter()
+            .filter_map(|id| self.layout.properties(id, &property_names).ok().map(|props| (id, props)))
+            .collect()
+    }
+
+    fn get_property(&self, id: i32, name: String) -> fdo::Result<OwnedValue> {
+        self.layout
+            .properties(id, std::slice::from_ref(&name))?
+            .remove(&name)
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("Unknown property {name} for menu item id {id}")))
+    }
+
+    async fn event(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        id: i32,
+        event_id: String,
+        data: OwnedValue,
+        timestamp: u32,
+    ) -> fdo::Result<()> {
+        debug!("dbusmenu event {event_id} for {id}, data: {data:?}, timestamp: {timestamp}");
+        if event_id == "clicked" {
+            self.activate(&emitter, id).await?;
+        }
+        Ok(())
+    }
+
+    async fn event_group(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        events: Vec<(i32, String, OwnedValue, u32)>,
+    ) -> fdo::Result<Vec<i32>> {
+        let mut id_errors = Vec::new();
+        for (id, event_id, _data, _timestamp) in events {
+            if event_id == "clicked" && self.activate(&emitter, id).await.is_err() {
+                id_errors.push(id);
+            }
+        }
+        Ok(id_errors)
+    }
+
+    async fn about_to_show(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>, id: i32) -> fdo::Result<bool> {
+        self.refresh_item_states(&emitter, id).await?;
+        Ok(false)
+    }
+
+    async fn about_to_show_group(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>, ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+        let mut id_errors = Vec::new();
+        for id in ids {
+            if self.refresh_item_states(&emitter, id).await.is_err() {
+                id_errors.push(id);
+            }
+        }
+        (Vec::new(), id_errors)
+    }
+
+    #[zbus(signal)]
+    async fn items_properties_updated(
+        emitter: &SignalEmitter<'_>,
+        updated_props: Vec<(i32, DbusMenuProperties)>,
+        removed_props: Vec<(i32, Vec<String>)>,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn layout_updated(emitter: &SignalEmitter<'_>, revision: u32, parent: i32) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    #[allow(clippy::unused_self)]
+    const fn version(&self) -> u32 {
+        3
+    }
+
+    #[zbus(property)]
+    #[allow(clippy::unused_self)]
+    fn text_direction(&self) -> String {
+        "ltr".to_owned()
+    }
+
+    #[zbus(property)]
+    #[allow(clippy::unused_self)]
+    fn status(&self) -> String {
+        "normal".to_owned()
+    }
+
+    #[zbus(property)]
+    #[allow(clippy::unused_self)]
+    const fn icon_theme_path(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl DbusMenu {
+    async fn activate(&mut self, emitter: &SignalEmitter<'_>, id: i32) -> fdo::Result<()> {
+        let node = self.layout.node_mut(id)?;
+        let DbusMenuNodeKind::Action {
+            item_id,
+            state,
+            toggle_type,
+            ..
+        } = &mut node.kind
+        else {
+            return Ok(());
+        };
+        if !state.enabled {
+            debug!("Ignoring activation of disabled menu item {item_id}");
+            return Ok(());
+        }
+        let old_toggle_state = state.toggle_state;
+        state.toggle_state = match toggle_type {
+            AppMenuItemToggleType::None => state.toggle_state,
+            AppMenuItemToggleType::CheckMark => match state.toggle_state {
+                AppMenuItemToggleState::On => AppMenuItemToggleState::Off,
+                AppMenuItemToggleState::Off | AppMenuItemToggleState::Indeterminate => AppMenuItemToggleState::On,
+            },
+            AppMenuItemToggleType::Radio => AppMenuItemToggleState::On,
+        };
+        let request = AppMenuRequest::ItemActivated {
+            item_id: *item_id,
+            toggle_state: state.toggle_state,
+        };
+        if old_toggle_state != state.toggle_state {
+            let props = self.layout.properties(id, &[])?;
+            Self::items_properties_updated(emitter, vec![(id, props)], Vec::new()).await?;
+        }
+        self.request_sender
+            .send(request)
+            .map_err(|e| fdo::Error::Failed(format!("Event loop is not running: {e}")))
+    }
+
+    async fn refresh_item_states(&mut self, emitter: &SignalEmitter<'_>, id: i32) -> fdo::Result<()> {
+        let items = self.layout.child_action_items(id)?;
+        if items.is_empty() {
+            return Ok(());
+        }
+        let (reply, reply_receiver) = tokio::sync::oneshot::channel();
+        let item_ids = items.iter().map(|(_, item_id)| *item_id).collect();
+        self.request_sender
+            .send(AppMenuRequest::QueryItemStates { item_ids, reply })
+            .map_err(|e| fdo::Error::Failed(format!("Event loop is not running: {e}")))?;
+        let Ok(new_states) = reply_receiver.await else {
+            return Ok(());
+        };
+
+        let mut updated_props = Vec::new();
+        for ((id, _), new_state) in items.into_iter().zip(new_states) {
+            if let DbusMenuNodeKind::Action { state, .. } = &mut self.layout.node_mut(id)?.kind
+                && *state != new_state
+            {
+                *state = new_state;
+                updated_props.push((id, self.layout.properties(id, &[])?));
+            }
+        }
+        if !updated_props.is_empty() {
+            Self::items_properties_updated(emitter, updated_props, Vec::new()).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Exports the menu on the first call, and updates the exported menu on the next ones.
+/// Returns the D-Bus service name that should be announced for the windows, if the menu was just exported.
+pub async fn publish_app_menu(layout: DbusMenuLayout, request_sender: Sender<AppMenuRequest>) -> anyhow::Result<Option<String>> {
+    let connection = Application::dbus_connection().await?;
+    let object_server = connection.object_server();
+    if let Ok(iface_ref) = object_server.interface::<_, DbusMenu>(APP_MENU_OBJECT_PATH).await {
+        let revision = {
+            let mut iface = iface_ref.get_mut().await;
+            iface.revision = iface.revision.wrapping_add(1);
+            iface.layout = layout;
+            iface.revision
+        };
+        DbusMenu::layout_updated(iface_ref.signal_emitter(), revision, ROOT_ID).await?;
+        debug!("Updated application menu, revision {revision}");
+        return Ok(None);
+    }
+
+    let menu = DbusMenu {
+        revision: 1,
+        layout,
+        request_sender,
+    };
+    if !object_server.at(APP_MENU_OBJECT_PATH, menu).await? {
+        warn!("Application menu was already exported");
+        return Ok(None);
+    }
+    let service_name = connection.unique_name().context("No unique name for D-Bus connection")?.to_string();
+    debug!("Exported application menu as {service_name} {APP_MENU_OBJECT_PATH}");
+    Ok(Some(service_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(item_id: AppMenuItemId, title: &str, toggle_type: AppMenuItemToggleType, shortcut: Option<&str>) -> AppMenuItemSafe {
+        AppMenuItemSafe::Action {
+            item_id,
+            title: title.to_owned(),
+            state: AppMenuItemState {
+                enabled: true,
+                toggle_state: AppMenuItemToggleState::On,
+            },
+            toggle_type,
+            shortcut: shortcut.map(str::to_owned),
+        }
+    }
+
+    /// Submenu items get their ids before the submenu itself:
+    /// File (5): Open (1), separator (2), Recent (4): Foo (3); Edit (7): Wrap (6)
+    fn test_layout() -> DbusMenuLayout {
+        let menu = AppMenuStructureSafe {
+            items: vec![
+                AppMenuItemSafe::SubMenu {
+                    title: "File".to_owned(),
+                    items: vec![
+                        action(10, "Open", AppMenuItemToggleType::None, Some("<Control>o")),
+                        AppMenuItemSafe::Separator,
+                        AppMenuItemSafe::SubMenu {
+                            title: "Recent".to_owned(),
+                            items: vec![action(11, "Foo", AppMenuItemToggleType::None, None)],
+                        },
+                    ],
+                },
+                AppMenuItemSafe::SubMenu {
+                    title: "Edit".to_owned(),
+                    items: vec![action(12, "Wrap", AppMenuItemToggleType::CheckMark, None)],
+                },
+            ],
+        };
+        DbusMenuLayout::new(&menu).unwrap()
+    }
+
+    fn prop<T: TryFrom<OwnedValue>>(props: &DbusMenuProperties, name: &str) -> T {
+        T::try_from(props[name].try_clone().unwrap()).ok().unwrap()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|&n| n.to_owned()).collect()
+    }
+
+    #[test]
+    fn test_parse_shortcut() {
+        let cases: &[(&str, Option<&[&str]>)] = &[
+            ("s", Some(&["s"])),
+            ("<Control>s", Some(&["Control", "s"])),
+            ("<ctrl><Shift>S", Some(&["Control", "Shift", "S"])),
+            ("<Primary><Alt>F4", Some(&["Control", "Alt", "F4"])),
+            ("<Mod1>x", Some(&["Alt", "x"])),
+            ("<Super>space", Some(&["Super", "space"])),
+            ("<Meta>a", Some(&["Super", "a"])),
+            ("  <Control>q  ", Some(&["Control", "q"])),
+            ("", None),
+            ("<Control>", None),
+            ("<Control", None),
+            ("<Fn>a", None),
+        ];
+        for &(accelerator, expected) in cases {
+            assert_eq!(parse_shortcut(accelerator), expected.map(names), "{accelerator:?}");
+        }
+    }
+
+    #[test]
+    fn test_layout_ids() {
+        let layout = test_layout();
+        let children = |id| layout.node(id).unwrap().children.clone();
+        assert_eq!(children(ROOT_ID), vec![5, 7]);
+        assert_eq!(children(5), vec![1, 2, 4]);
+        assert_eq!(children(4), vec![3]);
+        assert_eq!(children(7), vec![6]);
+        assert!(children(1).is_empty());
+        assert!(layout.node(8).is_err());
+        assert!(layout.node(-1).is_err());
+    }
+
+    #[test]
+    fn test_properties() {
+        let layout = test_layout();
+
+        let root = layout.properties(ROOT_ID, &[]).unwrap();
+        assert_eq!(prop::<String>(&root, "children-display"), "submenu");
+
+        let file = layout.properties(5, &[]).unwrap();
+        assert_eq!(prop::<String>(&file, "label"), "File");
+        assert_eq!(prop::<String>(&file, "children-display"), "submenu");
+
+        let open = layout.properties(1, &[]).unwrap();
+        assert_eq!(prop::<String>(&open, "label"), "Open");
+        assert!(prop::<bool>(&open, "enabled"));
+        assert_eq!(prop::<Vec<Vec<String>>>(&open, "shortcut"), vec![names(&["Control", "o"])]);
+        assert!(!open.contains_key("toggle-type"));
+        assert!(!open.contains_key("toggle-state"));
+
+        let separator = layout.properties(2, &[]).unwrap();
+        assert_eq!(prop::<String>(&separator, "type"), "separator");
+        assert_eq!(separator.len(), 1);
+
+        let wrap = layout.properties(6, &[]).unwrap();
+        assert_eq!(prop::<String>(&wrap, "toggle-type"), "checkmark");
+        assert_eq!(prop::<i32>(&wrap, "toggle-state"), 1);
+        assert!(!wrap.contains_key("shortcut"));
+
+        let filtered = layout.properties(1, &names(&["label", "unknown"])).unwrap();
+        assert_eq!(filtered.keys().collect::<Vec<_>>(), vec!["label"]);
+    }
+
+    #[test]
+    fn test_layout_item() {
+        let layout = test_layout();
+
+        let (id, _, children) = layout.layout_item(ROOT_ID, 0, &[]).unwrap();
+        assert_eq!(id, ROOT_ID);
+        assert!(children.is_empty());
+
+        let (_, _, children) = layout.layout_item(ROOT_ID, 1, &[]).unwrap();
+        let child_ids = children
+            .into_iter()
+            .map(|child| {
+                let (id, props, grandchildren) = DbusMenuLayoutItem::try_from(child).unwrap();
+                assert!(grandchildren.is_empty());
+                assert!(props.contains_key("label"));
+                id
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(child_ids, vec![5, 7]);
+
+        let (_, _, children) = layout.layout_item(5, -1, &[]).unwrap();
+        let (recent_id, _, recent_children) = DbusMenuLayoutItem::try_from(children[2].try_clone().unwrap()).unwrap();
+        assert_eq!(recent_id, 4);
+        assert_eq!(recent_children.len(), 1);
+
+        assert!(layout.layout_item(42, -1, &[]).is_err());
+    }
+
+    #[test]
+    fn test_child_action_items() {
+        let layout = test_layout();
+        assert_eq!(layout.child_action_items(5).unwrap(), vec![(1, 10)]);
+        assert_eq!(layout.child_action_items(4).unwrap(), vec![(3, 11)]);
+        assert!(layout.child_action_items(ROOT_ID).unwrap().is_empty());
+    }
+}
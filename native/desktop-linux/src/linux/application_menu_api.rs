@@ -0,0 +1,81 @@
+use crate::linux::{application::Application, application_api::AppPtr, application_menu::AppMenuStructureSafe};
+use desktop_common::{
+    ffi_utils::{BorrowedArray, BorrowedUtf8},
+    logger::ffi_boundary,
+};
+use log::debug;
+
+pub type AppMenuItemId = i64;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AppMenuItemToggleType {
+    None,
+    CheckMark,
+    Radio,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AppMenuItemToggleState {
+    Off,
+    On,
+    Indeterminate,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub enum AppMenuItem<'a> {
+    ActionItem {
+        item_id: AppMenuItemId,
+
+        /// Underscore marks the mnemonic, e.g. `"_Open"`.
+        title: BorrowedUtf8<'a>,
+
+        enabled: bool,
+        toggle_type: AppMenuItemToggleType,
+        toggle_state: AppMenuItemToggleState,
+
+        /// Optional. Accelerator in the GTK format, e.g. `"<Control><Shift>s"`.
+        /// Only displayed in the menu, the key events are still delivered to the window as usual.
+        shortcut: BorrowedUtf8<'a>,
+    },
+    SeparatorItem,
+    SubMenuItem {
+        /// Underscore marks the mnemonic, e.g. `"_File"`.
+        title: BorrowedUtf8<'a>,
+        items: BorrowedArray<'a, Self>,
+    },
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct AppMenuStructure<'a> {
+    pub items: BorrowedArray<'a, AppMenuItem<'a>>,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AppMenuItemState {
+    pub enabled: bool,
+    pub toggle_state: AppMenuItemToggleState,
+}
+
+/// Called right before a submenu is shown, for every action item in it.
+pub type FfiQueryAppMenuItemState = extern "C" fn(item_id: AppMenuItemId) -> AppMenuItemState;
+
+/// Exports the application menu over D-Bus (`com.canonical.dbusmenu`) for the global menu (e.g. in KDE Plasma).
+///
+/// The menu is announced for all the application windows.
+/// Activation of the menu items is reported with `Event::AppMenuItemActivated`.
+/// An empty structure publishes an empty menu, the menu stays exported until the application exits.
+/// See <https://wayland.app/protocols/kde-appmenu>
+#[unsafe(no_mangle)]
+pub extern "C" fn application_menu_update(mut app_ptr: AppPtr, menu: AppMenuStructure) {
+    debug!("application_menu_update");
+    ffi_boundary("application_menu_update", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        let menu = AppMenuStructureSafe::from_unsafe(&menu)?;
+        app.app_menu_update(&menu)
+    });
+}
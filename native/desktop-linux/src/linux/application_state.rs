@@ -1,11 +1,15 @@
 use crate::linux::application::send_event;
+use crate::linux::application_menu::APP_MENU_OBJECT_PATH;
 use crate::linux::ffi_return_conversions::{QueryDragAndDropTarget, TransferDataGetter};
 use crate::linux::notifications::NotificationAction;
 use crate::linux::{
-    application_api::{ApplicationCallbacks, RenderingMode},
+    application_api::{ApplicationCallbacks, DataSource, RenderingMode},
+    data_control::{DataControlDevice, DataControlManager, DataControlOffer, DataControlSource},
+    data_transfer_stream::OutgoingDataTransfer,
     drag_icon::DragIcon,
     events::{
         ActivationTokenResponse,
+        DataTransferId,
         Event,
         RequestId,
         ScreenId,
@@ -32,6 +36,7 @@ use smithay_client_toolkit::{
     data_device_manager::{
         DataDeviceManagerState,
         data_device::DataDevice,
+        data_offer::DragOffer,
         data_source::{CopyPasteSource, DragSource},
     },
     delegate_activation,
@@ -88,7 +93,10 @@ use smithay_client_toolkit::{
     shm::{Shm, ShmHandler},
     //
 };
-use std::{collections::HashMap, sync::LazyLock};
+use std::{cell::Cell, collections::HashMap, rc::Rc, sync::LazyLock};
+use wayland_protocols_plasma::appmenu::client::{
+    org_kde_kwin_appmenu::OrgKdeKwinAppmenu, org_kde_kwin_appmenu_manager::OrgKdeKwinAppmenuManager,
+};
 
 /// cbindgen:ignore
 pub type EglInstance = khronos_egl::DynamicInstance<khronos_egl::EGL1_0>;
@@ -129,17 +137,42 @@ pub struct ApplicationState {
     pub viewporter: Option<WpViewporter>,
     pub fractional_scale_manager: Option<WpFractionalScaleManagerV1>,
     pub text_input_manager: Option<ZwpTextInputManagerV3>,
+    pub app_menu_manager: Option<OrgKdeKwinAppmenuManager>,
+    pub app_menu_service_name: Option<String>,
     pub data_device_manager_state: DataDeviceManagerState,
     pub copy_paste_source: Option<CopyPasteSource>,
     pub drag_source: Option<DragSource>,
+
+    /// MIME types provided by the application for each data source, without the added aliases.
+    pub provided_mime_types: HashMap<DataSource, Vec<String>>,
+
     pub current_drag_target_window_id: Option<WindowId>,
     pub current_drag_source_window_id: Option<WindowId>,
     pub current_drag_source_action: Option<DndAction>,
+
+    /// The drop that was performed with `DndAction::Ask`, waiting for the application to choose the final action.
+    /// Shared with the callback reading the dropped data, which sets it only if the read succeeds.
+    pub pending_ask_drag_offer: Rc<Cell<Option<DragOffer>>>,
+
+    pub data_transfer_counter: u64,
+    pub incoming_data_transfers: HashMap<DataTransferId, tokio::sync::oneshot::Sender<()>>,
+    pub outgoing_data_transfers: HashMap<DataTransferId, OutgoingDataTransfer>,
+
+    /// Set with `application_set_drop_chunked`.
+    pub drop_chunked: bool,
     pub drag_icon: Option<DragIcon>,
     pub data_device: Option<DataDevice>,
     pub primary_selection_manager: Option<PrimarySelectionManagerState>,
     pub primary_selection_device: Option<PrimarySelectionDevice>,
     pub primary_selection_source: Option<PrimarySelectionSource>,
+    pub data_control_manager: Option<DataControlManager>,
+    pub data_control_device: Option<DataControlDevice>,
+    pub data_control_offers: HashMap<DataSource, DataControlOffer>,
+    pub data_control_sources: HashMap<DataSource, DataControlSource>,
+
+    /// The clipboard data served after the event loop has stopped, see `Application::store_clipboard`.
+    pub clipboard_snapshot: Option<HashMap<String, Vec<u8>>>,
+    pub clipboard_snapshot_served: bool,
 
     pub window_id_to_surface_id: HashMap<WindowId, ObjectId>,
     pub windows: HashMap<ObjectId, SimpleWindow>,
@@ -149,6 +182,7 @@ pub struct ApplicationState {
     pub pending_text_input_event: PendingTextInputEvent,
     pub notification_action_sender: Option<tokio::sync::mpsc::Sender<NotificationAction>>,
     pub calloop_scheduler: calloop::futures::Scheduler<()>,
+    pub rt_handle: tokio::runtime::Handle,
 }
 
 impl ApplicationState {
@@ -158,6 +192,7 @@ impl ApplicationState {
         callbacks: ApplicationCallbacks,
         loop_handle: LoopHandle<'static, Self>,
         display: WlDisplay,
+        rt_handle: tokio::runtime::Handle,
     ) -> anyhow::Result<Self> {
         let registry_state = RegistryState::new(globals);
         let seat_state = SeatState::new(globals, qh);
@@ -200,17 +235,31 @@ impl ApplicationState {
             viewporter: globals.bind(qh, 1..=1, ()).ok(),
             fractional_scale_manager: globals.bind(qh, 1..=1, ()).ok(),
             text_input_manager: globals.bind(qh, 1..=1, ()).ok(),
+            app_menu_manager: globals.bind(qh, 1..=2, ()).ok(),
+            app_menu_service_name: None,
             data_device_manager_state,
             copy_paste_source: None,
             drag_source: None,
+            provided_mime_types: HashMap::new(),
             current_drag_target_window_id: None,
             current_drag_source_window_id: None,
             current_drag_source_action: None,
+            pending_ask_drag_offer: Rc::default(),
+            data_transfer_counter: 0,
+            incoming_data_transfers: HashMap::new(),
+            outgoing_data_transfers: HashMap::new(),
+            drop_chunked: false,
             drag_icon: None,
             data_device: None,
             primary_selection_manager: PrimarySelectionManagerState::bind(globals, qh).ok(),
             primary_selection_device: None,
             primary_selection_source: None,
+            data_control_manager: DataControlManager::bind(globals, qh),
+            data_control_device: None,
+            data_control_offers: HashMap::new(),
+            data_control_sources: HashMap::new(),
+            clipboard_snapshot: None,
+            clipboard_snapshot_served: false,
             window_id_to_surface_id: HashMap::new(),
             windows: HashMap::new(),
             last_pointer_down_event_serial: None,
@@ -219,6 +268,7 @@ impl ApplicationState {
             pending_text_input_event: PendingTextInputEvent::default(),
             notification_action_sender: None,
             calloop_scheduler,
+            rt_handle,
         })
     }
 
@@ -277,6 +327,17 @@ impl ApplicationState {
         send_event(self.callbacks.event_handler, event_data)
     }
 
+    pub fn set_app_menu_service_name(&mut self, qh: &QueueHandle<Self>, service_name: String) {
+        let Some(app_menu_manager) = &self.app_menu_manager else {
+            debug!("org_kde_kwin_appmenu_manager not available, the application menu won't be shown");
+            return;
+        };
+        for w in self.windows.values_mut() {
+            w.set_app_menu_address(app_menu_manager, qh, &service_name, APP_MENU_OBJECT_PATH);
+        }
+        self.app_menu_service_name = Some(service_name);
+    }
+
     pub fn get_latest_pointer_button_seat_and_serial(&self) -> Option<(&WlSeat, u32)> {
         if let Some(p) = &self.themed_pointer
             && let Some(pointer_data) = p.pointer().data::<PointerData>()
@@ -320,6 +381,8 @@ impl SeatHandler for ApplicationState {
             self.data_device = Some(self.data_device_manager_state.get_data_device(qh, &seat));
 
             self.primary_selection_device = self.primary_selection_manager.as_ref().map(|m| m.get_selection_device(qh, &seat));
+
+            self.data_control_device = self.data_control_manager.as_ref().map(|m| m.get_data_device(qh, &seat));
         }
 
         if capability == Capability::Keyboard && self.keyboard.is_none() {
@@ -437,9 +500,11 @@ impl CompositorHandler for ApplicationState {
 
     fn frame(&mut self, conn: &Connection, qh: &QueueHandle<Self>, surface: &WlSurface, _time: u32) {
         if let Some(window) = self.windows.get_mut(&surface.id()) {
-            window.draw(conn, qh, self.themed_pointer.as_mut(), &|e: WindowDrawEvent| {
+            if let Some(response) = window.draw(conn, qh, self.themed_pointer.as_mut(), &|e: WindowDrawEvent| {
                 send_event(self.callbacks.event_handler, e)
-            });
+            }) {
+                response.send_as_event(self.callbacks.event_handler);
+            }
         } else if let Some(drag_icon) = &mut self.drag_icon
             && drag_icon.surface.wl_surface() == surface
         {
@@ -507,9 +572,11 @@ impl WindowHandler for ApplicationState {
 
             if is_first_configure {
                 // Initiate the first draw.
-                w.draw(conn, qh, self.themed_pointer.as_mut(), &|e: WindowDrawEvent| {
+                if let Some(response) = w.draw(conn, qh, self.themed_pointer.as_mut(), &|e: WindowDrawEvent| {
                     send_event(self.callbacks.event_handler, e)
-                });
+                }) {
+                    response.send_as_event(self.callbacks.event_handler);
+                }
             }
         }
     }
@@ -525,6 +592,8 @@ delegate_noop!(ApplicationState: ignore WpFractionalScaleManagerV1);
 delegate_noop!(ApplicationState: ignore WpFractionalScaleV1);
 delegate_noop!(ApplicationState: ignore WpViewporter);
 delegate_noop!(ApplicationState: ignore WpViewport);
+delegate_noop!(ApplicationState: ignore OrgKdeKwinAppmenuManager);
+delegate_noop!(ApplicationState: ignore OrgKdeKwinAppmenu);
 
 impl Dispatch<WpFractionalScaleV1, ObjectId> for ApplicationState {
     fn event(
@@ -1,7 +1,22 @@
-use desktop_common::ffi_utils::BorrowedUtf8;
+use desktop_common::ffi_utils::{BorrowedArray, BorrowedUtf8};
 use log::error;
 
-use crate::linux::events::{EventHandler, FileChooserResponse, NotificationShownEvent, RequestId};
+use crate::linux::{
+    background::BackgroundResult,
+    desktop_settings_api::Color,
+    events::{
+        BackgroundResponse, ColorPickerResponse, EventHandler, FileChooserResponse, InhibitResponse, NotificationShownEvent,
+        PreparePrintResponse, PrintResponse, RequestId, ScreenshotResponse, SecretResponse, WindowCaptureFrameResponse, WindowId,
+    },
+    file_dialog::FileChooserResult,
+    file_dialog_api::FileDialogChoiceSelection,
+    frame_capture::CapturedFrame,
+    geometry::{PhysicalPixels, PhysicalSize},
+    print::PreparePrintResult,
+    print_api::{PrintPageSetup, PrintSettings},
+    secrets::{SecretError, SecretItemData},
+    secrets_api::{SecretAttribute, SecretErrorKind, SecretItem},
+};
 
 #[allow(clippy::enum_variant_names)]
 pub enum AsyncEventResult {
@@ -11,13 +26,53 @@ pub enum AsyncEventResult {
     },
     FileChooserResponse {
         request_id: RequestId,
-        result: anyhow::Result<String>,
+        /// `None` if cancelled by the user.
+        result: anyhow::Result<Option<FileChooserResult>>,
+    },
+    ScreenshotResponse {
+        request_id: RequestId,
+        /// `None` if cancelled by the user.
+        result: anyhow::Result<Option<String>>,
+    },
+    ColorPickerResponse {
+        request_id: RequestId,
+        /// `None` if cancelled by the user.
+        result: anyhow::Result<Option<Color>>,
+    },
+    PreparePrintResponse {
+        request_id: RequestId,
+        /// `None` if cancelled by the user.
+        result: anyhow::Result<Option<Box<PreparePrintResult>>>,
+    },
+    PrintResponse {
+        request_id: RequestId,
+        /// `false` if cancelled by the user.
+        result: anyhow::Result<bool>,
+    },
+    InhibitResponse {
+        request_id: RequestId,
+        result: anyhow::Result<()>,
+    },
+    BackgroundResponse {
+        request_id: RequestId,
+        result: anyhow::Result<BackgroundResult>,
+    },
+    SecretResponse {
+        request_id: RequestId,
+        result: Result<Vec<SecretItemData>, SecretError>,
     },
     NotificationClosed {},
     NotificationShown {
         request_id: RequestId,
         result: anyhow::Result<u32>,
     },
+    /// The same frame is reported for all the requests that were waiting for it.
+    WindowCaptureFrameResponse {
+        request_ids: Vec<RequestId>,
+        window_id: WindowId,
+        scale: f64,
+        result: anyhow::Result<CapturedFrame>,
+    },
 }
 
 impl AsyncEventResult {
@@ -28,24 +83,73 @@ impl AsyncEventResult {
                     error!("Error trying to open URL for {request_id:?}: {e}");
                 }
             }
-            Self::FileChooserResponse { request_id, result } => {
-                let send = |newline_separated_files| {
-                    let response = FileChooserResponse {
-                        request_id,
-                        newline_separated_files,
-                    };
-                    event_handler(&response.into())
-                };
-                match result {
-                    Ok(files) => {
-                        send(BorrowedUtf8::new(&files));
+            Self::FileChooserResponse { request_id, result } => send_file_chooser_response(request_id, result, event_handler),
+            Self::ScreenshotResponse { request_id, result } => {
+                let (uri, error) = match result {
+                    Ok(uri) => (uri, None),
+                    Err(e) => {
+                        error!("Error taking screenshot for {request_id:?}: {e}");
+                        (None, Some(format!("{e:#}")))
                     }
+                };
+                let response = ScreenshotResponse {
+                    request_id,
+                    uri: BorrowedUtf8::optional(uri.as_ref()),
+                    error: BorrowedUtf8::optional(error.as_ref()),
+                };
+                event_handler(&response.into());
+            }
+            Self::ColorPickerResponse { request_id, result } => {
+                let (color, error) = match result {
+                    Ok(color) => (color, None),
                     Err(e) => {
-                        error!("{e}");
-                        send(BorrowedUtf8::null());
+                        error!("Error picking color for {request_id:?}: {e}");
+                        (None, Some(format!("{e:#}")))
                     }
-                }
+                };
+                let response = ColorPickerResponse {
+                    request_id,
+                    picked: color.is_some(),
+                    color: color.unwrap_or(Color {
+                        red: 0.0,
+                        green: 0.0,
+                        blue: 0.0,
+                        alpha: 0.0,
+                    }),
+                    error: BorrowedUtf8::optional(error.as_ref()),
+                };
+                event_handler(&response.into());
+            }
+            Self::PreparePrintResponse { request_id, result } => send_prepare_print_response(request_id, result, event_handler),
+            Self::PrintResponse { request_id, result } => {
+                let (printed, error) = match result {
+                    Ok(printed) => (printed, None),
+                    Err(e) => {
+                        error!("Error printing for {request_id:?}: {e}");
+                        (false, Some(format!("{e:#}")))
+                    }
+                };
+                let response = PrintResponse {
+                    request_id,
+                    printed,
+                    error: BorrowedUtf8::optional(error.as_ref()),
+                };
+                event_handler(&response.into());
+            }
+            Self::InhibitResponse { request_id, result } => {
+                let error = result.err().map(|e| {
+                    error!("Error inhibiting for {request_id:?}: {e}");
+                    format!("{e:#}")
+                });
+                let response = InhibitResponse {
+                    request_id,
+                    inhibited: error.is_none(),
+                    error: BorrowedUtf8::optional(error.as_ref()),
+                };
+                event_handler(&response.into());
             }
+            Self::BackgroundResponse { request_id, result } => send_background_response(request_id, result, event_handler),
+            Self::SecretResponse { request_id, result } => send_secret_response(request_id, result, event_handler),
             Self::NotificationClosed {} => {}
             Self::NotificationShown { request_id, result } => {
                 let notification_id = result.unwrap_or_else(|e| {
@@ -58,6 +162,179 @@ impl AsyncEventResult {
                 };
                 event_handler(&event.into());
             }
+            Self::WindowCaptureFrameResponse {
+                request_ids,
+                window_id,
+                scale,
+                result,
+            } => send_window_capture_frame_response(&request_ids, window_id, scale, result, event_handler),
+        }
+    }
+}
+
+fn send_file_chooser_response(request_id: RequestId, result: anyhow::Result<Option<FileChooserResult>>, event_handler: EventHandler) {
+    match result {
+        Ok(Some(result)) => {
+            let choices = result
+                .choices
+                .iter()
+                .map(|(id, value)| FileDialogChoiceSelection {
+                    id: BorrowedUtf8::new(id),
+                    value: BorrowedUtf8::new(value),
+                })
+                .collect::<Vec<_>>();
+            let response = FileChooserResponse {
+                request_id,
+                newline_separated_files: BorrowedUtf8::new(&result.newline_separated_files),
+                error: BorrowedUtf8::null(),
+                current_filter_index: result.current_filter_index.and_then(|i| i32::try_from(i).ok()).unwrap_or(-1),
+                choices: BorrowedArray::from_slice(&choices),
+            };
+            event_handler(&response.into());
+        }
+        Ok(None) => {
+            let response = FileChooserResponse {
+                request_id,
+                newline_separated_files: BorrowedUtf8::null(),
+                error: BorrowedUtf8::null(),
+                current_filter_index: -1,
+                choices: BorrowedArray::null(),
+            };
+            event_handler(&response.into());
+        }
+        Err(e) => {
+            error!("{e}");
+            let error = format!("{e:#}");
+            let response = FileChooserResponse {
+                request_id,
+                newline_separated_files: BorrowedUtf8::null(),
+                error: BorrowedUtf8::new(&error),
+                current_filter_index: -1,
+                choices: BorrowedArray::null(),
+            };
+            event_handler(&response.into());
+        }
+    }
+}
+
+fn send_prepare_print_response(
+    request_id: RequestId,
+    result: anyhow::Result<Option<Box<PreparePrintResult>>>,
+    event_handler: EventHandler,
+) {
+    let (prepared, error) = match result {
+        Ok(prepared) => (prepared, None),
+        Err(e) => {
+            error!("Error preparing print for {request_id:?}: {e}");
+            (None, Some(format!("{e:#}")))
+        }
+    };
+    let default_settings = ashpd::desktop::print::Settings::default();
+    let default_page_setup = ashpd::desktop::print::PageSetup::default();
+    let output_uri = prepared
+        .as_ref()
+        .and_then(|prepared| prepared.settings.output_uri.as_ref())
+        .map(ToString::to_string);
+    let response = PreparePrintResponse {
+        request_id,
+        prepared: prepared.is_some(),
+        token: prepared.as_ref().map_or(0, |prepared| prepared.token),
+        settings: PrintSettings::new(
+            prepared.as_ref().map_or(&default_settings, |prepared| &prepared.settings),
+            output_uri.as_ref(),
+        ),
+        page_setup: PrintPageSetup::new(prepared.as_ref().map_or(&default_page_setup, |prepared| &prepared.page_setup)),
+        error: BorrowedUtf8::optional(error.as_ref()),
+    };
+    event_handler(&response.into());
+}
+
+fn send_background_response(request_id: RequestId, result: anyhow::Result<BackgroundResult>, event_handler: EventHandler) {
+    let (result, error) = match result {
+        Ok(result) => (Some(result), None),
+        Err(e) => {
+            error!("Error requesting background for {request_id:?}: {e}");
+            (None, Some(format!("{e:#}")))
+        }
+    };
+    let response = BackgroundResponse {
+        request_id,
+        run_in_background: result.is_some_and(|r| r.run_in_background),
+        autostart: result.is_some_and(|r| r.autostart),
+        error: BorrowedUtf8::optional(error.as_ref()),
+    };
+    event_handler(&response.into());
+}
+
+fn send_secret_response(request_id: RequestId, result: Result<Vec<SecretItemData>, SecretError>, event_handler: EventHandler) {
+    let (items, error_kind, error) = match result {
+        Ok(items) => (items, SecretErrorKind::None, None),
+        Err(e) => {
+            error!("Secret Service error for {request_id:?}: {e}");
+            (Vec::new(), e.kind(), Some(e.to_string()))
+        }
+    };
+    let attributes = items
+        .iter()
+        .map(|item| {
+            item.attributes
+                .iter()
+                .map(|(name, value)| SecretAttribute {
+                    name: BorrowedUtf8::new(name),
+                    value: BorrowedUtf8::new(value),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let ffi_items = items
+        .iter()
+        .zip(&attributes)
+        .map(|(item, attributes)| SecretItem {
+            label: BorrowedUtf8::new(&item.label),
+            attributes: BorrowedArray::from_slice(attributes),
+            secret: BorrowedArray::from_slice(&item.secret),
+        })
+        .collect::<Vec<_>>();
+    let response = SecretResponse {
+        request_id,
+        items: BorrowedArray::from_slice(&ffi_items),
+        error_kind,
+        error: BorrowedUtf8::optional(error.as_ref()),
+    };
+    event_handler(&response.into());
+}
+
+fn send_window_capture_frame_response(
+    request_ids: &[RequestId],
+    window_id: WindowId,
+    scale: f64,
+    result: anyhow::Result<CapturedFrame>,
+    event_handler: EventHandler,
+) {
+    let (frame, error) = match result {
+        Ok(frame) => (Some(frame), None),
+        Err(e) => {
+            error!("Error capturing the frame of {window_id:?} for {request_ids:?}: {e}");
+            (None, Some(format!("{e:#}")))
         }
+    };
+    for &request_id in request_ids {
+        let response = WindowCaptureFrameResponse {
+            request_id,
+            window_id,
+            physical_size: frame.as_ref().map_or(
+                PhysicalSize {
+                    width: PhysicalPixels(0),
+                    height: PhysicalPixels(0),
+                },
+                |frame| frame.physical_size,
+            ),
+            scale,
+            pixels: frame
+                .as_ref()
+                .map_or_else(BorrowedArray::null, |frame| BorrowedArray::from_slice(&frame.pixels)),
+            error: BorrowedUtf8::optional(error.as_ref()),
+        };
+        event_handler(&response.into());
     }
 }
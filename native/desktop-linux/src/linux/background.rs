@@ -0,0 +1,208 @@
+use crate::linux::{application::Application, background_api::BackgroundRequestParams};
+use anyhow::{Context, ensure};
+use ashpd::desktop::{
+    ResponseError,
+    background::{BackgroundProxy, BackgroundRequestOptions},
+};
+use log::debug;
+use std::{fmt::Write, path::PathBuf};
+
+#[derive(Debug)]
+pub struct BackgroundRequest {
+    reason: Option<String>,
+    autostart: bool,
+    commandline: Vec<String>,
+    dbus_activatable: bool,
+    desktop_file_id: Option<String>,
+}
+
+impl BackgroundRequestParams<'_> {
+    pub fn to_request(&self) -> anyhow::Result<BackgroundRequest> {
+        Ok(BackgroundRequest {
+            reason: self.get_reason()?.map(ToOwned::to_owned),
+            autostart: self.autostart,
+            commandline: self.get_commandline()?.into_iter().map(ToOwned::to_owned).collect(),
+            dbus_activatable: self.dbus_activatable,
+            desktop_file_id: self
+                .get_desktop_file_id()?
+                .map(|id| {
+                    let id = id.strip_suffix(".desktop").unwrap_or(id);
+                    ensure!(
+                        is_valid_desktop_file_id(id),
+                        "BackgroundRequestParams.desktop_file_id: {id:?} is not a valid application ID"
+                    );
+                    Ok(id.to_owned())
+                })
+                .transpose()?,
+        })
+    }
+}
+
+/// The ID is used as a file name, so it must follow the D-Bus application ID rules,
+/// see <https://specifications.freedesktop.org/desktop-entry-spec/latest/file-naming.html>
+fn is_valid_desktop_file_id(id: &str) -> bool {
+    id.len() <= 255
+        && id.split('.').all(|element| {
+            !element.is_empty()
+                && !element.starts_with(|c: char| c.is_ascii_digit())
+                && element.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        })
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BackgroundResult {
+    pub run_in_background: bool,
+    pub autostart: bool,
+}
+
+async fn request_background_portal(request: BackgroundRequest) -> anyhow::Result<BackgroundResult> {
+    let connection = Application::dbus_connection().await?;
+    let proxy = BackgroundProxy::with_connection(connection).await?;
+    // An empty command line is not sent, so the portal uses the `Exec` key of the application's desktop file.
+    let options = BackgroundRequestOptions::default()
+        .set_reason(request.reason.as_deref())
+        .set_auto_start(request.autostart)
+        .set_dbus_activatable(request.dbus_activatable)
+        .set_command(request.commandline);
+    let response = proxy.request_background(None, options).await?.response();
+    match response {
+        Ok(background) => Ok(BackgroundResult {
+            run_in_background: background.run_in_background(),
+            autostart: background.auto_start(),
+        }),
+        Err(ashpd::Error::Response(ResponseError::Cancelled)) => Ok(BackgroundResult {
+            run_in_background: false,
+            autostart: false,
+        }),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn default_commandline() -> anyhow::Result<Vec<String>> {
+    let exe = std::env::current_exe().context("Couldn't determine the current executable")?;
+    let exe = exe.to_str().context("The path of the current executable is not valid UTF-8")?;
+    Ok(vec![exe.to_owned()])
+}
+
+fn autostart_dir() -> anyhow::Result<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME").context("HOME is not set")?).join(".config"),
+    };
+    Ok(config_dir.join("autostart"))
+}
+
+/// Escapes a string value of a desktop entry key.
+fn escape_desktop_entry_string(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r")
+}
+
+/// Quotes an argument of the `Exec` key, see <https://specifications.freedesktop.org/desktop-entry-spec/latest/exec-variables.html>
+fn quote_exec_arg(arg: &str) -> String {
+    const RESERVED_CHARS: &str = " \t\n\"'\\><~|&;$*?#()`";
+    let arg = arg.replace('%', "%%");
+    if !arg.is_empty() && !arg.contains(|c| RESERVED_CHARS.contains(c)) {
+        return arg;
+    }
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn autostart_entry(desktop_file_id: &str, request: &BackgroundRequest) -> anyhow::Result<String> {
+    let commandline = if request.commandline.is_empty() {
+        default_commandline()?
+    } else {
+        request.commandline.clone()
+    };
+    let exec = commandline.iter().map(|arg| quote_exec_arg(arg)).collect::<Vec<_>>().join(" ");
+    let mut entry = String::from("[Desktop Entry]\nType=Application\n");
+    writeln!(entry, "Name={}", escape_desktop_entry_string(desktop_file_id))?;
+    if let Some(reason) = &request.reason {
+        writeln!(entry, "Comment={}", escape_desktop_entry_string(reason))?;
+    }
+    writeln!(entry, "Exec={}", escape_desktop_entry_string(&exec))?;
+    if request.dbus_activatable {
+        entry.push_str("DBusActivatable=true\n");
+    }
+    entry.push_str("X-GNOME-Autostart-enabled=true\n");
+    Ok(entry)
+}
+
+/// Outside a sandbox, applications are not restricted from running in the background,
+/// and autostart is configured with an entry in the XDG autostart directory.
+fn request_background_fallback(request: &BackgroundRequest) -> anyhow::Result<BackgroundResult> {
+    let Some(desktop_file_id) = request.desktop_file_id.as_deref() else {
+        if request.autostart {
+            anyhow::bail!("BackgroundRequestParams.desktop_file_id is required for autostart outside a sandbox");
+        }
+        return Ok(BackgroundResult {
+            run_in_background: true,
+            autostart: false,
+        });
+    };
+    let dir = autostart_dir()?;
+    let path = dir.join(format!("{desktop_file_id}.desktop"));
+    if request.autostart {
+        std::fs::create_dir_all(&dir).with_context(|| format!("Couldn't create {}", dir.display()))?;
+        std::fs::write(&path, autostart_entry(desktop_file_id, request)?).with_context(|| format!("Couldn't write {}", path.display()))?;
+        debug!("Wrote the autostart entry {}", path.display());
+    } else {
+        match std::fs::remove_file(&path) {
+            Ok(()) => debug!("Removed the autostart entry {}", path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("Couldn't remove {}", path.display())),
+        }
+    }
+    Ok(BackgroundResult {
+        run_in_background: true,
+        autostart: request.autostart,
+    })
+}
+
+pub async fn request_background_impl(request: BackgroundRequest) -> anyhow::Result<BackgroundResult> {
+    if ashpd::is_sandboxed() {
+        request_background_portal(request).await
+    } else {
+        debug!("Not running in a sandbox, using the autostart directory instead of the background portal");
+        request_background_fallback(&request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_desktop_file_id() {
+        for id in ["org.example.App", "org.example.my-app", "org.example.My_App2", "firefox"] {
+            assert!(is_valid_desktop_file_id(id), "{id:?}");
+        }
+        for id in [
+            "",
+            ".",
+            "..",
+            "../App",
+            "org..example",
+            "org.example.",
+            ".org.example",
+            "org/example",
+            "org.example.1App",
+            "org.example.App ",
+            "org.example.Äpp",
+            &"a".repeat(256),
+        ] {
+            assert!(!is_valid_desktop_file_id(id), "{id:?}");
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::linux::{application::Application, application_api::AppPtr, events::RequestId};
+use desktop_common::{
+    ffi_utils::{BorrowedArray, BorrowedUtf8},
+    logger::ffi_boundary,
+};
+use log::debug;
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct BackgroundRequestParams<'a> {
+    /// Optional. User-visible reason for the request.
+    pub reason: BorrowedUtf8<'a>,
+
+    /// Whether the application should be started automatically at login.
+    pub autostart: bool,
+
+    /// Optional. Command line used for autostarting the application. Defaults to the current executable.
+    pub commandline: BorrowedArray<'a, BorrowedUtf8<'a>>,
+
+    /// Whether the application should be autostarted by D-Bus activation, instead of running `commandline`.
+    pub dbus_activatable: bool,
+
+    /// Optional. Desktop file ID of the application, e.g. `"org.example.App"` (the `.desktop` suffix is optional).
+    /// Must be a valid D-Bus application ID, otherwise the request fails.
+    /// Required for autostart outside a sandbox, where the autostart entry is written to `~/.config/autostart/<desktop_file_id>.desktop`.
+    pub desktop_file_id: BorrowedUtf8<'a>,
+}
+
+impl BackgroundRequestParams<'_> {
+    pub(crate) fn get_reason(&self) -> anyhow::Result<Option<&str>> {
+        self.reason.get_optional("BackgroundRequestParams.reason")
+    }
+
+    pub(crate) fn get_commandline(&self) -> anyhow::Result<Vec<&str>> {
+        self.commandline
+            .as_optional_slice()
+            .unwrap_or_default()
+            .iter()
+            .map(|arg| arg.get("BackgroundRequestParams.commandline"))
+            .collect()
+    }
+
+    pub(crate) fn get_desktop_file_id(&self) -> anyhow::Result<Option<&str>> {
+        self.desktop_file_id.get_optional("BackgroundRequestParams.desktop_file_id")
+    }
+}
+
+/// Requests permission to keep running in the background after the last window is closed, and optionally to be started at login.
+/// The result is reported with `Event::BackgroundResponse`.
+///
+/// Uses the background portal when running in a sandbox (e.g. Flatpak). Otherwise, running in the background is always allowed,
+/// and autostart is configured by writing (or removing) an XDG autostart entry.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_request_background(mut app_ptr: AppPtr, params: &BackgroundRequestParams) -> RequestId {
+    debug!("application_request_background: {params:?}");
+    ffi_boundary("application_request_background", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        app.request_background(params)
+    })
+}
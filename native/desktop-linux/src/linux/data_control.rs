@@ -0,0 +1,434 @@
+use crate::linux::{
+    application_api::DataSource,
+    application_state::ApplicationState,
+    data_transfer::MimeTypes,
+    events::{ClipboardChangedEvent, DataTransferCancelledEvent},
+    file_transfer::mime_type_to_receive,
+    mime_conversion::{can_convert_to_png, offered_mime_types},
+};
+use anyhow::Context;
+use desktop_common::mime_types::{NegotiatedMimeType, negotiate_mime_type};
+use log::{debug, warn};
+use smithay_client_toolkit::{
+    data_device_manager::{ReadPipe, WritePipe},
+    reexports::{
+        client::{
+            Connection, Dispatch, Proxy, QueueHandle,
+            backend::protocol::Interface,
+            delegate_noop, event_created_child,
+            globals::{Global, GlobalList},
+            protocol::wl_seat::WlSeat,
+        },
+        protocols::ext::data_control::v1::client::{
+            ext_data_control_device_v1::{self, ExtDataControlDeviceV1},
+            ext_data_control_manager_v1::ExtDataControlManagerV1,
+            ext_data_control_offer_v1::{self, ExtDataControlOfferV1},
+            ext_data_control_source_v1::{self, ExtDataControlSourceV1},
+        },
+        protocols_wlr::data_control::v1::client::{
+            zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+            zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+            zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1},
+            zwlr_data_control_source_v1::{self, ZwlrDataControlSourceV1},
+        },
+    },
+};
+use std::{
+    os::fd::{AsFd, OwnedFd},
+    sync::Mutex,
+};
+
+/// `ext_data_control_v1`, or its predecessor `wlr_data_control_unstable_v1`.
+/// Unlike `wl_data_device`, it gives access to the selection without the keyboard focus, e.g. for clipboard managers.
+#[derive(Debug)]
+pub enum DataControlManager {
+    Ext(ExtDataControlManagerV1),
+    Wlr(ZwlrDataControlManagerV1),
+}
+
+#[derive(Debug)]
+pub enum DataControlDevice {
+    Ext(ExtDataControlDeviceV1),
+    Wlr(ZwlrDataControlDeviceV1),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataControlOffer {
+    Ext(ExtDataControlOfferV1),
+    Wlr(ZwlrDataControlOfferV1),
+}
+
+#[derive(Debug)]
+pub enum DataControlSource {
+    Ext(ExtDataControlSourceV1),
+    Wlr(ZwlrDataControlSourceV1),
+}
+
+#[derive(Debug, Default)]
+pub struct DataControlOfferData {
+    mime_types: Mutex<Vec<String>>,
+}
+
+impl DataControlOfferData {
+    fn push_mime_type(&self, mime_type: String) {
+        self.mime_types.lock().unwrap().push(mime_type);
+    }
+
+    fn mime_types(&self) -> Vec<String> {
+        self.mime_types.lock().unwrap().clone()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DataControlProtocol {
+    Ext,
+    Wlr,
+}
+
+impl DataControlProtocol {
+    /// `ext_data_control_v1` is preferred, since it replaced `wlr_data_control_unstable_v1`.
+    fn select(globals: &[Global]) -> Option<Self> {
+        let has_global = |interface: &Interface| globals.iter().any(|global| global.interface == interface.name);
+        if has_global(ExtDataControlManagerV1::interface()) {
+            Some(Self::Ext)
+        } else if has_global(ZwlrDataControlManagerV1::interface()) {
+            Some(Self::Wlr)
+        } else {
+            None
+        }
+    }
+}
+
+/// Runs `$body` with the proxy of either protocol as `$proxy`, the requests being the same in both protocols.
+/// With `$wrapper(…)`, the result is wrapped in the variant of `$wrapper` for the same protocol.
+macro_rules! with_proxy {
+    ($value:expr, |$proxy:ident| $wrapper:ident($body:expr)) => {
+        match $value {
+            Self::Ext($proxy) => $wrapper::Ext($body),
+            Self::Wlr($proxy) => $wrapper::Wlr($body),
+        }
+    };
+    ($value:expr, |$proxy:ident| $body:expr) => {
+        match $value {
+            Self::Ext($proxy) => $body,
+            Self::Wlr($proxy) => $body,
+        }
+    };
+}
+
+impl DataControlManager {
+    pub fn bind(globals: &GlobalList, qh: &QueueHandle<ApplicationState>) -> Option<Self> {
+        let result = match globals.contents().with_list(DataControlProtocol::select)? {
+            DataControlProtocol::Ext => globals.bind(qh, 1..=1, ()).map(Self::Ext),
+            // Version 2 added the primary selection
+            DataControlProtocol::Wlr => globals.bind(qh, 1..=2, ()).map(Self::Wlr),
+        };
+        result.inspect_err(|e| warn!("DataControlManager: {e}")).ok()
+    }
+
+    pub fn get_data_device(&self, qh: &QueueHandle<ApplicationState>, seat: &WlSeat) -> DataControlDevice {
+        with_proxy!(self, |manager| DataControlDevice(manager.get_data_device(seat, qh, ())))
+    }
+
+    fn create_data_source(&self, qh: &QueueHandle<ApplicationState>, data_source: DataSource) -> DataControlSource {
+        with_proxy!(self, |manager| DataControlSource(manager.create_data_source(qh, data_source)))
+    }
+
+    fn supports_primary_selection(&self) -> bool {
+        match self {
+            Self::Ext(_) => true,
+            Self::Wlr(manager) => manager.version() >= 2,
+        }
+    }
+}
+
+impl DataControlDevice {
+    fn set_selection(&self, data_source: DataSource, source: Option<&DataControlSource>) {
+        macro_rules! set_selection {
+            ($device:expr, $source:expr) => {
+                if data_source == DataSource::PrimarySelection {
+                    $device.set_primary_selection($source);
+                } else {
+                    $device.set_selection($source);
+                }
+            };
+        }
+        match (self, source) {
+            (Self::Ext(device), None) => set_selection!(device, None),
+            (Self::Ext(device), Some(DataControlSource::Ext(source))) => set_selection!(device, Some(source)),
+            (Self::Wlr(device), None) => set_selection!(device, None),
+            (Self::Wlr(device), Some(DataControlSource::Wlr(source))) => set_selection!(device, Some(source)),
+            _ => warn!("DataControlDevice::set_selection: the source is from a different protocol"),
+        }
+    }
+
+    fn destroy(&self) {
+        with_proxy!(self, |device| device.destroy());
+    }
+}
+
+impl DataControlOffer {
+    fn mime_types(&self) -> Vec<String> {
+        let data = with_proxy!(self, |offer| offer.data::<DataControlOfferData>());
+        data.map(DataControlOfferData::mime_types).unwrap_or_default()
+    }
+
+    fn receive(&self, mime_type: String) -> anyhow::Result<ReadPipe> {
+        let (reader, writer) = std::io::pipe().context("Failed to create a pipe")?;
+        with_proxy!(self, |offer| offer.receive(mime_type, writer.as_fd()));
+        Ok(ReadPipe::from(OwnedFd::from(reader)))
+    }
+
+    fn destroy(&self) {
+        with_proxy!(self, |offer| offer.destroy());
+    }
+}
+
+impl DataControlSource {
+    fn offer(&self, mime_type: String) {
+        with_proxy!(self, |source| source.offer(mime_type));
+    }
+
+    fn destroy(&self) {
+        with_proxy!(self, |source| source.destroy());
+    }
+
+    fn is(&self, other: &impl Proxy) -> bool {
+        with_proxy!(self, |source| source.id() == other.id())
+    }
+}
+
+enum DataControlDeviceEvent {
+    Selection(DataSource, Option<DataControlOffer>),
+    Finished,
+}
+
+impl ApplicationState {
+    fn on_data_control_device_event(&mut self, event: DataControlDeviceEvent) {
+        match event {
+            DataControlDeviceEvent::Selection(data_source, offer) => {
+                let mime_types = offer.as_ref().map(DataControlOffer::mime_types).unwrap_or_default();
+                debug!("DataControlDevice: {data_source:?} changed, mime_types={mime_types:?}");
+                let previous_offer = match offer {
+                    Some(offer) => self.data_control_offers.insert(data_source, offer),
+                    None => self.data_control_offers.remove(&data_source),
+                };
+                if let Some(previous_offer) = previous_offer {
+                    previous_offer.destroy();
+                }
+                ClipboardChangedEvent::with(data_source, &mime_types, |event| self.send_event(event));
+            }
+            DataControlDeviceEvent::Finished => {
+                debug!("DataControlDevice: finished");
+                if let Some(device) = self.data_control_device.take() {
+                    device.destroy();
+                }
+                for (_, offer) in self.data_control_offers.drain() {
+                    offer.destroy();
+                }
+            }
+        }
+    }
+
+    fn on_data_control_source_cancelled(&mut self, data_source: DataSource, source: &impl Proxy) {
+        debug!("DataControlSource: {data_source:?} cancelled");
+        if self.data_control_sources.get(&data_source).is_some_and(|s| s.is(source)) {
+            if let Some(source) = self.data_control_sources.remove(&data_source) {
+                source.destroy();
+            }
+            self.send_event(DataTransferCancelledEvent { data_source });
+        }
+    }
+
+    pub const fn is_data_control_available(&self) -> bool {
+        self.data_control_device.is_some()
+    }
+
+    /// Reads the selection, even when none of the windows has the keyboard focus.
+    pub fn data_control_read_pipe(
+        &self,
+        f_name: &str,
+        data_source: DataSource,
+        supported_mime_types: &MimeTypes,
+    ) -> Option<(ReadPipe, NegotiatedMimeType)> {
+        let Some(offer) = self.data_control_offers.get(&data_source) else {
+            debug!("{f_name}: No {data_source:?} offer found");
+            return None;
+        };
+        let mime_types = offer.mime_types();
+        debug!(
+            "{f_name}: offer MIME types: {mime_types:?}, supported MIME types: {:?}",
+            supported_mime_types.val
+        );
+        let Some(mime_type) =
+            negotiate_mime_type(supported_mime_types.val.iter().copied(), &mime_types, can_convert_to_png).map(|(requested, received)| {
+                NegotiatedMimeType {
+                    requested: requested.to_owned(),
+                    received: mime_type_to_receive(received, &mime_types).to_owned(),
+                }
+            })
+        else {
+            debug!("{f_name}: {data_source:?} content not supported");
+            return None;
+        };
+        debug!("{f_name}: reading {mime_type:?}");
+        match offer.receive(mime_type.received.clone()) {
+            Ok(read_pipe) => Some((read_pipe, mime_type)),
+            Err(e) => {
+                warn!("{f_name}: failed receive the data offer: {e}");
+                None
+            }
+        }
+    }
+
+    /// Sets the selection, even when none of the windows has the keyboard focus.
+    /// The data is requested with the `transfer_data_getter`, like for `wl_data_device`.
+    pub fn data_control_put(&mut self, qh: &QueueHandle<Self>, data_source: DataSource, mime_types: &MimeTypes) -> anyhow::Result<()> {
+        let manager = self.data_control_manager.as_ref().context("Data control is not supported")?;
+        let device = self.data_control_device.as_ref().context("No data control device")?;
+        let with_file_transfer = match data_source {
+            DataSource::Clipboard => true,
+            DataSource::PrimarySelection if manager.supports_primary_selection() => false,
+            _ => anyhow::bail!("Setting the {data_source:?} with data control is not supported"),
+        };
+        if mime_types.val.is_empty() {
+            device.set_selection(data_source, None);
+        } else {
+            let source = manager.create_data_source(qh, data_source);
+            for mime_type in offered_mime_types(&mime_types.val, with_file_transfer) {
+                source.offer(mime_type.to_owned());
+            }
+            device.set_selection(data_source, Some(&source));
+            self.set_provided_mime_types(data_source, mime_types);
+            if let Some(previous_source) = self.data_control_sources.insert(data_source, source) {
+                previous_source.destroy();
+            }
+            return Ok(());
+        }
+        if let Some(previous_source) = self.data_control_sources.remove(&data_source) {
+            previous_source.destroy();
+        }
+        Ok(())
+    }
+}
+
+delegate_noop!(ApplicationState: ignore ExtDataControlManagerV1);
+delegate_noop!(ApplicationState: ignore ZwlrDataControlManagerV1);
+
+/// The `Dispatch` implementations for the objects of one of the protocols, the events being the same in both protocols.
+macro_rules! impl_data_control_dispatch {
+    ($variant:ident, $device_mod:ident::$device:ident, $offer_mod:ident::$offer:ident, $source_mod:ident::$source:ident) => {
+        impl Dispatch<$device, ()> for ApplicationState {
+            fn event(state: &mut Self, _: &$device, event: $device_mod::Event, (): &(), _: &Connection, _: &QueueHandle<Self>) {
+                let event = match event {
+                    $device_mod::Event::Selection { id } => {
+                        DataControlDeviceEvent::Selection(DataSource::Clipboard, id.map(DataControlOffer::$variant))
+                    }
+                    $device_mod::Event::PrimarySelection { id } => {
+                        DataControlDeviceEvent::Selection(DataSource::PrimarySelection, id.map(DataControlOffer::$variant))
+                    }
+                    $device_mod::Event::Finished => DataControlDeviceEvent::Finished,
+                    // The MIME types of the offer are collected before the selection event
+                    _ => return,
+                };
+                state.on_data_control_device_event(event);
+            }
+
+            event_created_child!(ApplicationState, $device, [
+                $device_mod::EVT_DATA_OFFER_OPCODE => ($offer, DataControlOfferData::default())
+            ]);
+        }
+
+        impl Dispatch<$offer, DataControlOfferData> for ApplicationState {
+            fn event(_: &mut Self, _: &$offer, event: $offer_mod::Event, data: &DataControlOfferData, _: &Connection, _: &QueueHandle<Self>) {
+                if let $offer_mod::Event::Offer { mime_type } = event {
+                    data.push_mime_type(mime_type);
+                }
+            }
+        }
+
+        impl Dispatch<$source, DataSource> for ApplicationState {
+            fn event(
+                state: &mut Self,
+                source: &$source,
+                event: $source_mod::Event,
+                data_source: &DataSource,
+                _: &Connection,
+                _: &QueueHandle<Self>,
+            ) {
+                const SEND_EVENT_NAME: &str = concat!(stringify!($source_mod), "::Event::Send");
+                match event {
+                    $source_mod::Event::Send { mime_type, fd } => {
+                        debug!("{SEND_EVENT_NAME}: {mime_type}");
+                        state.write_data(SEND_EVENT_NAME, *data_source, &mime_type, WritePipe::from(fd));
+                    }
+                    $source_mod::Event::Cancelled => state.on_data_control_source_cancelled(*data_source, source),
+                    _ => {}
+                }
+            }
+        }
+    };
+}
+
+impl_data_control_dispatch!(
+    Ext,
+    ext_data_control_device_v1::ExtDataControlDeviceV1,
+    ext_data_control_offer_v1::ExtDataControlOfferV1,
+    ext_data_control_source_v1::ExtDataControlSourceV1
+);
+impl_data_control_dispatch!(
+    Wlr,
+    zwlr_data_control_device_v1::ZwlrDataControlDeviceV1,
+    zwlr_data_control_offer_v1::ZwlrDataControlOfferV1,
+    zwlr_data_control_source_v1::ZwlrDataControlSourceV1
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn globals(interfaces: &[&Interface]) -> Vec<Global> {
+        interfaces
+            .iter()
+            .zip(1..)
+            .map(|(interface, name)| Global {
+                name,
+                interface: interface.name.to_owned(),
+                version: interface.version,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_select_protocol() {
+        let ext = ExtDataControlManagerV1::interface();
+        let wlr = ZwlrDataControlManagerV1::interface();
+        let seat = WlSeat::interface();
+        let cases: [(&[&Interface], Option<DataControlProtocol>); 6] = [
+            (&[ext, wlr], Some(DataControlProtocol::Ext)),
+            (&[seat, wlr, ext], Some(DataControlProtocol::Ext)),
+            (&[ext], Some(DataControlProtocol::Ext)),
+            (&[seat, wlr], Some(DataControlProtocol::Wlr)),
+            (&[seat], None),
+            (&[], None),
+        ];
+        for (interfaces, expected) in cases {
+            let names = interfaces.iter().map(|interface| interface.name).collect::<Vec<_>>();
+            assert_eq!(DataControlProtocol::select(&globals(interfaces)), expected, "{names:?}");
+        }
+    }
+
+    #[test]
+    fn test_offer_mime_types() {
+        let data = DataControlOfferData::default();
+        assert!(data.mime_types().is_empty());
+        data.push_mime_type("text/plain;charset=utf-8".to_owned());
+        data.push_mime_type("UTF8_STRING".to_owned());
+        let mime_types = data.mime_types();
+        // The order of the offer events is kept, it's the preference order of the source
+        assert_eq!(mime_types, ["text/plain;charset=utf-8", "UTF8_STRING"]);
+        data.push_mime_type("text/html".to_owned());
+        assert_eq!(mime_types.len(), 2);
+        assert_eq!(data.mime_types(), ["text/plain;charset=utf-8", "UTF8_STRING", "text/html"]);
+    }
+}
@@ -1,5 +1,8 @@
 use crate::linux::application::send_event;
+use crate::linux::ffi_return_conversions::TransferData;
+use crate::linux::file_transfer::{FILE_TRANSFER_MIME_TYPE, URI_LIST_MIME_TYPE, mime_type_to_receive, register_files, retrieve_files};
 use crate::linux::geometry::{LogicalPixels, LogicalPoint};
+use crate::linux::mime_conversion::{can_convert_to_png, convert_data_async};
 use crate::linux::{
     application_api::{DataSource, DragAndDropAction, DragAndDropActions, DragAndDropQueryData},
     application_state::ApplicationState,
@@ -7,6 +10,8 @@ use crate::linux::{
         DataTransferAvailableEvent,
         DataTransferCancelledEvent,
         DataTransferContent,
+        DataTransferId,
+        DataTransferStatus,
         DragAndDropFinishedEvent,
         DragAndDropLeaveEvent,
         DropPerformedEvent,
@@ -14,7 +19,10 @@ use crate::linux::{
         //
     },
 };
+use anyhow::Context;
 use desktop_common::ffi_utils::{BorrowedArray, BorrowedUtf8};
+use desktop_common::mime_types::{NegotiatedMimeType, ReceivedContent, is_text_mime_type, source_mime_type};
+use desktop_common::perf_trace::AsyncTraceSpan;
 use futures_lite::{AsyncReadExt, AsyncWriteExt};
 use log::{debug, trace, warn};
 use smithay_client_toolkit::{
@@ -41,6 +49,7 @@ use smithay_client_toolkit::{
         },
     },
 };
+use std::{collections::HashMap, rc::Rc};
 
 delegate_data_device!(ApplicationState);
 delegate_primary_selection!(ApplicationState);
@@ -51,6 +60,7 @@ impl From<DragAndDropAction> for DndAction {
             DragAndDropAction::None => Self::None,
             DragAndDropAction::Copy => Self::Copy,
             DragAndDropAction::Move => Self::Move,
+            DragAndDropAction::Ask => Self::Ask,
         }
     }
 }
@@ -67,7 +77,7 @@ impl From<DndAction> for DragAndDropAction {
             DndAction::None => Self::None,
             DndAction::Copy => Self::Copy,
             DndAction::Move => Self::Move,
-            DndAction::Ask => Self::Copy, // TODO
+            DndAction::Ask => Self::Ask,
             _ => Self::None,
         }
     }
@@ -80,36 +90,119 @@ impl From<DndAction> for DragAndDropActions {
 }
 
 struct DragOfferMimetypeAndActions {
-    pub mime_type: Option<String>,
+    pub mime_type: Option<NegotiatedMimeType>,
     pub supported_actions: DndAction,
     pub preferred_action: DndAction,
 }
 
 impl ApplicationState {
+    pub fn set_provided_mime_types(&mut self, data_source: DataSource, mime_types: &MimeTypes) {
+        let mime_types = mime_types.val.iter().map(|&m| m.to_owned()).collect();
+        self.provided_mime_types.insert(data_source, mime_types);
+    }
+
+    /// Returns the MIME type that the application provides the data in, to get the data for the `requested_mime_type`.
+    fn provided_mime_type<'a>(&'a self, data_source: DataSource, requested_mime_type: &'a str) -> &'a str {
+        self.provided_mime_types
+            .get(&data_source)
+            .and_then(|mime_types| source_mime_type(requested_mime_type, mime_types, can_convert_to_png))
+            .unwrap_or(requested_mime_type)
+    }
+
+    pub fn owns_clipboard(&self) -> bool {
+        self.copy_paste_source.is_some() || self.data_control_sources.contains_key(&DataSource::Clipboard)
+    }
+
+    /// Fetches the clipboard data for all the MIME types provided by the application,
+    /// so that it can still be served when the application is not able to provide it anymore.
+    ///
+    /// Returns `false` if we don't own the clipboard, or there's no data to serve.
+    pub fn snapshot_clipboard(&mut self) -> bool {
+        if !self.owns_clipboard() {
+            return false;
+        }
+        let Some(mime_types) = self.provided_mime_types.get(&DataSource::Clipboard) else {
+            return false;
+        };
+        let mut snapshot = HashMap::new();
+        for mime_type in mime_types {
+            match self.transfer_data_getter.get(DataSource::Clipboard, mime_type) {
+                Some(TransferData::Data(data)) => {
+                    snapshot.insert(mime_type.clone(), data);
+                }
+                Some(TransferData::Streamed) => debug!("snapshot_clipboard: skipping streamed {mime_type} data"),
+                None => debug!("snapshot_clipboard: no {mime_type} data"),
+            }
+        }
+        if snapshot.is_empty() {
+            return false;
+        }
+        self.clipboard_snapshot = Some(snapshot);
+        self.clipboard_snapshot_served = false;
+        true
+    }
+
+    fn get_transfer_data(&mut self, data_source: DataSource, mime_type: &str) -> Option<TransferData> {
+        if data_source == DataSource::Clipboard
+            && let Some(snapshot) = &self.clipboard_snapshot
+        {
+            self.clipboard_snapshot_served = true;
+            return snapshot.get(mime_type).cloned().map(TransferData::Data);
+        }
+        self.transfer_data_getter.get(data_source, mime_type)
+    }
+
+    /// Reports the data converted to the requested MIME type, as `DataTransferContent`.
     pub fn read_from_pipe(
         &self,
         f_name: &'static str,
         read_pipe: data_device_manager::ReadPipe,
-        mime_type: String,
+        mime_type: NegotiatedMimeType,
         callback: impl Fn(Option<DataTransferContent>) + 'static + Clone,
     ) {
         let callback_clone = callback.clone();
 
+        let rt_handle = self.rt_handle.clone();
         match self.loop_handle.adapt_io(read_pipe) {
             Ok(mut async_read_pipe) => {
+                let span = AsyncTraceSpan::new("data_transfer", f_name);
                 if let Err(e) = self.calloop_scheduler.schedule(async move {
                     let mut buf = Vec::new();
-                    let content = match async_read_pipe.read_to_end(&mut buf).await {
+                    let NegotiatedMimeType { requested, mut received } = mime_type;
+                    let mut result = async_read_pipe.read_to_end(&mut buf).await.map_err(anyhow::Error::from);
+                    if result.is_ok() && received == FILE_TRANSFER_MIME_TYPE {
+                        // Report the retrieved files as `text/uri-list`, which was requested by the application
+                        let key = std::mem::take(&mut buf);
+                        let retrieved = rt_handle.spawn(async move { retrieve_files(&key).await }).await;
+                        result = retrieved.unwrap_or_else(|e| Err(e.into())).map(|uri_list| {
+                            buf = uri_list;
+                            URI_LIST_MIME_TYPE.clone_into(&mut received);
+                            buf.len()
+                        });
+                    }
+                    // Text is converted by `ReceivedContent`, other conversions can take a while
+                    if result.is_ok() && received != requested && !is_text_mime_type(&received) {
+                        let data = std::mem::take(&mut buf);
+                        result = convert_data_async(&rt_handle, received.clone(), requested.clone(), data)
+                            .await
+                            .map(|converted| {
+                                buf = converted;
+                                received.clone_from(&requested);
+                                buf.len()
+                            });
+                    }
+                    drop(span);
+                    match result {
                         Ok(size) => {
                             debug!("{f_name}: read {size} bytes");
-                            Some(DataTransferContent::new(&mime_type, &buf))
+                            let content = ReceivedContent::new(&requested, &received, buf);
+                            DataTransferContent::with_received(&content, |content| callback(Some(content)));
                         }
                         Err(e) => {
                             warn!("{f_name}: error receiving data: {e}");
-                            None
+                            callback(None);
                         }
-                    };
-                    callback(content);
+                    }
                 }) {
                     warn!("{f_name}: failed to start reading: {e}");
                     callback_clone(None);
@@ -122,6 +215,59 @@ impl ApplicationState {
         }
     }
 
+    /// Writes the data of the `data_type` source in the MIME type requested by the other client.
+    pub fn write_data(&mut self, f_name: &'static str, data_type: DataSource, mime_type: &str, fd: WritePipe) {
+        let is_file_transfer = mime_type == FILE_TRANSFER_MIME_TYPE;
+        let requested_mime = if is_file_transfer { URI_LIST_MIME_TYPE } else { mime_type };
+        let provided_mime = self.provided_mime_type(data_type, requested_mime).to_owned();
+        match self.get_transfer_data(data_type, &provided_mime) {
+            Some(TransferData::Data(data)) => {
+                let rt_handle = self.rt_handle.clone();
+                let requested_mime = requested_mime.to_owned();
+                match self.loop_handle.adapt_io(fd) {
+                    Ok(mut async_write_pipe) => {
+                        if let Err(e) = self.calloop_scheduler.schedule(async move {
+                            let data = match convert_data_async(&rt_handle, provided_mime, requested_mime, data).await {
+                                Ok(data) => data,
+                                Err(e) => {
+                                    warn!("Converting {data_type:?} data failed: {e}");
+                                    return;
+                                }
+                            };
+                            let data = if is_file_transfer {
+                                let result = rt_handle.spawn(async move { register_files(&data).await }).await;
+                                match result.unwrap_or_else(|e| Err(e.into())) {
+                                    Ok(key) => key,
+                                    Err(e) => {
+                                        warn!("Registering {data_type:?} files for the file transfer failed: {e}");
+                                        return;
+                                    }
+                                }
+                            } else {
+                                data
+                            };
+                            if let Err(e) = async_write_pipe.write_all(&data).await {
+                                warn!("Write of {data_type:?} data failed: {e}");
+                            } else {
+                                debug!("Finished writing {data_type:?} data");
+                            }
+                        }) {
+                            warn!("Write of {data_type:?} data failed: {e}");
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Write of {data_type:?} data failed: {e}");
+                    }
+                }
+            }
+            Some(TransferData::Streamed) => {
+                // Streamed data is written as provided, without conversions
+                self.write_to_pipe_chunked(f_name, data_type, &provided_mime, fd, is_file_transfer);
+            }
+            None => warn!("Don't have any {data_type:?} data"),
+        }
+    }
+
     #[must_use]
     fn get_drag_offer_actions(
         &self,
@@ -140,15 +286,21 @@ impl ApplicationState {
                 actions: DragAndDropActions::from(drag_offer.source_actions),
             };
             self.query_drag_and_drop_target.with(&drag_and_drop_query_data, |target_info| {
-                let supported_mime_with_actions = target_info
-                    .iter()
-                    .find(|&e| mime_types.iter().any(|s| s == e.get_supported_mime_type().unwrap()));
+                let supported_mime_with_actions = target_info.iter().find_map(|e| {
+                    let requested = e.get_supported_mime_type().unwrap();
+                    let received = source_mime_type(requested, mime_types, can_convert_to_png)?;
+                    let mime_type = NegotiatedMimeType {
+                        requested: requested.to_owned(),
+                        received: received.to_owned(),
+                    };
+                    Some((e, mime_type))
+                });
 
                 trace!("query_drag_and_drop_target -> {target_info:?}, supported_mime_with_actions={supported_mime_with_actions:?}");
 
-                if let Some(v) = supported_mime_with_actions {
+                if let Some((v, mime_type)) = supported_mime_with_actions {
                     DragOfferMimetypeAndActions {
-                        mime_type: Some(v.get_supported_mime_type().unwrap().to_owned()),
+                        mime_type: Some(mime_type),
                         supported_actions: DndAction::from(v.supported_actions),
                         preferred_action: DndAction::from(v.preferred_action),
                     }
@@ -173,9 +325,81 @@ impl ApplicationState {
         };
         let mime_type_and_actions = self.get_drag_offer_actions(&drag_offer, (x, y).into(), window_id);
         drag_offer.set_actions(mime_type_and_actions.supported_actions, mime_type_and_actions.preferred_action);
-        drag_offer.accept_mime_type(drag_offer.serial, mime_type_and_actions.mime_type);
+        drag_offer.accept_mime_type(drag_offer.serial, mime_type_and_actions.mime_type.map(|m| m.received));
         Some(window_id)
     }
+
+    /// Sets the final action of the drop performed with `DndAction::Ask`, as described in `wl_data_offer.set_actions`.
+    pub fn drop_finish(&self, action: DragAndDropAction) -> anyhow::Result<()> {
+        let drag_offer = self
+            .pending_ask_drag_offer
+            .take()
+            .context("No drop with the Ask action is waiting to be finished")?;
+        if matches!(action, DragAndDropAction::Copy | DragAndDropAction::Move) {
+            let wl_action = DndAction::from(action);
+            drag_offer.set_actions(wl_action, wl_action);
+            drag_offer.finish();
+        } else if action != DragAndDropAction::None {
+            warn!("application_drop_finish: invalid final action {action:?}, cancelling the drop");
+        }
+        drag_offer.destroy();
+        Ok(())
+    }
+
+    /// Reports `DropPerformed` right away, followed by the data in `DataTransferChunk` events.
+    /// The offer is finished (or, with `Ask`, left for `drop_finish`) only after all the data is received.
+    fn drop_performed_chunked(
+        &mut self,
+        drag_offer: &DragOffer,
+        window_id: WindowId,
+        location_in_window: LogicalPoint,
+        action: DragAndDropAction,
+        mime_type: String,
+    ) {
+        const F_NAME: &str = "DataDeviceHandler::drop_performed";
+        let read_pipe = match drag_offer.receive(mime_type.clone()) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                warn!("{F_NAME}: failed receiving data offer: {e}");
+                None
+            }
+        };
+        let pending_ask_drag_offer = Rc::clone(&self.pending_ask_drag_offer);
+        let offer = drag_offer.clone();
+        let transfer_id = read_pipe.and_then(|read_pipe| {
+            self.read_from_pipe_chunked(F_NAME, read_pipe, mime_type, 0, move |status| {
+                let finished = status == DataTransferStatus::Finished;
+                match action {
+                    DragAndDropAction::Ask if finished => {
+                        if let Some(previous_drag_offer) = pending_ask_drag_offer.replace(Some(offer)) {
+                            debug!("{F_NAME}: the previous drop wasn't finished");
+                            previous_drag_offer.destroy();
+                        }
+                    }
+                    DragAndDropAction::Copy | DragAndDropAction::Move if finished => {
+                        offer.finish();
+                        offer.destroy();
+                    }
+                    // Destroying the offer without finishing it cancels the drop
+                    _ => offer.destroy(),
+                }
+            })
+        });
+        let (action, transfer_id) = transfer_id.map_or_else(
+            || {
+                drag_offer.destroy();
+                (DragAndDropAction::None, DataTransferId(0))
+            },
+            |transfer_id| (action, transfer_id),
+        );
+        self.send_event(DropPerformedEvent {
+            window_id,
+            content: DataTransferContent::null(),
+            action,
+            location_in_window,
+            transfer_id,
+        });
+    }
 }
 
 impl DataDeviceHandler for ApplicationState {
@@ -204,8 +428,7 @@ impl DataDeviceHandler for ApplicationState {
         };
         selection_offer.with_mime_types(|mime_types| {
             debug!("DataDeviceHandler::selection: mime_types={mime_types:?}");
-            let mime_types = mime_types.join(",");
-            self.send_event(DataTransferAvailableEvent::new(DataSource::Clipboard, &mime_types));
+            DataTransferAvailableEvent::with(DataSource::Clipboard, mime_types, |event| self.send_event(event));
         });
     }
 
@@ -242,13 +465,25 @@ impl DataDeviceHandler for ApplicationState {
                 content: DataTransferContent::null(),
                 action: DragAndDropAction::None,
                 location_in_window,
+                transfer_id: DataTransferId(0),
             });
             drag_offer.finish();
             drag_offer.destroy();
             return;
         };
 
-        let read_pipe = match drag_offer.receive(mime_type.clone()) {
+        if self.drop_chunked {
+            // The file transfer portal is not used, like for the chunked paste
+            let action = DragAndDropAction::from(wl_action);
+            self.drop_performed_chunked(&drag_offer, window_id, location_in_window, action, mime_type.received);
+            return;
+        }
+
+        let mime_type = NegotiatedMimeType {
+            received: drag_offer.with_mime_types(|mime_types| mime_type_to_receive(&mime_type.received, mime_types).to_owned()),
+            requested: mime_type.requested,
+        };
+        let read_pipe = match drag_offer.receive(mime_type.received.clone()) {
             Ok(v) => v,
             Err(e) => {
                 warn!("DataDeviceHandler::drop_performed: failed receiving data offer: {e}");
@@ -257,11 +492,13 @@ impl DataDeviceHandler for ApplicationState {
                     content: DataTransferContent::null(),
                     action: DragAndDropAction::None,
                     location_in_window,
+                    transfer_id: DataTransferId(0),
                 });
                 return;
             }
         };
 
+        let pending_ask_drag_offer = Rc::clone(&self.pending_ask_drag_offer);
         let event_handler = self.callbacks.event_handler;
         self.read_from_pipe("DataDeviceHandler::drop_performed", read_pipe, mime_type, move |data| {
             let action = if data.is_some() {
@@ -270,6 +507,14 @@ impl DataDeviceHandler for ApplicationState {
                 DragAndDropAction::None
             };
 
+            // With `Ask`, the offer is finished by `ApplicationState::drop_finish`, after the application chooses the action,
+            // possibly while handling the event
+            if action == DragAndDropAction::Ask
+                && let Some(previous_drag_offer) = pending_ask_drag_offer.replace(Some(drag_offer.clone()))
+            {
+                debug!("DataDeviceHandler::drop_performed: the previous drop wasn't finished");
+                previous_drag_offer.destroy();
+            }
             send_event(
                 event_handler,
                 DropPerformedEvent {
@@ -277,10 +522,18 @@ impl DataDeviceHandler for ApplicationState {
                     content: data.unwrap_or(DataTransferContent::null()),
                     action,
                     location_in_window,
+                    transfer_id: DataTransferId(0),
                 },
             );
-            drag_offer.finish();
-            drag_offer.destroy();
+            match action {
+                DragAndDropAction::Ask => {}
+                // Only a successful drop is finished, destroying the offer without finishing it cancels the drop
+                DragAndDropAction::None => drag_offer.destroy(),
+                DragAndDropAction::Copy | DragAndDropAction::Move => {
+                    drag_offer.finish();
+                    drag_offer.destroy();
+                }
+            }
         });
     }
 }
@@ -309,26 +562,7 @@ impl DataSourceHandler for ApplicationState {
         } else {
             return;
         };
-        if let Some(data) = self.transfer_data_getter.get(data_type, mime.as_str()) {
-            match self.loop_handle.adapt_io(fd) {
-                Ok(mut async_write_pipe) => {
-                    if let Err(e) = self.calloop_scheduler.schedule(async move {
-                        if let Err(e) = async_write_pipe.write_all(&data).await {
-                            warn!("Write of {data_type:?} data failed: {e}");
-                        } else {
-                            debug!("Finished writing {data_type:?} data");
-                        }
-                    }) {
-                        warn!("Write of {data_type:?} data failed: {e}");
-                    }
-                }
-                Err(e) => {
-                    warn!("Write of {data_type:?} data failed: {e}");
-                }
-            }
-        } else {
-            warn!("Don't have any {data_type:?} data");
-        }
+        self.write_data("DataSourceHandler::send_request", data_type, &mime, fd);
     }
 
     fn cancelled(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, source: &WlDataSource) {
@@ -389,6 +623,12 @@ impl<'a> MimeTypes<'a> {
             }
         }
     }
+
+    pub fn from_array(mime_types: &'a BorrowedArray<'a, BorrowedUtf8<'a>>, context: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            val: mime_types.get_strs(context)?,
+        })
+    }
 }
 
 impl PrimarySelectionDeviceHandler for ApplicationState {
@@ -402,8 +642,7 @@ impl PrimarySelectionDeviceHandler for ApplicationState {
         };
         selection_offer.with_mime_types(|mime_types| {
             debug!("PrimarySelectionDeviceHandler::selection: mime_types={mime_types:?}");
-            let mime_types = mime_types.join(",");
-            self.send_event(DataTransferAvailableEvent::new(DataSource::PrimarySelection, &mime_types));
+            DataTransferAvailableEvent::with(DataSource::PrimarySelection, mime_types, |event| self.send_event(event));
         });
     }
 }
@@ -418,26 +657,12 @@ impl PrimarySelectionSourceHandler for ApplicationState {
         write_pipe: WritePipe,
     ) {
         debug!("PrimarySelectionSourceHandler::send_request: mime={mime}");
-        if let Some(data) = self.transfer_data_getter.get(DataSource::PrimarySelection, mime.as_str()) {
-            match self.loop_handle.adapt_io(write_pipe) {
-                Ok(mut async_write_pipe) => {
-                    if let Err(e) = self.calloop_scheduler.schedule(async move {
-                        if let Err(e) = async_write_pipe.write_all(&data).await {
-                            warn!("Write of primary selection data failed: {e}");
-                        } else {
-                            debug!("Finished writing primary selection data");
-                        }
-                    }) {
-                        warn!("Write of primary selection data failed: {e}");
-                    }
-                }
-                Err(e) => {
-                    warn!("Write of primary selection data failed: {e}");
-                }
-            }
-        } else {
-            warn!("Don't have any primary selection data");
-        }
+        self.write_data(
+            "PrimarySelectionSourceHandler::send_request",
+            DataSource::PrimarySelection,
+            &mime,
+            write_pipe,
+        );
     }
 
     fn cancelled(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, source: &ZwpPrimarySelectionSourceV1) {
@@ -0,0 +1,499 @@
+use crate::linux::{
+    application::send_event,
+    application_api::DataSource,
+    application_state::ApplicationState,
+    events::{
+        DataTransferChunkEvent, DataTransferId, DataTransferStatus, DataTransferWriteReadyEvent, DataTransferWriteRequestedEvent,
+        DataTransferWriteResult,
+    },
+    file_transfer::register_files,
+};
+use anyhow::bail;
+use desktop_common::ffi_utils::{BorrowedArray, BorrowedUtf8};
+use desktop_common::perf_trace::AsyncTraceSpan;
+use futures_lite::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use log::{debug, warn};
+use smithay_client_toolkit::data_device_manager::{ReadPipe, WritePipe};
+use std::{cell::Cell, rc::Rc};
+use tokio::sync::{
+    mpsc::{self, error::TrySendError},
+    oneshot,
+};
+
+/// Maximum size of the data in a single `DataTransferChunk` event.
+const DATA_TRANSFER_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Maximum number of chunks queued with `data_transfer_write`, which are not written to the pipe yet.
+const OUTGOING_CHUNK_QUEUE_SIZE: usize = 16;
+
+pub enum OutgoingChunk {
+    Data(Vec<u8>),
+    End,
+}
+
+pub struct OutgoingDataTransfer {
+    sender: mpsc::Sender<OutgoingChunk>,
+
+    /// Set when `data_transfer_write` found the queue full, to report `DataTransferWriteReady` once a chunk is taken from it.
+    write_ready_wanted: Rc<Cell<bool>>,
+}
+
+impl OutgoingDataTransfer {
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+/// Returns the number of written bytes, or an error if the transfer was cancelled before `OutgoingChunk::End`.
+/// `on_dequeued` is called whenever a chunk is taken from the queue.
+async fn write_chunks(
+    write_pipe: &mut (impl AsyncWrite + Unpin),
+    receiver: &mut mpsc::Receiver<OutgoingChunk>,
+    mut on_dequeued: impl FnMut(),
+) -> anyhow::Result<usize> {
+    let mut size = 0;
+    while let Some(chunk) = receiver.recv().await {
+        on_dequeued();
+        match chunk {
+            OutgoingChunk::Data(data) => {
+                write_pipe.write_all(&data).await?;
+                size += data.len();
+            }
+            OutgoingChunk::End => return Ok(size),
+        }
+    }
+    bail!("The transfer was cancelled")
+}
+
+async fn collect_chunks(receiver: &mut mpsc::Receiver<OutgoingChunk>, mut on_dequeued: impl FnMut()) -> anyhow::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    while let Some(chunk) = receiver.recv().await {
+        on_dequeued();
+        match chunk {
+            OutgoingChunk::Data(data) => buf.extend_from_slice(&data),
+            OutgoingChunk::End => return Ok(buf),
+        }
+    }
+    bail!("The transfer was cancelled")
+}
+
+/// Reports the chunks to `on_chunk`, with the total size of the data received so far.
+/// The last reported chunk is empty, with the final status, which is also returned.
+async fn read_chunks(
+    f_name: &str,
+    transfer_id: DataTransferId,
+    read_pipe: &mut (impl AsyncRead + Unpin),
+    cancel_receiver: &mut oneshot::Receiver<()>,
+    mut on_chunk: impl FnMut(&[u8], u64, DataTransferStatus),
+) -> DataTransferStatus {
+    let mut buf = vec![0; DATA_TRANSFER_CHUNK_SIZE];
+    let mut bytes_received = 0;
+    loop {
+        let read = futures_lite::future::or(async { Some(read_pipe.read(&mut buf).await) }, async {
+            let _ = (&mut *cancel_receiver).await;
+            None
+        })
+        .await;
+        let (status, len) = match read {
+            Some(Ok(0)) => (DataTransferStatus::Finished, 0),
+            Some(Ok(len)) => (DataTransferStatus::InProgress, len),
+            Some(Err(e)) => {
+                warn!("{f_name}: error receiving data for {transfer_id:?}: {e}");
+                (DataTransferStatus::Failed, 0)
+            }
+            None => (DataTransferStatus::Cancelled, 0),
+        };
+        bytes_received += len as u64;
+        on_chunk(&buf[..len], bytes_received, status);
+        if status != DataTransferStatus::InProgress {
+            debug!("{f_name}: {transfer_id:?} {status:?} after {bytes_received} bytes");
+            return status;
+        }
+    }
+}
+
+impl ApplicationState {
+    const fn next_data_transfer_id(&mut self) -> DataTransferId {
+        self.data_transfer_counter = self.data_transfer_counter.wrapping_add(1);
+        DataTransferId(self.data_transfer_counter)
+    }
+
+    /// Reports the data as `DataTransferChunk` events, until the end of the data, or until cancelled with `data_transfer_cancel`.
+    /// `on_end` is called with the final status, after the last event.
+    pub fn read_from_pipe_chunked(
+        &mut self,
+        f_name: &'static str,
+        read_pipe: ReadPipe,
+        mime_type: String,
+        serial: i32,
+        on_end: impl FnOnce(DataTransferStatus) + 'static,
+    ) -> Option<DataTransferId> {
+        let transfer_id = self.next_data_transfer_id();
+        let (cancel_sender, mut cancel_receiver) = oneshot::channel::<()>();
+        let event_handler = self.callbacks.event_handler;
+        let mut async_read_pipe = match self.loop_handle.adapt_io(read_pipe) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("{f_name}: error receiving data: {e}");
+                return None;
+            }
+        };
+        let span = AsyncTraceSpan::new("data_transfer", f_name);
+        if let Err(e) = self.calloop_scheduler.schedule(async move {
+            let status = read_chunks(
+                f_name,
+                transfer_id,
+                &mut async_read_pipe,
+                &mut cancel_receiver,
+                |data, bytes_received, status| {
+                    let event = DataTransferChunkEvent {
+                        transfer_id,
+                        serial,
+                        mime_type: BorrowedUtf8::new(&mime_type),
+                        data: BorrowedArray::from_slice(data),
+                        bytes_received,
+                        status,
+                    };
+                    send_event(event_handler, event);
+                },
+            )
+            .await;
+            drop(span);
+            on_end(status);
+        }) {
+            warn!("{f_name}: failed to start reading: {e}");
+            return None;
+        }
+        self.incoming_data_transfers.retain(|_, sender| !sender.is_closed());
+        self.incoming_data_transfers.insert(transfer_id, cancel_sender);
+        Some(transfer_id)
+    }
+
+    /// Lets the application provide the data in chunks, with `data_transfer_write` and `data_transfer_write_end`,
+    /// after receiving the `DataTransferWriteRequested` event.
+    pub fn write_to_pipe_chunked(
+        &mut self,
+        f_name: &'static str,
+        data_source: DataSource,
+        mime_type: &str,
+        write_pipe: WritePipe,
+        is_file_transfer: bool,
+    ) {
+        let transfer_id = self.next_data_transfer_id();
+        let (sender, mut receiver) = mpsc::channel(OUTGOING_CHUNK_QUEUE_SIZE);
+        let write_ready_wanted = Rc::new(Cell::new(false));
+        let event_handler = self.callbacks.event_handler;
+        let on_dequeued = {
+            let write_ready_wanted = Rc::clone(&write_ready_wanted);
+            move || {
+                if write_ready_wanted.replace(false) {
+                    send_event(event_handler, DataTransferWriteReadyEvent { transfer_id });
+                }
+            }
+        };
+        let rt_handle = self.rt_handle.clone();
+        let mut async_write_pipe = match self.loop_handle.adapt_io(write_pipe) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("{f_name}: write of {data_source:?} data failed: {e}");
+                return;
+            }
+        };
+        if let Err(e) = self.calloop_scheduler.schedule(async move {
+            let result = if is_file_transfer {
+                match collect_chunks(&mut receiver, on_dequeued).await {
+                    Ok(data) => match rt_handle.spawn(async move { register_files(&data).await }).await {
+                        Ok(Ok(key)) => async_write_pipe.write_all(&key).await.map(|()| key.len()).map_err(Into::into),
+                        Ok(Err(e)) => Err(e),
+                        Err(e) => Err(e.into()),
+                    },
+                    Err(e) => Err(e),
+                }
+            } else {
+                write_chunks(&mut async_write_pipe, &mut receiver, on_dequeued).await
+            };
+            match result {
+                Ok(size) => debug!("{f_name}: finished writing {size} bytes of {data_source:?} data for {transfer_id:?}"),
+                Err(e) => warn!("{f_name}: write of {data_source:?} data for {transfer_id:?} failed: {e}"),
+            }
+        }) {
+            warn!("{f_name}: write of {data_source:?} data failed: {e}");
+            return;
+        }
+        self.outgoing_data_transfers.retain(|_, transfer| !transfer.is_closed());
+        self.outgoing_data_transfers.insert(
+            transfer_id,
+            OutgoingDataTransfer {
+                sender,
+                write_ready_wanted,
+            },
+        );
+        self.send_event(DataTransferWriteRequestedEvent {
+            transfer_id,
+            data_source,
+            mime_type: BorrowedUtf8::new(mime_type),
+        });
+    }
+
+    /// Doesn't queue more than `OUTGOING_CHUNK_QUEUE_SIZE` chunks, so the application doesn't keep all the data in memory
+    /// when the receiver is slow. `DataTransferWriteReady` is reported when the queue was full, and there is space again.
+    pub fn data_transfer_write(&self, transfer_id: DataTransferId, data: &[u8]) -> DataTransferWriteResult {
+        let Some(transfer) = self.outgoing_data_transfers.get(&transfer_id) else {
+            return DataTransferWriteResult::Closed;
+        };
+        match transfer.sender.try_send(OutgoingChunk::Data(data.to_vec())) {
+            Ok(()) => DataTransferWriteResult::Queued,
+            Err(TrySendError::Full(_)) => {
+                transfer.write_ready_wanted.set(true);
+                DataTransferWriteResult::QueueFull
+            }
+            Err(TrySendError::Closed(_)) => DataTransferWriteResult::Closed,
+        }
+    }
+
+    /// Returns `false` if the transfer doesn't exist anymore, e.g. because it was cancelled or the receiver closed the pipe.
+    pub fn data_transfer_write_end(&mut self, transfer_id: DataTransferId) -> bool {
+        let Some(transfer) = self.outgoing_data_transfers.remove(&transfer_id) else {
+            return false;
+        };
+        match transfer.sender.try_send(OutgoingChunk::End) {
+            Ok(()) => true,
+            // The end is not data, so it's queued even when the queue is full
+            Err(TrySendError::Full(chunk)) => self
+                .calloop_scheduler
+                .schedule(async move {
+                    let _ = transfer.sender.send(chunk).await;
+                })
+                .is_ok(),
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+
+    pub fn data_transfer_cancel(&mut self, transfer_id: DataTransferId) {
+        if let Some(cancel_sender) = self.incoming_data_transfers.remove(&transfer_id) {
+            let _ = cancel_sender.send(());
+        } else if self.outgoing_data_transfers.remove(&transfer_id).is_none() {
+            debug!("data_transfer_cancel: {transfer_id:?} is already finished");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smithay_client_toolkit::reexports::calloop::{EventLoop, LoopHandle};
+    use std::io::{PipeReader, PipeWriter, Read as _, Write as _};
+    use std::time::{Duration, Instant};
+
+    /// Runs the future created by `f` in a calloop event loop, like the data transfers are run by the application.
+    fn run_in_event_loop<T: 'static, F: Future<Output = T> + 'static>(f: impl FnOnce(&LoopHandle<'static, Option<T>>) -> F) -> T {
+        let mut event_loop = EventLoop::<Option<T>>::try_new().unwrap();
+        let (executor, scheduler) = smithay_client_toolkit::reexports::calloop::futures::executor().unwrap();
+        event_loop
+            .handle()
+            .insert_source(executor, |result, (), output| *output = Some(result))
+            .unwrap();
+        scheduler.schedule(f(&event_loop.handle())).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut output = None;
+        while output.is_none() {
+            assert!(Instant::now() < deadline, "Timed out");
+            event_loop.dispatch(Some(Duration::from_millis(100)), &mut output).unwrap();
+        }
+        output.unwrap()
+    }
+
+    type Chunk = (Vec<u8>, u64, DataTransferStatus);
+
+    /// Reads from `reader`, calling `on_chunk` after every chunk is recorded.
+    fn read_all_chunks(
+        reader: PipeReader,
+        mut cancel_receiver: oneshot::Receiver<()>,
+        mut on_chunk: impl FnMut(&[Chunk]) + 'static,
+    ) -> Vec<Chunk> {
+        run_in_event_loop(|loop_handle| {
+            let mut async_reader = loop_handle.adapt_io(reader).unwrap();
+            async move {
+                let mut chunks = Vec::new();
+                let status = read_chunks(
+                    "test",
+                    DataTransferId(1),
+                    &mut async_reader,
+                    &mut cancel_receiver,
+                    |data, bytes_received, status| {
+                        chunks.push((data.to_vec(), bytes_received, status));
+                        on_chunk(&chunks);
+                    },
+                )
+                .await;
+                assert_eq!(chunks.last().map(|chunk| chunk.2), Some(status));
+                chunks
+            }
+        })
+    }
+
+    fn test_data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| u8::try_from(i % 251).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_read_chunks_until_end() {
+        let data = test_data(DATA_TRANSFER_CHUNK_SIZE * 2 + 1000);
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        let writer_thread = std::thread::spawn({
+            let data = data.clone();
+            move || writer.write_all(&data).unwrap()
+        });
+        let (_cancel_sender, cancel_receiver) = oneshot::channel();
+        let chunks = read_all_chunks(reader, cancel_receiver, |_| {});
+        writer_thread.join().unwrap();
+
+        let (last, in_progress) = chunks.split_last().unwrap();
+        assert_eq!(last, &(Vec::new(), data.len() as u64, DataTransferStatus::Finished));
+        let mut total = 0;
+        for (chunk, bytes_received, status) in in_progress {
+            assert_eq!(*status, DataTransferStatus::InProgress);
+            assert!(!chunk.is_empty() && chunk.len() <= DATA_TRANSFER_CHUNK_SIZE);
+            total += chunk.len() as u64;
+            assert_eq!(*bytes_received, total);
+        }
+        assert_eq!(
+            in_progress.iter().flat_map(|(chunk, _, _)| chunk).copied().collect::<Vec<_>>(),
+            data
+        );
+    }
+
+    #[test]
+    fn test_read_chunks_cancelled() {
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        writer.write_all(b"first").unwrap();
+        let (cancel_sender, cancel_receiver) = oneshot::channel();
+        let mut cancel_sender = Some(cancel_sender);
+        // The writer stays open, so the transfer only ends because of the cancellation
+        let chunks = read_all_chunks(reader, cancel_receiver, move |_| {
+            if let Some(sender) = cancel_sender.take() {
+                sender.send(()).unwrap();
+            }
+        });
+        drop(writer);
+        assert_eq!(
+            chunks,
+            [
+                (b"first".to_vec(), 5, DataTransferStatus::InProgress),
+                (Vec::new(), 5, DataTransferStatus::Cancelled)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_chunks_cancel_handle_dropped() {
+        let (reader, writer) = std::io::pipe().unwrap();
+        let (cancel_sender, cancel_receiver) = oneshot::channel();
+        drop(cancel_sender);
+        let chunks = read_all_chunks(reader, cancel_receiver, |_| {});
+        drop(writer);
+        assert_eq!(chunks, [(Vec::new(), 0, DataTransferStatus::Cancelled)]);
+    }
+
+    #[test]
+    fn test_read_chunks_writer_dropped() {
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        writer.write_all(b"partial").unwrap();
+        let mut writer = Some(writer);
+        let (_cancel_sender, cancel_receiver) = oneshot::channel();
+        // The writer is closed in the middle of the transfer, without writing the rest of the data
+        let chunks = read_all_chunks(reader, cancel_receiver, move |_| drop(writer.take()));
+        assert_eq!(
+            chunks,
+            [
+                (b"partial".to_vec(), 7, DataTransferStatus::InProgress),
+                (Vec::new(), 7, DataTransferStatus::Finished)
+            ]
+        );
+    }
+
+    /// Runs `write_chunks` with the chunks already queued, and returns its result with the data read from the pipe,
+    /// and the number of dequeued chunks.
+    fn write_all_chunks(receiver: mpsc::Receiver<OutgoingChunk>) -> (anyhow::Result<usize>, Vec<u8>, usize) {
+        let (mut reader, writer): (PipeReader, PipeWriter) = std::io::pipe().unwrap();
+        let dequeued = Rc::new(Cell::new(0));
+        let result = run_in_event_loop(|loop_handle| {
+            let mut async_writer = loop_handle.adapt_io(writer).unwrap();
+            let mut receiver = receiver;
+            let dequeued = Rc::clone(&dequeued);
+            async move { write_chunks(&mut async_writer, &mut receiver, || dequeued.set(dequeued.get() + 1)).await }
+        });
+        let mut written = Vec::new();
+        reader.read_to_end(&mut written).unwrap();
+        (result, written, dequeued.get())
+    }
+
+    #[test]
+    fn test_write_chunks() {
+        let (sender, receiver) = mpsc::channel(OUTGOING_CHUNK_QUEUE_SIZE);
+        sender.try_send(OutgoingChunk::Data(b"abc".to_vec())).unwrap();
+        sender.try_send(OutgoingChunk::Data(Vec::new())).unwrap();
+        sender.try_send(OutgoingChunk::Data(b"def".to_vec())).unwrap();
+        sender.try_send(OutgoingChunk::End).unwrap();
+        // Ignored after `End`
+        sender.try_send(OutgoingChunk::Data(b"ghi".to_vec())).unwrap();
+        let (result, written, dequeued) = write_all_chunks(receiver);
+        assert_eq!(result.unwrap(), 6);
+        assert_eq!(written, b"abcdef");
+        assert_eq!(dequeued, 4);
+    }
+
+    #[test]
+    fn test_write_chunks_sender_dropped() {
+        let (sender, receiver) = mpsc::channel(OUTGOING_CHUNK_QUEUE_SIZE);
+        sender.try_send(OutgoingChunk::Data(b"abc".to_vec())).unwrap();
+        drop(sender);
+        let (result, written, _) = write_all_chunks(receiver);
+        assert!(result.is_err());
+        assert_eq!(written, b"abc");
+    }
+
+    #[test]
+    fn test_write_chunks_queue_full() {
+        let (sender, receiver) = mpsc::channel(2);
+        sender.try_send(OutgoingChunk::Data(b"abc".to_vec())).unwrap();
+        sender.try_send(OutgoingChunk::Data(b"def".to_vec())).unwrap();
+        assert!(matches!(
+            sender.try_send(OutgoingChunk::Data(b"ghi".to_vec())),
+            Err(TrySendError::Full(_))
+        ));
+        let (mut reader, writer): (PipeReader, PipeWriter) = std::io::pipe().unwrap();
+        let result = run_in_event_loop(|loop_handle| {
+            let mut async_writer = loop_handle.adapt_io(writer).unwrap();
+            let mut receiver = receiver;
+            // Queues the next chunk as soon as there is space, like the application does after `DataTransferWriteReady`
+            let mut pending = vec![OutgoingChunk::End, OutgoingChunk::Data(b"ghi".to_vec())];
+            async move {
+                write_chunks(&mut async_writer, &mut receiver, || {
+                    if let Some(chunk) = pending.pop() {
+                        sender.try_send(chunk).unwrap();
+                    }
+                })
+                .await
+            }
+        });
+        let mut written = Vec::new();
+        reader.read_to_end(&mut written).unwrap();
+        assert_eq!(result.unwrap(), 9);
+        assert_eq!(written, b"abcdefghi");
+    }
+
+    #[test]
+    fn test_collect_chunks() {
+        let (sender, mut receiver) = mpsc::channel(OUTGOING_CHUNK_QUEUE_SIZE);
+        sender.try_send(OutgoingChunk::Data(b"abc".to_vec())).unwrap();
+        sender.try_send(OutgoingChunk::Data(b"def".to_vec())).unwrap();
+        sender.try_send(OutgoingChunk::End).unwrap();
+        let data = futures_lite::future::block_on(collect_chunks(&mut receiver, || {})).unwrap();
+        assert_eq!(data, b"abcdef");
+
+        sender.try_send(OutgoingChunk::Data(b"abc".to_vec())).unwrap();
+        drop(sender);
+        assert!(futures_lite::future::block_on(collect_chunks(&mut receiver, || {})).is_err());
+    }
+}
@@ -89,7 +89,7 @@ impl DragIcon {
         let physical_size = self.size.to_physical(self.current_scale);
 
         self.rendering_data
-            .draw(wl_surface, physical_size, |software_draw_data: SoftwareDrawData| {
+            .draw(wl_surface, physical_size, false, |software_draw_data: SoftwareDrawData| {
                 let did_draw = callback(DragIconDrawEvent {
                     software_draw_data,
                     physical_size,
@@ -0,0 +1,636 @@
+use crate::linux::{
+    application_api::{DataSource, DragAndDropAction},
+    application_menu_api::AppMenuItemToggleState,
+    desktop_settings_api::{
+        Color, DesktopTitlebarAction, FfiDesktopSetting, FontAntialiasing, FontHinting, FontRgbaOrder, XdgDesktopColorScheme,
+        XdgDesktopContrast, XdgDesktopReducedMotion,
+    },
+    events::{
+        ActivationTokenResponse, AppMenuItemActivatedEvent, BackgroundResponse, ClipboardChangedEvent, ColorPickerResponse,
+        DataTransferAvailableEvent, DataTransferCancelledEvent, DataTransferChunkEvent, DataTransferContent, DataTransferEvent,
+        DataTransferId, DataTransferStatus, DataTransferWriteReadyEvent, DataTransferWriteRequestedEvent, DragAndDropFinishedEvent,
+        DragAndDropLeaveEvent, DragIconDrawEvent, DropPerformedEvent, Event, FileChooserResponse, InhibitResponse, KeyCode, KeyDownEvent,
+        KeyModifiers, KeyUpEvent, LowMemoryWarningEvent, ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseEnteredEvent,
+        MouseExitedEvent, MouseMovedEvent, MouseUpEvent, NetworkAvailabilityChangedEvent, NetworkConnectivity, NotificationClosedEvent,
+        NotificationShownEvent, OnBatteryChangedEvent, PowerSaverChangedEvent, PreparePrintResponse, PrintResponse, RequestId, ScreenId,
+        ScreenshotResponse, ScrollData, ScrollWheelEvent, SecretResponse, SessionState, SessionStateChangedEvent, SoftwareDrawData,
+        TextInputAvailabilityEvent, TextInputDeleteSurroundingTextData, TextInputEvent, TextInputPreeditStringData, Timestamp,
+        WindowCapabilities, WindowCaptureFrameResponse, WindowCloseRequestEvent, WindowClosedEvent, WindowConfigureEvent,
+        WindowDecorationMode, WindowDrawEvent, WindowId, WindowKeyboardEnterEvent, WindowKeyboardLeaveEvent, WindowScaleChangedEvent,
+        WindowScreenChangeEvent,
+    },
+    file_dialog_api::FileDialogChoiceSelection,
+    geometry::{LogicalPixels, LogicalPoint, LogicalSize, PhysicalPixels, PhysicalSize},
+    print_api::{PrintOrientation, PrintPageSetup, PrintPages, PrintSettings},
+    secrets_api::{SecretAttribute, SecretErrorKind, SecretItem},
+};
+use desktop_common::{
+    event_recording::{RecordedValue, ReplayArena, Value, replay_field},
+    ffi_utils::BorrowedArray,
+    recorded_enum, recorded_newtype, recorded_struct, recorded_variants,
+};
+
+pub const EVENT_RECORDING_PLATFORM: &str = "linux";
+
+recorded_newtype!(Timestamp);
+recorded_newtype!(ScreenId);
+recorded_newtype!(WindowId);
+recorded_newtype!(RequestId);
+recorded_newtype!(DataTransferId);
+recorded_newtype!(MouseButton);
+recorded_newtype!(KeyCode);
+recorded_newtype!(PhysicalPixels);
+recorded_newtype!(LogicalPixels);
+
+recorded_struct!(LogicalPoint { x, y });
+recorded_struct!(LogicalSize { width, height });
+recorded_struct!(PhysicalSize { width, height });
+recorded_struct!(Color { red, green, blue, alpha });
+
+impl<'a> RecordedValue<'a> for KeyModifiers {
+    fn record(&self) -> Value {
+        self.bits().record()
+    }
+
+    fn replay(value: &'a Value, arena: &'a ReplayArena) -> anyhow::Result<Self> {
+        Ok(Self::from_bits_retain(RecordedValue::replay(value, arena)?))
+    }
+}
+
+/// Only the `stride` is recorded, the `canvas` is replayed as null.
+impl<'a> RecordedValue<'a> for SoftwareDrawData {
+    fn record(&self) -> Value {
+        let mut fields = desktop_common::event_recording::Map::new();
+        fields.insert("stride".to_owned(), self.stride.record());
+        Value::Object(fields)
+    }
+
+    fn replay(value: &'a Value, arena: &'a ReplayArena) -> anyhow::Result<Self> {
+        Ok(Self {
+            canvas: std::ptr::null_mut(),
+            stride: replay_field(value, "stride", arena)?,
+        })
+    }
+}
+
+/// The `secret` is not recorded, and is replayed as empty.
+impl<'a> RecordedValue<'a> for SecretItem<'a> {
+    fn record(&self) -> Value {
+        let mut fields = desktop_common::event_recording::Map::new();
+        fields.insert("label".to_owned(), self.label.record());
+        fields.insert("attributes".to_owned(), self.attributes.record());
+        Value::Object(fields)
+    }
+
+    fn replay(value: &'a Value, arena: &'a ReplayArena) -> anyhow::Result<Self> {
+        Ok(Self {
+            label: replay_field(value, "label", arena)?,
+            attributes: replay_field(value, "attributes", arena)?,
+            secret: BorrowedArray::from_slice(&[]),
+        })
+    }
+}
+
+/// The `pixels` are not recorded, and are replayed as empty.
+impl<'a> RecordedValue<'a> for WindowCaptureFrameResponse<'a> {
+    fn record(&self) -> Value {
+        let mut fields = desktop_common::event_recording::Map::new();
+        fields.insert("request_id".to_owned(), self.request_id.record());
+        fields.insert("window_id".to_owned(), self.window_id.record());
+        fields.insert("physical_size".to_owned(), self.physical_size.record());
+        fields.insert("scale".to_owned(), self.scale.record());
+        fields.insert("error".to_owned(), self.error.record());
+        Value::Object(fields)
+    }
+
+    fn replay(value: &'a Value, arena: &'a ReplayArena) -> anyhow::Result<Self> {
+        Ok(Self {
+            request_id: replay_field(value, "request_id", arena)?,
+            window_id: replay_field(value, "window_id", arena)?,
+            physical_size: replay_field(value, "physical_size", arena)?,
+            scale: replay_field(value, "scale", arena)?,
+            pixels: BorrowedArray::from_slice(&[]),
+            error: replay_field(value, "error", arena)?,
+        })
+    }
+}
+
+recorded_enum!(DataSource {
+    Clipboard,
+    DragAndDrop,
+    PrimarySelection
+});
+recorded_enum!(DragAndDropAction { None, Copy, Move, Ask });
+recorded_enum!(AppMenuItemToggleState { Off, On, Indeterminate });
+recorded_enum!(DataTransferStatus {
+    InProgress,
+    Finished,
+    Failed,
+    Cancelled
+});
+recorded_enum!(WindowDecorationMode { Client, Server });
+recorded_enum!(SessionState { Running, QueryEnd, Ending });
+recorded_enum!(NetworkConnectivity {
+    Local,
+    Limited,
+    CaptivePortal,
+    Full
+});
+recorded_enum!(SecretErrorKind {
+    None,
+    ServiceUnavailable,
+    Locked,
+    Other
+});
+recorded_enum!(PrintOrientation {
+    Unspecified,
+    Portrait,
+    Landscape,
+    ReversePortrait,
+    ReverseLandscape
+});
+recorded_enum!(PrintPages {
+    Unspecified,
+    All,
+    Selection,
+    Current,
+    Ranges
+});
+recorded_enum!(XdgDesktopColorScheme {
+    NoPreference,
+    PreferDark,
+    PreferLight
+});
+recorded_enum!(XdgDesktopContrast { NoPreference, High });
+recorded_enum!(XdgDesktopReducedMotion {
+    NoPreference,
+    ReducedMotion
+});
+recorded_enum!(FontAntialiasing { None, Grayscale, Rgba });
+recorded_enum!(FontHinting {
+    None,
+    Slight,
+    Medium,
+    Full
+});
+recorded_enum!(FontRgbaOrder { Rgb, Bgr, Vrgb, Vbgr });
+recorded_enum!(DesktopTitlebarAction {
+    Minimize,
+    ToggleMaximize,
+    Menu,
+    None
+});
+
+recorded_variants!(FfiDesktopSetting<'a> {
+    unit: [],
+    data: [
+        AccentColor,
+        AudibleBell,
+        ActionDoubleClickTitlebar,
+        ActionRightClickTitlebar,
+        ActionMiddleClickTitlebar,
+        ColorScheme,
+        Contrast,
+        CursorBlink,
+        CursorBlinkTimeMs,
+        CursorBlinkTimeoutMs,
+        CursorSize,
+        CursorTheme,
+        DoubleClickIntervalMs,
+        DragThresholdPixels,
+        EnableAnimations,
+        FontAntialiasing,
+        FontHinting,
+        FontRgbaOrder,
+        MiddleClickPaste,
+        OverlayScrolling,
+        RecentFilesEnabled,
+        RecentFilesMaxAgeDays,
+        ReducedMotion,
+        TitlebarLayout,
+    ],
+});
+
+recorded_struct!(DataTransferContent<'a> {
+    mime_type,
+    data,
+    kind,
+    text,
+    files,
+    image_format
+});
+recorded_struct!(DataTransferEvent<'a> { serial, content });
+recorded_struct!(DataTransferChunkEvent<'a> {
+    transfer_id,
+    serial,
+    mime_type,
+    data,
+    bytes_received,
+    status
+});
+recorded_struct!(DataTransferWriteRequestedEvent<'a> {
+    transfer_id,
+    data_source,
+    mime_type
+});
+recorded_struct!(DataTransferWriteReadyEvent { transfer_id });
+recorded_struct!(DragAndDropLeaveEvent { window_id });
+recorded_struct!(DragAndDropFinishedEvent { window_id, action });
+recorded_struct!(DropPerformedEvent<'a> {
+    window_id,
+    content,
+    action,
+    location_in_window,
+    transfer_id
+});
+recorded_struct!(DataTransferAvailableEvent<'a> { data_source, mime_types, mime_types_list });
+recorded_struct!(ClipboardChangedEvent<'a> { data_source, mime_types });
+recorded_struct!(DataTransferCancelledEvent { data_source });
+recorded_struct!(KeyDownEvent<'a> {
+    characters,
+    code,
+    key,
+    is_repeat
+});
+recorded_struct!(KeyUpEvent { code, key });
+recorded_struct!(ModifiersChangedEvent { modifiers });
+recorded_struct!(MouseEnteredEvent {
+    window_id,
+    location_in_window
+});
+recorded_struct!(MouseExitedEvent {
+    window_id,
+    location_in_window
+});
+recorded_struct!(MouseMovedEvent {
+    window_id,
+    location_in_window,
+    timestamp
+});
+recorded_struct!(MouseDownEvent {
+    window_id,
+    button,
+    location_in_window,
+    timestamp
+});
+recorded_struct!(MouseUpEvent {
+    window_id,
+    button,
+    location_in_window,
+    timestamp
+});
+recorded_struct!(ScrollData {
+    delta,
+    wheel_value120,
+    is_inverted,
+    is_stop
+});
+recorded_struct!(ScrollWheelEvent {
+    window_id,
+    location_in_window,
+    timestamp,
+    horizontal_scroll,
+    vertical_scroll
+});
+recorded_struct!(TextInputPreeditStringData<'a> {
+    text,
+    cursor_begin_byte_pos,
+    cursor_end_byte_pos
+});
+recorded_struct!(TextInputDeleteSurroundingTextData {
+    before_length_in_bytes,
+    after_length_in_bytes
+});
+recorded_struct!(TextInputAvailabilityEvent { window_id, available });
+recorded_struct!(TextInputEvent<'a> {
+    has_preedit_string,
+    preedit_string,
+    has_commit_string,
+    commit_string,
+    has_delete_surrounding_text,
+    delete_surrounding_text
+});
+recorded_struct!(WindowCapabilities {
+    window_menu,
+    maximize,
+    fullscreen,
+    minimize
+});
+recorded_struct!(WindowCloseRequestEvent { window_id });
+recorded_struct!(WindowClosedEvent { window_id });
+recorded_struct!(WindowConfigureEvent {
+    window_id,
+    size,
+    active,
+    maximized,
+    fullscreen,
+    tiled_left,
+    tiled_right,
+    tiled_top,
+    tiled_bottom,
+    decoration_mode,
+    capabilities
+});
+recorded_struct!(WindowDrawEvent {
+    window_id,
+    software_draw_data,
+    physical_size,
+    scale
+});
+recorded_struct!(DragIconDrawEvent {
+    software_draw_data,
+    physical_size,
+    scale
+});
+recorded_struct!(WindowKeyboardEnterEvent<'a> { window_id, raw, keysyms });
+recorded_struct!(WindowKeyboardLeaveEvent { window_id });
+recorded_struct!(WindowScaleChangedEvent { window_id, new_scale });
+recorded_struct!(WindowScreenChangeEvent { window_id, new_screen_id });
+recorded_struct!(FileDialogChoiceSelection<'a> { id, value });
+recorded_struct!(FileChooserResponse<'a> {
+    request_id,
+    newline_separated_files,
+    error,
+    current_filter_index,
+    choices
+});
+recorded_struct!(ScreenshotResponse<'a> { request_id, uri, error });
+recorded_struct!(ColorPickerResponse<'a> {
+    request_id,
+    picked,
+    color,
+    error
+});
+recorded_struct!(PrintSettings<'a> {
+    orientation,
+    paper_format,
+    paper_width,
+    paper_height,
+    n_copies,
+    print_pages,
+    page_ranges,
+    scale,
+    output_uri
+});
+recorded_struct!(PrintPageSetup<'a> {
+    name,
+    display_name,
+    width,
+    height,
+    margin_top,
+    margin_bottom,
+    margin_left,
+    margin_right,
+    orientation
+});
+recorded_struct!(PreparePrintResponse<'a> {
+    request_id,
+    prepared,
+    token,
+    settings,
+    page_setup,
+    error
+});
+recorded_struct!(PrintResponse<'a> { request_id, printed, error });
+recorded_struct!(InhibitResponse<'a> {
+    request_id,
+    inhibited,
+    error
+});
+recorded_struct!(BackgroundResponse<'a> {
+    request_id,
+    run_in_background,
+    autostart,
+    error
+});
+recorded_struct!(SecretAttribute<'a> { name, value });
+recorded_struct!(SecretResponse<'a> {
+    request_id,
+    items,
+    error_kind,
+    error
+});
+recorded_struct!(SessionStateChangedEvent {
+    session_state,
+    screensaver_active
+});
+recorded_struct!(NetworkAvailabilityChangedEvent {
+    available,
+    metered,
+    connectivity
+});
+recorded_struct!(PowerSaverChangedEvent { enabled });
+recorded_struct!(OnBatteryChangedEvent { on_battery });
+recorded_struct!(LowMemoryWarningEvent { level });
+recorded_struct!(ActivationTokenResponse<'a> { request_id, token });
+recorded_struct!(NotificationShownEvent {
+    request_id,
+    notification_id
+});
+recorded_struct!(NotificationClosedEvent<'a> {
+    notification_id,
+    action,
+    activation_token
+});
+recorded_struct!(AppMenuItemActivatedEvent { item_id, toggle_state });
+
+recorded_variants!(Event<'a> {
+    unit: [
+        ApplicationStarted,
+        ApplicationWantsToTerminate,
+        ApplicationWillTerminate,
+        DisplayConfigurationChange,
+        SessionQueryEnd,
+        SessionEnding,
+    ],
+    data: [
+        SessionStateChanged,
+        NetworkAvailabilityChanged,
+        PowerSaverChanged,
+        OnBatteryChanged,
+        LowMemoryWarning,
+        DesktopSettingChange,
+        DataTransfer,
+        DataTransferChunk,
+        DataTransferWriteRequested,
+        DataTransferWriteReady,
+        DragAndDropLeave,
+        DragAndDropFinished,
+        DragIconDraw,
+        DropPerformed,
+        DataTransferAvailable,
+        DataTransferCancelled,
+        ClipboardChanged,
+        FileChooserResponse,
+        ScreenshotResponse,
+        ColorPickerResponse,
+        PreparePrintResponse,
+        PrintResponse,
+        InhibitResponse,
+        BackgroundResponse,
+        SecretResponse,
+        ActivationTokenResponse,
+        WindowCaptureFrameResponse,
+        NotificationShown,
+        NotificationClosed,
+        AppMenuItemActivated,
+        KeyDown,
+        KeyUp,
+        ModifiersChanged,
+        MouseEntered,
+        MouseExited,
+        MouseMoved,
+        MouseDown,
+        MouseUp,
+        ScrollWheel,
+        TextInputAvailability,
+        TextInput,
+        WindowCloseRequest,
+        WindowClosed,
+        WindowConfigure,
+        WindowDraw,
+        WindowKeyboardEnter,
+        WindowKeyboardLeave,
+        WindowScaleChanged,
+        WindowScreenChange,
+    ],
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use desktop_common::{
+        event_recording::{RecordedVariant, replay_events_from},
+        ffi_utils::BorrowedUtf8,
+        mime_types::DataTransferContentKind,
+    };
+
+    /// Records the event to a JSON line, like in the recording file, and replays it from the parsed line.
+    fn round_trip(event: &Event, check: impl for<'v> FnOnce(&Event<'v>)) {
+        let line = event.record().to_string();
+        let value: Value = line.parse().unwrap();
+        let arena = ReplayArena::new();
+        let replayed = Event::replay(&value, &arena).unwrap();
+        assert_eq!(replayed.variant_name(), event.variant_name());
+        assert_eq!(replayed.record(), event.record(), "{line}");
+        check(&replayed);
+    }
+
+    #[test]
+    fn test_text_input_round_trip() {
+        let event = Event::TextInput(TextInputEvent {
+            has_preedit_string: true,
+            preedit_string: TextInputPreeditStringData {
+                text: BorrowedUtf8::new("にほんご"),
+                cursor_begin_byte_pos: 3,
+                cursor_end_byte_pos: 12,
+            },
+            has_commit_string: false,
+            commit_string: BorrowedUtf8::null(),
+            has_delete_surrounding_text: true,
+            delete_surrounding_text: TextInputDeleteSurroundingTextData {
+                before_length_in_bytes: 1,
+                after_length_in_bytes: 2,
+            },
+        });
+        round_trip(&event, |replayed| {
+            let Event::TextInput(replayed) = replayed else {
+                panic!("Unexpected event {replayed:?}");
+            };
+            assert!(replayed.has_preedit_string);
+            assert_eq!(replayed.preedit_string.text.get("text").unwrap(), "にほんご");
+            assert_eq!(
+                (
+                    replayed.preedit_string.cursor_begin_byte_pos,
+                    replayed.preedit_string.cursor_end_byte_pos
+                ),
+                (3, 12)
+            );
+            assert_eq!(replayed.commit_string.get_optional("commit_string").unwrap(), None);
+            assert_eq!(replayed.delete_surrounding_text.after_length_in_bytes, 2);
+        });
+    }
+
+    #[test]
+    fn test_drag_and_drop_round_trip() {
+        let mime_types = [BorrowedUtf8::new("text/uri-list"), BorrowedUtf8::new("text/plain;charset=utf-8")];
+        let event = Event::ClipboardChanged(ClipboardChangedEvent {
+            data_source: DataSource::DragAndDrop,
+            mime_types: BorrowedArray::from_slice(&mime_types),
+        });
+        round_trip(&event, |replayed| {
+            let Event::ClipboardChanged(replayed) = replayed else {
+                panic!("Unexpected event {replayed:?}");
+            };
+            assert_eq!(replayed.data_source, DataSource::DragAndDrop);
+            assert_eq!(
+                replayed.mime_types.get_strs("mime_types").unwrap().as_ref(),
+                ["text/uri-list", "text/plain;charset=utf-8"]
+            );
+        });
+
+        let files = [BorrowedUtf8::new("/tmp/a b.txt"), BorrowedUtf8::new("/tmp/ü.png")];
+        let data = b"file:///tmp/a%20b.txt\r\nfile:///tmp/%C3%BC.png\r\n";
+        let event = Event::DropPerformed(DropPerformedEvent {
+            window_id: WindowId(2),
+            content: DataTransferContent {
+                kind: DataTransferContentKind::Files,
+                files: BorrowedArray::from_slice(&files),
+                ..DataTransferContent::new("text/uri-list", data)
+            },
+            action: DragAndDropAction::Ask,
+            location_in_window: LogicalPoint {
+                x: LogicalPixels(1.5),
+                y: LogicalPixels(-2.0),
+            },
+            transfer_id: DataTransferId(0),
+        });
+        round_trip(&event, |replayed| {
+            let Event::DropPerformed(replayed) = replayed else {
+                panic!("Unexpected event {replayed:?}");
+            };
+            assert_eq!(replayed.window_id, WindowId(2));
+            assert_eq!(replayed.action, DragAndDropAction::Ask);
+            assert_eq!(replayed.content.kind, DataTransferContentKind::Files);
+            assert_eq!(replayed.content.data.as_slice().unwrap(), data);
+            assert_eq!(
+                replayed.content.files.get_strs("files").unwrap().as_ref(),
+                ["/tmp/a b.txt", "/tmp/ü.png"]
+            );
+            assert_eq!(replayed.content.image_format.get_optional("image_format").unwrap(), None);
+            assert!((replayed.location_in_window.x.0 - 1.5).abs() < f64::EPSILON);
+        });
+    }
+
+    #[test]
+    fn test_data_transfer_available_round_trip() {
+        let mime_types = ["text/plain;charset=utf-8", "application/x-custom;params=a,b"];
+        DataTransferAvailableEvent::with(DataSource::Clipboard, &mime_types, |event| {
+            assert_eq!(
+                event.mime_types.get("mime_types").unwrap(),
+                "text/plain;charset=utf-8,application/x-custom;params=a,b"
+            );
+            round_trip(&event.into(), |replayed| {
+                let Event::DataTransferAvailable(replayed) = replayed else {
+                    panic!("Unexpected event {replayed:?}");
+                };
+                assert_eq!(replayed.data_source, DataSource::Clipboard);
+                assert_eq!(replayed.mime_types_list.get_strs("mime_types_list").unwrap().as_ref(), mime_types);
+            });
+        });
+    }
+
+    #[test]
+    fn test_replay_invalid_event() {
+        let recording = format!(
+            "{}\n{}\n",
+            r#"{"format":"kdt-event-recording","version":1,"platform":"linux"}"#,
+            r#"{"time_us":0,"event":{"DropPerformed":{"window_id":1}}}"#
+        );
+        let error = replay_events_from(recording.as_bytes(), EVENT_RECORDING_PLATFORM, 0.0, |value, arena| {
+            Event::replay(value, arena).map(drop)
+        })
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Can't replay the event on line 2");
+    }
+}
@@ -0,0 +1,139 @@
+use crate::linux::{
+    event_recording::EVENT_RECORDING_PLATFORM,
+    events::{Event, EventHandler},
+};
+use desktop_common::{
+    event_recording::{RecordedValue, replay_events, start_event_recording, stop_event_recording},
+    ffi_utils::BorrowedUtf8,
+    logger::ffi_boundary,
+};
+use log::debug;
+use std::path::Path;
+
+/// Starts writing the events sent to the event handler to the file at `path`, e.g. to attach it to a bug report.
+/// Replaces the recording in progress, if any.
+///
+/// The draw events are not recorded, and neither are the secrets from `Event::SecretResponse`.
+#[unsafe(no_mangle)]
+pub extern "C" fn event_recording_start(path: BorrowedUtf8) -> bool {
+    debug!("event_recording_start");
+    ffi_boundary("event_recording_start", || {
+        let path = path.get("event_recording_start: path")?;
+        start_event_recording(Path::new(path), EVENT_RECORDING_PLATFORM)?;
+        Ok(true)
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn event_recording_stop() {
+    debug!("event_recording_stop");
+    ffi_boundary("event_recording_stop", stop_event_recording);
+}
+
+/// Passes the events from the recording at `path` to `event_handler`, on the calling thread.
+/// Returns `false` if the recording couldn't be read, or one of its events couldn't be replayed.
+///
+/// * `speed`: `1.0` keeps the original timing, `2.0` replays twice as fast, and `0.0` replays without any delays.
+#[unsafe(no_mangle)]
+pub extern "C" fn event_recording_replay(path: BorrowedUtf8, speed: f64, event_handler: EventHandler) -> bool {
+    debug!("event_recording_replay: speed = {speed}");
+    ffi_boundary("event_recording_replay", || {
+        let path = path.get("event_recording_replay: path")?;
+        let count = replay_events(Path::new(path), EVENT_RECORDING_PLATFORM, speed, |value, arena| {
+            let event = Event::replay(value, arena)?;
+            event_handler(&event);
+            Ok(())
+        })?;
+        debug!("event_recording_replay: replayed {count} events");
+        Ok(true)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linux::events::{KeyCode, KeyDownEvent, TextInputDeleteSurroundingTextData, TextInputEvent, TextInputPreeditStringData};
+    use desktop_common::event_recording::{EVENT_RECORDING_FORMAT, EVENT_RECORDING_VERSION, Value, record_event};
+    use std::{
+        cell::RefCell,
+        time::{Duration, Instant},
+    };
+
+    thread_local! {
+        static REPLAYED: RefCell<Vec<(Value, Instant)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    extern "C" fn test_event_handler(event: &Event) -> bool {
+        REPLAYED.with_borrow_mut(|replayed| replayed.push((event.record(), Instant::now())));
+        true
+    }
+
+    fn replay(path: &Path, speed: f64) -> Option<Vec<(Value, Duration)>> {
+        REPLAYED.with_borrow_mut(Vec::clear);
+        let started = Instant::now();
+        let path = path.to_str().unwrap();
+        let result = event_recording_replay(BorrowedUtf8::new(path), speed, test_event_handler);
+        let replayed = REPLAYED.take().into_iter().map(|(event, time)| (event, time - started)).collect();
+        result.then_some(replayed)
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let events = [
+            Event::KeyDown(KeyDownEvent {
+                characters: BorrowedUtf8::new("a"),
+                code: KeyCode(38),
+                key: 97,
+                is_repeat: false,
+            }),
+            Event::TextInput(TextInputEvent {
+                has_preedit_string: true,
+                preedit_string: TextInputPreeditStringData {
+                    text: BorrowedUtf8::new("préédit"),
+                    cursor_begin_byte_pos: 1,
+                    cursor_end_byte_pos: 3,
+                },
+                has_commit_string: false,
+                commit_string: BorrowedUtf8::null(),
+                has_delete_surrounding_text: false,
+                delete_surrounding_text: TextInputDeleteSurroundingTextData::default(),
+            }),
+        ];
+
+        assert!(event_recording_start(BorrowedUtf8::new(path.to_str().unwrap())));
+        record_event(&events[0]);
+        std::thread::sleep(Duration::from_millis(200));
+        record_event(&events[1]);
+        event_recording_stop();
+
+        // 4 times faster, so the second event is replayed after about 50ms instead of 200ms
+        let replayed = replay(&path, 4.0).unwrap();
+        let recorded: Vec<_> = events.iter().map(RecordedValue::record).collect();
+        assert_eq!(replayed.iter().map(|(event, _)| event.clone()).collect::<Vec<_>>(), recorded);
+        assert!(replayed[1].1 >= Duration::from_millis(50), "{replayed:?}");
+        assert!(replayed[1].1 < Duration::from_millis(200), "{replayed:?}");
+
+        let replayed = replay(&path, 0.0).unwrap();
+        assert_eq!(replayed.len(), events.len());
+    }
+
+    #[test]
+    fn test_replay_version_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let header = format!(
+            r#"{{"format":"{EVENT_RECORDING_FORMAT}","version":{},"platform":"linux"}}"#,
+            EVENT_RECORDING_VERSION + 1
+        );
+        std::fs::write(
+            &path,
+            format!("{header}\n{{\"time_us\":0,\"event\":{{\"KeyUp\":{{\"code\":38,\"key\":97}}}}}}\n"),
+        )
+        .unwrap();
+        assert_eq!(replay(&path, 0.0), None);
+
+        assert_eq!(replay(&dir.path().join("missing.jsonl"), 0.0), None);
+    }
+}
@@ -1,11 +1,16 @@
 use crate::linux::{
     application_api::{DataSource, DragAndDropAction},
-    desktop_settings_api::FfiDesktopSetting,
+    application_menu_api::{AppMenuItemId, AppMenuItemToggleState},
+    desktop_settings_api::{Color, FfiDesktopSetting},
+    file_dialog_api::FileDialogChoiceSelection,
     geometry::{LogicalPixels, LogicalPoint, LogicalSize, PhysicalSize},
+    print_api::{PrintPageSetup, PrintSettings},
+    secrets_api::{SecretErrorKind, SecretItem},
 };
 use bitflag_attr::bitflag;
 use core::f64;
 use desktop_common::ffi_utils::BorrowedUtf8;
+use desktop_common::mime_types::{DataTransferContentKind, ReceivedContent};
 use desktop_common::{ffi_utils::BorrowedArray, logger::PanicDefault};
 use std::ffi::c_int;
 
@@ -34,6 +39,17 @@ impl PanicDefault for RequestId {
     }
 }
 
+/// Identifies a chunked clipboard or drag&drop data transfer. `0` means that the transfer couldn't be started.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct DataTransferId(pub u64);
+
+impl PanicDefault for DataTransferId {
+    fn default() -> Self {
+        Self(0)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 #[repr(transparent)]
 pub struct MouseButton(pub u32);
@@ -83,9 +99,22 @@ pub enum WindowDecorationMode {
 ///
 /// * `{ mime_type: "text/uri-list", data: "file:///data/some-file\r\nfile:///data/Some%20File%20With%20Spaces.txt\r\n" }`
 /// * `{ mime_type: "text/plain;charset=utf-8", data: "some text\r\nhere" }`
+///
+/// Text received in a different MIME type or charset (e.g. `UTF8_STRING` or `text/plain;charset=utf-16`)
+/// is converted to the requested one, and `text/uri-list` is decoded into `files`.
 pub struct DataTransferContent<'a> {
     pub mime_type: BorrowedUtf8<'a>,
     pub data: BorrowedArray<'a, u8>,
+    pub kind: DataTransferContentKind,
+
+    /// Set for `DataTransferContentKind::Text`.
+    pub text: BorrowedUtf8<'a>,
+
+    /// Local file paths, set for `DataTransferContentKind::Files`.
+    pub files: BorrowedArray<'a, BorrowedUtf8<'a>>,
+
+    /// Set for `DataTransferContentKind::Image`, e.g. `"png"` or `"jpeg"`.
+    pub image_format: BorrowedUtf8<'a>,
 }
 
 impl<'a> DataTransferContent<'a> {
@@ -94,6 +123,10 @@ impl<'a> DataTransferContent<'a> {
         Self {
             mime_type: BorrowedUtf8::new(mime_type),
             data: BorrowedArray::from_slice(data),
+            kind: DataTransferContentKind::Other,
+            text: BorrowedUtf8::null(),
+            files: BorrowedArray::null(),
+            image_format: BorrowedUtf8::null(),
         }
     }
 
@@ -102,8 +135,25 @@ impl<'a> DataTransferContent<'a> {
         Self {
             mime_type: BorrowedUtf8::null(),
             data: BorrowedArray::null(),
+            kind: DataTransferContentKind::Other,
+            text: BorrowedUtf8::null(),
+            files: BorrowedArray::null(),
+            image_format: BorrowedUtf8::null(),
         }
     }
+
+    /// Calls `f` with the content that borrows from the received `content`.
+    pub fn with_received<R>(content: &ReceivedContent, f: impl FnOnce(DataTransferContent) -> R) -> R {
+        let files = content.files.iter().map(|path| BorrowedUtf8::new(path)).collect::<Vec<_>>();
+        f(DataTransferContent {
+            mime_type: BorrowedUtf8::new(&content.mime_type),
+            data: BorrowedArray::from_slice(&content.data),
+            kind: content.kind,
+            text: content.text().map_or_else(BorrowedUtf8::null, BorrowedUtf8::new),
+            files: BorrowedArray::from_slice(&files),
+            image_format: content.image_format().map_or_else(BorrowedUtf8::null, BorrowedUtf8::new),
+        })
+    }
 }
 
 #[repr(C)]
@@ -119,6 +169,93 @@ impl<'a> From<DataTransferEvent<'a>> for Event<'a> {
     }
 }
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataTransferStatus {
+    /// More chunks will follow.
+    InProgress,
+
+    /// This is the last chunk, and all the data was received.
+    Finished,
+
+    /// This is the last chunk, and the data is incomplete because of an error.
+    Failed,
+
+    /// This is the last chunk, and the data is incomplete because the transfer was cancelled with `application_data_transfer_cancel`.
+    Cancelled,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct DataTransferChunkEvent<'a> {
+    pub transfer_id: DataTransferId,
+
+    /// The serial passed to the paste function that started the transfer, `0` for drag&drop.
+    pub serial: i32,
+
+    pub mime_type: BorrowedUtf8<'a>,
+
+    /// Can be empty, e.g. for the last chunk.
+    pub data: BorrowedArray<'a, u8>,
+
+    /// Total size of the data received so far, including this chunk. The total size of the data is not known in advance.
+    pub bytes_received: u64,
+
+    pub status: DataTransferStatus,
+}
+
+impl<'a> From<DataTransferChunkEvent<'a>> for Event<'a> {
+    fn from(value: DataTransferChunkEvent<'a>) -> Self {
+        Self::DataTransferChunk(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct DataTransferWriteRequestedEvent<'a> {
+    pub transfer_id: DataTransferId,
+    pub data_source: DataSource,
+    pub mime_type: BorrowedUtf8<'a>,
+}
+
+impl<'a> From<DataTransferWriteRequestedEvent<'a>> for Event<'a> {
+    fn from(value: DataTransferWriteRequestedEvent<'a>) -> Self {
+        Self::DataTransferWriteRequested(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataTransferWriteResult {
+    /// The data was queued, and will be written asynchronously.
+    Queued,
+
+    /// Too much data is queued already, and this data was not queued.
+    /// It should be written again after the `DataTransferWriteReady` event.
+    QueueFull,
+
+    /// The transfer is not active anymore, e.g. because the receiver closed it, or it was cancelled.
+    Closed,
+}
+
+impl PanicDefault for DataTransferWriteResult {
+    fn default() -> Self {
+        Self::Closed
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct DataTransferWriteReadyEvent {
+    pub transfer_id: DataTransferId,
+}
+
+impl From<DataTransferWriteReadyEvent> for Event<'_> {
+    fn from(value: DataTransferWriteReadyEvent) -> Self {
+        Self::DataTransferWriteReady(value)
+    }
+}
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct DragAndDropLeaveEvent {
@@ -148,9 +285,15 @@ impl From<DragAndDropFinishedEvent> for Event<'_> {
 #[derive(Debug)]
 pub struct DropPerformedEvent<'a> {
     pub window_id: WindowId,
+
+    /// Null when the drop is received in chunks, or when it failed.
     pub content: DataTransferContent<'a>,
     pub action: DragAndDropAction,
     pub location_in_window: LogicalPoint,
+
+    /// When enabled with `application_set_drop_chunked`, the dropped data is reported with `DataTransferChunk` events
+    /// for this transfer, after this event. `0` otherwise.
+    pub transfer_id: DataTransferId,
 }
 
 impl<'a> From<DropPerformedEvent<'a>> for Event<'a> {
@@ -163,7 +306,11 @@ impl<'a> From<DropPerformedEvent<'a>> for Event<'a> {
 #[derive(Debug)]
 pub struct DataTransferAvailableEvent<'a> {
     pub data_source: DataSource,
+    /// Comma-separated list of the MIME types.
+    ///
+    /// Deprecated: MIME types may contain commas, use `mime_types_list` instead.
     pub mime_types: BorrowedUtf8<'a>,
+    pub mime_types_list: BorrowedArray<'a, BorrowedUtf8<'a>>,
 }
 
 impl<'a> From<DataTransferAvailableEvent<'a>> for Event<'a> {
@@ -172,13 +319,39 @@ impl<'a> From<DataTransferAvailableEvent<'a>> for Event<'a> {
     }
 }
 
-impl<'a> DataTransferAvailableEvent<'a> {
-    #[must_use]
-    pub const fn new(data_source: DataSource, mime_types: &'a str) -> Self {
-        Self {
+impl DataTransferAvailableEvent<'_> {
+    pub fn with<T>(data_source: DataSource, mime_types: &[impl AsRef<str>], f: impl FnOnce(DataTransferAvailableEvent) -> T) -> T {
+        let mime_types_str = mime_types.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(",");
+        let mime_types_list = mime_types.iter().map(|m| BorrowedUtf8::new(m.as_ref())).collect::<Vec<_>>();
+        f(DataTransferAvailableEvent {
             data_source,
-            mime_types: BorrowedUtf8::new(mime_types),
-        }
+            mime_types: BorrowedUtf8::new(&mime_types_str),
+            mime_types_list: BorrowedArray::from_slice(&mime_types_list),
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct ClipboardChangedEvent<'a> {
+    pub data_source: DataSource,
+    /// Empty when the selection was cleared.
+    pub mime_types: BorrowedArray<'a, BorrowedUtf8<'a>>,
+}
+
+impl<'a> From<ClipboardChangedEvent<'a>> for Event<'a> {
+    fn from(value: ClipboardChangedEvent<'a>) -> Self {
+        Self::ClipboardChanged(value)
+    }
+}
+
+impl ClipboardChangedEvent<'_> {
+    pub fn with<T>(data_source: DataSource, mime_types: &[String], f: impl FnOnce(ClipboardChangedEvent) -> T) -> T {
+        let mime_types = mime_types.iter().map(|m| BorrowedUtf8::new(m)).collect::<Vec<_>>();
+        f(ClipboardChangedEvent {
+            data_source,
+            mime_types: BorrowedArray::from_slice(&mime_types),
+        })
     }
 }
 
@@ -584,7 +757,18 @@ impl From<WindowScreenChangeEvent> for Event<'_> {
 #[derive(Debug)]
 pub struct FileChooserResponse<'a> {
     pub request_id: RequestId,
+
+    /// Null if the dialog was cancelled or failed.
     pub newline_separated_files: BorrowedUtf8<'a>,
+
+    /// Optional. Present only if the dialog couldn't be shown, e.g. when neither the file chooser portal nor `zenity`/`kdialog` is available.
+    pub error: BorrowedUtf8<'a>,
+
+    /// Index in `CommonFileDialogParams::filters` of the filter selected by the user, or `-1`.
+    pub current_filter_index: i32,
+
+    /// Values of `CommonFileDialogParams::choices` selected by the user.
+    pub choices: BorrowedArray<'a, FileDialogChoiceSelection<'a>>,
 }
 
 impl<'a> From<FileChooserResponse<'a>> for Event<'a> {
@@ -593,6 +777,241 @@ impl<'a> From<FileChooserResponse<'a>> for Event<'a> {
     }
 }
 
+#[repr(C)]
+#[derive(Debug)]
+pub struct ScreenshotResponse<'a> {
+    pub request_id: RequestId,
+
+    /// URI of the screenshot image file. Null if the request was cancelled or failed.
+    pub uri: BorrowedUtf8<'a>,
+
+    /// Optional. Present only if the screenshot couldn't be taken, e.g. when the screenshot portal is not available.
+    pub error: BorrowedUtf8<'a>,
+}
+
+impl<'a> From<ScreenshotResponse<'a>> for Event<'a> {
+    fn from(value: ScreenshotResponse<'a>) -> Self {
+        Self::ScreenshotResponse(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct ColorPickerResponse<'a> {
+    pub request_id: RequestId,
+
+    /// `false` if the request was cancelled or failed.
+    pub picked: bool,
+
+    /// The picked color, in the sRGB color space. Valid only if `picked` is `true`.
+    pub color: Color,
+
+    /// Optional. Present only if the color couldn't be picked, e.g. when the screenshot portal is not available.
+    pub error: BorrowedUtf8<'a>,
+}
+
+impl<'a> From<ColorPickerResponse<'a>> for Event<'a> {
+    fn from(value: ColorPickerResponse<'a>) -> Self {
+        Self::ColorPickerResponse(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct PreparePrintResponse<'a> {
+    pub request_id: RequestId,
+
+    /// `false` if the dialog was cancelled or failed.
+    pub prepared: bool,
+
+    /// Token to pass to `PrintParams`. Valid only if `prepared` is `true`.
+    pub token: u32,
+
+    /// Valid only if `prepared` is `true`.
+    pub settings: PrintSettings<'a>,
+
+    /// Valid only if `prepared` is `true`.
+    pub page_setup: PrintPageSetup<'a>,
+
+    /// Optional. Present only if the dialog couldn't be shown, e.g. when the print portal is not available.
+    pub error: BorrowedUtf8<'a>,
+}
+
+impl<'a> From<PreparePrintResponse<'a>> for Event<'a> {
+    fn from(value: PreparePrintResponse<'a>) -> Self {
+        Self::PreparePrintResponse(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct PrintResponse<'a> {
+    pub request_id: RequestId,
+
+    /// `false` if printing was cancelled or failed.
+    pub printed: bool,
+
+    /// Optional. Present only if the document couldn't be printed.
+    pub error: BorrowedUtf8<'a>,
+}
+
+impl<'a> From<PrintResponse<'a>> for Event<'a> {
+    fn from(value: PrintResponse<'a>) -> Self {
+        Self::PrintResponse(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct InhibitResponse<'a> {
+    pub request_id: RequestId,
+
+    /// `false` if the inhibition failed.
+    pub inhibited: bool,
+
+    /// Optional. Present only if the inhibition failed, e.g. when the inhibit portal is not available.
+    pub error: BorrowedUtf8<'a>,
+}
+
+impl<'a> From<InhibitResponse<'a>> for Event<'a> {
+    fn from(value: InhibitResponse<'a>) -> Self {
+        Self::InhibitResponse(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct BackgroundResponse<'a> {
+    pub request_id: RequestId,
+
+    /// Whether the application is allowed to keep running after its last window is closed.
+    pub run_in_background: bool,
+
+    /// Whether the application will be started automatically at login.
+    pub autostart: bool,
+
+    /// Optional. Present only if the request failed, e.g. when the background portal is not available.
+    pub error: BorrowedUtf8<'a>,
+}
+
+impl<'a> From<BackgroundResponse<'a>> for Event<'a> {
+    fn from(value: BackgroundResponse<'a>) -> Self {
+        Self::BackgroundResponse(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct SecretResponse<'a> {
+    pub request_id: RequestId,
+
+    /// Items found by `application_secret_lookup` and `application_secret_search`. Empty for the other requests, or on failure.
+    pub items: BorrowedArray<'a, SecretItem<'a>>,
+
+    pub error_kind: SecretErrorKind,
+
+    /// Optional. Present only if the request failed.
+    pub error: BorrowedUtf8<'a>,
+}
+
+impl<'a> From<SecretResponse<'a>> for Event<'a> {
+    fn from(value: SecretResponse<'a>) -> Self {
+        Self::SecretResponse(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    Running,
+    QueryEnd,
+    Ending,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SessionStateChangedEvent {
+    pub session_state: SessionState,
+    pub screensaver_active: bool,
+}
+
+impl From<SessionStateChangedEvent> for Event<'_> {
+    fn from(value: SessionStateChangedEvent) -> Self {
+        Self::SessionStateChanged(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkConnectivity {
+    /// The host is not configured with a route to the Internet.
+    Local,
+
+    /// The host is connected to a network, but can't reach the full Internet.
+    Limited,
+
+    /// The host is behind a captive portal, and can't reach the full Internet.
+    CaptivePortal,
+
+    /// The host is connected to a network, and can reach the full Internet.
+    Full,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkAvailabilityChangedEvent {
+    /// Whether the network is considered available, i.e. the host has a default route.
+    pub available: bool,
+
+    /// Whether the network is considered metered, i.e. the data usage should be limited.
+    pub metered: bool,
+
+    pub connectivity: NetworkConnectivity,
+}
+
+impl From<NetworkAvailabilityChangedEvent> for Event<'_> {
+    fn from(value: NetworkAvailabilityChangedEvent) -> Self {
+        Self::NetworkAvailabilityChanged(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerSaverChangedEvent {
+    pub enabled: bool,
+}
+
+impl From<PowerSaverChangedEvent> for Event<'_> {
+    fn from(value: PowerSaverChangedEvent) -> Self {
+        Self::PowerSaverChanged(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OnBatteryChangedEvent {
+    pub on_battery: bool,
+}
+
+impl From<OnBatteryChangedEvent> for Event<'_> {
+    fn from(value: OnBatteryChangedEvent) -> Self {
+        Self::OnBatteryChanged(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LowMemoryWarningEvent {
+    /// Severity of the memory shortage: `50` (low), `100` (medium) or `255` (critical).
+    pub level: u8,
+}
+
+impl From<LowMemoryWarningEvent> for Event<'_> {
+    fn from(value: LowMemoryWarningEvent) -> Self {
+        Self::LowMemoryWarning(value)
+    }
+}
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct ActivationTokenResponse<'a> {
@@ -616,6 +1035,31 @@ impl<'a> From<ActivationTokenResponse<'a>> for Event<'a> {
     }
 }
 
+#[repr(C)]
+#[derive(Debug)]
+pub struct WindowCaptureFrameResponse<'a> {
+    pub request_id: RequestId,
+    pub window_id: WindowId,
+
+    /// The size of the captured frame. Zero if the frame couldn't be captured.
+    pub physical_size: PhysicalSize,
+
+    /// The window scale when the frame was rendered.
+    pub scale: f64,
+
+    /// RGBA, 4 bytes per pixel, top row first, without any padding between the rows. Null if the frame couldn't be captured.
+    pub pixels: BorrowedArray<'a, u8>,
+
+    /// Optional. Present only if the frame couldn't be captured, e.g. when the window was closed before rendering it.
+    pub error: BorrowedUtf8<'a>,
+}
+
+impl<'a> From<WindowCaptureFrameResponse<'a>> for Event<'a> {
+    fn from(value: WindowCaptureFrameResponse<'a>) -> Self {
+        Self::WindowCaptureFrameResponse(value)
+    }
+}
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct NotificationShownEvent {
@@ -660,6 +1104,21 @@ impl<'a> From<NotificationClosedEvent<'a>> for Event<'a> {
     }
 }
 
+#[repr(C)]
+#[derive(Debug)]
+pub struct AppMenuItemActivatedEvent {
+    pub item_id: AppMenuItemId,
+
+    /// The new state, for the check mark and radio items. The application should update the menu accordingly.
+    pub toggle_state: AppMenuItemToggleState,
+}
+
+impl From<AppMenuItemActivatedEvent> for Event<'_> {
+    fn from(value: AppMenuItemActivatedEvent) -> Self {
+        Self::AppMenuItemActivated(value)
+    }
+}
+
 #[repr(C)]
 #[derive(Debug)]
 pub enum Event<'a> {
@@ -672,11 +1131,46 @@ pub enum Event<'a> {
 
     DisplayConfigurationChange,
 
+    /// Reported by the session monitor of the inhibit portal.
+    SessionStateChanged(SessionStateChangedEvent),
+
+    /// The session is about to end, e.g. because the user is logging out.
+    /// Return `true` from the event handler to answer later with `application_session_query_end_response`,
+    /// otherwise the answer is sent immediately.
+    SessionQueryEnd,
+
+    /// The session is ending, and the application will be terminated soon.
+    SessionEnding,
+
+    /// Reported after `application_monitors_enable`, and then whenever the network status changes.
+    NetworkAvailabilityChanged(NetworkAvailabilityChangedEvent),
+
+    /// Reported after `application_monitors_enable`, and then whenever the power saver mode is toggled.
+    PowerSaverChanged(PowerSaverChangedEvent),
+
+    /// Reported after `application_monitors_enable`, and then whenever the system switches between battery and external power.
+    OnBatteryChanged(OnBatteryChangedEvent),
+
+    /// The system is running low on memory, and the application should free the memory it doesn't need.
+    /// Reported only after `application_monitors_enable`.
+    LowMemoryWarning(LowMemoryWarningEvent),
+
     DesktopSettingChange(FfiDesktopSetting<'a>),
 
     /// Data received from clipboard or primary selection. For drag&drop, see `DropPerformed`.
     DataTransfer(DataTransferEvent<'a>),
 
+    /// Data received from clipboard or primary selection, when pasting with `application_clipboard_paste_chunked`
+    /// or `application_primary_selection_paste_chunked`.
+    DataTransferChunk(DataTransferChunkEvent<'a>),
+
+    /// The data for the transfer should be provided with `application_data_transfer_write`, followed by
+    /// `application_data_transfer_write_end`. Reported when `get_data_transfer_data` returned `streamed = true`.
+    DataTransferWriteRequested(DataTransferWriteRequestedEvent<'a>),
+
+    /// More data can be queued for the transfer, after `application_data_transfer_write` returned `QueueFull`.
+    DataTransferWriteReady(DataTransferWriteReadyEvent),
+
     /// Drag&drop targeting our application left the specified window.
     DragAndDropLeave(DragAndDropLeaveEvent),
 
@@ -694,13 +1188,35 @@ pub enum Event<'a> {
     /// Data transfer for data from our application was canceled
     DataTransferCancelled(DataTransferCancelledEvent),
 
+    /// Reported through the data control protocol for clipboard and primary selection, regardless of the keyboard focus.
+    ClipboardChanged(ClipboardChangedEvent<'a>),
+
     FileChooserResponse(FileChooserResponse<'a>),
 
+    ScreenshotResponse(ScreenshotResponse<'a>),
+
+    ColorPickerResponse(ColorPickerResponse<'a>),
+
+    PreparePrintResponse(PreparePrintResponse<'a>),
+
+    PrintResponse(PrintResponse<'a>),
+
+    InhibitResponse(InhibitResponse<'a>),
+
+    BackgroundResponse(BackgroundResponse<'a>),
+
+    SecretResponse(SecretResponse<'a>),
+
     ActivationTokenResponse(ActivationTokenResponse<'a>),
 
+    /// The frame requested with `window_capture_frame`.
+    WindowCaptureFrameResponse(WindowCaptureFrameResponse<'a>),
+
     NotificationShown(NotificationShownEvent),
     NotificationClosed(NotificationClosedEvent<'a>),
 
+    AppMenuItemActivated(AppMenuItemActivatedEvent),
+
     /// Modifier keys (e.g Ctrl, Shift, etc) are never reported. Use `ModifiersChanged` for them.
     KeyDown(KeyDownEvent<'a>),
 
@@ -9,10 +9,21 @@ pub struct TransferDataGetter {
     pub ffi_dealloc: FfiObjDealloc,
 }
 
+pub enum TransferData {
+    Data(Vec<u8>),
+
+    /// The data will be provided in chunks, see `ApplicationState::write_to_pipe_chunked`.
+    Streamed,
+}
+
 impl TransferDataGetter {
-    pub fn get(&self, clipboard_type: DataSource, mime_type: &str) -> Option<Vec<u8>> {
+    pub fn get(&self, clipboard_type: DataSource, mime_type: &str) -> Option<TransferData> {
         let ffi_response = (self.ffi_get)(clipboard_type, BorrowedUtf8::new(mime_type));
-        let ret = ffi_response.data.as_optional_slice().map(Into::into);
+        let ret = if ffi_response.streamed {
+            Some(TransferData::Streamed)
+        } else {
+            ffi_response.data.as_optional_slice().map(|data| TransferData::Data(data.into()))
+        };
         (self.ffi_dealloc)(ffi_response.obj_id);
         ret
     }
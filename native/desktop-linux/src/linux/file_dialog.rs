@@ -1,78 +1,303 @@
-use ashpd::{WindowIdentifier, desktop::file_chooser};
+use anyhow::{Context as _, bail};
+use ashpd::{
+    FilePath, WindowIdentifier,
+    desktop::file_chooser::{Choice, FileFilter, OpenFileOptions, SaveFileOptions},
+};
+use futures_lite::StreamExt;
+use log::warn;
+use std::{collections::HashMap, path::PathBuf};
+use zbus::zvariant::{Endian, OwnedObjectPath, OwnedValue, Value, serialized::Context, to_bytes};
 
 use crate::linux::application::Application;
 use crate::linux::{
-    file_dialog_api::{CommonFileDialogParams, OpenFileDialogParams, SaveFileDialogParams},
+    file_dialog_api::{CommonFileDialogParams, FileDialogChoice, FileDialogFilter, OpenFileDialogParams, SaveFileDialogParams},
+    file_dialog_fallback::{FallbackDialogKind, FallbackDialogParams, show_fallback_file_dialog},
     string_utils::join_str_iter,
 };
 
-impl OpenFileDialogParams {
-    fn apply(&self, request: file_chooser::OpenFileRequest) -> file_chooser::OpenFileRequest {
-        request.directory(self.select_directories).multiple(self.allows_multiple_selection)
+const PORTAL_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const FILE_CHOOSER_INTERFACE: &str = "org.freedesktop.portal.FileChooser";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+
+/// `FilePath::new` is private, but `FilePath` can be deserialized from nul-terminated bytes.
+fn file_path(path: &str) -> anyhow::Result<FilePath> {
+    let ctxt = Context::new_dbus(Endian::Little, 0);
+    let mut bytes = path.as_bytes().to_vec();
+    bytes.push(0);
+    let (file_path, _size) = to_bytes(ctxt, &bytes)?.deserialize()?;
+    Ok(file_path)
+}
+
+impl FileDialogFilter<'_> {
+    fn to_file_filter(&self) -> anyhow::Result<FileFilter> {
+        let mut filter = FileFilter::new(self.name.get("FileDialogFilter.name")?);
+        for glob in self.globs.as_optional_slice().unwrap_or_default() {
+            filter = filter.glob(glob.get("FileDialogFilter.globs")?);
+        }
+        for mime_type in self.mime_types.as_optional_slice().unwrap_or_default() {
+            filter = filter.mimetype(mime_type.get("FileDialogFilter.mime_types")?);
+        }
+        Ok(filter)
     }
 }
 
-impl SaveFileDialogParams<'_> {
-    fn apply(&self, mut request: file_chooser::SaveFileRequest) -> anyhow::Result<file_chooser::SaveFileRequest> {
-        if let Some(name_field_string_value) = self.get_name_field_string_value()? {
-            request = request.current_name(name_field_string_value);
+impl FileDialogChoice<'_> {
+    fn to_choice(&self) -> anyhow::Result<Choice> {
+        let mut choice = Choice::new(
+            self.id.get("FileDialogChoice.id")?,
+            self.label.get("FileDialogChoice.label")?,
+            self.initial_selection.get("FileDialogChoice.initial_selection")?,
+        );
+        for option in self.options.as_optional_slice().unwrap_or_default() {
+            choice = choice.insert(
+                option.id.get("FileDialogChoiceOption.id")?,
+                option.label.get("FileDialogChoiceOption.label")?,
+            );
         }
-        Ok(request)
+        Ok(choice)
     }
 }
 
+#[derive(Debug)]
+enum FileChooserOptions {
+    Open(OpenFileOptions),
+    Save(SaveFileOptions),
+}
+
+/// The portal request is sent manually instead of with `ashpd::desktop::file_chooser::OpenFileRequest`,
+/// because `ashpd` doesn't expose the `current_filter` from the response.
+#[derive(Debug)]
+pub struct FileChooserRequest {
+    title: String,
+    options: FileChooserOptions,
+    filter_names: Vec<String>,
+
+    /// The portal options can't be read back, so the parameters for the fallback are stored separately.
+    fallback_params: FallbackDialogParams,
+}
+
+#[derive(Debug, Default)]
+pub struct FileChooserResult {
+    pub newline_separated_files: String,
+
+    /// Index of the filter (in the requested filters) selected by the user.
+    pub current_filter_index: Option<usize>,
+
+    /// Pairs of choice ID and the selected value.
+    pub choices: Vec<(String, String)>,
+}
+
+struct FilterParams {
+    filters: Vec<FileFilter>,
+    current_filter: Option<FileFilter>,
+    filter_names: Vec<String>,
+    choices: Vec<Choice>,
+}
+
 impl CommonFileDialogParams<'_> {
-    pub fn create_open_request(&self, open_params: &OpenFileDialogParams) -> anyhow::Result<file_chooser::OpenFileRequest> {
-        let mut request = file_chooser::SelectedFiles::open_file().modal(self.modal);
-        if let Some(title) = self.get_title()? {
-            request = request.title(title);
-        }
-        if let Some(accept_label) = self.get_accept_label()? {
-            request = request.accept_label(accept_label);
-        }
-        if let Some(current_folder) = self.get_current_folder()? {
-            request = request.current_folder(current_folder)?;
-        }
-        Ok(open_params.apply(request))
+    fn filter_params(&self) -> anyhow::Result<FilterParams> {
+        let filters = self
+            .get_filters()
+            .iter()
+            .map(FileDialogFilter::to_file_filter)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let current_filter = self.get_current_filter_index().and_then(|index| filters.get(index).cloned());
+        let filter_names = filters.iter().map(|filter| filter.label().to_owned()).collect();
+        let choices = self
+            .get_choices()
+            .iter()
+            .map(FileDialogChoice::to_choice)
+            .collect::<anyhow::Result<_>>()?;
+        Ok(FilterParams {
+            filters,
+            current_filter,
+            filter_names,
+            choices,
+        })
     }
 
-    pub fn create_save_request(&self, save_params: &SaveFileDialogParams) -> anyhow::Result<file_chooser::SaveFileRequest> {
-        let mut request = file_chooser::SelectedFiles::save_file().modal(self.modal);
-        if let Some(title) = self.get_title()? {
-            request = request.title(title);
-        }
-        if let Some(accept_label) = self.get_accept_label()? {
-            request = request.accept_label(accept_label);
-        }
-        if let Some(current_folder) = self.get_current_folder()? {
-            request = request.current_folder(current_folder)?;
-        }
-        save_params.apply(request)
+    pub fn create_open_request(&self, open_params: &OpenFileDialogParams) -> anyhow::Result<FileChooserRequest> {
+        let filter_params = self.filter_params()?;
+        let title = self.get_title()?.unwrap_or_default().to_owned();
+        let fallback_params = FallbackDialogParams {
+            title: title.clone(),
+            kind: FallbackDialogKind::Open {
+                directory: open_params.select_directories,
+                multiple: open_params.allows_multiple_selection,
+            },
+            current_folder: self.get_current_folder()?.map(PathBuf::from),
+            filters: filter_params.filters.clone(),
+            choices: filter_params.choices.clone(),
+        };
+        let options = OpenFileOptions::default()
+            .set_modal(self.modal)
+            .set_accept_label(self.get_accept_label()?)
+            .set_current_folder(self.get_current_folder()?.map(file_path).transpose()?)
+            .set_directory(open_params.select_directories)
+            .set_multiple(open_params.allows_multiple_selection)
+            .set_filters(filter_params.filters)
+            .set_current_filter(filter_params.current_filter)
+            .set_choices(filter_params.choices);
+        Ok(FileChooserRequest {
+            title,
+            options: FileChooserOptions::Open(options),
+            filter_names: filter_params.filter_names,
+            fallback_params,
+        })
+    }
+
+    pub fn create_save_request(&self, save_params: &SaveFileDialogParams) -> anyhow::Result<FileChooserRequest> {
+        let filter_params = self.filter_params()?;
+        let title = self.get_title()?.unwrap_or_default().to_owned();
+        let fallback_params = FallbackDialogParams {
+            title: title.clone(),
+            kind: FallbackDialogKind::Save {
+                current_name: save_params.get_name_field_string_value()?.map(ToOwned::to_owned),
+            },
+            current_folder: self.get_current_folder()?.map(PathBuf::from),
+            filters: filter_params.filters.clone(),
+            choices: filter_params.choices.clone(),
+        };
+        let options = SaveFileOptions::default()
+            .set_modal(self.modal)
+            .set_accept_label(self.get_accept_label()?)
+            .set_current_folder(self.get_current_folder()?.map(file_path).transpose()?)
+            .set_current_name(save_params.get_name_field_string_value()?)
+            .set_filters(filter_params.filters)
+            .set_current_filter(filter_params.current_filter)
+            .set_choices(filter_params.choices);
+        Ok(FileChooserRequest {
+            title,
+            options: FileChooserOptions::Save(options),
+            filter_names: filter_params.filter_names,
+            fallback_params,
+        })
     }
 }
 
-fn convert_file_chooser_response(response: Result<file_chooser::SelectedFiles, ashpd::Error>) -> anyhow::Result<String> {
-    let files = response?;
-    let newline_separated_files = join_str_iter(files.uris().iter().map(ashpd::Uri::as_str), "\r\n");
-    Ok(newline_separated_files)
+fn convert_file_chooser_response(mut results: HashMap<String, OwnedValue>, filter_names: &[String]) -> anyhow::Result<FileChooserResult> {
+    let uris = match results.remove("uris") {
+        Some(uris) => Vec::<String>::try_from(Value::from(uris))?,
+        None => Vec::new(),
+    };
+    let choices = match results.remove("choices") {
+        Some(choices) => Vec::<(String, String)>::try_from(Value::from(choices))?,
+        None => Vec::new(),
+    };
+    let current_filter_index = match results.remove("current_filter") {
+        Some(current_filter) => {
+            let (name, _patterns) = <(String, Vec<(u32, String)>)>::try_from(Value::from(current_filter))?;
+            filter_names.iter().position(|filter_name| *filter_name == name)
+        }
+        None => None,
+    };
+    Ok(FileChooserResult {
+        newline_separated_files: join_str_iter(uris.iter().map(String::as_str), "\r\n"),
+        current_filter_index,
+        choices,
+    })
 }
 
-pub async fn show_open_file_dialog_impl(
+/// Returned when nothing implements the `FileChooser` portal, e.g. on minimal window managers without `xdg-desktop-portal-gtk`.
+fn is_portal_unavailable(e: &zbus::Error) -> bool {
+    const UNAVAILABLE_ERRORS: [&str; 4] = [
+        "org.freedesktop.DBus.Error.ServiceUnknown",
+        "org.freedesktop.DBus.Error.UnknownMethod",
+        "org.freedesktop.DBus.Error.UnknownInterface",
+        "org.freedesktop.DBus.Error.UnknownObject",
+    ];
+    match e {
+        zbus::Error::MethodError(name, _, _) => UNAVAILABLE_ERRORS.contains(&name.as_str()),
+        zbus::Error::FDO(e) => matches!(
+            **e,
+            zbus::fdo::Error::ServiceUnknown(_)
+                | zbus::fdo::Error::UnknownMethod(_)
+                | zbus::fdo::Error::UnknownInterface(_)
+                | zbus::fdo::Error::UnknownObject(_)
+        ),
+        _ => false,
+    }
+}
+
+async fn call_file_chooser(
+    connection: &zbus::Connection,
     identifier: Option<WindowIdentifier>,
-    request: file_chooser::OpenFileRequest,
-) -> anyhow::Result<String> {
-    let connection = Application::dbus_connection().await?;
-    let open_file_request = request.connection(Some(connection)).identifier(identifier);
-    let response = open_file_request.send().await?.response();
-    convert_file_chooser_response(response)
+    request: &FileChooserRequest,
+) -> zbus::Result<OwnedObjectPath> {
+    let parent_window = identifier.map(|identifier| identifier.to_string()).unwrap_or_default();
+    let reply = match &request.options {
+        FileChooserOptions::Open(options) => {
+            connection
+                .call_method(
+                    Some(PORTAL_DESTINATION),
+                    PORTAL_PATH,
+                    Some(FILE_CHOOSER_INTERFACE),
+                    "OpenFile",
+                    &(parent_window, request.title.as_str(), options),
+                )
+                .await?
+        }
+        FileChooserOptions::Save(options) => {
+            connection
+                .call_method(
+                    Some(PORTAL_DESTINATION),
+                    PORTAL_PATH,
+                    Some(FILE_CHOOSER_INTERFACE),
+                    "SaveFile",
+                    &(parent_window, request.title.as_str(), options),
+                )
+                .await?
+        }
+    };
+    reply.body().deserialize()
 }
 
-pub async fn show_save_file_dialog_impl(
+/// Returns `None` if the dialog was cancelled by the user.
+pub async fn show_file_dialog_impl(
     identifier: Option<WindowIdentifier>,
-    request: file_chooser::SaveFileRequest,
-) -> anyhow::Result<String> {
-    let connection = Application::dbus_connection().await?;
-    let open_file_request = request.connection(Some(connection)).identifier(identifier);
-    let response = open_file_request.send().await?.response();
-    convert_file_chooser_response(response)
+    request: FileChooserRequest,
+) -> anyhow::Result<Option<FileChooserResult>> {
+    let connection = match Application::dbus_connection().await {
+        Ok(connection) => connection,
+        Err(e) => {
+            warn!("DBus session connection is not available ({e}), falling back to zenity/kdialog");
+            return show_fallback_file_dialog(&request.fallback_params).await;
+        }
+    };
+    let unique_name = connection.unique_name().context("No unique name for the DBus connection")?;
+    let sender_path_part = unique_name.trim_start_matches(':').replace('.', "_");
+
+    // Start listening before making the call, so that the response can't be missed.
+    let rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(PORTAL_DESTINATION)?
+        .interface(REQUEST_INTERFACE)?
+        .member("Response")?
+        .path_namespace(format!("{PORTAL_PATH}/request/{sender_path_part}"))?
+        .build();
+    let mut stream = zbus::MessageStream::for_match_rule(rule, &connection, None).await?;
+
+    let handle = match call_file_chooser(&connection, identifier, &request).await {
+        Ok(handle) => handle,
+        Err(e) if is_portal_unavailable(&e) => {
+            warn!("File chooser portal is not available ({e}), falling back to zenity/kdialog");
+            return show_fallback_file_dialog(&request.fallback_params).await;
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    while let Some(message) = stream.next().await {
+        let message = message?;
+        if message.header().path().map(zbus::zvariant::ObjectPath::as_str) != Some(handle.as_str()) {
+            continue;
+        }
+        let (response, results): (u32, HashMap<String, OwnedValue>) = message.body().deserialize()?;
+        match response {
+            0 => return convert_file_chooser_response(results, &request.filter_names).map(Some),
+            1 => return Ok(None),
+            _ => bail!("File dialog interaction was ended in some other way"),
+        }
+    }
+    bail!("No response from the file chooser portal")
 }
@@ -1,4 +1,51 @@
-use desktop_common::ffi_utils::BorrowedUtf8;
+use desktop_common::ffi_utils::{BorrowedArray, BorrowedUtf8};
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct FileDialogFilter<'a> {
+    /// User-visible name of the filter, e.g. `"Images"`.
+    pub name: BorrowedUtf8<'a>,
+
+    /// Optional. Case-sensitive glob patterns, e.g. `"*.png"`.
+    pub globs: BorrowedArray<'a, BorrowedUtf8<'a>>,
+
+    /// Optional. MIME types, e.g. `"image/png"` or `"image/*"`.
+    pub mime_types: BorrowedArray<'a, BorrowedUtf8<'a>>,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct FileDialogChoiceOption<'a> {
+    pub id: BorrowedUtf8<'a>,
+
+    /// User-visible text.
+    pub label: BorrowedUtf8<'a>,
+}
+
+/// Extra control in the file dialog: a combo box, or a checkbox if there are no `options`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FileDialogChoice<'a> {
+    pub id: BorrowedUtf8<'a>,
+
+    /// User-visible text.
+    pub label: BorrowedUtf8<'a>,
+
+    /// Optional.
+    pub options: BorrowedArray<'a, FileDialogChoiceOption<'a>>,
+
+    /// ID of the selected option, or `"true"`/`"false"` for a checkbox.
+    pub initial_selection: BorrowedUtf8<'a>,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct FileDialogChoiceSelection<'a> {
+    pub id: BorrowedUtf8<'a>,
+
+    /// ID of the selected option, or `"true"`/`"false"` for a checkbox.
+    pub value: BorrowedUtf8<'a>,
+}
 
 #[repr(C)]
 pub struct CommonFileDialogParams<'a> {
@@ -6,6 +53,15 @@ pub struct CommonFileDialogParams<'a> {
     pub title: BorrowedUtf8<'a>,
     pub accept_label: BorrowedUtf8<'a>,
     pub current_folder: BorrowedUtf8<'a>,
+
+    /// Optional.
+    pub filters: BorrowedArray<'a, FileDialogFilter<'a>>,
+
+    /// Index in `filters` of the filter selected by default. Ignored if negative or out of bounds.
+    pub current_filter_index: i32,
+
+    /// Optional.
+    pub choices: BorrowedArray<'a, FileDialogChoice<'a>>,
 }
 
 impl CommonFileDialogParams<'_> {
@@ -20,6 +76,18 @@ impl CommonFileDialogParams<'_> {
     pub(crate) fn get_current_folder(&self) -> anyhow::Result<Option<&str>> {
         self.current_folder.get_optional("CommonFileDialogParams.current_folder")
     }
+
+    pub(crate) fn get_filters(&self) -> &[FileDialogFilter<'_>] {
+        self.filters.as_optional_slice().unwrap_or_default()
+    }
+
+    pub(crate) fn get_current_filter_index(&self) -> Option<usize> {
+        usize::try_from(self.current_filter_index).ok()
+    }
+
+    pub(crate) fn get_choices(&self) -> &[FileDialogChoice<'_>] {
+        self.choices.as_optional_slice().unwrap_or_default()
+    }
 }
 
 #[repr(C)]
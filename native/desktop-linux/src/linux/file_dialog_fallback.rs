@@ -0,0 +1,359 @@
+use crate::linux::{
+    file_dialog::FileChooserResult,
+    string_utils::{file_uri, join_str_iter},
+};
+use anyhow::bail;
+use ashpd::desktop::file_chooser::{Choice, FileFilter};
+use log::debug;
+use std::{
+    ffi::OsStr,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+#[derive(Debug, Clone, Copy)]
+enum FallbackTool {
+    Zenity,
+    KDialog,
+}
+
+impl FallbackTool {
+    const fn executable_name(self) -> &'static str {
+        match self {
+            Self::Zenity => "zenity",
+            Self::KDialog => "kdialog",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum FallbackDialogKind {
+    Open { directory: bool, multiple: bool },
+    Save { current_name: Option<String> },
+}
+
+/// Parameters of the file dialog that can be passed to `zenity` or `kdialog`.
+#[derive(Debug)]
+pub struct FallbackDialogParams {
+    pub title: String,
+    pub kind: FallbackDialogKind,
+    pub current_folder: Option<PathBuf>,
+    pub filters: Vec<FileFilter>,
+    pub choices: Vec<Choice>,
+}
+
+impl FallbackDialogParams {
+    /// Neither tool supports MIME type filters, so only the glob patterns are used.
+    fn glob_filters(&self) -> impl Iterator<Item = (&str, Vec<&str>)> {
+        self.filters
+            .iter()
+            .map(|filter| (filter.label(), filter.pattern_filters()))
+            .filter(|(_label, globs)| !globs.is_empty())
+    }
+
+    fn start_path(&self) -> Option<PathBuf> {
+        match (&self.kind, &self.current_folder) {
+            (FallbackDialogKind::Save { current_name: Some(name) }, Some(folder)) => Some(folder.join(name)),
+            (FallbackDialogKind::Save { current_name: Some(name) }, None) => Some(PathBuf::from(name)),
+            (_, Some(folder)) => Some(folder.clone()),
+            (_, None) => None,
+        }
+    }
+
+    fn zenity_args(&self) -> Vec<String> {
+        let mut args = vec!["--file-selection".to_owned()];
+        if !self.title.is_empty() {
+            args.push(format!("--title={}", self.title));
+        }
+        match &self.kind {
+            FallbackDialogKind::Open { directory, multiple } => {
+                if *directory {
+                    args.push("--directory".to_owned());
+                }
+                if *multiple {
+                    args.push("--multiple".to_owned());
+                    args.push("--separator=\n".to_owned());
+                }
+            }
+            FallbackDialogKind::Save { .. } => args.push("--save".to_owned()),
+        }
+        if let Some(start_path) = self.start_path() {
+            let mut filename = start_path.to_string_lossy().into_owned();
+            // Without the trailing slash, the last path component is used as the preselected file name
+            if matches!(self.kind, FallbackDialogKind::Open { .. }) && !filename.ends_with('/') {
+                filename.push('/');
+            }
+            args.push(format!("--filename={filename}"));
+        }
+        for (label, globs) in self.glob_filters() {
+            args.push(format!("--file-filter={label} | {}", globs.join(" ")));
+        }
+        args
+    }
+
+    fn kdialog_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if !self.title.is_empty() {
+            args.push("--title".to_owned());
+            args.push(self.title.clone());
+        }
+        let mut accepts_filter = true;
+        match &self.kind {
+            FallbackDialogKind::Open { directory: true, .. } => {
+                args.push("--getexistingdirectory".to_owned());
+                accepts_filter = false;
+            }
+            FallbackDialogKind::Open {
+                directory: false,
+                multiple,
+            } => {
+                args.push("--getopenfilename".to_owned());
+                if *multiple {
+                    args.push("--multiple".to_owned());
+                    args.push("--separate-output".to_owned());
+                }
+            }
+            FallbackDialogKind::Save { .. } => args.push("--getsavefilename".to_owned()),
+        }
+        let start_path = self
+            .start_path()
+            .map_or_else(|| ".".to_owned(), |p| p.to_string_lossy().into_owned());
+        args.push(start_path);
+        if accepts_filter {
+            let filters = self
+                .glob_filters()
+                .map(|(label, globs)| format!("{label} ({})", globs.join(" ")))
+                .collect::<Vec<_>>();
+            if !filters.is_empty() {
+                args.push(filters.join("|"));
+            }
+        }
+        args
+    }
+}
+
+fn find_executable(name: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Prefers `kdialog` on KDE Plasma, and `zenity` elsewhere.
+fn find_tool() -> Option<(FallbackTool, PathBuf)> {
+    let is_kde = std::env::var("XDG_CURRENT_DESKTOP").is_ok_and(|desktop| desktop.split(':').any(|d| d.eq_ignore_ascii_case("KDE")));
+    let tools = if is_kde {
+        [FallbackTool::KDialog, FallbackTool::Zenity]
+    } else {
+        [FallbackTool::Zenity, FallbackTool::KDialog]
+    };
+    tools
+        .into_iter()
+        .find_map(|tool| find_executable(tool.executable_name()).map(|path| (tool, path)))
+}
+
+fn convert_selected_files(stdout: &[u8], params: &FallbackDialogParams) -> FileChooserResult {
+    let uris = stdout
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| file_uri(Path::new(OsStr::from_bytes(line))))
+        .collect::<Vec<_>>();
+    FileChooserResult {
+        newline_separated_files: join_str_iter(uris.iter().map(String::as_str), "\r\n"),
+        // Not reported by the tools
+        current_filter_index: None,
+        // Not supported by the tools, so the initial values are reported
+        choices: params
+            .choices
+            .iter()
+            .map(|choice| (choice.id().to_owned(), choice.initial_selection().to_owned()))
+            .collect(),
+    }
+}
+
+/// Shows the dialog using `zenity` or `kdialog`, for environments where the file chooser portal is not available.
+///
+/// Returns `None` if the dialog was cancelled by the user.
+pub async fn show_fallback_file_dialog(params: &FallbackDialogParams) -> anyhow::Result<Option<FileChooserResult>> {
+    let Some((tool, executable)) = find_tool() else {
+        bail!("Cannot show the file dialog: the file chooser portal is not available, and neither zenity nor kdialog is installed");
+    };
+    let args = match tool {
+        FallbackTool::Zenity => params.zenity_args(),
+        FallbackTool::KDialog => params.kdialog_args(),
+    };
+    debug!("Showing the file dialog with {} {args:?}", executable.display());
+    let output = tokio::task::spawn_blocking(move || Command::new(executable).args(args).output()).await??;
+    convert_output(tool, &output, params)
+}
+
+fn convert_output(tool: FallbackTool, output: &Output, params: &FallbackDialogParams) -> anyhow::Result<Option<FileChooserResult>> {
+    match output.status.code() {
+        Some(0) => Ok(Some(convert_selected_files(&output.stdout, params))),
+        // Both tools exit with 1 when the dialog is cancelled
+        Some(1) => Ok(None),
+        _ => bail!(
+            "{} failed with {}: {}",
+            tool.executable_name(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn params(kind: FallbackDialogKind) -> FallbackDialogParams {
+        FallbackDialogParams {
+            title: "Pick".to_owned(),
+            kind,
+            current_folder: Some(PathBuf::from("/home/user/docs")),
+            filters: vec![
+                FileFilter::new("Images").glob("*.png").glob("*.jpg").mimetype("image/gif"),
+                FileFilter::new("Only MIME").mimetype("text/plain"),
+                FileFilter::new("All").glob("*"),
+            ],
+            choices: vec![Choice::boolean("recursive", "Recursive", true)],
+        }
+    }
+
+    const OPEN_FILE: FallbackDialogKind = FallbackDialogKind::Open {
+        directory: false,
+        multiple: false,
+    };
+
+    const OPEN_FILES: FallbackDialogKind = FallbackDialogKind::Open {
+        directory: false,
+        multiple: true,
+    };
+
+    const OPEN_DIRECTORY: FallbackDialogKind = FallbackDialogKind::Open {
+        directory: true,
+        multiple: false,
+    };
+
+    fn save(current_name: Option<&str>) -> FallbackDialogKind {
+        FallbackDialogKind::Save {
+            current_name: current_name.map(str::to_owned),
+        }
+    }
+
+    fn output(code: i32, stdout: &str, stderr: &str) -> Output {
+        Output {
+            status: ExitStatus::from_raw(code << 8),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_zenity_args() {
+        let filters = ["--file-filter=Images | *.png *.jpg", "--file-filter=All | *"];
+        let cases: &[(FallbackDialogKind, &[&str])] = &[
+            (OPEN_FILE, &["--filename=/home/user/docs/"]),
+            (OPEN_FILES, &["--multiple", "--separator=\n", "--filename=/home/user/docs/"]),
+            (OPEN_DIRECTORY, &["--directory", "--filename=/home/user/docs/"]),
+            (save(Some("a b.txt")), &["--save", "--filename=/home/user/docs/a b.txt"]),
+            (save(None), &["--save", "--filename=/home/user/docs"]),
+        ];
+        for (kind, expected) in cases {
+            let mut expected_args = vec!["--file-selection", "--title=Pick"];
+            expected_args.extend_from_slice(expected);
+            expected_args.extend_from_slice(&filters);
+            assert_eq!(params(kind.clone()).zenity_args(), expected_args, "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn test_zenity_args_without_folder() {
+        let mut params = params(save(Some("new.txt")));
+        params.title = String::new();
+        params.current_folder = None;
+        params.filters.clear();
+        assert_eq!(params.zenity_args(), ["--file-selection", "--save", "--filename=new.txt"]);
+
+        params.kind = OPEN_FILE;
+        assert_eq!(params.zenity_args(), ["--file-selection"]);
+    }
+
+    #[test]
+    fn test_kdialog_args() {
+        let filters = "Images (*.png *.jpg)|All (*)";
+        let cases: &[(FallbackDialogKind, &[&str])] = &[
+            (OPEN_FILE, &["--getopenfilename", "/home/user/docs", filters]),
+            (
+                OPEN_FILES,
+                &["--getopenfilename", "--multiple", "--separate-output", "/home/user/docs", filters],
+            ),
+            (OPEN_DIRECTORY, &["--getexistingdirectory", "/home/user/docs"]),
+            (save(Some("a b.txt")), &["--getsavefilename", "/home/user/docs/a b.txt", filters]),
+        ];
+        for (kind, expected) in cases {
+            let mut expected_args = vec!["--title", "Pick"];
+            expected_args.extend_from_slice(expected);
+            assert_eq!(params(kind.clone()).kdialog_args(), expected_args, "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn test_kdialog_args_without_folder() {
+        let mut params = params(OPEN_FILE);
+        params.title = String::new();
+        params.current_folder = None;
+        params.filters.clear();
+        assert_eq!(params.kdialog_args(), ["--getopenfilename", "."]);
+    }
+
+    #[test]
+    fn test_convert_output() {
+        let params = params(OPEN_FILES);
+        let cases: &[(FallbackTool, &str, &str)] = &[
+            (FallbackTool::Zenity, "/tmp/a.txt\n", "file:///tmp/a.txt\r\n"),
+            // With `--separator=\n`, the default `|` separator of zenity is just a file name character
+            (
+                FallbackTool::Zenity,
+                "/tmp/a|b.txt\n/tmp/c d.txt\n",
+                "file:///tmp/a%7Cb.txt\r\nfile:///tmp/c%20d.txt\r\n",
+            ),
+            (
+                FallbackTool::KDialog,
+                "/tmp/a.txt\n/tmp/b.txt\n",
+                "file:///tmp/a.txt\r\nfile:///tmp/b.txt\r\n",
+            ),
+            (FallbackTool::KDialog, "/tmp/no-newline", "file:///tmp/no-newline\r\n"),
+            (FallbackTool::KDialog, "\n/tmp/a\n\n", "file:///tmp/a\r\n"),
+        ];
+        for &(tool, stdout, expected) in cases {
+            let result = convert_output(tool, &output(0, stdout, ""), &params).unwrap().unwrap();
+            assert_eq!(result.newline_separated_files, expected, "{tool:?} {stdout:?}");
+            assert_eq!(result.current_filter_index, None);
+            assert_eq!(result.choices, [("recursive".to_owned(), "true".to_owned())]);
+        }
+    }
+
+    #[test]
+    fn test_convert_output_failure() {
+        let params = params(OPEN_FILE);
+        for tool in [FallbackTool::Zenity, FallbackTool::KDialog] {
+            assert!(convert_output(tool, &output(1, "", ""), &params).unwrap().is_none(), "{tool:?}");
+
+            let message = convert_output(tool, &output(255, "", "cannot open display\n"), &params)
+                .unwrap_err()
+                .to_string();
+            assert!(message.starts_with(tool.executable_name()), "{message}");
+            assert!(message.ends_with(": cannot open display"), "{message}");
+
+            let killed = Output {
+                status: ExitStatus::from_raw(9),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            };
+            assert!(convert_output(tool, &killed, &params).is_err(), "{tool:?}");
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use crate::linux::{
+    application::Application,
+    string_utils::{file_uri, file_uri_to_path, join_str_iter},
+};
+use anyhow::Context as _;
+use ashpd::documents::file_transfer::{AddFilesOptions, FileTransfer, RetrieveFilesOptions, StartTransferOptions};
+use log::{debug, warn};
+use std::{
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Data of this MIME type is the key of the `org.freedesktop.portal.FileTransfer` session.
+pub const FILE_TRANSFER_MIME_TYPE: &str = "application/vnd.portal.filetransfer";
+pub const URI_LIST_MIME_TYPE: &str = "text/uri-list";
+
+static FILE_TRANSFER_AVAILABLE: AtomicBool = AtomicBool::new(false);
+
+async fn file_transfer_proxy() -> anyhow::Result<FileTransfer> {
+    let connection = Application::dbus_connection().await?;
+    Ok(FileTransfer::with_connection(connection).await?)
+}
+
+/// The file transfer MIME type is only offered when the portal is available, because the target would not be able to use it otherwise.
+pub async fn check_file_transfer_availability() {
+    match file_transfer_proxy().await {
+        Ok(proxy) => {
+            debug!("Got FileTransfer portal proxy, version {}", proxy.version());
+            FILE_TRANSFER_AVAILABLE.store(true, Ordering::Relaxed);
+        }
+        Err(e) => debug!("FileTransfer portal is not available: {e}"),
+    }
+}
+
+fn is_file_transfer_available() -> bool {
+    FILE_TRANSFER_AVAILABLE.load(Ordering::Relaxed)
+}
+
+/// Adds `application/vnd.portal.filetransfer` to the offered MIME types if there are files offered,
+/// so that sandboxed applications can access them.
+pub fn add_file_transfer_mime_type(mime_types: Box<[&str]>) -> Box<[&str]> {
+    if is_file_transfer_available() && mime_types.contains(&URI_LIST_MIME_TYPE) && !mime_types.contains(&FILE_TRANSFER_MIME_TYPE) {
+        let mut mime_types = mime_types.into_vec();
+        mime_types.push(FILE_TRANSFER_MIME_TYPE);
+        mime_types.into_boxed_slice()
+    } else {
+        mime_types
+    }
+}
+
+/// Returns the MIME type that should be received from the offer to get the data for `mime_type`.
+/// `text/uri-list` is received through the portal if possible, because the paths in it might not be accessible to us.
+pub fn mime_type_to_receive<'a>(mime_type: &'a str, offered_mime_types: &[String]) -> &'a str {
+    if mime_type == URI_LIST_MIME_TYPE && is_file_transfer_available() && offered_mime_types.iter().any(|m| m == FILE_TRANSFER_MIME_TYPE) {
+        FILE_TRANSFER_MIME_TYPE
+    } else {
+        mime_type
+    }
+}
+
+/// Registers the files from the `text/uri-list` data, and returns the file transfer key.
+/// Only local regular files can be transferred, other entries are skipped.
+pub async fn register_files(uri_list: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let uri_list = std::str::from_utf8(uri_list).context("Invalid text/uri-list data")?;
+    let files = uri_list
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|uri| {
+            let path = file_uri_to_path(uri.trim())?;
+            match std::fs::File::open(&path) {
+                Ok(file) if file.metadata().is_ok_and(|m| m.is_file()) => Some(file),
+                Ok(_) => {
+                    debug!("Skipping {} in the file transfer, not a regular file", path.display());
+                    None
+                }
+                Err(e) => {
+                    warn!("Skipping {} in the file transfer: {e}", path.display());
+                    None
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    let proxy = file_transfer_proxy().await?;
+    let key = proxy
+        .start_transfer(StartTransferOptions::default().set_writeable(false).set_auto_stop(true))
+        .await?;
+    proxy.add_files(&key, &files, AddFilesOptions::default()).await?;
+    debug!("Registered {} files for the file transfer", files.len());
+    Ok(key.into_bytes())
+}
+
+/// Retrieves the files for the file transfer key, and returns them as `text/uri-list` data.
+pub async fn retrieve_files(key: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let key = std::str::from_utf8(key).context("Invalid file transfer key")?;
+    let key = key.trim_end_matches('\0');
+    let proxy = file_transfer_proxy().await?;
+    let paths = proxy.retrieve_files(key, RetrieveFilesOptions::default()).await?;
+    debug!("Retrieved {} files from the file transfer", paths.len());
+    let uris = paths.iter().map(|path| file_uri(Path::new(path))).collect::<Vec<_>>();
+    Ok(join_str_iter(uris.iter().map(String::as_str), "\r\n").into_bytes())
+}
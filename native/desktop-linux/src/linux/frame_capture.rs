@@ -0,0 +1,134 @@
+use anyhow::ensure;
+
+use crate::linux::geometry::PhysicalSize;
+
+/// The rendered window content, requested with `window_capture_frame`.
+pub struct CapturedFrame {
+    pub physical_size: PhysicalSize,
+
+    /// RGBA, top row first, without any padding between the rows.
+    pub pixels: Vec<u8>,
+}
+
+fn row_count_and_len(physical_size: PhysicalSize) -> anyhow::Result<(usize, usize)> {
+    let width = usize::try_from(physical_size.width.0)?;
+    let height = usize::try_from(physical_size.height.0)?;
+    ensure!(width > 0 && height > 0, "The frame is empty: {physical_size:?}");
+    Ok((height, width * 4))
+}
+
+impl CapturedFrame {
+    /// Converts from `wl_shm::Format::Argb8888`, which is stored as BGRA.
+    pub fn from_argb8888(physical_size: PhysicalSize, stride: usize, data: &[u8]) -> anyhow::Result<Self> {
+        let (row_count, row_len) = row_count_and_len(physical_size)?;
+        ensure!(
+            stride >= row_len && data.len() >= stride * row_count,
+            "Unexpected buffer layout for {physical_size:?}: stride={stride}, len={}",
+            data.len()
+        );
+        let mut pixels = Vec::with_capacity(row_len * row_count);
+        for row in data.chunks_exact(stride).take(row_count) {
+            for bgra in row[..row_len].chunks_exact(4) {
+                pixels.extend_from_slice(&[bgra[2], bgra[1], bgra[0], bgra[3]]);
+            }
+        }
+        Ok(Self { physical_size, pixels })
+    }
+
+    /// Converts from the `glReadPixels` output, which has the bottom row first.
+    pub fn from_gl_rgba(physical_size: PhysicalSize, data: &[u8]) -> anyhow::Result<Self> {
+        let (row_count, row_len) = row_count_and_len(physical_size)?;
+        ensure!(
+            data.len() == row_len * row_count,
+            "Unexpected pixels size for {physical_size:?}: {}",
+            data.len()
+        );
+        let pixels = data.chunks_exact(row_len).rev().flatten().copied().collect();
+        Ok(Self { physical_size, pixels })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linux::geometry::PhysicalPixels;
+
+    const fn size(width: i32, height: i32) -> PhysicalSize {
+        PhysicalSize {
+            width: PhysicalPixels(width),
+            height: PhysicalPixels(height),
+        }
+    }
+
+    #[test]
+    fn test_from_argb8888() {
+        // 2x2 BGRA pixels, with 4 bytes of padding after each row
+        #[rustfmt::skip]
+        let data = [
+            0x01, 0x02, 0x03, 0x04, 0x11, 0x12, 0x13, 0x14, 0xEE, 0xEE, 0xEE, 0xEE,
+            0x21, 0x22, 0x23, 0x24, 0x31, 0x32, 0x33, 0x34, 0xEE, 0xEE, 0xEE, 0xEE,
+        ];
+        let frame = CapturedFrame::from_argb8888(size(2, 2), 12, &data).unwrap();
+        assert_eq!(frame.physical_size, size(2, 2));
+        #[rustfmt::skip]
+        assert_eq!(frame.pixels, [
+            0x03, 0x02, 0x01, 0x04, 0x13, 0x12, 0x11, 0x14,
+            0x23, 0x22, 0x21, 0x24, 0x33, 0x32, 0x31, 0x34,
+        ]);
+
+        // Without padding, and with the extra data after the last row ignored
+        let frame = CapturedFrame::from_argb8888(size(1, 2), 4, &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0xEE]).unwrap();
+        assert_eq!(frame.pixels, [0x03, 0x02, 0x01, 0x04, 0x07, 0x06, 0x05, 0x08]);
+    }
+
+    #[test]
+    fn test_from_argb8888_errors() {
+        let cases = [
+            (size(0, 1), 4, 4),
+            (size(1, -1), 4, 4),
+            // Stride shorter than the row
+            (size(2, 1), 4, 8),
+            // Truncated data
+            (size(2, 2), 8, 15),
+        ];
+        for (physical_size, stride, len) in cases {
+            assert!(
+                CapturedFrame::from_argb8888(physical_size, stride, &vec![0; len]).is_err(),
+                "{physical_size:?} {stride} {len}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_gl_rgba() {
+        // 2x3 RGBA pixels, bottom row first
+        #[rustfmt::skip]
+        let data = [
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+            0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18,
+            0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28,
+        ];
+        let frame = CapturedFrame::from_gl_rgba(size(2, 3), &data).unwrap();
+        assert_eq!(frame.physical_size, size(2, 3));
+        #[rustfmt::skip]
+        assert_eq!(frame.pixels, [
+            0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28,
+            0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18,
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+        ]);
+
+        let frame = CapturedFrame::from_gl_rgba(size(1, 1), &[1, 2, 3, 4]).unwrap();
+        assert_eq!(frame.pixels, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_from_gl_rgba_errors() {
+        let cases = [(size(0, 0), 0), (size(2, 2), 15), (size(2, 2), 17)];
+        for (physical_size, len) in cases {
+            assert!(
+                CapturedFrame::from_gl_rgba(physical_size, &vec![0; len]).is_err(),
+                "{physical_size:?} {len}"
+            );
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::linux::{application::Application, launcher_entry_api::LauncherEntryParams};
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+use zbus::zvariant::Value;
+
+const LAUNCHER_ENTRY_INTERFACE: &str = "com.canonical.Unity.LauncherEntry";
+
+#[derive(Debug, Clone)]
+pub struct LauncherEntry {
+    /// `application://<desktop file id>.desktop`
+    pub app_uri: String,
+    pub count: i64,
+    pub count_visible: bool,
+    pub progress: f64,
+    pub progress_visible: bool,
+    pub urgent: bool,
+}
+
+impl LauncherEntry {
+    pub fn from_unsafe(params: &LauncherEntryParams) -> anyhow::Result<Self> {
+        let desktop_file_id = params.desktop_file_id.get("LauncherEntryParams: desktop_file_id")?;
+        let app_uri = if desktop_file_id.ends_with(".desktop") {
+            format!("application://{desktop_file_id}")
+        } else {
+            format!("application://{desktop_file_id}.desktop")
+        };
+        Ok(Self {
+            app_uri,
+            count: params.count,
+            count_visible: params.count_visible,
+            progress: params.progress.clamp(0.0, 1.0),
+            progress_visible: params.progress_visible,
+            urgent: params.urgent,
+        })
+    }
+
+    /// The object path doesn't matter to the listeners, but it should be unique per application.
+    fn object_path(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.app_uri.hash(&mut hasher);
+        format!("/com/canonical/unity/launcherentry/{}", hasher.finish())
+    }
+
+    fn properties(&self) -> HashMap<&'static str, Value<'static>> {
+        HashMap::from([
+            ("count", Value::I64(self.count)),
+            ("count-visible", Value::Bool(self.count_visible)),
+            ("progress", Value::F64(self.progress)),
+            ("progress-visible", Value::Bool(self.progress_visible)),
+            ("urgent", Value::Bool(self.urgent)),
+        ])
+    }
+}
+
+pub async fn emit_launcher_entry_update(entry: LauncherEntry) -> anyhow::Result<()> {
+    let connection = Application::dbus_connection().await?;
+    connection
+        .emit_signal(
+            None::<()>,
+            entry.object_path(),
+            LAUNCHER_ENTRY_INTERFACE,
+            "Update",
+            &(entry.app_uri.as_str(), entry.properties()),
+        )
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::linux::{application::Application, application_api::AppPtr, launcher_entry::LauncherEntry};
+use desktop_common::{ffi_utils::BorrowedUtf8, logger::ffi_boundary};
+use log::debug;
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct LauncherEntryParams<'a> {
+    /// Desktop file ID of the application, e.g. `"org.example.App"` (the `.desktop` suffix is optional).
+    pub desktop_file_id: BorrowedUtf8<'a>,
+
+    /// Number displayed as a badge on the application icon.
+    pub count: i64,
+    pub count_visible: bool,
+
+    /// From `0.0` to `1.0`.
+    pub progress: f64,
+    pub progress_visible: bool,
+
+    /// Requests the user attention, e.g. by highlighting the application icon.
+    pub urgent: bool,
+}
+
+/// Updates the application icon in the dock or task manager, by emitting the `com.canonical.Unity.LauncherEntry` `Update` signal.
+///
+/// Supported by e.g. KDE Plasma, Dash to Dock, and Plank.
+/// See <https://wiki.ubuntu.com/Unity/LauncherAPI>
+#[unsafe(no_mangle)]
+pub extern "C" fn application_launcher_entry_update(mut app_ptr: AppPtr, params: LauncherEntryParams) {
+    debug!("application_launcher_entry_update");
+    ffi_boundary("application_launcher_entry_update", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        let entry = LauncherEntry::from_unsafe(&params)?;
+        app.launcher_entry_update(entry);
+        Ok(())
+    });
+}
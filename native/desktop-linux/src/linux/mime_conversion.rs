@@ -0,0 +1,149 @@
+use crate::linux::file_transfer::add_file_transfer_mime_type;
+use anyhow::{Context, bail};
+use desktop_common::mime_types::{PNG_MIME_TYPE, add_mime_type_aliases, convert_text, is_text_mime_type};
+use image::ImageFormat;
+use std::io::Cursor;
+
+/// Only BMP images are converted to PNG, since other image formats are rarely offered without PNG.
+pub fn can_convert_to_png(mime_type: &str) -> bool {
+    matches!(mime_type, "image/bmp" | "image/x-bmp" | "image/x-ms-bmp")
+}
+
+/// The MIME types to offer for the content that the application provides in `mime_types`.
+pub fn offered_mime_types<'a>(mime_types: &[&'a str], with_file_transfer: bool) -> Box<[&'a str]> {
+    let mime_types = add_mime_type_aliases(mime_types, can_convert_to_png).into_boxed_slice();
+    if with_file_transfer {
+        add_file_transfer_mime_type(mime_types)
+    } else {
+        mime_types
+    }
+}
+
+/// Converts the data between the MIME types matched by `desktop_common::mime_types::source_mime_type`.
+pub fn convert_data(from_mime_type: &str, to_mime_type: &str, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+    if from_mime_type == to_mime_type {
+        Ok(data)
+    } else if is_text_mime_type(from_mime_type) && is_text_mime_type(to_mime_type) {
+        Ok(convert_text(from_mime_type, to_mime_type, data))
+    } else if to_mime_type == PNG_MIME_TYPE && can_convert_to_png(from_mime_type) {
+        bmp_to_png(&data)
+    } else {
+        bail!("Conversion from {from_mime_type} to {to_mime_type} is not supported")
+    }
+}
+
+/// Converting images can take a while, so it's done on a blocking thread of the runtime.
+pub async fn convert_data_async(
+    rt_handle: &tokio::runtime::Handle,
+    from_mime_type: String,
+    to_mime_type: String,
+    data: Vec<u8>,
+) -> anyhow::Result<Vec<u8>> {
+    if from_mime_type == to_mime_type {
+        return Ok(data);
+    }
+    rt_handle
+        .spawn_blocking(move || convert_data(&from_mime_type, &to_mime_type, data))
+        .await?
+}
+
+fn bmp_to_png(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let image = image::load_from_memory_with_format(data, ImageFormat::Bmp).context("Invalid BMP image")?;
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    /// Builds a BMP image with a `BITMAPV4HEADER` (108 bytes) for the bit fields, or a `BITMAPINFOHEADER` (40 bytes).
+    fn bmp(width: i32, height: i32, bits_per_pixel: u16, masks: Option<[u32; 4]>, pixels: &[u8]) -> Vec<u8> {
+        let header_size: u32 = if masks.is_some() { 108 } else { 40 };
+        let pixels_offset = 14 + header_size;
+        let mut data = b"BM".to_vec();
+        data.extend_from_slice(&(pixels_offset + u32::try_from(pixels.len()).unwrap()).to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&pixels_offset.to_le_bytes());
+        data.extend_from_slice(&header_size.to_le_bytes());
+        data.extend_from_slice(&width.to_le_bytes());
+        data.extend_from_slice(&height.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&bits_per_pixel.to_le_bytes());
+        data.extend_from_slice(&u32::from(masks.is_some()).wrapping_mul(3).to_le_bytes());
+        data.extend_from_slice(&[0; 20]);
+        if let Some(masks) = masks {
+            for mask in masks {
+                data.extend_from_slice(&mask.to_le_bytes());
+            }
+            data.extend_from_slice(&[0; 52]);
+        }
+        assert_eq!(data.len(), pixels_offset as usize);
+        data.extend_from_slice(pixels);
+        data
+    }
+
+    fn decode_png(png: &[u8]) -> RgbaImage {
+        image::load_from_memory_with_format(png, ImageFormat::Png).unwrap().into_rgba8()
+    }
+
+    #[test]
+    fn test_bmp_to_png_24_bits() {
+        // Bottom-up rows, each padded to 4 bytes
+        #[rustfmt::skip]
+        let pixels = [
+            0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0, 0,
+            0xFF, 0x00, 0x00, 0x10, 0x20, 0x30, 0, 0,
+        ];
+        let image = decode_png(&bmp_to_png(&bmp(2, 2, 24, None, &pixels)).unwrap());
+        assert_eq!(image.dimensions(), (2, 2));
+        #[rustfmt::skip]
+        assert_eq!(image.into_raw(), [
+            0x00, 0x00, 0xFF, 0xFF, 0x30, 0x20, 0x10, 0xFF,
+            0xFF, 0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF,
+        ]);
+    }
+
+    #[test]
+    fn test_bmp_to_png_32_bits() {
+        // Top-down, the unused fourth byte is not alpha
+        let image = decode_png(&bmp_to_png(&bmp(2, -1, 32, None, &[0x01, 0x02, 0x03, 0x00, 0x04, 0x05, 0x06, 0x80])).unwrap());
+        assert_eq!(image.into_raw(), [0x03, 0x02, 0x01, 0xFF, 0x06, 0x05, 0x04, 0xFF]);
+    }
+
+    #[test]
+    fn test_bmp_to_png_bit_fields() {
+        let pixel = 0x1122_3344u32.to_le_bytes();
+        let image = decode_png(&bmp_to_png(&bmp(1, -1, 32, Some([0x0000_00FF, 0x0000_FF00, 0x00FF_0000, 0xFF00_0000]), &pixel)).unwrap());
+        assert_eq!(image.into_raw(), [0x44, 0x33, 0x22, 0x11]);
+        let image = decode_png(&bmp_to_png(&bmp(1, 1, 32, Some([0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0]), &pixel)).unwrap());
+        assert_eq!(image.into_raw(), [0x22, 0x33, 0x44, 0xFF]);
+    }
+
+    #[test]
+    fn test_bmp_to_png_errors() {
+        let cases = [
+            b"PNG".to_vec(),
+            b"BM\0\0".to_vec(),
+            bmp(0, 1, 24, None, &[]),
+            bmp(2, 2, 24, None, &[0; 15]),
+            bmp(i32::MAX, i32::MIN, 32, None, &[0; 4]),
+        ];
+        for data in cases {
+            let e = bmp_to_png(&data).unwrap_err();
+            assert!(e.to_string().starts_with("Invalid BMP image"), "{data:?}: {e:#}");
+        }
+    }
+
+    #[test]
+    fn test_convert_data() {
+        let png = convert_data("image/bmp", PNG_MIME_TYPE, bmp(1, 1, 24, None, &[0x01, 0x02, 0x03, 0])).unwrap();
+        assert_eq!(decode_png(&png).into_raw(), [0x03, 0x02, 0x01, 0xFF]);
+
+        assert_eq!(convert_data("STRING", "UTF8_STRING", b"\xE9".to_vec()).unwrap(), "é".as_bytes());
+        assert_eq!(convert_data("image/jpeg", "image/jpeg", vec![1]).unwrap(), [1]);
+        assert!(convert_data("image/jpeg", PNG_MIME_TYPE, vec![1]).is_err());
+    }
+}
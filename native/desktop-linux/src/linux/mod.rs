@@ -1,24 +1,48 @@
 mod application;
 pub mod application_api;
+mod application_menu;
+pub mod application_menu_api;
 mod application_state;
 mod async_event_result;
+mod background;
+pub mod background_api;
+mod data_control;
 mod data_transfer;
+mod data_transfer_stream;
 mod desktop_settings;
 pub mod desktop_settings_api;
 mod drag_icon;
+mod event_recording;
+pub mod event_recording_api;
 pub mod events;
 mod ffi_return_conversions;
 mod file_dialog;
 pub mod file_dialog_api;
+mod file_dialog_fallback;
+mod file_transfer;
+mod frame_capture;
 pub mod geometry;
 mod keyboard;
+mod launcher_entry;
+pub mod launcher_entry_api;
+mod mime_conversion;
+mod monitors;
 mod mouse;
 mod notifications;
+mod notifications_portal;
+pub mod perf_trace_api;
 mod pointer_shapes;
 pub mod pointer_shapes_api;
+mod print;
+pub mod print_api;
 mod rendering_egl;
 mod rendering_software;
 pub mod screen;
+mod screenshot;
+mod secrets;
+pub mod secrets_api;
+mod session;
+pub mod session_api;
 pub mod string_api;
 mod string_utils;
 mod text_input;
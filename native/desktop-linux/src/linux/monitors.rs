@@ -0,0 +1,276 @@
+use crate::linux::{
+    application::Application,
+    events::{LowMemoryWarningEvent, NetworkAvailabilityChangedEvent, NetworkConnectivity, OnBatteryChangedEvent, PowerSaverChangedEvent},
+};
+use ashpd::desktop::{
+    memory_monitor::MemoryMonitor,
+    network_monitor::{Connectivity, NetworkMonitor},
+    power_profile_monitor::PowerProfileMonitor,
+};
+use futures_lite::StreamExt;
+use log::{debug, warn};
+use zbus::{proxy, proxy::CacheProperties};
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager"
+)]
+trait NetworkManager {
+    /// `NMState`
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+
+    /// `NMConnectivityState`
+    #[zbus(property)]
+    fn connectivity(&self) -> zbus::Result<u32>;
+
+    /// `NMMetered`
+    #[zbus(property)]
+    fn metered(&self) -> zbus::Result<u32>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.UPower",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower"
+)]
+trait UPower {
+    #[zbus(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.UPower.PowerProfiles",
+    default_service = "org.freedesktop.UPower.PowerProfiles",
+    default_path = "/org/freedesktop/UPower/PowerProfiles"
+)]
+trait PowerProfiles {
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonitorEvent {
+    NetworkAvailabilityChanged(NetworkAvailabilityChangedEvent),
+    PowerSaverChanged(PowerSaverChangedEvent),
+    OnBatteryChanged(OnBatteryChangedEvent),
+    LowMemoryWarning(LowMemoryWarningEvent),
+}
+
+/// Sends the event only if it differs from the previously sent one.
+struct DedupSender<'a, F> {
+    sender: &'a F,
+    last: Option<MonitorEvent>,
+}
+
+impl<'a, F: Fn(MonitorEvent) -> anyhow::Result<()>> DedupSender<'a, F> {
+    const fn new(sender: &'a F) -> Self {
+        Self { sender, last: None }
+    }
+
+    fn send(&mut self, event: MonitorEvent) -> anyhow::Result<()> {
+        if self.last != Some(event) {
+            self.last = Some(event);
+            (self.sender)(event)?;
+        }
+        Ok(())
+    }
+}
+
+const fn network_connectivity(connectivity: Connectivity) -> NetworkConnectivity {
+    match connectivity {
+        Connectivity::Local => NetworkConnectivity::Local,
+        Connectivity::Limited => NetworkConnectivity::Limited,
+        Connectivity::CaptivePortal => NetworkConnectivity::CaptivePortal,
+        Connectivity::FullNetwork => NetworkConnectivity::Full,
+    }
+}
+
+async fn network_portal_monitor(
+    connection: zbus::Connection,
+    sender: &(impl Fn(MonitorEvent) -> anyhow::Result<()> + Sync),
+) -> anyhow::Result<()> {
+    let monitor = NetworkMonitor::with_connection(connection).await?;
+    let mut changes = monitor.receive_changed().await?;
+    debug!("Network monitor started, NetworkMonitor portal version {}", monitor.version());
+    let mut sender = DedupSender::new(sender);
+    loop {
+        let status = monitor.status().await?;
+        sender.send(MonitorEvent::NetworkAvailabilityChanged(NetworkAvailabilityChangedEvent {
+            available: status.is_available(),
+            metered: status.is_metered(),
+            connectivity: network_connectivity(status.connectivity()),
+        }))?;
+        if changes.next().await.is_none() {
+            return Ok(());
+        }
+    }
+}
+
+async fn read_network_manager_status(network_manager: &NetworkManagerProxy<'_>) -> zbus::Result<NetworkAvailabilityChangedEvent> {
+    // See https://networkmanager.dev/docs/api/latest/nm-dbus-types.html
+    const NM_STATE_CONNECTED_SITE: u32 = 60;
+    const NM_STATE_CONNECTED_GLOBAL: u32 = 70;
+    const NM_CONNECTIVITY_PORTAL: u32 = 2;
+    const NM_METERED_YES: u32 = 1;
+    const NM_METERED_GUESS_YES: u32 = 3;
+
+    let state = network_manager.state().await?;
+    let connectivity = if network_manager.connectivity().await? == NM_CONNECTIVITY_PORTAL {
+        NetworkConnectivity::CaptivePortal
+    } else if state >= NM_STATE_CONNECTED_GLOBAL {
+        NetworkConnectivity::Full
+    } else if state >= NM_STATE_CONNECTED_SITE {
+        NetworkConnectivity::Limited
+    } else {
+        NetworkConnectivity::Local
+    };
+    let metered = network_manager.metered().await?;
+    Ok(NetworkAvailabilityChangedEvent {
+        available: state >= NM_STATE_CONNECTED_SITE,
+        metered: metered == NM_METERED_YES || metered == NM_METERED_GUESS_YES,
+        connectivity,
+    })
+}
+
+async fn network_manager_monitor(sender: &(impl Fn(MonitorEvent) -> anyhow::Result<()> + Sync)) -> anyhow::Result<()> {
+    let connection = zbus::Connection::system().await?;
+    let network_manager = NetworkManagerProxy::builder(&connection)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    let properties = zbus::fdo::PropertiesProxy::builder(&connection)
+        .destination(network_manager.inner().destination().to_owned())?
+        .path(network_manager.inner().path().to_owned())?
+        .build()
+        .await?;
+    let mut changes = properties.receive_properties_changed().await?;
+    debug!("Network monitor started, using NetworkManager");
+    let mut sender = DedupSender::new(sender);
+    loop {
+        let status = read_network_manager_status(&network_manager).await?;
+        sender.send(MonitorEvent::NetworkAvailabilityChanged(status))?;
+        if changes.next().await.is_none() {
+            return Ok(());
+        }
+    }
+}
+
+async fn power_saver_portal_monitor(
+    connection: zbus::Connection,
+    sender: &(impl Fn(MonitorEvent) -> anyhow::Result<()> + Sync),
+) -> anyhow::Result<()> {
+    let monitor = PowerProfileMonitor::with_connection(connection).await?;
+    let mut changes = monitor.receive_property_changed::<bool>("power-saver-enabled").await;
+    debug!(
+        "Power saver monitor started, PowerProfileMonitor portal version {}",
+        monitor.version()
+    );
+    let mut sender = DedupSender::new(sender);
+    sender.send(MonitorEvent::PowerSaverChanged(PowerSaverChangedEvent {
+        enabled: monitor.is_enabled().await?,
+    }))?;
+    while let Some(change) = changes.next().await {
+        sender.send(MonitorEvent::PowerSaverChanged(PowerSaverChangedEvent {
+            enabled: change.get().await?,
+        }))?;
+    }
+    Ok(())
+}
+
+async fn power_profiles_monitor(sender: &(impl Fn(MonitorEvent) -> anyhow::Result<()> + Sync)) -> anyhow::Result<()> {
+    const POWER_SAVER_PROFILE: &str = "power-saver";
+
+    let connection = zbus::Connection::system().await?;
+    let power_profiles = PowerProfilesProxy::new(&connection).await?;
+    let mut changes = power_profiles.receive_active_profile_changed().await;
+    debug!("Power saver monitor started, using power-profiles-daemon");
+    let mut sender = DedupSender::new(sender);
+    sender.send(MonitorEvent::PowerSaverChanged(PowerSaverChangedEvent {
+        enabled: power_profiles.active_profile().await? == POWER_SAVER_PROFILE,
+    }))?;
+    while let Some(change) = changes.next().await {
+        sender.send(MonitorEvent::PowerSaverChanged(PowerSaverChangedEvent {
+            enabled: change.get().await? == POWER_SAVER_PROFILE,
+        }))?;
+    }
+    Ok(())
+}
+
+/// There is no portal reporting the battery state.
+async fn on_battery_monitor(sender: &(impl Fn(MonitorEvent) -> anyhow::Result<()> + Sync)) -> anyhow::Result<()> {
+    let connection = zbus::Connection::system().await?;
+    let upower = UPowerProxy::new(&connection).await?;
+    let mut changes = upower.receive_on_battery_changed().await;
+    debug!("Battery monitor started, using UPower");
+    let mut sender = DedupSender::new(sender);
+    sender.send(MonitorEvent::OnBatteryChanged(OnBatteryChangedEvent {
+        on_battery: upower.on_battery().await?,
+    }))?;
+    while let Some(change) = changes.next().await {
+        sender.send(MonitorEvent::OnBatteryChanged(OnBatteryChangedEvent {
+            on_battery: change.get().await?,
+        }))?;
+    }
+    Ok(())
+}
+
+/// There is no fallback for the memory monitor portal.
+async fn low_memory_portal_monitor(
+    connection: zbus::Connection,
+    sender: &(impl Fn(MonitorEvent) -> anyhow::Result<()> + Sync),
+) -> anyhow::Result<()> {
+    let monitor = MemoryMonitor::with_connection(connection).await?;
+    let mut warnings = monitor.receive_low_memory_warning().await?;
+    debug!("Memory monitor started, MemoryMonitor portal version {}", monitor.version());
+    while let Some(level) = warnings.next().await {
+        sender(MonitorEvent::LowMemoryWarning(LowMemoryWarningEvent {
+            level: u8::try_from(level).unwrap_or(u8::MAX),
+        }))?;
+    }
+    Ok(())
+}
+
+async fn network_monitor(connection: zbus::Connection, sender: &(impl Fn(MonitorEvent) -> anyhow::Result<()> + Sync)) {
+    if let Err(portal_error) = network_portal_monitor(connection, sender).await {
+        debug!("NetworkMonitor portal is not available ({portal_error}), falling back to NetworkManager");
+        if let Err(e) = network_manager_monitor(sender).await {
+            warn!("Network monitor stopped: {e}");
+        }
+    }
+}
+
+async fn power_saver_monitor(connection: zbus::Connection, sender: &(impl Fn(MonitorEvent) -> anyhow::Result<()> + Sync)) {
+    if let Err(portal_error) = power_saver_portal_monitor(connection, sender).await {
+        debug!("PowerProfileMonitor portal is not available ({portal_error}), falling back to power-profiles-daemon");
+        if let Err(e) = power_profiles_monitor(sender).await {
+            warn!("Power saver monitor stopped: {e}");
+        }
+    }
+}
+
+/// Each monitor uses the corresponding portal if possible, falling back to the system bus services.
+/// The current state is reported initially, and then only when it changes.
+pub async fn init_monitors_task(sender: impl Fn(MonitorEvent) -> anyhow::Result<()> + Send + Sync + 'static) -> anyhow::Result<()> {
+    let connection = Application::dbus_connection().await?;
+    let on_battery = async {
+        if let Err(e) = on_battery_monitor(&sender).await {
+            warn!("Battery monitor stopped: {e}");
+        }
+    };
+    let low_memory = async {
+        if let Err(e) = low_memory_portal_monitor(connection.clone(), &sender).await {
+            warn!("Memory monitor stopped: {e}");
+        }
+    };
+    futures_lite::future::zip(
+        futures_lite::future::zip(
+            network_monitor(connection.clone(), &sender),
+            power_saver_monitor(connection.clone(), &sender),
+        ),
+        futures_lite::future::zip(on_battery, low_memory),
+    )
+    .await;
+    Ok(())
+}
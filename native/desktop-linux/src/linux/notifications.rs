@@ -1,7 +1,11 @@
 #![allow(clippy::too_many_arguments)]
 
-use crate::linux::application::Application;
-use anyhow::Context as _;
+use crate::linux::{
+    application::Application,
+    application_api::{NotificationParams, NotificationUrgency},
+    notifications_portal::init_portal_notifications_task,
+};
+use anyhow::{Context as _, bail, ensure};
 use futures_lite::StreamExt;
 use log::debug;
 use std::collections::HashMap;
@@ -121,41 +125,170 @@ pub trait Notifications {
     fn activation_token(&self, id: u32, activation_token: String) -> zbus::Result<()>;
 }
 
-pub struct NewNotificationData {
+/// The action invoked when the notification itself is clicked, so it can't be used as a key of the action buttons.
+/// cbindgen:ignore
+pub const DEFAULT_ACTION: &str = "default";
+
+#[derive(Clone)]
+pub struct NotificationImage {
+    pub width: i32,
+    pub height: i32,
+    pub rgba_data: Vec<u8>,
+}
+
+impl std::fmt::Debug for NotificationImage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "NotificationImage {{ width: {}, height: {}, rgba_data.len(): {} }}",
+            self.width,
+            self.height,
+            self.rgba_data.len()
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NotificationContent {
     pub summary: String,
     pub body: String,
     pub sound_file_path: Option<String>,
+
+    /// Pairs of action key and label, displayed as buttons.
+    pub actions: Vec<(String, String)>,
+
+    pub app_icon: Option<String>,
+    pub image: Option<NotificationImage>,
+    pub urgency: NotificationUrgency,
+    pub category: Option<String>,
+    pub expire_timeout: i32,
+    pub replaces_id: u32,
+}
+
+impl NotificationContent {
+    pub const fn new(summary: String, body: String, sound_file_path: Option<String>) -> Self {
+        Self {
+            summary,
+            body,
+            sound_file_path,
+            actions: Vec::new(),
+            app_icon: None,
+            image: None,
+            urgency: NotificationUrgency::Normal,
+            category: None,
+            expire_timeout: -1,
+            replaces_id: 0,
+        }
+    }
+
+    pub fn from_unsafe(params: &NotificationParams) -> anyhow::Result<Self> {
+        let actions = params
+            .actions
+            .as_optional_slice()
+            .unwrap_or_default()
+            .iter()
+            .map(|action| {
+                let key = action.key.get("NotificationParams: actions: key")?;
+                ensure!(
+                    key != DEFAULT_ACTION,
+                    "NotificationParams: actions: the {DEFAULT_ACTION:?} key is reserved"
+                );
+                let label = action.label.get("NotificationParams: actions: label")?;
+                Ok((key.to_owned(), label.to_owned()))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let image = if let Some(rgba_data) = params.image.rgba_data.as_optional_slice() {
+            let expected_len = usize::try_from(params.image.width)? * usize::try_from(params.image.height)? * 4;
+            if rgba_data.len() != expected_len {
+                bail!(
+                    "NotificationParams: image: expected {expected_len} bytes for {}x{} RGBA image, got {}",
+                    params.image.width,
+                    params.image.height,
+                    rgba_data.len()
+                );
+            }
+            Some(NotificationImage {
+                width: params.image.width,
+                height: params.image.height,
+                rgba_data: rgba_data.to_vec(),
+            })
+        } else {
+            None
+        };
+        Ok(Self {
+            summary: params.title.get("NotificationParams: title")?.to_owned(),
+            body: params.body.get("NotificationParams: body")?.to_owned(),
+            sound_file_path: params
+                .sound_file_path
+                .get_optional("NotificationParams: sound_file_path")?
+                .map(ToOwned::to_owned),
+            actions,
+            app_icon: params.app_icon.get_optional("NotificationParams: app_icon")?.map(ToOwned::to_owned),
+            image,
+            urgency: params.urgency,
+            category: params.category.get_optional("NotificationParams: category")?.map(ToOwned::to_owned),
+            expire_timeout: params.expire_timeout_ms,
+            replaces_id: params.replaces_id,
+        })
+    }
+}
+
+pub struct NewNotificationData {
+    pub content: NotificationContent,
     pub result_reporter: Box<dyn FnOnce(anyhow::Result<u32>) + 'static + Send + Sync>,
 }
 
 impl std::fmt::Debug for NewNotificationData {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "NewNotificationData {{ summary: {}, body: {}, sound_file_path: {:?} }}",
-            self.summary, self.body, self.sound_file_path
-        )
+        write!(f, "NewNotificationData {{ content: {:?} }}", self.content)
     }
 }
 
-async fn show_notification_async(
-    proxy: &NotificationsProxy<'_>,
-    summary: &str,
-    body: &str,
-    sound_file_path: Option<String>,
-) -> anyhow::Result<u32> {
+async fn show_notification_async(proxy: &NotificationsProxy<'_>, content: &NotificationContent) -> anyhow::Result<u32> {
     let app_name = ""; // auto
-    let app_icon = ""; // auto
-    let replaces_id = 0; // no replacement
-    let expire_timeout = -1; // auto
-    let actions = vec!["default", ""];
+    let app_icon = content.app_icon.as_deref().unwrap_or(""); // auto if empty
+
+    let mut actions = vec![DEFAULT_ACTION, ""];
+    for (key, label) in &content.actions {
+        actions.push(key);
+        actions.push(label);
+    }
+
     let mut hints = HashMap::new();
-    if let Some(sound_file) = sound_file_path {
-        hints.insert("sound-file", sound_file.into());
+    if let Some(sound_file) = &content.sound_file_path {
+        hints.insert("sound-file", sound_file.as_str().into());
+    }
+    hints.insert("urgency", Value::U8(content.urgency as u8));
+    if let Some(category) = &content.category {
+        hints.insert("category", category.as_str().into());
+    }
+    if let Some(image) = &content.image {
+        let bits_per_sample = 8;
+        let channels = 4;
+        let rowstride = image.width * channels;
+        let image_data = (
+            image.width,
+            image.height,
+            rowstride,
+            true,
+            bits_per_sample,
+            channels,
+            image.rgba_data.as_slice(),
+        );
+        hints.insert("image-data", image_data.into());
     }
 
     let notification_id = proxy
-        .notify(app_name, replaces_id, app_icon, summary, body, actions, hints, expire_timeout)
+        .notify(
+            app_name,
+            content.replaces_id,
+            app_icon,
+            &content.summary,
+            &content.body,
+            actions,
+            hints,
+            content.expire_timeout,
+        )
         .await?;
     Ok(notification_id)
 }
@@ -183,7 +316,7 @@ impl NotificationData {
 
 #[derive(Debug)]
 pub enum NotificationAction {
-    Show(NewNotificationData),
+    Show(Box<NewNotificationData>),
     Close(u32),
 }
 
@@ -256,7 +389,7 @@ async fn notification_action_receiver_task(
         debug!("Received notification action {action:?}");
         match action {
             NotificationAction::Show(data) => {
-                (data.result_reporter)(show_notification_async(&proxy, &data.summary, &data.body, data.sound_file_path).await);
+                (data.result_reporter)(show_notification_async(&proxy, &data.content).await);
             }
             NotificationAction::Close(notification_id) => close_notification_async(&proxy, notification_id).await?,
         }
@@ -269,6 +402,10 @@ pub async fn init_notifications_task(
     sender: impl Fn(NotificationData) -> anyhow::Result<()> + Send + Sync + 'static,
     notification_action_receiver: tokio::sync::mpsc::Receiver<NotificationAction>,
 ) -> anyhow::Result<()> {
+    if ashpd::is_sandboxed() {
+        debug!("Running in a sandbox, using the notification portal");
+        return init_portal_notifications_task(sender, notification_action_receiver).await;
+    }
     let connection = Application::dbus_connection().await?;
     let proxy = NotificationsProxy::new(&connection).await?;
     debug!("Got DBus Notifications proxy");
@@ -0,0 +1,150 @@
+use crate::linux::{
+    application::Application,
+    application_api::NotificationUrgency,
+    notifications::{DEFAULT_ACTION, NotificationAction, NotificationContent, NotificationData},
+};
+use ashpd::{
+    desktop::{
+        Icon,
+        notification::{Button, Category, Notification, NotificationProxy, Priority},
+    },
+    zvariant::OwnedValue,
+};
+use futures_lite::StreamExt;
+use log::{debug, warn};
+use std::collections::HashMap;
+
+const fn priority(urgency: NotificationUrgency) -> Priority {
+    match urgency {
+        NotificationUrgency::Low => Priority::Low,
+        NotificationUrgency::Normal => Priority::Normal,
+        NotificationUrgency::Critical => Priority::Urgent,
+    }
+}
+
+fn icon(content: &NotificationContent) -> Option<Icon> {
+    if content.image.is_some() {
+        debug!("Notification images are not supported by the portal backend, using app_icon instead");
+    }
+    let app_icon = content.app_icon.as_deref()?;
+    if app_icon.starts_with("file://") {
+        match ashpd::Uri::parse(app_icon) {
+            Ok(uri) => Some(Icon::Uri(uri)),
+            Err(e) => {
+                warn!("Invalid notification icon URI {app_icon:?}: {e}");
+                None
+            }
+        }
+    } else {
+        Some(Icon::with_names([app_icon]))
+    }
+}
+
+/// The portal only accepts its own list of categories, and vendor-specific ones starting with `x-`.
+fn category(content: &NotificationContent) -> Option<Category> {
+    let category_str = content.category.as_deref()?;
+    let category: Category = category_str.parse().ok()?;
+    if matches!(&category, Category::Other(s) if !s.starts_with("x-")) {
+        debug!("Notification category {category_str:?} is not supported by the portal backend");
+        return None;
+    }
+    Some(category)
+}
+
+fn create_notification(content: &NotificationContent) -> Notification {
+    let mut notification = Notification::new(&content.summary)
+        .body(content.body.as_str())
+        // Doesn't start with `app.`, so the portal reports it with the `ActionInvoked` signal instead of activating the application.
+        .default_action(DEFAULT_ACTION)
+        .priority(priority(content.urgency))
+        .icon(icon(content))
+        .category(category(content));
+    if let Some(sound_file_path) = &content.sound_file_path {
+        match std::fs::File::open(sound_file_path) {
+            Ok(sound_file) => notification = notification.sound(&sound_file),
+            Err(e) => warn!("Error opening notification sound file {sound_file_path:?}: {e}"),
+        }
+    }
+    for (key, label) in &content.actions {
+        notification = notification.button(Button::new(label, key));
+    }
+    notification
+}
+
+/// Since version 2 of the portal, the last parameter is the platform data, possibly containing the activation token.
+fn activation_token(parameters: &[OwnedValue]) -> Option<String> {
+    parameters.iter().rev().find_map(|parameter| {
+        let mut platform_data = HashMap::<String, OwnedValue>::try_from(parameter.try_clone().ok()?).ok()?;
+        String::try_from(platform_data.remove("activation-token")?).ok()
+    })
+}
+
+async fn actions_receiver(
+    proxy: &NotificationProxy,
+    sender: impl Fn(NotificationData) -> anyhow::Result<()> + Send + Sync + 'static,
+) -> anyhow::Result<()> {
+    let mut stream = proxy.receive_action_invoked().await?;
+    debug!("Portal notifications receiver started");
+    while let Some(action) = stream.next().await {
+        debug!("Portal notification action invoked: id={}, action={}", action.id(), action.name());
+        let Ok(id) = action.id().parse() else {
+            warn!("Unexpected notification ID: {}", action.id());
+            continue;
+        };
+        sender(NotificationData {
+            id,
+            action: Some(action.name().to_owned()),
+            activation_token: activation_token(action.parameter()),
+        })?;
+    }
+    debug!("Portal notifications receiver stopped");
+    Ok(())
+}
+
+async fn notification_action_receiver_task(
+    proxy: &NotificationProxy,
+    mut notification_action_receiver: tokio::sync::mpsc::Receiver<NotificationAction>,
+) -> anyhow::Result<()> {
+    debug!("Portal notifications action receiver started");
+    let mut last_notification_id = 0u32;
+    while let Some(action) = notification_action_receiver.recv().await {
+        debug!("Received notification action {action:?}");
+        match action {
+            NotificationAction::Show(data) => {
+                // The portal uses application-provided string IDs, adding a notification with the same ID replaces it.
+                let notification_id = if data.content.replaces_id == 0 {
+                    last_notification_id = last_notification_id.checked_add(1).unwrap_or(1);
+                    last_notification_id
+                } else {
+                    data.content.replaces_id
+                };
+                let notification = create_notification(&data.content);
+                let result = proxy.add_notification(&notification_id.to_string(), notification).await;
+                (data.result_reporter)(result.map(|()| notification_id).map_err(Into::into));
+            }
+            NotificationAction::Close(notification_id) => {
+                proxy.remove_notification(&notification_id.to_string()).await?;
+            }
+        }
+    }
+    debug!("Portal notifications action receiver stopped");
+    Ok(())
+}
+
+/// Used inside a sandbox (e.g. Flatpak), where `org.freedesktop.Notifications` might not be accessible.
+///
+/// The portal doesn't report notifications being dismissed, so `NotificationData` is only sent when an action is invoked.
+pub async fn init_portal_notifications_task(
+    sender: impl Fn(NotificationData) -> anyhow::Result<()> + Send + Sync + 'static,
+    notification_action_receiver: tokio::sync::mpsc::Receiver<NotificationAction>,
+) -> anyhow::Result<()> {
+    let connection = Application::dbus_connection().await?;
+    let proxy = NotificationProxy::with_connection(connection).await?;
+    debug!("Got portal Notification proxy, version {}", proxy.version());
+
+    futures_lite::future::or(
+        notification_action_receiver_task(&proxy, notification_action_receiver),
+        actions_receiver(&proxy, sender),
+    )
+    .await
+}
@@ -0,0 +1,31 @@
+use desktop_common::{
+    ffi_utils::BorrowedUtf8,
+    logger::ffi_boundary,
+    perf_trace::{start_performance_trace, stop_performance_trace},
+};
+use log::debug;
+use std::path::Path;
+
+/// Starts tracing the event dispatch, FFI calls, buffer swaps and data transfer reads.
+/// Discards the spans collected so far, if any.
+///
+/// Returns `false` if the library was built without the `perf-trace` feature.
+#[unsafe(no_mangle)]
+pub extern "C" fn performance_trace_start() -> bool {
+    debug!("performance_trace_start");
+    ffi_boundary("performance_trace_start", || {
+        start_performance_trace()?;
+        Ok(true)
+    })
+}
+
+/// Stops the tracing, and writes the trace to `path`, in the Chrome trace event format, which Perfetto can open.
+#[unsafe(no_mangle)]
+pub extern "C" fn performance_trace_stop(path: BorrowedUtf8) -> bool {
+    debug!("performance_trace_stop");
+    ffi_boundary("performance_trace_stop", || {
+        let path = path.get("performance_trace_stop: path")?;
+        stop_performance_trace(Path::new(path))?;
+        Ok(true)
+    })
+}
@@ -0,0 +1,177 @@
+use ashpd::{
+    WindowIdentifier,
+    desktop::{
+        ResponseError,
+        print::{Orientation, PageSetup, PreparePrintOptions, PrintOptions, PrintProxy, Settings},
+    },
+};
+use desktop_common::ffi_utils::BorrowedUtf8;
+use std::{
+    io::{Seek, Write},
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use crate::linux::{
+    application::Application,
+    print_api::{PreparePrintParams, PrintOrientation, PrintPageSetup, PrintPages, PrintParams, PrintSettings},
+};
+
+#[derive(Debug)]
+pub struct PreparePrintRequest {
+    modal: bool,
+    title: String,
+    accept_label: Option<String>,
+    has_current_page: bool,
+    has_selected_pages: bool,
+}
+
+impl PreparePrintParams<'_> {
+    pub fn to_request(&self) -> anyhow::Result<PreparePrintRequest> {
+        Ok(PreparePrintRequest {
+            modal: self.modal,
+            title: self.get_title()?.to_owned(),
+            accept_label: self.get_accept_label()?.map(ToOwned::to_owned),
+            has_current_page: self.has_current_page,
+            has_selected_pages: self.has_selected_pages,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct PrintRequest {
+    modal: bool,
+    title: String,
+    token: u32,
+    document: std::fs::File,
+}
+
+impl PrintParams<'_> {
+    /// The portal reads the document from a file descriptor, so the data is copied to an unlinked temporary file.
+    pub fn to_request(&self) -> anyhow::Result<PrintRequest> {
+        static DOCUMENT_COUNTER: AtomicU32 = AtomicU32::new(0);
+        let document_number = DOCUMENT_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("kdt-print-{}-{document_number}.pdf", std::process::id()));
+        let mut document = std::fs::File::options().read(true).write(true).create_new(true).open(&path)?;
+        std::fs::remove_file(&path)?;
+        document.write_all(self.get_pdf_data()?)?;
+        document.rewind()?;
+        Ok(PrintRequest {
+            modal: self.modal,
+            title: self.get_title()?.to_owned(),
+            token: self.token,
+            document,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct PreparePrintResult {
+    pub token: u32,
+    pub settings: Settings,
+    pub page_setup: PageSetup,
+}
+
+const fn print_orientation(orientation: Option<Orientation>) -> PrintOrientation {
+    match orientation {
+        None => PrintOrientation::Unspecified,
+        Some(Orientation::Portrait) => PrintOrientation::Portrait,
+        Some(Orientation::Landscape) => PrintOrientation::Landscape,
+        Some(Orientation::ReversePortrait) => PrintOrientation::ReversePortrait,
+        Some(Orientation::ReverseLandscape) => PrintOrientation::ReverseLandscape,
+    }
+}
+
+/// Paper sizes in the print settings are strings, in millimeters.
+fn parse_length(length: Option<&String>) -> f64 {
+    length.and_then(|s| s.parse().ok()).unwrap_or(0.0)
+}
+
+impl<'a> PrintSettings<'a> {
+    #[must_use]
+    pub fn new(settings: &'a Settings, output_uri: Option<&'a String>) -> Self {
+        Self {
+            orientation: print_orientation(settings.orientation),
+            paper_format: BorrowedUtf8::optional(settings.paper_format.as_ref()),
+            paper_width: parse_length(settings.paper_width.as_ref()),
+            paper_height: parse_length(settings.paper_height.as_ref()),
+            n_copies: settings.n_copies.unwrap_or(0),
+            print_pages: match settings.print_pages {
+                None => PrintPages::Unspecified,
+                Some(ashpd::desktop::print::PrintPages::All) => PrintPages::All,
+                Some(ashpd::desktop::print::PrintPages::Selection) => PrintPages::Selection,
+                Some(ashpd::desktop::print::PrintPages::Current) => PrintPages::Current,
+                Some(ashpd::desktop::print::PrintPages::Ranges) => PrintPages::Ranges,
+            },
+            page_ranges: BorrowedUtf8::optional(settings.page_ranges.as_ref()),
+            scale: settings.scale.unwrap_or(0),
+            output_uri: BorrowedUtf8::optional(output_uri),
+        }
+    }
+}
+
+impl<'a> PrintPageSetup<'a> {
+    #[must_use]
+    pub fn new(page_setup: &'a PageSetup) -> Self {
+        Self {
+            name: BorrowedUtf8::optional(page_setup.name.as_ref()),
+            display_name: BorrowedUtf8::optional(page_setup.display_name.as_ref()),
+            width: page_setup.width.unwrap_or(0.0),
+            height: page_setup.height.unwrap_or(0.0),
+            margin_top: page_setup.margin_top.unwrap_or(0.0),
+            margin_bottom: page_setup.margin_bottom.unwrap_or(0.0),
+            margin_left: page_setup.margin_left.unwrap_or(0.0),
+            margin_right: page_setup.margin_right.unwrap_or(0.0),
+            orientation: print_orientation(page_setup.orientation),
+        }
+    }
+}
+
+/// Returns `None` if the dialog was cancelled by the user.
+pub async fn prepare_print_impl(
+    identifier: Option<WindowIdentifier>,
+    request: PreparePrintRequest,
+) -> anyhow::Result<Option<Box<PreparePrintResult>>> {
+    let connection = Application::dbus_connection().await?;
+    let proxy = PrintProxy::with_connection(connection).await?;
+    let options = PreparePrintOptions::default()
+        .set_modal(request.modal)
+        .set_accept_label(request.accept_label.as_deref())
+        .set_has_current_page(request.has_current_page)
+        .set_has_selected_pages(request.has_selected_pages);
+    let response = proxy
+        .prepare_print(
+            identifier.as_ref(),
+            &request.title,
+            Settings::default(),
+            PageSetup::default(),
+            options,
+        )
+        .await?
+        .response();
+    match response {
+        Ok(prepared) => Ok(Some(Box::new(PreparePrintResult {
+            token: prepared.token,
+            settings: prepared.settings,
+            page_setup: prepared.page_setup,
+        }))),
+        Err(ashpd::Error::Response(ResponseError::Cancelled)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Returns `false` if printing was cancelled by the user.
+pub async fn print_impl(identifier: Option<WindowIdentifier>, request: PrintRequest) -> anyhow::Result<bool> {
+    let connection = Application::dbus_connection().await?;
+    let proxy = PrintProxy::with_connection(connection).await?;
+    let token = (request.token != 0).then_some(request.token);
+    let options = PrintOptions::default().set_modal(request.modal).set_token(token);
+    let response = proxy
+        .print(identifier.as_ref(), &request.title, &request.document, options)
+        .await?
+        .response();
+    match response {
+        Ok(()) => Ok(true),
+        Err(ashpd::Error::Response(ResponseError::Cancelled)) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,119 @@
+use desktop_common::ffi_utils::{BorrowedArray, BorrowedUtf8};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrintOrientation {
+    Unspecified,
+    Portrait,
+    Landscape,
+    ReversePortrait,
+    ReverseLandscape,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrintPages {
+    Unspecified,
+    All,
+    Selection,
+    Current,
+    /// Print the pages specified in `PrintSettings::page_ranges`.
+    Ranges,
+}
+
+/// Print settings chosen by the user. Lengths are in millimeters, and `0` means unspecified.
+#[repr(C)]
+#[derive(Debug)]
+pub struct PrintSettings<'a> {
+    pub orientation: PrintOrientation,
+
+    /// Optional. PWG paper name, e.g. `"iso_a4"`.
+    pub paper_format: BorrowedUtf8<'a>,
+
+    pub paper_width: f64,
+    pub paper_height: f64,
+
+    /// `0` if unspecified.
+    pub n_copies: u32,
+
+    pub print_pages: PrintPages,
+
+    /// Optional. Comma-separated page ranges, e.g. `"0-2,4"`, with 0-based page numbers.
+    pub page_ranges: BorrowedUtf8<'a>,
+
+    /// Scale in percent, or `0` if unspecified.
+    pub scale: u32,
+
+    /// Optional. Present if the user chose to print to a file.
+    pub output_uri: BorrowedUtf8<'a>,
+}
+
+/// Page setup chosen by the user. Lengths are in millimeters, and `0` means unspecified.
+#[repr(C)]
+#[derive(Debug)]
+pub struct PrintPageSetup<'a> {
+    /// Optional. PWG paper name, e.g. `"iso_a4"`.
+    pub name: BorrowedUtf8<'a>,
+
+    /// Optional. User-visible paper name.
+    pub display_name: BorrowedUtf8<'a>,
+
+    pub width: f64,
+    pub height: f64,
+    pub margin_top: f64,
+    pub margin_bottom: f64,
+    pub margin_left: f64,
+    pub margin_right: f64,
+    pub orientation: PrintOrientation,
+}
+
+#[repr(C)]
+pub struct PreparePrintParams<'a> {
+    pub modal: bool,
+
+    /// User-visible title of the print job.
+    pub title: BorrowedUtf8<'a>,
+
+    /// Optional.
+    pub accept_label: BorrowedUtf8<'a>,
+
+    /// Whether the "Current page" option should be offered in the dialog.
+    pub has_current_page: bool,
+
+    /// Whether the "Selection" option should be offered in the dialog.
+    pub has_selected_pages: bool,
+}
+
+impl PreparePrintParams<'_> {
+    pub(crate) fn get_title(&self) -> anyhow::Result<&str> {
+        self.title.get("PreparePrintParams.title")
+    }
+
+    pub(crate) fn get_accept_label(&self) -> anyhow::Result<Option<&str>> {
+        self.accept_label.get_optional("PreparePrintParams.accept_label")
+    }
+}
+
+#[repr(C)]
+pub struct PrintParams<'a> {
+    pub modal: bool,
+
+    /// User-visible title of the print job.
+    pub title: BorrowedUtf8<'a>,
+
+    /// Token from `PreparePrintResponse`. If `0`, the print dialog will be shown before printing.
+    pub token: u32,
+
+    /// The document to print, in the PDF format.
+    pub pdf_data: BorrowedArray<'a, u8>,
+}
+
+impl PrintParams<'_> {
+    pub(crate) fn get_title(&self) -> anyhow::Result<&str> {
+        self.title.get("PrintParams.title")
+    }
+
+    pub(crate) fn get_pdf_data(&self) -> anyhow::Result<&[u8]> {
+        self.pdf_data.as_slice()
+    }
+}
@@ -1,4 +1,5 @@
-use anyhow::Context;
+use anyhow::{Context, bail};
+use desktop_common::perf_trace::TraceSpan;
 use khronos_egl as egl;
 use log::{debug, info};
 use smithay_client_toolkit::reexports::client::{
@@ -7,7 +8,8 @@ use smithay_client_toolkit::reexports::client::{
 };
 use wayland_egl::WlEglSurface;
 
-use crate::linux::{application_state::EglInstance, events::SoftwareDrawData, geometry::PhysicalSize};
+use crate::linux::{application_state::EglInstance, events::SoftwareDrawData, frame_capture::CapturedFrame, geometry::PhysicalSize};
+use std::ffi::{c_int, c_uint, c_void};
 
 #[derive(Debug)]
 pub struct EglRendering<'a> {
@@ -78,7 +80,60 @@ impl<'a> EglRendering<'a> {
         self.wl_egl_surface.resize(size.width.0, size.height.0, 0, 0);
     }
 
-    pub fn draw<F>(&self, surface: &WlSurface, do_draw: F)
+    fn get_gl_f<T: Copy>(&self, name: &str) -> anyhow::Result<T> {
+        let f = self.egl.get_proc_address(name).with_context(|| format!("{name:?} not found"))?;
+        Ok(unsafe { std::mem::transmute_copy::<extern "system" fn(), T>(&f) })
+    }
+
+    /// Reads the back buffer, so it should be called before swapping the buffers.
+    fn read_pixels(&self, size: PhysicalSize) -> anyhow::Result<CapturedFrame> {
+        const GL_NO_ERROR: c_uint = 0;
+        const GL_UNSIGNED_BYTE: c_uint = 0x1401;
+        const GL_RGBA: c_uint = 0x1908;
+        const GL_READ_FRAMEBUFFER: c_uint = 0x8CA8;
+        const GL_READ_FRAMEBUFFER_BINDING: c_uint = 0x8CAA;
+
+        let gl_get_integerv: extern "C" fn(pname: c_uint, data: *mut c_int) = self.get_gl_f("glGetIntegerv")?;
+        let gl_bind_framebuffer: extern "C" fn(target: c_uint, framebuffer: c_uint) = self.get_gl_f("glBindFramebuffer")?;
+        let gl_read_pixels: extern "C" fn(
+            x: c_int,
+            y: c_int,
+            width: c_int,
+            height: c_int,
+            format: c_uint,
+            type_: c_uint,
+            data: *mut c_void,
+        ) = self.get_gl_f("glReadPixels")?;
+        let gl_get_error: extern "C" fn() -> c_uint = self.get_gl_f("glGetError")?;
+
+        let len = usize::try_from(size.width.0)? * usize::try_from(size.height.0)? * 4;
+        let mut data = vec![0_u8; len];
+
+        // The application might have left its own framebuffer bound
+        let mut read_framebuffer = 0;
+        gl_get_integerv(GL_READ_FRAMEBUFFER_BINDING, &raw mut read_framebuffer);
+        gl_bind_framebuffer(GL_READ_FRAMEBUFFER, 0);
+        gl_read_pixels(
+            0,
+            0,
+            size.width.0,
+            size.height.0,
+            GL_RGBA,
+            GL_UNSIGNED_BYTE,
+            data.as_mut_ptr().cast(),
+        );
+        let error = gl_get_error();
+        gl_bind_framebuffer(GL_READ_FRAMEBUFFER, c_uint::try_from(read_framebuffer).unwrap_or(0));
+        if error != GL_NO_ERROR {
+            bail!("glReadPixels failed with {error:#x}");
+        }
+
+        CapturedFrame::from_gl_rgba(size, &data)
+    }
+
+    /// Returns the content of the drawn frame if `capture` is `true`, and the frame was drawn.
+    /// Nothing is read if `do_draw` skipped the frame, the buffer doesn't have the window content then.
+    pub fn draw<F>(&self, surface: &WlSurface, size: PhysicalSize, capture: bool, do_draw: F) -> Option<anyhow::Result<CapturedFrame>>
     where
         F: FnOnce(SoftwareDrawData) -> bool,
     {
@@ -92,11 +147,15 @@ impl<'a> EglRendering<'a> {
             .context("egl.make_current")
             .unwrap();
 
-        if do_draw(SoftwareDrawData::default()) {
-            self.egl
-                .swap_buffers(self.egl_display, self.egl_window_surface)
-                .context(surface.id())
-                .unwrap();
+        if !do_draw(SoftwareDrawData::default()) {
+            return None;
         }
+        let captured = capture.then(|| self.read_pixels(size));
+        let _span = TraceSpan::new("render", "swap_buffers");
+        self.egl
+            .swap_buffers(self.egl_display, self.egl_window_surface)
+            .context(surface.id())
+            .unwrap();
+        captured
     }
 }
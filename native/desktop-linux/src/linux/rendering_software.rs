@@ -8,13 +8,19 @@ use smithay_client_toolkit::{
     },
 };
 
-use crate::linux::{events::SoftwareDrawData, geometry::PhysicalSize};
+use crate::linux::{
+    events::SoftwareDrawData,
+    frame_capture::CapturedFrame,
+    geometry::{PhysicalPixels, PhysicalSize},
+};
 
 #[derive(Debug)]
 pub struct SoftwareRendering {
     pool: SlotPool,
     buffer: Buffer,
     stride: i32,
+    /// Whether `buffer` was attached to the surface.
+    presented: bool,
 }
 
 struct SoftwareBuffer<'a> {
@@ -39,7 +45,12 @@ impl SoftwareRendering {
             shm,
         )?;
         let buffer = Self::create_buffer(&mut pool, size)?.buffer;
-        Ok(Self { pool, buffer, stride })
+        Ok(Self {
+            pool,
+            buffer,
+            stride,
+            presented: false,
+        })
     }
 
     pub fn resize(&mut self, shm: &Shm, size: PhysicalSize) -> anyhow::Result<()> {
@@ -50,7 +61,22 @@ impl SoftwareRendering {
         Ok(())
     }
 
-    pub fn draw<F>(&mut self, surface: &WlSurface, size: PhysicalSize, do_draw: F)
+    /// Copies the content of the buffer that was last attached to the surface, or returns `None` if there is no such buffer.
+    pub fn read_presented_frame(&mut self) -> Option<anyhow::Result<CapturedFrame>> {
+        if !self.presented {
+            return None;
+        }
+        let size = PhysicalSize {
+            width: PhysicalPixels(self.stride / Self::BYTES_PER_PIXEL),
+            height: PhysicalPixels(self.buffer.height()),
+        };
+        let stride = usize::try_from(self.stride).ok()?;
+        let data = self.pool.raw_data_mut(&self.buffer.slot());
+        Some(CapturedFrame::from_argb8888(size, stride, data))
+    }
+
+    /// Returns the content of the drawn frame if `capture` is `true`, and the frame was drawn.
+    pub fn draw<F>(&mut self, surface: &WlSurface, size: PhysicalSize, capture: bool, do_draw: F) -> Option<anyhow::Result<CapturedFrame>>
     where
         F: FnOnce(SoftwareDrawData) -> bool,
     {
@@ -62,11 +88,12 @@ impl SoftwareRendering {
             match Self::create_buffer(&mut self.pool, size) {
                 Ok(second_draw_data) => {
                     self.buffer = second_draw_data.buffer;
+                    self.presented = false;
                     second_draw_data.canvas
                 }
                 Err(e) => {
                     warn!("Failed to create second buffer: {e}");
-                    return;
+                    return None;
                 }
             }
         };
@@ -75,8 +102,11 @@ impl SoftwareRendering {
             canvas: canvas.as_mut_ptr(),
             stride: self.stride,
         };
-        if do_draw(draw_data) {
-            self.buffer.attach_to(surface).expect("buffer attach");
+        if !do_draw(draw_data) {
+            return None;
         }
+        self.buffer.attach_to(surface).expect("buffer attach");
+        self.presented = true;
+        if capture { self.read_presented_frame() } else { None }
     }
 }
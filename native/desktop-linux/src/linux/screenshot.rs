@@ -0,0 +1,43 @@
+use ashpd::{
+    WindowIdentifier,
+    desktop::{Color as PortalColor, ResponseError, screenshot::Screenshot},
+};
+
+use crate::linux::{application::Application, desktop_settings_api::Color};
+
+/// Returns `None` if the request was cancelled by the user.
+pub async fn take_screenshot_impl(identifier: Option<WindowIdentifier>, interactive: bool) -> anyhow::Result<Option<String>> {
+    let connection = Application::dbus_connection().await?;
+    let request = Screenshot::request()
+        .connection(Some(connection))
+        .identifier(identifier)
+        .modal(true)
+        .interactive(interactive)
+        .send()
+        .await?;
+    match request.response() {
+        Ok(screenshot) => Ok(Some(screenshot.uri().to_string())),
+        Err(ashpd::Error::Response(ResponseError::Cancelled)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Returns `None` if the request was cancelled by the user.
+pub async fn pick_color_impl(identifier: Option<WindowIdentifier>) -> anyhow::Result<Option<Color>> {
+    let connection = Application::dbus_connection().await?;
+    let request = PortalColor::pick()
+        .connection(Some(connection))
+        .identifier(identifier)
+        .send()
+        .await?;
+    match request.response() {
+        Ok(color) => Ok(Some(Color {
+            red: color.red(),
+            green: color.green(),
+            blue: color.blue(),
+            alpha: 1.0,
+        })),
+        Err(ashpd::Error::Response(ResponseError::Cancelled)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
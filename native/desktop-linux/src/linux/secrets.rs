@@ -0,0 +1,743 @@
+use crate::linux::secrets_api::{SecretAttribute, SecretErrorKind};
+use futures_lite::StreamExt;
+use log::debug;
+use std::collections::HashMap;
+use zbus::{
+    DBusError as _, proxy,
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+};
+
+// See https://specifications.freedesktop.org/secret-service-spec/latest/
+
+const DEFAULT_COLLECTION_ALIAS: &str = "default";
+const ITEM_LABEL_PROPERTY: &str = "org.freedesktop.Secret.Item.Label";
+const ITEM_ATTRIBUTES_PROPERTY: &str = "org.freedesktop.Secret.Item.Attributes";
+const SECRET_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// `(session, parameters, value, content_type)`
+type Secret = (OwnedObjectPath, Vec<u8>, Vec<u8>, String);
+
+#[proxy(
+    interface = "org.freedesktop.Secret.Service",
+    default_service = "org.freedesktop.secrets",
+    default_path = "/org/freedesktop/secrets"
+)]
+trait SecretService {
+    fn open_session(&self, algorithm: &str, input: &Value<'_>) -> zbus::Result<(OwnedValue, OwnedObjectPath)>;
+
+    /// Returns the unlocked objects, and a prompt (or `/`) for unlocking the rest.
+    fn unlock(&self, objects: &[ObjectPath<'_>]) -> zbus::Result<(Vec<OwnedObjectPath>, OwnedObjectPath)>;
+
+    fn get_secrets(&self, items: &[ObjectPath<'_>], session: &ObjectPath<'_>) -> zbus::Result<HashMap<OwnedObjectPath, Secret>>;
+
+    /// Returns `/` if there is no collection with the alias.
+    fn read_alias(&self, name: &str) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[proxy(interface = "org.freedesktop.Secret.Collection", default_service = "org.freedesktop.secrets")]
+trait SecretCollection {
+    /// Returns the created item (or `/` if a prompt is needed), and a prompt (or `/`).
+    fn create_item(
+        &self,
+        properties: HashMap<&str, Value<'_>>,
+        secret: &Secret,
+        replace: bool,
+    ) -> zbus::Result<(OwnedObjectPath, OwnedObjectPath)>;
+
+    fn search_items(&self, attributes: &HashMap<String, String>) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[proxy(interface = "org.freedesktop.Secret.Item", default_service = "org.freedesktop.secrets")]
+trait SecretItem {
+    /// Returns a prompt, or `/`.
+    fn delete(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn label(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn attributes(&self) -> zbus::Result<HashMap<String, String>>;
+}
+
+#[proxy(interface = "org.freedesktop.Secret.Session", default_service = "org.freedesktop.secrets")]
+trait SecretSession {
+    fn close(&self) -> zbus::Result<()>;
+}
+
+#[proxy(interface = "org.freedesktop.Secret.Prompt", default_service = "org.freedesktop.secrets")]
+trait SecretPrompt {
+    fn prompt(&self, window_id: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn completed(&self, dismissed: bool, result: OwnedValue) -> zbus::Result<()>;
+}
+
+#[derive(Debug)]
+pub enum SecretError {
+    /// There is no Secret Service implementation (e.g. GNOME Keyring or KDE Wallet) running.
+    ServiceUnavailable(zbus::Error),
+
+    /// The user dismissed the prompt for unlocking the collection or the items.
+    Locked,
+
+    Other(anyhow::Error),
+}
+
+impl SecretError {
+    #[must_use]
+    pub const fn kind(&self) -> SecretErrorKind {
+        match self {
+            Self::ServiceUnavailable(_) => SecretErrorKind::ServiceUnavailable,
+            Self::Locked => SecretErrorKind::Locked,
+            Self::Other(_) => SecretErrorKind::Other,
+        }
+    }
+}
+
+impl std::fmt::Display for SecretError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ServiceUnavailable(e) => write!(f, "Secret Service is not available: {e}"),
+            Self::Locked => write!(f, "Secret Service collection is locked"),
+            Self::Other(e) => write!(f, "{e:#}"),
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+impl From<zbus::Error> for SecretError {
+    fn from(e: zbus::Error) -> Self {
+        let error_name = match &e {
+            zbus::Error::MethodError(name, _, _) => Some(name.to_string()),
+            zbus::Error::FDO(fdo_error) => Some(fdo_error.name().to_string()),
+            _ => None,
+        };
+        match error_name.as_deref() {
+            Some("org.freedesktop.DBus.Error.ServiceUnknown" | "org.freedesktop.DBus.Error.NameHasNoOwner") => Self::ServiceUnavailable(e),
+            Some("org.freedesktop.Secret.Error.IsLocked") => Self::Locked,
+            _ => Self::Other(e.into()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for SecretError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Other(e)
+    }
+}
+
+pub struct SecretItemData {
+    pub label: String,
+
+    /// Sorted by name.
+    pub attributes: Vec<(String, String)>,
+
+    pub secret: Vec<u8>,
+}
+
+/// Intentionally doesn't implement `Debug`, to avoid logging the secrets.
+pub enum SecretRequest {
+    Store {
+        label: String,
+        attributes: HashMap<String, String>,
+        secret: Vec<u8>,
+    },
+    Lookup {
+        attributes: HashMap<String, String>,
+    },
+    Search {
+        attributes: HashMap<String, String>,
+    },
+    Delete {
+        attributes: HashMap<String, String>,
+    },
+}
+
+pub fn secret_attributes(attributes: &[SecretAttribute]) -> anyhow::Result<HashMap<String, String>> {
+    attributes
+        .iter()
+        .map(|attribute| {
+            Ok((
+                attribute.name.get("SecretAttribute.name")?.to_owned(),
+                attribute.value.get("SecretAttribute.value")?.to_owned(),
+            ))
+        })
+        .collect()
+}
+
+fn is_none_path(path: &ObjectPath<'_>) -> bool {
+    path.as_str() == "/"
+}
+
+/// Client of the Secret Service API, operating on the default collection.
+///
+/// Takes the connection as a parameter, so that it can be used with any bus that has an `org.freedesktop.secrets` implementation.
+pub struct SecretServiceClient<'a> {
+    connection: zbus::Connection,
+    service: SecretServiceProxy<'a>,
+}
+
+impl SecretServiceClient<'_> {
+    pub async fn new(connection: zbus::Connection) -> Result<Self, SecretError> {
+        let service = SecretServiceProxy::new(&connection).await?;
+        Ok(Self { connection, service })
+    }
+
+    async fn default_collection(&self) -> Result<SecretCollectionProxy<'_>, SecretError> {
+        let path = self.service.read_alias(DEFAULT_COLLECTION_ALIAS).await?;
+        if is_none_path(&path) {
+            return Err(anyhow::anyhow!("Secret Service has no default collection").into());
+        }
+        Ok(SecretCollectionProxy::new(&self.connection, path).await?)
+    }
+
+    /// Shows the prompt, and waits for it to be completed. Returns `false` if it was dismissed by the user.
+    async fn complete_prompt(&self, prompt_path: OwnedObjectPath) -> Result<bool, SecretError> {
+        if is_none_path(&prompt_path) {
+            return Ok(true);
+        }
+        debug!("Secret Service prompt: {prompt_path}");
+        let prompt = SecretPromptProxy::new(&self.connection, prompt_path).await?;
+        let mut completed = prompt.receive_completed().await?;
+        prompt.prompt("").await?;
+        let signal = completed
+            .next()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("Secret Service prompt was destroyed before completing"))?;
+        Ok(!signal.args()?.dismissed)
+    }
+
+    async fn unlock(&self, objects: &[ObjectPath<'_>]) -> Result<(), SecretError> {
+        if objects.is_empty() {
+            return Ok(());
+        }
+        let (_, prompt) = self.service.unlock(objects).await?;
+        if self.complete_prompt(prompt).await? {
+            Ok(())
+        } else {
+            Err(SecretError::Locked)
+        }
+    }
+
+    /// Uses the `plain` algorithm, since the session bus is not accessible by other users.
+    async fn with_session<T>(&self, f: impl AsyncFnOnce(&ObjectPath<'_>) -> Result<T, SecretError>) -> Result<T, SecretError> {
+        let (_, session_path) = self.service.open_session("plain", &Value::from("")).await?;
+        let result = f(&session_path).await;
+        let session = SecretSessionProxy::new(&self.connection, &session_path).await?;
+        if let Err(e) = session.close().await {
+            debug!("Error closing the Secret Service session {session_path}: {e}");
+        }
+        result
+    }
+
+    pub async fn store(&self, label: &str, attributes: &HashMap<String, String>, secret: &[u8]) -> Result<(), SecretError> {
+        let collection = self.default_collection().await?;
+        self.unlock(&[collection.inner().path().clone()]).await?;
+        self.with_session(async |session| {
+            let properties = HashMap::from([
+                (ITEM_LABEL_PROPERTY, Value::from(label)),
+                (ITEM_ATTRIBUTES_PROPERTY, Value::from(attributes.clone())),
+            ]);
+            let secret = (
+                session.to_owned().into(),
+                Vec::new(),
+                secret.to_vec(),
+                SECRET_CONTENT_TYPE.to_owned(),
+            );
+            let (_, prompt) = collection.create_item(properties, &secret, true).await?;
+            if self.complete_prompt(prompt).await? {
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!("Storing the secret was cancelled by the user").into())
+            }
+        })
+        .await
+    }
+
+    /// Empty `attributes` match every item, which is never what the caller wants, especially for `delete`.
+    async fn search_item_paths(&self, attributes: &HashMap<String, String>) -> Result<Vec<OwnedObjectPath>, SecretError> {
+        if attributes.is_empty() {
+            return Err(anyhow::anyhow!("At least one attribute is required to find the secrets").into());
+        }
+        let collection = self.default_collection().await?;
+        Ok(collection.search_items(attributes).await?)
+    }
+
+    async fn read_items(&self, paths: Vec<OwnedObjectPath>) -> Result<Vec<SecretItemData>, SecretError> {
+        let paths = paths.into_iter().map(ObjectPath::from).collect::<Vec<_>>();
+        self.unlock(&paths).await?;
+        let mut secrets = self
+            .with_session(async |session| Ok(self.service.get_secrets(&paths, session).await?))
+            .await?;
+        let mut items = Vec::with_capacity(paths.len());
+        for path in paths {
+            let Some((_, _, secret, _)) = secrets.remove(&OwnedObjectPath::from(path.clone())) else {
+                continue;
+            };
+            let item = SecretItemProxy::new(&self.connection, path).await?;
+            let mut attributes = item.attributes().await?.into_iter().collect::<Vec<_>>();
+            attributes.sort();
+            items.push(SecretItemData {
+                label: item.label().await?,
+                attributes,
+                secret,
+            });
+        }
+        Ok(items)
+    }
+
+    /// Returns the first item matching all the `attributes`.
+    pub async fn lookup(&self, attributes: &HashMap<String, String>) -> Result<Option<SecretItemData>, SecretError> {
+        let mut paths = self.search_item_paths(attributes).await?;
+        paths.truncate(1);
+        Ok(self.read_items(paths).await?.pop())
+    }
+
+    /// Returns all the items matching all the `attributes`.
+    pub async fn search(&self, attributes: &HashMap<String, String>) -> Result<Vec<SecretItemData>, SecretError> {
+        let paths = self.search_item_paths(attributes).await?;
+        self.read_items(paths).await
+    }
+
+    /// Deletes all the items matching all the `attributes`.
+    pub async fn delete(&self, attributes: &HashMap<String, String>) -> Result<(), SecretError> {
+        let paths = self.search_item_paths(attributes).await?;
+        let paths = paths.into_iter().map(ObjectPath::from).collect::<Vec<_>>();
+        self.unlock(&paths).await?;
+        for path in paths {
+            let item = SecretItemProxy::new(&self.connection, path).await?;
+            let prompt = item.delete().await?;
+            if !self.complete_prompt(prompt).await? {
+                return Err(anyhow::anyhow!("Deleting the secret was cancelled by the user").into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the items found by `Lookup` and `Search` requests, and nothing for the other requests.
+    pub async fn execute(&self, request: SecretRequest) -> Result<Vec<SecretItemData>, SecretError> {
+        match request {
+            SecretRequest::Store { label, attributes, secret } => self.store(&label, &attributes, &secret).await.map(|()| Vec::new()),
+            SecretRequest::Lookup { attributes } => Ok(self.lookup(&attributes).await?.into_iter().collect()),
+            SecretRequest::Search { attributes } => self.search(&attributes).await,
+            SecretRequest::Delete { attributes } => self.delete(&attributes).await.map(|()| Vec::new()),
+        }
+    }
+}
+
+pub async fn secret_request_impl(connection: zbus::Connection, request: SecretRequest) -> Result<Vec<SecretItemData>, SecretError> {
+    SecretServiceClient::new(connection).await?.execute(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use zbus::{ObjectServer, fdo, interface, object_server::SignalEmitter, zvariant::Str};
+
+    const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+    const COLLECTION_PATH: &str = "/org/freedesktop/secrets/collection/login";
+    const SESSION_PATH: &str = "/org/freedesktop/secrets/session/s1";
+    const PROMPT_PATH: &str = "/org/freedesktop/secrets/prompt/p1";
+
+    #[derive(Debug, zbus::DBusError)]
+    #[zbus(prefix = "org.freedesktop.Secret.Error")]
+    enum StandInError {
+        #[zbus(error)]
+        ZBus(zbus::Error),
+        IsLocked(String),
+        NoSuchObject(String),
+    }
+
+    struct StandInItem {
+        label: String,
+        attributes: HashMap<String, String>,
+        secret: Vec<u8>,
+        deleted: bool,
+    }
+
+    #[derive(Default)]
+    struct StandInState {
+        /// Replies like the bus does when nothing owns `org.freedesktop.secrets`.
+        service_unknown: bool,
+        locked: bool,
+        dismiss_prompt: bool,
+        prompt_count: usize,
+        items: Vec<StandInItem>,
+    }
+
+    type SharedState = Arc<Mutex<StandInState>>;
+
+    fn item_path(index: usize) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(format!("{COLLECTION_PATH}/{index}")).unwrap()
+    }
+
+    fn none_path() -> OwnedObjectPath {
+        OwnedObjectPath::try_from("/").unwrap()
+    }
+
+    struct StandInService(SharedState);
+
+    impl StandInService {
+        fn check_available(&self) -> fdo::Result<()> {
+            if self.0.lock().unwrap().service_unknown {
+                return Err(fdo::Error::ServiceUnknown(
+                    "The name org.freedesktop.secrets was not provided by any .service files".to_owned(),
+                ));
+            }
+            Ok(())
+        }
+    }
+
+    // D-Bus methods take their arguments by value
+    #[allow(clippy::needless_pass_by_value)]
+    #[interface(name = "org.freedesktop.Secret.Service")]
+    impl StandInService {
+        fn open_session(&self, algorithm: String, input: OwnedValue) -> fdo::Result<(OwnedValue, OwnedObjectPath)> {
+            self.check_available()?;
+            assert_eq!((algorithm.as_str(), String::try_from(input).unwrap().as_str()), ("plain", ""));
+            Ok((OwnedValue::from(Str::from("")), OwnedObjectPath::try_from(SESSION_PATH).unwrap()))
+        }
+
+        fn unlock(&self, objects: Vec<OwnedObjectPath>) -> fdo::Result<(Vec<OwnedObjectPath>, OwnedObjectPath)> {
+            self.check_available()?;
+            if self.0.lock().unwrap().locked {
+                Ok((Vec::new(), OwnedObjectPath::try_from(PROMPT_PATH).unwrap()))
+            } else {
+                Ok((objects, none_path()))
+            }
+        }
+
+        /// Skips the locked items, like the real implementations.
+        fn get_secrets(&self, items: Vec<OwnedObjectPath>, session: OwnedObjectPath) -> fdo::Result<HashMap<OwnedObjectPath, Secret>> {
+            self.check_available()?;
+            let state = self.0.lock().unwrap();
+            if state.locked {
+                return Ok(HashMap::new());
+            }
+            Ok(state
+                .items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| (item_path(index), item))
+                .filter(|(path, item)| !item.deleted && items.contains(path))
+                .map(|(path, item)| {
+                    let secret = (session.clone(), Vec::new(), item.secret.clone(), SECRET_CONTENT_TYPE.to_owned());
+                    (path, secret)
+                })
+                .collect())
+        }
+
+        fn read_alias(&self, name: String) -> fdo::Result<OwnedObjectPath> {
+            self.check_available()?;
+            Ok(if name == DEFAULT_COLLECTION_ALIAS {
+                OwnedObjectPath::try_from(COLLECTION_PATH).unwrap()
+            } else {
+                none_path()
+            })
+        }
+    }
+
+    struct StandInCollection(SharedState);
+
+    // D-Bus methods take their arguments by value
+    #[allow(clippy::needless_pass_by_value)]
+    #[interface(name = "org.freedesktop.Secret.Collection")]
+    impl StandInCollection {
+        async fn create_item(
+            &self,
+            #[zbus(object_server)] object_server: &ObjectServer,
+            properties: HashMap<String, OwnedValue>,
+            secret: Secret,
+            replace: bool,
+        ) -> Result<(OwnedObjectPath, OwnedObjectPath), StandInError> {
+            let label = String::try_from(properties[ITEM_LABEL_PROPERTY].try_clone().unwrap()).unwrap();
+            let attributes = HashMap::<String, String>::try_from(properties[ITEM_ATTRIBUTES_PROPERTY].try_clone().unwrap()).unwrap();
+            let (_, _, value, content_type) = secret;
+            assert_eq!(content_type, SECRET_CONTENT_TYPE);
+            let (index, is_new) = {
+                let mut state = self.0.lock().unwrap();
+                if state.locked {
+                    return Err(StandInError::IsLocked("The collection is locked".to_owned()));
+                }
+                let existing = state.items.iter().position(|item| !item.deleted && item.attributes == attributes);
+                match existing {
+                    Some(index) if replace => {
+                        let item = &mut state.items[index];
+                        item.label = label;
+                        item.secret = value;
+                        (index, false)
+                    }
+                    _ => {
+                        state.items.push(StandInItem {
+                            label,
+                            attributes,
+                            secret: value,
+                            deleted: false,
+                        });
+                        (state.items.len() - 1, true)
+                    }
+                }
+            };
+            let path = item_path(index);
+            if is_new {
+                object_server.at(&path, StandInItemObject(self.0.clone(), index)).await?;
+            }
+            Ok((path, none_path()))
+        }
+
+        fn search_items(&self, attributes: HashMap<String, String>) -> Vec<OwnedObjectPath> {
+            let state = self.0.lock().unwrap();
+            state
+                .items
+                .iter()
+                .enumerate()
+                .filter(|(_, item)| !item.deleted && attributes.iter().all(|(k, v)| item.attributes.get(k) == Some(v)))
+                .map(|(index, _)| item_path(index))
+                .collect()
+        }
+    }
+
+    struct StandInItemObject(SharedState, usize);
+
+    impl StandInItemObject {
+        fn with_item<T>(&self, f: impl FnOnce(&mut StandInItem) -> T) -> Result<T, StandInError> {
+            let mut state = self.0.lock().unwrap();
+            match state.items.get_mut(self.1) {
+                Some(item) if !item.deleted => Ok(f(item)),
+                _ => Err(StandInError::NoSuchObject(format!("No item {}", self.1))),
+            }
+        }
+    }
+
+    #[interface(name = "org.freedesktop.Secret.Item")]
+    impl StandInItemObject {
+        fn delete(&self) -> Result<OwnedObjectPath, StandInError> {
+            self.with_item(|item| item.deleted = true)?;
+            Ok(none_path())
+        }
+
+        #[zbus(property)]
+        fn label(&self) -> fdo::Result<String> {
+            self.with_item(|item| item.label.clone())
+                .map_err(|e| fdo::Error::UnknownObject(e.to_string()))
+        }
+
+        #[zbus(property)]
+        fn attributes(&self) -> fdo::Result<HashMap<String, String>> {
+            self.with_item(|item| item.attributes.clone())
+                .map_err(|e| fdo::Error::UnknownObject(e.to_string()))
+        }
+    }
+
+    struct StandInSession;
+
+    #[interface(name = "org.freedesktop.Secret.Session")]
+    impl StandInSession {
+        #[allow(clippy::unused_self)]
+        const fn close(&self) {}
+    }
+
+    struct StandInPrompt(SharedState);
+
+    // D-Bus methods take their arguments by value
+    #[allow(clippy::needless_pass_by_value)]
+    #[interface(name = "org.freedesktop.Secret.Prompt")]
+    impl StandInPrompt {
+        async fn prompt(&self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>, window_id: String) -> fdo::Result<()> {
+            assert_eq!(window_id, "");
+            let dismissed = {
+                let mut state = self.0.lock().unwrap();
+                state.prompt_count += 1;
+                if !state.dismiss_prompt {
+                    state.locked = false;
+                }
+                state.dismiss_prompt
+            };
+            Ok(Self::completed(&emitter, dismissed, Value::from(Vec::<OwnedObjectPath>::new())).await?)
+        }
+
+        #[zbus(signal)]
+        async fn completed(emitter: &SignalEmitter<'_>, dismissed: bool, result: Value<'_>) -> zbus::Result<()>;
+    }
+
+    /// Runs `f` with a client connected to a stand-in Secret Service over a peer-to-peer connection.
+    fn with_stand_in_service(state: StandInState, f: impl AsyncFnOnce(SecretServiceClient<'_>, SharedState)) {
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        rt.block_on(async {
+            let state = Arc::new(Mutex::new(state));
+            let (server_stream, client_stream) = tokio::net::UnixStream::pair().unwrap();
+            let server = zbus::connection::Builder::unix_stream(server_stream)
+                .p2p()
+                .server(zbus::Guid::generate())
+                .unwrap()
+                .serve_at(SERVICE_PATH, StandInService(state.clone()))
+                .unwrap()
+                .serve_at(COLLECTION_PATH, StandInCollection(state.clone()))
+                .unwrap()
+                .serve_at(SESSION_PATH, StandInSession)
+                .unwrap()
+                .serve_at(PROMPT_PATH, StandInPrompt(state.clone()))
+                .unwrap()
+                .build();
+            let client = zbus::connection::Builder::unix_stream(client_stream).p2p().build();
+            let (server, client) = futures_lite::future::zip(server, client).await;
+            let _server = server.unwrap();
+            let client = SecretServiceClient::new(client.unwrap()).await.unwrap();
+            f(client, state).await;
+        });
+    }
+
+    fn attributes(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|&(k, v)| (k.to_owned(), v.to_owned())).collect()
+    }
+
+    fn sorted_attributes(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut attributes = attributes(pairs).into_iter().collect::<Vec<_>>();
+        attributes.sort();
+        attributes
+    }
+
+    #[test]
+    fn test_store_lookup_search_delete() {
+        with_stand_in_service(StandInState::default(), async |client, state| {
+            let alice = attributes(&[("service", "example"), ("user", "alice")]);
+            let bob = attributes(&[("service", "example"), ("user", "bob")]);
+            let service = attributes(&[("service", "example")]);
+
+            client.store("Alice", &alice, b"secret-a").await.unwrap();
+            client.store("Bob", &bob, b"secret-b").await.unwrap();
+
+            let item = client.lookup(&alice).await.unwrap().unwrap();
+            assert_eq!(item.label, "Alice");
+            assert_eq!(item.attributes, sorted_attributes(&[("service", "example"), ("user", "alice")]));
+            assert_eq!(item.secret, b"secret-a");
+
+            // Replaces the item with the same attributes
+            client.store("Alice 2", &alice, b"secret-a2").await.unwrap();
+            let item = client.lookup(&alice).await.unwrap().unwrap();
+            assert_eq!((item.label.as_str(), item.secret.as_slice()), ("Alice 2", b"secret-a2".as_slice()));
+
+            let mut labels = client
+                .search(&service)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|item| item.label)
+                .collect::<Vec<_>>();
+            labels.sort();
+            assert_eq!(labels, ["Alice 2", "Bob"]);
+            assert!(client.search(&attributes(&[("service", "other")])).await.unwrap().is_empty());
+            assert!(client.lookup(&attributes(&[("user", "carol")])).await.unwrap().is_none());
+
+            client.delete(&alice).await.unwrap();
+            assert!(client.lookup(&alice).await.unwrap().is_none());
+            assert_eq!(client.search(&service).await.unwrap().len(), 1);
+
+            // Deleting nothing is not an error
+            client.delete(&alice).await.unwrap();
+            assert_eq!(state.lock().unwrap().prompt_count, 0);
+        });
+    }
+
+    #[test]
+    fn test_empty_attributes() {
+        with_stand_in_service(StandInState::default(), async |client, state| {
+            client.store("Label", &attributes(&[("key", "value")]), b"secret").await.unwrap();
+
+            let e = client.delete(&attributes(&[])).await.unwrap_err();
+            assert_eq!(e.kind(), SecretErrorKind::Other, "{e}");
+            let e = client.search(&attributes(&[])).await.err().unwrap();
+            assert_eq!(e.kind(), SecretErrorKind::Other, "{e}");
+            let e = client.lookup(&attributes(&[])).await.err().unwrap();
+            assert_eq!(e.kind(), SecretErrorKind::Other, "{e}");
+
+            assert!(state.lock().unwrap().items.iter().all(|item| !item.deleted));
+        });
+    }
+
+    #[test]
+    fn test_unlock_prompt() {
+        let state = StandInState {
+            locked: true,
+            ..Default::default()
+        };
+        with_stand_in_service(state, async |client, state| {
+            let request = SecretRequest::Store {
+                label: "Label".to_owned(),
+                attributes: attributes(&[("key", "value")]),
+                secret: b"secret".to_vec(),
+            };
+            client.execute(request).await.unwrap();
+            assert_eq!(state.lock().unwrap().prompt_count, 1);
+            assert!(!state.lock().unwrap().locked);
+
+            let items = client
+                .execute(SecretRequest::Search {
+                    attributes: attributes(&[("key", "value")]),
+                })
+                .await
+                .unwrap();
+            assert_eq!(items.len(), 1);
+            assert_eq!(items[0].secret, b"secret");
+            assert_eq!(state.lock().unwrap().prompt_count, 1);
+        });
+    }
+
+    #[test]
+    fn test_dismissed_prompt() {
+        let state = StandInState {
+            locked: true,
+            dismiss_prompt: true,
+            ..Default::default()
+        };
+        with_stand_in_service(state, async |client, state| {
+            let e = client
+                .store("Label", &attributes(&[("key", "value")]), b"secret")
+                .await
+                .unwrap_err();
+            assert_eq!(e.kind(), SecretErrorKind::Locked, "{e}");
+            assert!(state.lock().unwrap().items.is_empty());
+
+            // Searching doesn't need unlocking, reading the secrets does
+            state.lock().unwrap().items.push(StandInItem {
+                label: "Label".to_owned(),
+                attributes: attributes(&[("key", "value")]),
+                secret: b"secret".to_vec(),
+                deleted: false,
+            });
+            let e = client.lookup(&attributes(&[("key", "value")])).await.err().unwrap();
+            assert_eq!(e.kind(), SecretErrorKind::Locked, "{e}");
+            assert_eq!(state.lock().unwrap().prompt_count, 2);
+        });
+    }
+
+    #[test]
+    fn test_missing_service() {
+        let state = StandInState {
+            service_unknown: true,
+            ..Default::default()
+        };
+        with_stand_in_service(state, async |client, _state| {
+            let e = client.lookup(&attributes(&[("key", "value")])).await.err().unwrap();
+            assert_eq!(e.kind(), SecretErrorKind::ServiceUnavailable, "{e}");
+            let e = client.store("Label", &attributes(&[]), b"secret").await.unwrap_err();
+            assert_eq!(e.kind(), SecretErrorKind::ServiceUnavailable, "{e}");
+        });
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        let locked = zbus::Error::MethodError(
+            "org.freedesktop.Secret.Error.IsLocked".try_into().unwrap(),
+            None,
+            zbus::message::Message::method_call("/", "Test").unwrap().build(&()).unwrap(),
+        );
+        assert_eq!(SecretError::from(locked).kind(), SecretErrorKind::Locked);
+        let no_owner = zbus::Error::FDO(Box::new(fdo::Error::NameHasNoOwner(String::new())));
+        assert_eq!(SecretError::from(no_owner).kind(), SecretErrorKind::ServiceUnavailable);
+        let unknown_object = zbus::Error::FDO(Box::new(fdo::Error::UnknownObject(String::new())));
+        assert_eq!(SecretError::from(unknown_object).kind(), SecretErrorKind::Other);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::linux::{
+    application::Application,
+    application_api::AppPtr,
+    events::RequestId,
+    secrets::{SecretRequest, secret_attributes},
+};
+use anyhow::ensure;
+use desktop_common::{
+    ffi_utils::{BorrowedArray, BorrowedUtf8},
+    logger::ffi_boundary,
+};
+use log::debug;
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct SecretAttribute<'a> {
+    pub name: BorrowedUtf8<'a>,
+    pub value: BorrowedUtf8<'a>,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct SecretItem<'a> {
+    pub label: BorrowedUtf8<'a>,
+    pub attributes: BorrowedArray<'a, SecretAttribute<'a>>,
+    pub secret: BorrowedArray<'a, u8>,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretErrorKind {
+    None,
+
+    /// There is no Secret Service implementation (e.g. GNOME Keyring or KDE Wallet) running.
+    ServiceUnavailable,
+
+    /// The user dismissed the prompt for unlocking the collection or the items.
+    Locked,
+
+    Other,
+}
+
+/// Stores the secret in the default collection of the Secret Service, replacing the item with the same `attributes`, if any.
+/// The result is reported with `Event::SecretResponse`.
+///
+/// * `label`: User-visible label of the item.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_secret_store(
+    mut app_ptr: AppPtr,
+    label: BorrowedUtf8,
+    attributes: BorrowedArray<SecretAttribute>,
+    secret: BorrowedArray<u8>,
+) -> RequestId {
+    debug!("application_secret_store");
+    ffi_boundary("application_secret_store", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        let request = SecretRequest::Store {
+            label: label.get("application_secret_store: label")?.to_owned(),
+            attributes: secret_attributes(attributes.as_slice()?)?,
+            secret: secret.as_slice()?.to_vec(),
+        };
+        Ok(app.secret_request(request))
+    })
+}
+
+/// Looks up the first item in the default collection of the Secret Service that matches all the `attributes`.
+/// The result is reported with `Event::SecretResponse`, with at most one item.
+///
+/// `attributes` must not be empty, since it would match every item.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_secret_lookup(mut app_ptr: AppPtr, attributes: BorrowedArray<SecretAttribute>) -> RequestId {
+    debug!("application_secret_lookup");
+    ffi_boundary("application_secret_lookup", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        let attributes = secret_attributes(attributes.as_slice()?)?;
+        ensure!(!attributes.is_empty(), "application_secret_lookup: attributes must not be empty");
+        let request = SecretRequest::Lookup { attributes };
+        Ok(app.secret_request(request))
+    })
+}
+
+/// Searches the default collection of the Secret Service for all the items that match all the `attributes`.
+/// The result is reported with `Event::SecretResponse`.
+///
+/// `attributes` must not be empty, since it would match every item.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_secret_search(mut app_ptr: AppPtr, attributes: BorrowedArray<SecretAttribute>) -> RequestId {
+    debug!("application_secret_search");
+    ffi_boundary("application_secret_search", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        let attributes = secret_attributes(attributes.as_slice()?)?;
+        ensure!(!attributes.is_empty(), "application_secret_search: attributes must not be empty");
+        let request = SecretRequest::Search { attributes };
+        Ok(app.secret_request(request))
+    })
+}
+
+/// Deletes all the items in the default collection of the Secret Service that match all the `attributes`.
+/// The result is reported with `Event::SecretResponse`, without items.
+///
+/// `attributes` must not be empty, since it would match every item.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_secret_delete(mut app_ptr: AppPtr, attributes: BorrowedArray<SecretAttribute>) -> RequestId {
+    debug!("application_secret_delete");
+    ffi_boundary("application_secret_delete", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        let attributes = secret_attributes(attributes.as_slice()?)?;
+        ensure!(!attributes.is_empty(), "application_secret_delete: attributes must not be empty");
+        let request = SecretRequest::Delete { attributes };
+        Ok(app.secret_request(request))
+    })
+}
@@ -0,0 +1,108 @@
+use crate::linux::{
+    application::Application,
+    events::{SessionState, SessionStateChangedEvent},
+    session_api::InhibitFlags,
+};
+use ashpd::{
+    WindowIdentifier,
+    desktop::inhibit::{CreateMonitorOptions, InhibitOptions, InhibitProxy},
+    enumflags2::BitFlags,
+};
+use futures_lite::StreamExt;
+use log::{debug, warn};
+
+#[derive(Debug)]
+pub struct InhibitRequest {
+    flags: BitFlags<ashpd::desktop::inhibit::InhibitFlags>,
+    reason: String,
+}
+
+impl InhibitRequest {
+    pub fn new(flags: InhibitFlags, reason: &str) -> Self {
+        let mut portal_flags = BitFlags::empty();
+        if flags.contains(InhibitFlags::Logout) {
+            portal_flags |= ashpd::desktop::inhibit::InhibitFlags::Logout;
+        }
+        if flags.contains(InhibitFlags::UserSwitch) {
+            portal_flags |= ashpd::desktop::inhibit::InhibitFlags::UserSwitch;
+        }
+        if flags.contains(InhibitFlags::Suspend) {
+            portal_flags |= ashpd::desktop::inhibit::InhibitFlags::Suspend;
+        }
+        if flags.contains(InhibitFlags::Idle) {
+            portal_flags |= ashpd::desktop::inhibit::InhibitFlags::Idle;
+        }
+        Self {
+            flags: portal_flags,
+            reason: reason.to_owned(),
+        }
+    }
+}
+
+/// The inhibition lasts until `uninhibit_receiver` receives a value or its sender is dropped.
+pub async fn inhibit_impl(
+    identifier: Option<WindowIdentifier>,
+    request: InhibitRequest,
+    uninhibit_receiver: tokio::sync::oneshot::Receiver<()>,
+) -> anyhow::Result<()> {
+    let connection = Application::dbus_connection().await?;
+    let proxy = InhibitProxy::with_connection(connection).await?;
+    let options = InhibitOptions::default().set_reason(request.reason.as_str());
+    let inhibit_request = proxy.inhibit(identifier.as_ref(), request.flags, options).await?;
+    inhibit_request.response()?;
+    debug!("Inhibited {:?}", request.flags);
+    tokio::spawn(async move {
+        let _ = uninhibit_receiver.await;
+        match inhibit_request.close().await {
+            Ok(()) => debug!("Uninhibited {:?}", request.flags),
+            Err(e) => warn!("Error removing the inhibition of {:?}: {e}", request.flags),
+        }
+    });
+    Ok(())
+}
+
+const fn session_state(state: ashpd::desktop::inhibit::SessionState) -> SessionState {
+    match state {
+        ashpd::desktop::inhibit::SessionState::Running => SessionState::Running,
+        ashpd::desktop::inhibit::SessionState::QueryEnd => SessionState::QueryEnd,
+        ashpd::desktop::inhibit::SessionState::Ending => SessionState::Ending,
+    }
+}
+
+/// Monitors the login session state, and answers `QueryEnd` when requested with `query_end_response_receiver`.
+pub async fn init_session_monitor_task(
+    sender: impl Fn(SessionStateChangedEvent) -> anyhow::Result<()> + Send + Sync + 'static,
+    mut query_end_response_receiver: tokio::sync::mpsc::Receiver<()>,
+) -> anyhow::Result<()> {
+    let connection = Application::dbus_connection().await?;
+    let proxy = InhibitProxy::with_connection(connection).await?;
+    let mut stream = proxy.receive_state_changed().await?;
+    let session = proxy.create_monitor(None, CreateMonitorOptions::default()).await?;
+    debug!("Session monitor started, Inhibit portal version {}", proxy.version());
+
+    let state_changes = async {
+        while let Some(state) = stream.next().await {
+            debug!(
+                "Session state changed: {:?}, screensaver_active={}",
+                state.session_state(),
+                state.screensaver_active()
+            );
+            sender(SessionStateChangedEvent {
+                session_state: session_state(state.session_state()),
+                screensaver_active: state.screensaver_active(),
+            })?;
+        }
+        anyhow::Ok(())
+    };
+    let query_end_responses = async {
+        while query_end_response_receiver.recv().await.is_some() {
+            debug!("Sending the session QueryEnd response");
+            proxy.query_end_response(&session).await?;
+        }
+        anyhow::Ok(())
+    };
+    let result = futures_lite::future::or(state_changes, query_end_responses).await;
+    session.close().await?;
+    debug!("Session monitor stopped");
+    result
+}
@@ -0,0 +1,64 @@
+use crate::linux::{
+    application::Application,
+    application_api::AppPtr,
+    events::{RequestId, WindowId},
+};
+use bitflag_attr::bitflag;
+use desktop_common::{ffi_utils::BorrowedUtf8, logger::ffi_boundary};
+use log::debug;
+use std::ffi::c_int;
+
+#[repr(C)]
+#[bitflag(c_int)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InhibitFlags {
+    /// Logging out, or shutting down.
+    Logout = 0b0000_0001,
+
+    UserSwitch = 0b0000_0010,
+
+    Suspend = 0b0000_0100,
+
+    /// The session being marked as idle, e.g. to start the screensaver.
+    Idle = 0b0000_1000,
+}
+
+/// Inhibits the session actions specified by `flags`, using the inhibit portal.
+/// The result is reported with `Event::InhibitResponse`.
+///
+/// The returned `RequestId` identifies the inhibition, and should be passed to `application_uninhibit` to end it.
+///
+/// * `reason`: User-visible reason for the inhibition.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_inhibit(mut app_ptr: AppPtr, window_id: WindowId, flags: InhibitFlags, reason: BorrowedUtf8) -> RequestId {
+    debug!("application_inhibit: {flags:?}");
+    ffi_boundary("application_inhibit", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        let reason = reason.get("application_inhibit: reason")?;
+        app.inhibit(window_id, flags, reason)
+    })
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn application_uninhibit(mut app_ptr: AppPtr, request_id: RequestId) {
+    debug!("application_uninhibit: {request_id:?}");
+    ffi_boundary("application_uninhibit", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        app.uninhibit(request_id);
+        Ok(())
+    });
+}
+
+/// Confirms that the application is ready for the session to end, in response to `Event::SessionQueryEnd`.
+///
+/// If the session ending should be delayed (e.g. to save the documents), `application_inhibit` with `InhibitFlags::Logout`
+/// should be called before this.
+#[unsafe(no_mangle)]
+pub extern "C" fn application_session_query_end_response(mut app_ptr: AppPtr) {
+    debug!("application_session_query_end_response");
+    ffi_boundary("application_session_query_end_response", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        app.session_query_end_response();
+        Ok(())
+    });
+}
@@ -1,4 +1,4 @@
-use desktop_common::ffi_utils::AutoDropArray;
+use desktop_common::ffi_utils::{AutoDropArray, AutoDropStrPtr};
 use desktop_common::logger::ffi_boundary;
 
 #[unsafe(no_mangle)]
@@ -8,3 +8,11 @@ pub extern "C" fn rust_allocated_u8_array_drop(array: AutoDropArray<u8>) {
         Ok(())
     });
 }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn string_array_drop(str_array: AutoDropArray<AutoDropStrPtr>) {
+    ffi_boundary("string_array_drop", || {
+        drop(str_array);
+        Ok(())
+    });
+}
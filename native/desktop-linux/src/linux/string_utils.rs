@@ -1,3 +1,11 @@
+use desktop_common::mime_types::decode_file_uri;
+use std::{
+    ffi::OsString,
+    fmt::Write as _,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+};
+
 pub fn join_str_iter<'a>(iter: impl Iterator<Item = &'a str> + Clone, sep: &str) -> String {
     let string_len: usize = iter.clone().map(|e| e.len() + sep.len()).sum();
     let acc = String::with_capacity(string_len + 1); // allocate taking into account the possible null terminator
@@ -7,3 +15,21 @@ pub fn join_str_iter<'a>(iter: impl Iterator<Item = &'a str> + Clone, sep: &str)
         acc
     })
 }
+
+/// Converts the path to a `file://` URI, percent-encoding everything except the unreserved characters and `/`.
+pub fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for &b in path.as_os_str().as_bytes() {
+        if b.is_ascii_alphanumeric() || b"/-._~".contains(&b) {
+            uri.push(char::from(b));
+        } else {
+            write!(uri, "%{b:02X}").unwrap();
+        }
+    }
+    uri
+}
+
+/// Returns `None` if the URI is not a local `file://` URI.
+pub fn file_uri_to_path(uri: &str) -> Option<PathBuf> {
+    decode_file_uri(uri).map(|bytes| PathBuf::from(OsString::from_vec(bytes)))
+}
@@ -1,3 +1,6 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
 use log::{debug, error, warn};
 use smithay_client_toolkit::{
     reexports::{
@@ -14,11 +17,16 @@ use smithay_client_toolkit::{
     },
     shm::Shm,
 };
+use wayland_protocols_plasma::appmenu::client::{
+    org_kde_kwin_appmenu::OrgKdeKwinAppmenu, org_kde_kwin_appmenu_manager::OrgKdeKwinAppmenuManager,
+};
 
 use crate::linux::{
     application_api::RenderingMode,
     application_state::{ApplicationState, EglInstance},
-    events::{SoftwareDrawData, WindowDecorationMode, WindowDrawEvent, WindowId},
+    async_event_result::AsyncEventResult,
+    events::{RequestId, SoftwareDrawData, WindowDecorationMode, WindowDrawEvent, WindowId},
+    frame_capture::CapturedFrame,
     geometry::{LogicalPoint, LogicalSize, PhysicalSize},
     pointer_shapes_api::PointerShape,
     rendering_egl::EglRendering,
@@ -27,6 +35,9 @@ use crate::linux::{
     window_resize_edge_api::WindowResizeEdge,
 };
 
+/// How long a frame capture waits for the application to draw a frame, e.g. while the window is hidden.
+pub const FRAME_CAPTURE_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Debug)]
 pub enum RenderingData {
     Egl(EglRendering<'static>),
@@ -34,13 +45,13 @@ pub enum RenderingData {
 }
 
 impl RenderingData {
-    pub fn draw<F>(&mut self, surface: &WlSurface, size: PhysicalSize, do_draw: F)
+    pub fn draw<F>(&mut self, surface: &WlSurface, size: PhysicalSize, capture: bool, do_draw: F) -> Option<anyhow::Result<CapturedFrame>>
     where
         F: FnOnce(SoftwareDrawData) -> bool,
     {
         match self {
-            Self::Egl(r) => r.draw(surface, do_draw),
-            Self::Software(r) => r.draw(surface, size, do_draw),
+            Self::Egl(r) => r.draw(surface, size, capture, do_draw),
+            Self::Software(r) => r.draw(surface, size, capture, do_draw),
         }
     }
 }
@@ -68,6 +79,16 @@ pub struct SimpleWindow {
     rendering_data: Option<RenderingData>,
     pub rendering_mode: RenderingMode,
     pub num_pointer_buttons_down: u32,
+    app_menu: Option<OrgKdeKwinAppmenu>,
+    pending_frame_captures: Vec<RequestId>,
+}
+
+impl Drop for SimpleWindow {
+    fn drop(&mut self) {
+        if let Some(app_menu) = self.app_menu.take() {
+            app_menu.release();
+        }
+    }
 }
 
 impl SimpleWindow {
@@ -136,9 +157,25 @@ impl SimpleWindow {
             rendering_data: None,
             rendering_mode: params.rendering_mode,
             num_pointer_buttons_down: 0,
+            app_menu: None,
+            pending_frame_captures: Vec::new(),
         })
     }
 
+    /// See <https://wayland.app/protocols/kde-appmenu>
+    pub fn set_app_menu_address(
+        &mut self,
+        app_menu_manager: &OrgKdeKwinAppmenuManager,
+        qh: &QueueHandle<ApplicationState>,
+        service_name: &str,
+        object_path: &str,
+    ) {
+        let app_menu = self
+            .app_menu
+            .get_or_insert_with(|| app_menu_manager.create(self.window.wl_surface(), qh, ()));
+        app_menu.set_address(service_name.to_owned(), object_path.to_owned());
+    }
+
     pub const fn close(&mut self) {
         self.close = true;
     }
@@ -236,13 +273,63 @@ impl SimpleWindow {
         }
     }
 
+    const fn frame_capture_response(&self, request_ids: Vec<RequestId>, result: anyhow::Result<CapturedFrame>) -> AsyncEventResult {
+        AsyncEventResult::WindowCaptureFrameResponse {
+            request_ids,
+            window_id: self.window_id,
+            scale: self.current_scale,
+            result,
+        }
+    }
+
+    /// Returns the response right away if the last presented frame can be read back (only with the software rendering),
+    /// otherwise it's returned from `draw` after the next frame that the application draws.
+    ///
+    /// A frame is requested, so that the application gets a `WindowDraw` event even if nothing else changes.
+    /// The frames that the application skips keep the capture pending, until `time_out_frame_capture` fails it.
+    pub fn capture_frame(&mut self, qh: &QueueHandle<ApplicationState>, request_id: RequestId) -> Option<AsyncEventResult> {
+        if let Some(RenderingData::Software(r)) = &mut self.rendering_data
+            && let Some(result) = r.read_presented_frame()
+        {
+            return Some(self.frame_capture_response(vec![request_id], result));
+        }
+        debug!("Capturing the next frame of {:?} for {request_id:?}", self.window_id);
+        if self.pending_frame_captures.is_empty() {
+            let surface = self.window.wl_surface();
+            surface.frame(qh, surface.clone());
+            surface.commit();
+        }
+        self.pending_frame_captures.push(request_id);
+        None
+    }
+
+    /// Fails the frame capture if it's still waiting for a frame after `FRAME_CAPTURE_TIMEOUT`.
+    pub fn time_out_frame_capture(&mut self, request_id: RequestId) -> Option<AsyncEventResult> {
+        let index = self.pending_frame_captures.iter().position(|id| *id == request_id)?;
+        self.pending_frame_captures.remove(index);
+        Some(self.frame_capture_response(
+            vec![request_id],
+            Err(anyhow!("No frame was drawn within {FRAME_CAPTURE_TIMEOUT:?}")),
+        ))
+    }
+
+    /// Fails the frame captures that are still waiting for a frame.
+    pub fn cancel_frame_captures(&mut self) -> Option<AsyncEventResult> {
+        if self.pending_frame_captures.is_empty() {
+            return None;
+        }
+        let request_ids = std::mem::take(&mut self.pending_frame_captures);
+        Some(self.frame_capture_response(request_ids, Err(anyhow!("The window was closed before rendering a frame"))))
+    }
+
+    /// Returns the response for the frame captures, if any were waiting for this frame.
     pub fn draw(
         &mut self,
         conn: &Connection,
         qh: &QueueHandle<ApplicationState>,
         themed_pointer: Option<&mut ThemedPointer>,
         callback: &dyn Fn(WindowDrawEvent) -> bool,
-    ) {
+    ) -> Option<AsyncEventResult> {
         self.update_pointer(conn, themed_pointer);
         let surface = self.window.wl_surface();
 
@@ -266,13 +353,20 @@ impl SimpleWindow {
             did_draw
         };
 
-        if let Some(r) = &mut self.rendering_data {
-            r.draw(surface, physical_size, do_draw);
+        let capture = !self.pending_frame_captures.is_empty();
+        let captured = if let Some(r) = &mut self.rendering_data {
+            r.draw(surface, physical_size, capture, do_draw)
         } else {
             warn!("Rendering data not initialized in draw");
-        }
+            None
+        };
 
         surface.commit();
+
+        // Also `None` if the application skipped the frame, then the captures wait for the next one
+        let result = captured?;
+        let request_ids = std::mem::take(&mut self.pending_frame_captures);
+        Some(self.frame_capture_response(request_ids, result))
     }
 
     fn on_resize(&mut self, size: LogicalSize, physical_size: PhysicalSize, shm: &Shm) {
@@ -7,10 +7,11 @@ use crate::linux::{
     file_dialog_api::{CommonFileDialogParams, OpenFileDialogParams, SaveFileDialogParams},
     geometry::{LogicalPoint, LogicalSize},
     pointer_shapes_api::PointerShape,
+    print_api::{PreparePrintParams, PrintParams},
     window_resize_edge_api::WindowResizeEdge,
 };
 use anyhow::Context;
-use desktop_common::ffi_utils::BorrowedUtf8;
+use desktop_common::ffi_utils::{BorrowedArray, BorrowedUtf8};
 use desktop_common::logger::{PanicDefault, ffi_boundary};
 use log::debug;
 use smithay_client_toolkit::shell::xdg::window::DecorationMode;
@@ -184,6 +185,7 @@ pub extern "C" fn window_unset_fullscreen(app_ptr: AppPtr, window_id: WindowId)
     });
 }
 
+#[deprecated(note = "use window_start_drag_and_drop_v2")]
 #[unsafe(no_mangle)]
 pub extern "C" fn window_start_drag_and_drop(
     mut app_ptr: AppPtr,
@@ -199,7 +201,7 @@ pub extern "C" fn window_start_drag_and_drop(
         let mime_types_str = mime_types.get("window_start_drag_and_drop: mime_types")?;
         app.start_drag(
             window_id,
-            MimeTypes::new(mime_types_str),
+            &MimeTypes::new(mime_types_str),
             actions,
             drag_icon_rendering_mode,
             drag_icon_size,
@@ -207,6 +209,23 @@ pub extern "C" fn window_start_drag_and_drop(
     });
 }
 
+#[unsafe(no_mangle)]
+pub extern "C" fn window_start_drag_and_drop_v2(
+    mut app_ptr: AppPtr,
+    window_id: WindowId,
+    mime_types: BorrowedArray<BorrowedUtf8>,
+    actions: DragAndDropActions,
+    drag_icon_rendering_mode: RenderingMode,
+    drag_icon_size: LogicalSize,
+) {
+    debug!("window_start_drag_and_drop_v2");
+    ffi_boundary("window_start_drag_and_drop_v2", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        let mime_types = MimeTypes::from_array(&mime_types, "window_start_drag_and_drop_v2: mime_types")?;
+        app.start_drag(window_id, &mime_types, actions, drag_icon_rendering_mode, drag_icon_size)
+    });
+}
+
 impl From<WindowDecorationMode> for DecorationMode {
     fn from(value: WindowDecorationMode) -> Self {
         match value {
@@ -269,6 +288,65 @@ pub extern "C" fn window_show_save_file_dialog(
     })
 }
 
+/// Takes a screenshot using the screenshot portal. The result is reported with `Event::ScreenshotResponse`.
+///
+/// * `interactive`: Whether the user should be able to choose the screenshot area and other options before taking it.
+#[unsafe(no_mangle)]
+pub extern "C" fn window_request_screenshot(mut app_ptr: AppPtr, window_id: WindowId, interactive: bool) -> RequestId {
+    debug!("window_request_screenshot: interactive={interactive}");
+    ffi_boundary("window_request_screenshot", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        app.request_screenshot(window_id, interactive)
+    })
+}
+
+/// Captures the rendered window content, e.g. for the screenshot tests. The result is reported with `Event::WindowCaptureFrameResponse`.
+///
+/// With the software rendering, it's the last presented frame. With EGL, it's read with `glReadPixels` from the next frame
+/// that the application draws, because the presented EGL buffer can't be read back.
+/// The next frame is also used if nothing has been rendered yet. A frame is requested for it, and the capture fails
+/// if the application doesn't draw one within 5 seconds, e.g. because the window is hidden.
+#[unsafe(no_mangle)]
+pub extern "C" fn window_capture_frame(mut app_ptr: AppPtr, window_id: WindowId) -> RequestId {
+    debug!("window_capture_frame for {window_id:?}");
+    ffi_boundary("window_capture_frame", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        app.window_capture_frame(window_id)
+    })
+}
+
+/// Lets the user pick a color from anywhere on the screen, using the screenshot portal.
+/// The result is reported with `Event::ColorPickerResponse`.
+#[unsafe(no_mangle)]
+pub extern "C" fn window_request_pick_color(mut app_ptr: AppPtr, window_id: WindowId) -> RequestId {
+    debug!("window_request_pick_color");
+    ffi_boundary("window_request_pick_color", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        app.request_pick_color(window_id)
+    })
+}
+
+/// Shows the print dialog using the print portal. The result is reported with `Event::PreparePrintResponse`.
+#[unsafe(no_mangle)]
+pub extern "C" fn window_request_prepare_print(mut app_ptr: AppPtr, window_id: WindowId, params: &PreparePrintParams) -> RequestId {
+    debug!("window_request_prepare_print");
+    ffi_boundary("window_request_prepare_print", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        app.request_prepare_print(window_id, params)
+    })
+}
+
+/// Prints the PDF document using the print portal, with the settings chosen in `window_request_prepare_print`.
+/// The result is reported with `Event::PrintResponse`.
+#[unsafe(no_mangle)]
+pub extern "C" fn window_request_print(mut app_ptr: AppPtr, window_id: WindowId, params: &PrintParams) -> RequestId {
+    debug!("window_request_print: token={}", params.token);
+    ffi_boundary("window_request_print", || {
+        let app = unsafe { app_ptr.borrow_mut::<Application>() };
+        app.request_print(window_id, params)
+    })
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn window_request_internal_activation_token(mut app_ptr: AppPtr, window_id: WindowId) -> RequestId {
     ffi_boundary("window_request_internal_activation_token", || {
@@ -1,6 +1,6 @@
 use desktop_common::{
     ffi_utils::BorrowedStrPtr,
-    logger_api::{ExceptionsArray, LoggerConfiguration},
+    logger_api::{ExceptionsArray, LoggerConfiguration, LoggerLevels},
 };
 use windows::{Win32::System::Diagnostics::Debug::OutputDebugStringA, core::PCSTR};
 
@@ -19,8 +19,18 @@ pub extern "C" fn logger_init(logger_configuration: &LoggerConfiguration) {
     desktop_common::logger_api::logger_init_impl(logger_configuration);
 }
 
+#[unsafe(no_mangle)]
+pub extern "C" fn logger_set_levels(logger_levels: &LoggerLevels) {
+    desktop_common::logger_api::logger_set_levels_impl(logger_levels);
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn logger_output_debug_string(message: BorrowedStrPtr) {
     let cstr = message.as_optional_cstr().expect("The debug string pointer is null.");
     unsafe { OutputDebugStringA(PCSTR::from_raw(cstr.as_ptr().cast())) };
 }
+
+#[unsafe(no_mangle)]
+pub extern "C" fn logger_set_fail_fast(enabled: bool) {
+    desktop_common::logger_api::logger_set_fail_fast_impl(enabled);
+}